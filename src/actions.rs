@@ -0,0 +1,390 @@
+use std::str::FromStr;
+
+// Represents a command which can be run by name from the command palette
+// * Adding an action only requires adding it to ACTIONS and handling it in Editor::run_action,
+// * and key bindings which run an action go through Editor::run_action as well
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Save,
+    SaveAll,
+    Quit,
+    Suspend,
+    ShowHelp,
+    OpenCommandPalette,
+    OpenCommandLine,
+    MoveWordLeft,
+    MoveWordRight,
+    LineStart,
+    LineEnd,
+    NextFunction,
+    PreviousFunction,
+    Copy,
+    Undo,
+    Redo,
+    OpenUndoTree,
+    TimeTravel,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    ReplaceRegex,
+    InsertFile,
+    InsertDate,
+    Yank,
+    KillLine,
+    KillRegion,
+    DeleteSentence,
+    MoveLineUp,
+    MoveLineDown,
+    SelectParagraph,
+    SelectEnclosingBlock,
+    Increment,
+    Decrement,
+    Calculate,
+    ToggleOverwrite,
+    ToggleRawPaste,
+    OpenMarks,
+    JumpBack,
+    JumpForward,
+    CenterView,
+    ScrollToTop,
+    ScrollToBottom,
+    ToggleFold,
+    FoldAll,
+    UnfoldAll,
+    ToggleTable,
+    ToggleTableHeader,
+    ToggleSoftWrap,
+    ShowLineNumbers,
+    ShowRelativeLineNumbers,
+    HideLineNumbers,
+    ToggleInvisibles,
+    ToggleCurrentLineHighlight,
+    ToggleZenMode,
+    TogglePerformanceOverlay,
+    ChangeEncoding,
+    ExportHtml,
+    RunTask,
+    ShowTaskOutput,
+    PreviewImage,
+    ShowFileInfo,
+    NextBuffer,
+    PreviousBuffer,
+    ListBuffers,
+    CloseBuffer,
+    OpenPastedPath,
+    ShowDiagnostics,
+    ToggleBreakpoint,
+    StartDebugging,
+    StopDebugging,
+    StepOver,
+    StepInto,
+    StepOut,
+    ShowVariables,
+    AddCursorAbove,
+    AddCursorBelow,
+    InsertSequence,
+}
+
+// Every action in the command palette, in the order they are listed before anything is typed,
+// along with its name and the key bound to it by default, if there is one
+pub const ACTIONS: &[(Action, &str, Option<&str>)] = &[
+    (Action::Save, "Save", Some("Ctrl+S")),
+    (Action::SaveAll, "Save every buffer", None),
+    (Action::Quit, "Quit", Some("Ctrl+Q")),
+    (Action::Suspend, "Suspend to the shell", None),
+    (Action::ShowHelp, "Show help", Some("F1")),
+    (
+        Action::OpenCommandPalette,
+        "Run a command by name",
+        Some("Ctrl+Shift+P"),
+    ),
+    (Action::OpenCommandLine, "Type a command", Some("Alt+;")),
+    (
+        Action::MoveWordLeft,
+        "Move to the previous word",
+        Some("Alt+B"),
+    ),
+    (
+        Action::MoveWordRight,
+        "Move to the next word",
+        Some("Alt+F"),
+    ),
+    (
+        Action::LineStart,
+        "Move to the start of the line",
+        Some("Home"),
+    ),
+    (Action::LineEnd, "Move to the end of the line", Some("End")),
+    (
+        Action::NextFunction,
+        "Go to the next function",
+        Some("Alt+PageDown"),
+    ),
+    (
+        Action::PreviousFunction,
+        "Go to the previous function",
+        Some("Alt+PageUp"),
+    ),
+    (Action::Copy, "Copy the selection or line", Some("Ctrl+K")),
+    (Action::Undo, "Undo", Some("Ctrl+Z")),
+    (Action::Redo, "Redo", Some("Ctrl+Y")),
+    (Action::OpenUndoTree, "Show the undo tree", Some("Ctrl+U")),
+    (Action::TimeTravel, "Time travel", Some("Ctrl+T")),
+    (Action::Search, "Search", Some("Ctrl+F")),
+    (Action::NextMatch, "Go to the next match", Some("F3")),
+    (
+        Action::PreviousMatch,
+        "Go to the previous match",
+        Some("Shift+F3"),
+    ),
+    (Action::ReplaceRegex, "Replace a regex", Some("Alt+R")),
+    (Action::InsertFile, "Insert a file", Some("Ctrl+R")),
+    (Action::InsertDate, "Insert the date and time", Some("F5")),
+    (Action::Yank, "Yank deleted text", Some("Alt+Y")),
+    (Action::KillLine, "Kill to the end of the line", None),
+    (Action::KillRegion, "Kill the selection", None),
+    (
+        Action::DeleteSentence,
+        "Delete to the end of the sentence",
+        Some("Alt+K"),
+    ),
+    (Action::MoveLineUp, "Move the line up", Some("Alt+Up")),
+    (Action::MoveLineDown, "Move the line down", Some("Alt+Down")),
+    (
+        Action::SelectParagraph,
+        "Select the paragraph",
+        Some("Alt+H"),
+    ),
+    (
+        Action::SelectEnclosingBlock,
+        "Select the enclosing block",
+        Some("Alt+S"),
+    ),
+    (Action::Increment, "Increment the number", Some("Ctrl+A")),
+    (Action::Decrement, "Decrement the number", Some("Ctrl+X")),
+    (Action::Calculate, "Calculate", Some("Alt+=")),
+    (
+        Action::ToggleOverwrite,
+        "Toggle overwrite mode",
+        Some("Insert"),
+    ),
+    (
+        Action::ToggleRawPaste,
+        "Toggle reindenting pasted text",
+        Some("Alt+P"),
+    ),
+    (Action::OpenMarks, "Show the marks", Some("Ctrl+J")),
+    (Action::JumpBack, "Jump back", Some("Ctrl+O")),
+    (Action::JumpForward, "Jump forward", Some("Ctrl+I")),
+    (
+        Action::CenterView,
+        "Center the view on the cursor",
+        Some("Ctrl+W C"),
+    ),
+    (
+        Action::ScrollToTop,
+        "Scroll the cursor to the top",
+        Some("Ctrl+W T"),
+    ),
+    (
+        Action::ScrollToBottom,
+        "Scroll the cursor to the bottom",
+        Some("Ctrl+W B"),
+    ),
+    (
+        Action::ToggleFold,
+        "Toggle the fold at the cursor",
+        Some("Ctrl+W Z"),
+    ),
+    (Action::FoldAll, "Fold everything", Some("Ctrl+W M")),
+    (Action::UnfoldAll, "Unfold everything", Some("Ctrl+W R")),
+    (
+        Action::ToggleTable,
+        "Toggle the table view",
+        Some("Ctrl+W A"),
+    ),
+    (
+        Action::ToggleTableHeader,
+        "Toggle the table header",
+        Some("Ctrl+W H"),
+    ),
+    (Action::ToggleSoftWrap, "Toggle soft wrap", Some("Ctrl+W W")),
+    (Action::ShowLineNumbers, "Show line numbers", None),
+    (
+        Action::ShowRelativeLineNumbers,
+        "Show relative line numbers",
+        None,
+    ),
+    (Action::HideLineNumbers, "Hide line numbers", None),
+    (
+        Action::ToggleInvisibles,
+        "Toggle invisible characters",
+        Some("F2"),
+    ),
+    (
+        Action::ToggleCurrentLineHighlight,
+        "Toggle the current line highlight",
+        Some("F4"),
+    ),
+    (Action::ToggleZenMode, "Toggle zen mode", Some("F11")),
+    (
+        Action::TogglePerformanceOverlay,
+        "Toggle the performance overlay",
+        Some("F12"),
+    ),
+    (Action::ChangeEncoding, "Change the encoding", Some("Alt+N")),
+    (Action::ExportHtml, "Export as HTML", Some("Ctrl+E")),
+    (Action::RunTask, "Run a task", Some("Alt+T")),
+    (
+        Action::ShowTaskOutput,
+        "Show the output of the last task",
+        Some("Alt+O"),
+    ),
+    (
+        Action::PreviewImage,
+        "Preview the image under the cursor",
+        Some("Alt+V"),
+    ),
+    (
+        Action::ShowFileInfo,
+        "Show information about the file",
+        Some("Alt+I"),
+    ),
+    (
+        Action::NextBuffer,
+        "Switch to the next buffer",
+        Some("Ctrl+PageDown"),
+    ),
+    (
+        Action::PreviousBuffer,
+        "Switch to the previous buffer",
+        Some("Ctrl+PageUp"),
+    ),
+    (Action::ListBuffers, "Show the open buffers", Some("Alt+L")),
+    (Action::CloseBuffer, "Close the buffer", None),
+    (
+        Action::ShowDiagnostics,
+        "Show the errors and warnings in every buffer",
+        Some("Alt+D"),
+    ),
+    (
+        Action::OpenPastedPath,
+        "Open the pasted file instead",
+        Some("Alt+Enter"),
+    ),
+    (
+        Action::ToggleBreakpoint,
+        "Toggle a breakpoint on the line",
+        Some("F9"),
+    ),
+    (
+        Action::StartDebugging,
+        "Start debugging, or continue once stopped",
+        Some("F6"),
+    ),
+    (Action::StopDebugging, "Stop debugging", Some("Shift+F6")),
+    (Action::StepOver, "Step over the line", Some("F10")),
+    (Action::StepInto, "Step into the function", Some("F7")),
+    (Action::StepOut, "Step out of the function", Some("F8")),
+    (
+        Action::ShowVariables,
+        "Show the variables where the program stopped",
+        Some("Alt+X"),
+    ),
+    (
+        Action::AddCursorAbove,
+        "Add a cursor on the line above",
+        Some("Ctrl+Alt+Up"),
+    ),
+    (
+        Action::AddCursorBelow,
+        "Add a cursor on the line below",
+        Some("Ctrl+Alt+Down"),
+    ),
+    (
+        Action::InsertSequence,
+        "Insert a sequence (1, 2, 3... or a, b, c...) at the cursors",
+        Some("Alt+C"),
+    ),
+];
+
+impl FromStr for Action {
+    type Err = String;
+
+    // Parses the identifier of an action, which is used to bind keys to it in the config file
+    // [EXAMPLE] "save", "toggle-soft-wrap", "line-start"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ACTIONS
+            .iter()
+            .map(|&(action, _, _)| action)
+            .find(|&action| id(action) == s)
+            .ok_or_else(|| format!("Unknown action: {}", s))
+    }
+}
+
+// Gets the identifier of an action, which is its name in kebab case
+// [EXAMPLE] Action::ToggleSoftWrap -> "toggle-soft-wrap"
+pub fn id(action: Action) -> String {
+    let mut id = String::new();
+    for c in format!("{:?}", action).chars() {
+        if c.is_ascii_uppercase() && !id.is_empty() {
+            id.push('-');
+        }
+        id.push(c.to_ascii_lowercase());
+    }
+
+    id
+}
+
+// Gets the name of an action, as it is listed in the command palette
+pub fn name(action: Action) -> &'static str {
+    ACTIONS
+        .iter()
+        .find(|&&(other, _, _)| other == action)
+        .map(|&(_, name, _)| name)
+        .expect("[INTERNAL ERROR] Action is missing from the list of actions")
+}
+
+// Gets the actions whose names fuzzy match a query, with the best matches first
+// Every action matches an empty query, so they are all listed before anything is typed
+pub fn search(query: &str) -> Vec<(Action, &'static str, Option<&'static str>)> {
+    let mut matches: Vec<_> = ACTIONS
+        .iter()
+        .filter_map(|&(action, name, key)| Some((fuzzy_score(query, name)?, (action, name, key))))
+        .collect();
+
+    // The sort is stable, so actions which match equally well stay in their usual order
+    matches.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+    matches.into_iter().map(|(_, action)| action).collect()
+}
+
+// Scores how well a query matches a name, if every character of the query appears in the name in order
+// Characters are compared without case, and matches which are next to each other or at the start
+// of a word score higher, so "tsw" ranks "Toggle soft wrap" above names which only contain the letters
+// [EXAMPLE] "undo" matches "Undo" better than "Show the undo tree", and does not match "Redo"
+fn fuzzy_score(query: &str, name: &str) -> Option<i32> {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous = None;
+
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let index = position + name[position..].iter().position(|&n| n == c)?;
+
+        score += match (
+            index == 0 || name[index - 1] == ' ',
+            previous == Some(index),
+        ) {
+            (_, true) => 3,
+            (true, false) => 2,
+            (false, false) => 0,
+        };
+        // Matches further into the name are worth slightly less
+        score -= (index - position) as i32 / 4;
+
+        position = index + 1;
+        previous = Some(position);
+    }
+
+    Some(score)
+}
@@ -1,17 +1,108 @@
 #![allow(dead_code)]
 
+use std::fmt;
 use std::fs::File;
-use std::io::Seek;
+use std::io::{self, Read, Seek, Write};
 use std::ops::Range;
+use std::sync::mpsc::{self, Receiver, Sender};
 
 use crossterm::Result;
-use ropey::{Rope, RopeSlice};
+use ropey::{str_utils::byte_to_char_idx, Rope, RopeSlice};
+use tree_sitter::{InputEdit, Point};
+use unicode_segmentation::{GraphemeCursor, GraphemeIncomplete};
+use unicode_width::UnicodeWidthChar;
+
+use crate::Encoding;
+use crate::Progress;
+use crate::WordChars;
+
+// The number of columns a tab character takes up if no other width is configured
+pub const DEFAULT_TAB_WIDTH: usize = 4;
 
-#[derive(Default, Clone)]
 // Represents the buffer of the editor
 // Basically a wrapper class for Rope to simplify/extend functionality
 pub struct Buffer {
     rope: Rope,
+    tab_width: usize,
+    // The characters which make up words, for word motions and deletion
+    word_chars: WordChars,
+    // Incremented on every edit, so that a snapshot of the buffer can be matched to its contents
+    version: u64,
+    // The changes made to the lines of the buffer since they were last taken by the renderer
+    line_changes: Vec<LineChange>,
+    // The edits made since they were last taken, in the form the syntax tree is updated with
+    // * Telling the parser exactly what changed lets it reparse only the parts of the tree around
+    // * each edit, instead of the whole buffer on every keypress
+    syntax_edits: Vec<InputEdit>,
+    // The channels that every edit is reported to, one for each subscriber
+    subscribers: Vec<Sender<BufferChange>>,
+}
+
+// Represents a group of lines in the buffer being replaced by an edit
+// Lines after the group are unchanged, although they may have moved up or down
+// [EXAMPLE] inserting "a\nb" on line 3 replaces 1 line with 2 lines, starting at line 3
+#[derive(Clone, Copy)]
+pub struct LineChange {
+    pub start: usize,
+    pub removed: usize,
+    pub inserted: usize,
+}
+
+// Represents an edit made to the buffer, as reported to its subscribers
+// An edit deletes some text, inserts some text in its place, or both
+#[derive(Clone)]
+pub struct BufferChange {
+    // The buffer index that the edit was made at
+    pub index: usize,
+    pub deleted: String,
+    pub inserted: String,
+}
+
+impl BufferChange {
+    // Gets the range of buffer indices that the deleted text used to take up
+    pub fn deleted_range(&self) -> Range<usize> {
+        self.index..self.index + self.deleted.chars().count()
+    }
+
+    // Gets the number of characters that were inserted
+    pub fn inserted_len(&self) -> usize {
+        self.inserted.chars().count()
+    }
+}
+
+// Represents something which keeps track of positions in the buffer, and so needs to shift them
+// whenever the buffer is edited
+pub trait BufferObserver {
+    // Shifts the positions after an insertion of `len` characters at the given index
+    fn adjust_for_insert(&mut self, index: usize, len: usize);
+
+    // Shifts the positions after the given range of characters has been deleted
+    fn adjust_for_delete(&mut self, range: Range<usize>);
+
+    // Shifts the positions to account for a change reported by the buffer
+    fn buffer_changed(&mut self, change: &BufferChange) {
+        if !change.deleted.is_empty() {
+            self.adjust_for_delete(change.deleted_range());
+        }
+
+        if !change.inserted.is_empty() {
+            self.adjust_for_insert(change.index, change.inserted_len());
+        }
+    }
+}
+
+impl Default for Buffer {
+    fn default() -> Self {
+        Self {
+            rope: Rope::default(),
+            tab_width: DEFAULT_TAB_WIDTH,
+            word_chars: WordChars::default(),
+            version: 0,
+            line_changes: Vec::new(),
+            syntax_edits: Vec::new(),
+            subscribers: Vec::new(),
+        }
+    }
 }
 
 #[derive(PartialEq)]
@@ -20,118 +111,420 @@ pub enum DeletionMode {
     Backspace,
 }
 
-impl ToString for Buffer {
-    fn to_string(&self) -> String {
-        self.rope.to_string()
+impl Clone for Buffer {
+    // Copies the buffer without its subscribers, or the line changes and syntax edits waiting to be taken
+    // * Copies are snapshots (such as the one written by a save), so edits made to them must not be
+    // * reported as if they had been made to the buffer being edited
+    fn clone(&self) -> Self {
+        Self {
+            rope: self.rope.clone(),
+            tab_width: self.tab_width,
+            word_chars: self.word_chars.clone(),
+            version: self.version,
+            line_changes: Vec::new(),
+            syntax_edits: Vec::new(),
+            subscribers: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for Buffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rope)
     }
 }
 
 impl Buffer {
     // Create a new Buffer instance from a File
     pub fn new(file: &File) -> Self {
-        // Read the file into a Rope
-        let rope = Rope::from_reader(file).expect("[INTERNAL ERROR] Failed to read file");
+        Self::read(file)
+            .map(|(buffer, _)| buffer)
+            .expect("[INTERNAL ERROR] Failed to read file")
+    }
 
-        Self { rope }
+    // Create a new Buffer instance from a File (or any other reader), along with the encoding it was
+    // found to be in, returning an error if it cannot be read as text
+    pub fn read(mut reader: impl Read) -> Result<(Self, Encoding)> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        let text = Encoding::detect(&bytes)
+            .and_then(|encoding| Some((encoding.decode(&bytes)?, encoding)));
+        let Some((text, encoding)) = text else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the file does not contain text in a supported encoding",
+            ));
+        };
+
+        // Read the text into a Rope
+        let buffer = Self {
+            rope: Rope::from_str(&text),
+            ..Self::empty()
+        };
+
+        Ok((buffer, encoding))
     }
 
-    // Writes the buffer to the given file
-    pub fn write_to_file(&self, file: &mut File) -> Result<()> {
+    // Create an empty Buffer instance, which is not read from any file
+    pub fn empty() -> Self {
+        Self {
+            rope: Rope::new(),
+            tab_width: DEFAULT_TAB_WIDTH,
+            word_chars: WordChars::default(),
+            version: 0,
+            line_changes: Vec::new(),
+            syntax_edits: Vec::new(),
+            subscribers: Vec::new(),
+        }
+    }
+
+    // Writes the buffer to the given file in the given encoding, reporting how many bytes of the
+    // buffer have been written
+    pub fn write_to_file(
+        &self,
+        file: &mut File,
+        encoding: Encoding,
+        progress: &Progress,
+    ) -> Result<()> {
         // Truncate the file and rewind to prepare it for writing
         file.set_len(0)?;
         file.rewind()?;
 
-        self.rope.write_to(file)
+        file.write_all(encoding.byte_order_mark())?;
+
+        // * Chunks never split a character, so each one can be encoded on its own
+        for chunk in self.rope.chunks() {
+            match encoding {
+                Encoding::Utf8 => file.write_all(chunk.as_bytes())?,
+                encoding => file.write_all(&encoding.encode(chunk))?,
+            }
+            progress.advance(chunk.len());
+        }
+
+        Ok(())
     }
 
     // Inserts a character at the given index
     pub fn insert(&mut self, index: usize, character: char) {
+        let line_count = self.line_count();
+        let start = self.syntax_position(index);
         self.rope.insert_char(index, character);
+        self.record_insert(index, line_count);
+        self.record_syntax_edit(start, start, index + 1);
+        self.version += 1;
+
+        self.notify(|| BufferChange {
+            index,
+            deleted: String::new(),
+            inserted: character.to_string(),
+        });
+    }
+
+    // Inserts a string at the given index
+    pub fn insert_str(&mut self, index: usize, text: &str) {
+        let line_count = self.line_count();
+        let start = self.syntax_position(index);
+        self.rope.insert(index, text);
+        self.record_insert(index, line_count);
+        self.record_syntax_edit(start, start, index + text.chars().count());
+        self.version += 1;
+
+        self.notify(|| BufferChange {
+            index,
+            deleted: String::new(),
+            inserted: text.to_string(),
+        });
     }
 
     // Deletes a group of characters at the given index
     pub fn delete(&mut self, range: Range<usize>) {
+        let line_count = self.line_count();
+        let start = self.rope.char_to_line(range.start);
+        let removed = self.rope.char_to_line(range.end) - start + 1;
+
+        // The deleted text is only needed if someone is going to be told about it
+        let deleted = match self.subscribers.is_empty() {
+            true => String::new(),
+            false => self.text_range(range.clone()),
+        };
+        let index = range.start;
+        let (syntax_start, syntax_end) = (
+            self.syntax_position(range.start),
+            self.syntax_position(range.end),
+        );
+
         self.rope.remove(range);
+        self.record_syntax_edit(syntax_start, syntax_end, index);
+
+        self.line_changes.push(LineChange {
+            start,
+            removed,
+            inserted: removed - (line_count - self.line_count()),
+        });
+        self.version += 1;
+
+        self.notify(|| BufferChange {
+            index,
+            deleted,
+            inserted: String::new(),
+        });
+    }
+
+    // Records the lines changed by an insertion at the given index
+    // * The line count before the insertion is used to find how many lines were added
+    fn record_insert(&mut self, index: usize, previous_line_count: usize) {
+        self.line_changes.push(LineChange {
+            start: self.rope.char_to_line(index),
+            removed: 1,
+            inserted: 1 + self.line_count() - previous_line_count,
+        });
+    }
+
+    // Records an edit for the syntax tree, given the byte offset and position of the start of the
+    // edited text and of its old end before the edit, and the buffer index of its new end after it
+    fn record_syntax_edit(
+        &mut self,
+        (start_byte, start_position): (usize, Point),
+        (old_end_byte, old_end_position): (usize, Point),
+        new_end: usize,
+    ) {
+        let (new_end_byte, new_end_position) = self.syntax_position(new_end);
+        self.syntax_edits.push(InputEdit {
+            start_byte,
+            old_end_byte,
+            new_end_byte,
+            start_position,
+            old_end_position,
+            new_end_position,
+        });
+    }
+
+    // Gets the byte offset of a buffer index, along with its row and byte column, which is how
+    // the syntax tree refers to positions
+    fn syntax_position(&self, index: usize) -> (usize, Point) {
+        let byte = self.rope.char_to_byte(index);
+        let row = self.rope.char_to_line(index);
+
+        (byte, Point::new(row, byte - self.rope.line_to_byte(row)))
+    }
+
+    // Subscribes to the changes made to the buffer, which are sent over the returned channel
+    // as they happen, so that other parts of the editor (or other threads) can react to them
+    // The subscription ends when the receiver is dropped
+    pub fn subscribe(&mut self) -> Receiver<BufferChange> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+
+        receiver
+    }
+
+    // Reports a change to every subscriber, dropping any which have stopped listening
+    // * The change is only built if there is someone to report it to
+    fn notify(&mut self, change: impl FnOnce() -> BufferChange) {
+        if self.subscribers.is_empty() {
+            return;
+        }
+
+        let change = change();
+        self.subscribers
+            .retain(|subscriber| subscriber.send(change.clone()).is_ok());
+    }
+
+    // Gets the version of the buffer, which changes whenever the buffer is edited
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    // Takes the line changes made since this was last called, so they can be redrawn
+    pub fn take_line_changes(&mut self) -> Vec<LineChange> {
+        std::mem::take(&mut self.line_changes)
+    }
+
+    // Takes the edits made since this was last called, so the syntax tree can be updated with them
+    pub fn take_syntax_edits(&mut self) -> Vec<InputEdit> {
+        std::mem::take(&mut self.syntax_edits)
+    }
+
+    // Gets the characters in the given range as a String
+    pub fn text_range(&self, range: Range<usize>) -> String {
+        self.rope.slice(range).to_string()
+    }
+
+    // Gets the text in the given range of byte offsets as a String
+    pub fn byte_range(&self, range: Range<usize>) -> String {
+        self.rope.byte_slice(range).to_string()
+    }
+
+    // Gets the text from a byte offset to the end of the chunk of the rope it is in, which is how
+    // the parser reads the buffer without it being copied into one string
+    // Returns an empty string at the end of the buffer
+    pub fn chunk_at_byte(&self, byte: usize) -> &str {
+        if byte >= self.rope.len_bytes() {
+            return "";
+        }
+
+        let (chunk, chunk_start, _, _) = self.rope.chunk_at_byte(byte);
+        &chunk[byte - chunk_start..]
+    }
+
+    // Gets the byte offset of a buffer index
+    pub fn char_to_byte(&self, index: usize) -> usize {
+        self.rope.char_to_byte(index)
+    }
+
+    // Gets the buffer index of the character at a byte offset
+    pub fn byte_to_char(&self, byte: usize) -> usize {
+        self.rope.byte_to_char(byte)
     }
 
     // Gets the current cursor coordinate from a given buffer index
+    // The X coordinate is a display column, which accounts for tab expansion
     pub fn cursor_coord(&self, index: usize) -> Option<(u16, u16)> {
         // Make sure the index is valid
         if index > self.size() {
             return None;
         }
 
-        let mut current_line: usize = 0;
-        let mut current_line_start = 0;
+        let line = self.rope.char_to_line(index);
+        let column = index - self.line_start(line);
 
-        for (i, c) in self.rope.chars().enumerate() {
-            // If the index is reached, return the current coordinate
-            if i == index {
-                // [EXAMPLE] if the searched index is 53, and the current_line_start
-                // is 50, then the coordinate would be (3, current_line)
-                return Some(((i - current_line_start) as u16, current_line as u16));
-            }
+        // [EXAMPLE] if the searched index is 53, and the line starts at index 50,
+        // then the coordinate would be (3, line) if there are no tabs before the index
+        Some((self.display_column(line, column) as u16, line as u16))
+    }
 
+    // Gets the display column of a character column on a given line
+    pub fn display_column(&self, line: usize, column: usize) -> usize {
+        self.line(line)
+            .chars()
+            .take(column)
+            .fold(0, |display_column, c| {
+                display_column + char_width(c, display_column, self.tab_width)
+            })
+    }
+
+    // Gets the character column on a given line which is displayed at a given display column
+    // If the display column is in the middle of a tab, the column of the tab is returned,
+    // and if it is past the end of the line, the column at the end of the line is returned
+    pub fn char_column(&self, line: usize, display_column: usize) -> usize {
+        let mut current_display_column = 0;
+
+        for (column, c) in self.line(line).chars().enumerate() {
             if c == '\n' {
-                current_line += 1;
-                current_line_start = i + 1;
+                return column;
             }
-        }
 
-        // If the index is at the end of the buffer, return the last coordinate
-        Some(((index - current_line_start) as u16, current_line as u16))
-    }
-    
-    // Get the index of the start of the current word
-    pub fn start_of_word(&self, index: usize) -> usize {
-        // Make sure the index is valid
-        if index == 0 {
-            return 0;
+            current_display_column += char_width(c, current_display_column, self.tab_width);
+            if current_display_column > display_column {
+                return column;
+            }
         }
 
-        // Ropey does not have a reverse iterator, so we have build one manually
-        // TODO: Make this more efficient (backward char iteration as seen in commented code)
-        let mut slice = self.rope.slice(..index).chars_at(index);
-        let chars = std::iter::from_fn(|| slice.prev());
-
-        // for c in self.rope.chars_at(index).reversed().enumerate() {
+        self.line_len(line)
+    }
 
-        // }
+    // Sets the number of columns a tab character takes up
+    pub fn set_tab_width(&mut self, tab_width: usize) {
+        self.tab_width = tab_width.max(1);
+    }
 
-        let mut start_of_word = index;
+    // Gets the number of columns a tab character takes up
+    pub fn tab_width(&self) -> usize {
+        self.tab_width
+    }
 
-        // If the cursor starts in the middle of two words, skip the trailing whitespace
-        let mut skipping_whitespace = false;
+    // Sets which characters make up words
+    pub fn set_word_chars(&mut self, word_chars: WordChars) {
+        self.word_chars = word_chars;
+    }
 
-        for (i, c) in chars.enumerate() {
-            // Skip all trailing whitespace
-            if i == 0 && c.is_whitespace() {
-                skipping_whitespace = true;
-                continue;
+    // Gets the index of the grapheme boundary before the given index
+    // A grapheme is what the user sees as a single character, which may be made up of
+    // multiple chars (such as a letter followed by a combining accent, or an emoji sequence)
+    // * This is based on the grapheme example in the Ropey repository
+    pub fn prev_grapheme_boundary(&self, index: usize) -> usize {
+        let byte_index = self.rope.char_to_byte(index.min(self.size()));
+
+        let (mut chunk, mut chunk_byte_index, mut chunk_char_index, _) =
+            self.rope.chunk_at_byte(byte_index);
+        let mut cursor = GraphemeCursor::new(byte_index, self.rope.len_bytes(), true);
+
+        loop {
+            match cursor.prev_boundary(chunk, chunk_byte_index) {
+                Ok(None) => return 0,
+                Ok(Some(boundary)) => {
+                    return chunk_char_index + byte_to_char_idx(chunk, boundary - chunk_byte_index);
+                }
+                Err(GraphemeIncomplete::PrevChunk) => {
+                    (chunk, chunk_byte_index, chunk_char_index, _) =
+                        self.rope.chunk_at_byte(chunk_byte_index - 1);
+                }
+                Err(GraphemeIncomplete::PreContext(context_end)) => {
+                    let context = self.rope.chunk_at_byte(context_end - 1).0;
+                    cursor.provide_context(context, context_end - context.len());
+                }
+                Err(_) => unreachable!("[INTERNAL ERROR] Unexpected grapheme cursor state"),
             }
+        }
+    }
 
-            if skipping_whitespace && !c.is_whitespace() {
-                skipping_whitespace = false;
-                continue;
+    // Gets the index of the grapheme boundary after the given index
+    // * This is based on the grapheme example in the Ropey repository
+    pub fn next_grapheme_boundary(&self, index: usize) -> usize {
+        let byte_index = self.rope.char_to_byte(index.min(self.size()));
+
+        let (mut chunk, mut chunk_byte_index, mut chunk_char_index, _) =
+            self.rope.chunk_at_byte(byte_index);
+        let mut cursor = GraphemeCursor::new(byte_index, self.rope.len_bytes(), true);
+
+        loop {
+            match cursor.next_boundary(chunk, chunk_byte_index) {
+                Ok(None) => return self.size(),
+                Ok(Some(boundary)) => {
+                    return chunk_char_index + byte_to_char_idx(chunk, boundary - chunk_byte_index);
+                }
+                Err(GraphemeIncomplete::NextChunk) => {
+                    chunk_byte_index += chunk.len();
+                    (chunk, _, chunk_char_index, _) = self.rope.chunk_at_byte(chunk_byte_index);
+                }
+                Err(GraphemeIncomplete::PreContext(context_end)) => {
+                    let context = self.rope.chunk_at_byte(context_end - 1).0;
+                    cursor.provide_context(context, context_end - context.len());
+                }
+                Err(_) => unreachable!("[INTERNAL ERROR] Unexpected grapheme cursor state"),
             }
+        }
+    }
 
-            // If the word is the first word in the buffer, return 0
-            if index - (i + 1) == 0 {
-                start_of_word = 0;
-                break;
-            }
+    // Get the index of the start of the current word
+    // If the cursor is preceded by whitespace, the whitespace is skipped first
+    // A word is either a run of word characters or a run of other punctuation (see WordChars)
+    // * This only walks over the characters between the cursor and the start of the word
+    pub fn start_of_word(&self, index: usize) -> usize {
+        let mut start_of_word = index.min(self.size());
+        let mut chars = self.rope.chars_at(start_of_word).reversed().peekable();
 
-            // If the char is whitespace, we have reached the start of the word
-            if !skipping_whitespace && c.is_whitespace() {
-                start_of_word = index - i;
-                break;
-            }
+        // Skip all trailing whitespace
+        while chars.next_if(|c| c.is_whitespace()).is_some() {
+            start_of_word -= 1;
+        }
+
+        // Skip the word itself, stopping at the first character of a different class
+        let class = chars.peek().map(|&c| self.word_chars.class(c));
+        while chars
+            .next_if(|&c| Some(self.word_chars.class(c)) == class)
+            .is_some()
+        {
+            start_of_word -= 1;
         }
 
         start_of_word
     }
 
     // Get the index of the end of the current word
+    // If the cursor is followed by whitespace, the whitespace is skipped first
+    // * This only walks over the characters between the cursor and the end of the word
     pub fn end_of_word(&self, index: usize) -> usize {
         // Make sure the index is valid
         if index > self.size() {
@@ -139,39 +532,403 @@ impl Buffer {
         }
 
         let mut end_of_word = index;
+        let mut chars = self.rope.chars_at(index).peekable();
+
+        // Skip all leading whitespace
+        while chars.next_if(|c| c.is_whitespace()).is_some() {
+            end_of_word += 1;
+        }
+
+        // Skip the word itself, stopping at the first character of a different class
+        let class = chars.peek().map(|&c| self.word_chars.class(c));
+        while chars
+            .next_if(|&c| Some(self.word_chars.class(c)) == class)
+            .is_some()
+        {
+            end_of_word += 1;
+        }
+
+        end_of_word
+    }
+
+    // Gets the range of the word at an index, which is the run of characters of the same class
+    // (see WordChars) around it, without crossing a line break
+    // At the end of a line, the word just before the line break is used instead
+    pub fn word_range(&self, index: usize) -> Range<usize> {
+        let is_line_end = |index: usize| index >= self.size() || self.rope.char(index) == '\n';
+
+        let mut index = index.min(self.size());
+        if is_line_end(index) && index > 0 && !is_line_end(index - 1) {
+            index -= 1;
+        }
+
+        if is_line_end(index) {
+            return index..index;
+        }
+
+        let class = self.word_chars.class(self.rope.char(index));
+        let same_class = |c: &char| *c != '\n' && self.word_chars.class(*c) == class;
+
+        let before = self
+            .rope
+            .chars_at(index)
+            .reversed()
+            .take_while(same_class)
+            .count();
+        let after = self.rope.chars_at(index).take_while(same_class).count();
 
-        // If the cursor starts in the middle of two words, skip the leading whitespace
-        let mut skipping_whitespace = false;
+        index - before..index + after
+    }
+
+    // Get the index of the start of the current subword, which is a part of an identifier
+    // such as a camelCase hump or a piece of a snake_case name
+    // If the cursor is preceded by whitespace, underscores, or hyphens, they are skipped first
+    // [EXAMPLE] from the end of "parseHttpRequest", the start of the subword is the index of "Request"
+    pub fn start_of_subword(&self, index: usize) -> usize {
+        let mut start = index.min(self.size());
+        let mut chars = self.rope.chars_at(start).reversed().peekable();
+
+        while chars
+            .next_if(|&c| subword_class(c) == SubwordClass::Separator)
+            .is_some()
+        {
+            start -= 1;
+        }
+
+        let Some(&last) = chars.peek() else {
+            return start;
+        };
+
+        let class = subword_class(last);
+        while chars.next_if(|&c| subword_class(c) == class).is_some() {
+            start -= 1;
+        }
+
+        // A capital letter directly before a lowercase hump is the start of it
+        if class == SubwordClass::Lower
+            && chars
+                .next_if(|&c| subword_class(c) == SubwordClass::Upper)
+                .is_some()
+        {
+            start -= 1;
+        }
+
+        start
+    }
+
+    // Get the index of the end of the current subword
+    // If the cursor is followed by whitespace, underscores, or hyphens, they are skipped first
+    // * In a run of capitals followed by a lowercase letter, the last capital starts the next subword,
+    // * so "HTTPServer" is split into "HTTP" and "Server"
+    pub fn end_of_subword(&self, index: usize) -> usize {
+        let mut end = index.min(self.size());
+        let mut chars = self.rope.chars_at(end).peekable();
+
+        while chars
+            .next_if(|&c| subword_class(c) == SubwordClass::Separator)
+            .is_some()
+        {
+            end += 1;
+        }
+
+        let Some(&first) = chars.peek() else {
+            return end;
+        };
+
+        let class = subword_class(first);
+        let mut length = 0;
+        while chars.next_if(|&c| subword_class(c) == class).is_some() {
+            end += 1;
+            length += 1;
+        }
+
+        if class == SubwordClass::Upper
+            && chars
+                .peek()
+                .is_some_and(|&c| subword_class(c) == SubwordClass::Lower)
+        {
+            match length {
+                1 => {
+                    while chars
+                        .next_if(|&c| subword_class(c) == SubwordClass::Lower)
+                        .is_some()
+                    {
+                        end += 1;
+                    }
+                }
+                _ => end -= 1,
+            }
+        }
+
+        end
+    }
+
+    // Get the index of the start of the current sentence, or the previous one if the cursor is already
+    // at the start of a sentence
+    // Sentences end with . ! or ? (optionally followed by closing quotes or brackets) and then whitespace,
+    // and a blank line also separates sentences
+    // [EXAMPLE] in "One. Two three.", the start of the sentence from the index of "three" is the index of "Two"
+    pub fn start_of_sentence(&self, index: usize) -> usize {
+        let mut position = index.min(self.size());
+        let mut chars = self.rope.chars_at(position).reversed().peekable();
+
+        // Skip the whitespace before the cursor, so that repeating the motion keeps moving backwards
+        while chars.next_if(|c| c.is_whitespace()).is_some() {
+            position -= 1;
+        }
+
+        loop {
+            // Skip back to the start of the current run of non-whitespace
+            while chars.next_if(|c| !c.is_whitespace()).is_some() {
+                position -= 1;
+            }
+            let start = position;
 
-        for (i, c) in self.rope.chars().enumerate().skip(index) {
-            // Skip all leading whitespace
-            if i == index && c.is_whitespace() {
-                skipping_whitespace = true;
-                continue;
+            // Skip the whitespace before it, counting how many lines it spans
+            let mut newlines = 0;
+            while let Some(c) = chars.next_if(|c| c.is_whitespace()) {
+                position -= 1;
+                newlines += (c == '\n') as usize;
             }
 
-            if skipping_whitespace && !c.is_whitespace() {
-                skipping_whitespace = false;
-                continue;
+            if chars.peek().is_none() || newlines >= 2 {
+                return start;
             }
 
-            // If the char is whitespace, we have reached the end of the word
-            if !skipping_whitespace && c.is_whitespace() {
-                end_of_word = i;
-                break;
+            // The run starts a sentence if the text before the whitespace ends one
+            while chars.next_if(|&c| is_sentence_closer(c)).is_some() {
+                position -= 1;
+            }
+            if chars.peek().is_some_and(|&c| is_sentence_terminator(c)) {
+                return start;
             }
         }
+    }
 
-        end_of_word
+    // Get the index just after the end of the current sentence, or the next one if the cursor is already
+    // at the end of a sentence
+    // [EXAMPLE] in "One. Two three.", the end of the sentence from index 0 is the index after "One."
+    pub fn end_of_sentence(&self, index: usize) -> usize {
+        let mut end = index.min(self.size());
+        let mut chars = self.rope.chars_at(end).peekable();
+
+        // Skip the whitespace after the cursor, so that repeating the motion keeps moving forwards
+        while chars.next_if(|c| c.is_whitespace()).is_some() {
+            end += 1;
+        }
+
+        while let Some(c) = chars.next() {
+            // A blank line ends the sentence, even without punctuation
+            if c == '\n' && chars.peek() == Some(&'\n') {
+                return end;
+            }
+            end += 1;
+
+            if is_sentence_terminator(c) {
+                while chars.next_if(|&c| is_sentence_closer(c)).is_some() {
+                    end += 1;
+                }
+
+                if chars.peek().is_none_or(|c| c.is_whitespace()) {
+                    return end;
+                }
+            }
+        }
+
+        end
+    }
+
+    // Gets the line after the end of the paragraph the given line is in, which is normally the blank
+    // line separating it from the next paragraph, or the last line if it is the last paragraph
+    // Blank lines between the given line and the paragraph are skipped first
+    pub fn next_paragraph(&self, line: usize) -> usize {
+        let last_line = self.line_count() - 1;
+        let mut line = line.min(last_line);
+
+        while line < last_line && self.is_blank_line(line) {
+            line += 1;
+        }
+        while line < last_line && !self.is_blank_line(line) {
+            line += 1;
+        }
+
+        line
+    }
+
+    // Gets the line before the start of the paragraph the given line is in, which is normally the
+    // blank line separating it from the previous paragraph, or the first line if it is the first paragraph
+    // Blank lines between the given line and the paragraph are skipped first
+    pub fn previous_paragraph(&self, line: usize) -> usize {
+        let mut line = line.min(self.line_count() - 1);
+
+        while line > 0 && self.is_blank_line(line) {
+            line -= 1;
+        }
+        while line > 0 && !self.is_blank_line(line) {
+            line -= 1;
+        }
+
+        line
+    }
+
+    // Gets the range of lines making up the paragraph the given line is in
+    // If the line is blank, the range covers the blank lines around it instead
+    pub fn paragraph_lines(&self, line: usize) -> Range<usize> {
+        let blank = self.is_blank_line(line);
+
+        let mut start = line;
+        while start > 0 && self.is_blank_line(start - 1) == blank {
+            start -= 1;
+        }
+
+        let mut end = line + 1;
+        while end < self.line_count() && self.is_blank_line(end) == blank {
+            end += 1;
+        }
+
+        start..end
+    }
+
+    // Checks whether a line is empty or only contains whitespace
+    pub fn is_blank_line(&self, line: usize) -> bool {
+        self.line(line).chars().all(char::is_whitespace)
     }
 
     // Gets a line from the buffer
     // ! THIS WILL CRASH IF THE LINE IS OUT OF BOUNDS
     // TODO: Make this safe to use
-    fn line(&self, line: usize) -> RopeSlice {
+    fn line(&self, line: usize) -> RopeSlice<'_> {
         self.rope.line(line)
     }
 
+    // Gets the contents of a given line, without the trailing newline
+    pub fn line_contents(&self, line: usize) -> String {
+        self.line(line)
+            .to_string()
+            .trim_end_matches('\n')
+            .to_string()
+    }
+
+    // Iterates over the lines of the buffer, including their trailing newlines
+    pub fn lines(&self) -> impl Iterator<Item = RopeSlice<'_>> {
+        self.rope.lines()
+    }
+
+    // Checks whether the buffer ends with a newline
+    pub fn ends_with_newline(&self) -> bool {
+        self.line_len(self.line_count() - 1) == 0 && self.line_count() > 1
+    }
+
+    // Gets the line that a given buffer index is on
+    pub fn line_of(&self, index: usize) -> usize {
+        self.rope.char_to_line(index)
+    }
+
+    // Gets the display width of the leading whitespace on a given line
+    // Lines which contain only whitespace have no indentation, and None is returned
+    pub fn indentation(&self, line: usize) -> Option<usize> {
+        let mut indentation = 0;
+
+        for c in self.line(line).chars() {
+            match c {
+                ' ' | '\t' => indentation += char_width(c, indentation, self.tab_width),
+                '\n' => return None,
+                _ => return Some(indentation),
+            }
+        }
+
+        None
+    }
+
+    // Gets the range of lines in the indentation block containing the given line, which is made up of
+    // the lines around it that are indented at least as far as it is, along with any blank lines
+    // between them (but not at either end of the block)
+    // Blank lines use the indentation of the next line which is not blank
+    pub fn indent_block(&self, line: usize) -> Range<usize> {
+        let level = (line..self.line_count())
+            .find_map(|line| self.indentation(line))
+            .unwrap_or(0);
+        let inside = |line| {
+            self.indentation(line)
+                .is_none_or(|indentation| indentation >= level)
+        };
+
+        let mut start = line;
+        while start > 0 && inside(start - 1) {
+            start -= 1;
+        }
+        while start < line && self.indentation(start).is_none() {
+            start += 1;
+        }
+
+        let mut end = line + 1;
+        while end < self.line_count() && inside(end) {
+            end += 1;
+        }
+        while end > line + 1 && self.indentation(end - 1).is_none() {
+            end -= 1;
+        }
+
+        start..end
+    }
+
+    // Gets the first line of the indentation block containing the given line
+    // If the line is already the first line of its block, the line before the block is returned instead
+    // (skipping blank lines), which is usually the line that opened the block
+    pub fn block_start(&self, line: usize) -> usize {
+        let start = self.indent_block(line).start;
+        if start < line || start == 0 {
+            return start;
+        }
+
+        (0..start)
+            .rev()
+            .find(|&line| self.indentation(line).is_some())
+            .unwrap_or(0)
+    }
+
+    // Gets the last line of the indentation block containing the given line
+    // If the line is already the last line of its block, the line after the block is returned instead
+    // (skipping blank lines)
+    pub fn block_end(&self, line: usize) -> usize {
+        let last = self.indent_block(line).end - 1;
+        if last > line || last + 1 == self.line_count() {
+            return last;
+        }
+
+        (last + 1..self.line_count())
+            .find(|&line| self.indentation(line).is_some())
+            .unwrap_or(self.line_count() - 1)
+    }
+
+    // Gets the buffer index of the first character on a line which is not whitespace,
+    // or the end of the line if it is blank
+    pub fn first_non_whitespace(&self, line: usize) -> usize {
+        let indentation = self
+            .line(line)
+            .chars()
+            .take_while(|&c| c == ' ' || c == '\t')
+            .count();
+
+        self.line_start(line) + indentation
+    }
+
+    // Gets the buffer index of the start of a given line
+    pub fn line_start(&self, line: usize) -> usize {
+        self.rope.line_to_char(line)
+    }
+
+    // Gets the byte offset of the start of a given line
+    pub fn line_start_byte(&self, line: usize) -> usize {
+        self.rope.line_to_byte(line)
+    }
+
+    // Gets the buffer index of the end of a given line, before its line ending
+    pub fn line_end(&self, line: usize) -> usize {
+        let contents = self.line_contents(line);
+        self.line_start(line) + contents.trim_end_matches('\r').chars().count()
+    }
+
     // Gets the length of a given line
     pub fn line_len(&self, line: usize) -> usize {
         self.line(line).len_chars()
@@ -186,4 +943,53 @@ impl Buffer {
     pub fn size(&self) -> usize {
         self.rope.len_chars()
     }
+
+    // Gets the number of bytes the buffer takes up when written to a file
+    pub fn size_bytes(&self) -> usize {
+        self.rope.len_bytes()
+    }
+}
+
+// Represents the kinds of characters which subword motions treat differently
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum SubwordClass {
+    // Whitespace, underscores, and hyphens, which separate subwords
+    Separator,
+    Upper,
+    // Lowercase letters and digits, along with letters which have no case
+    Lower,
+    // Punctuation, with each run of it counting as a subword
+    Other,
+}
+
+// Gets the subword class of a character
+fn subword_class(c: char) -> SubwordClass {
+    match c {
+        c if c.is_whitespace() || c == '_' || c == '-' => SubwordClass::Separator,
+        c if c.is_uppercase() => SubwordClass::Upper,
+        c if c.is_alphanumeric() => SubwordClass::Lower,
+        _ => SubwordClass::Other,
+    }
+}
+
+// Checks whether a character ends a sentence
+fn is_sentence_terminator(c: char) -> bool {
+    matches!(c, '.' | '!' | '?')
+}
+
+// Checks whether a character can come between the end of a sentence and the whitespace after it
+// [EXAMPLE] the quote and bracket in: (He said "hello.")
+fn is_sentence_closer(c: char) -> bool {
+    matches!(c, '"' | '\'' | ')' | ']' | '’' | '”')
+}
+
+// Gets the number of columns a character takes up when displayed at a given display column
+// Tabs extend to the next tab stop, which is a multiple of the tab width
+// Wide characters (such as CJK characters and most emoji) take up two columns,
+// and zero-width characters (such as combining accents) take up none
+pub fn char_width(character: char, display_column: usize, tab_width: usize) -> usize {
+    match character {
+        '\t' => tab_width - display_column % tab_width,
+        _ => character.width().unwrap_or(0),
+    }
 }
@@ -0,0 +1,201 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+// Evaluates an arithmetic expression, returning a description of the problem if it is invalid
+// Supports + - * / % ^ (power), parentheses, unary minus and decimal numbers,
+// with the usual precedence (^ binds tightest and is right-associative)
+// [EXAMPLE] "2 * (3 + 4) ^ 2" -> 98
+pub fn evaluate(expression: &str) -> Result<f64, String> {
+    let mut parser = Parser {
+        chars: expression.chars().peekable(),
+    };
+
+    let value = parser.expression()?;
+    match parser.next_token() {
+        None => Ok(value),
+        Some(c) => Err(format!("Unexpected '{}'", c)),
+    }
+}
+
+// Formats a result without a trailing fraction if it is a whole number
+// * Fractions are rounded to 12 decimal places to hide floating point error
+// [EXAMPLE] 4.0 -> "4", 0.1 + 0.2 -> "0.3"
+pub fn format_result(value: f64) -> String {
+    if value.fract() == 0.0 || !value.is_finite() {
+        return format!("{}", value);
+    }
+
+    let rounded = format!("{:.12}", value);
+    rounded
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+// Represents a recursive descent parser which evaluates the expression as it goes
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    // Parses a sum or difference of terms
+    fn expression(&mut self) -> Result<f64, String> {
+        let mut value = self.term()?;
+
+        loop {
+            match self.peek_token() {
+                Some('+') => {
+                    self.chars.next();
+                    value += self.term()?;
+                }
+                Some('-') => {
+                    self.chars.next();
+                    value -= self.term()?;
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    // Parses a product, quotient or remainder of factors
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.factor()?;
+
+        loop {
+            let operator = match self.peek_token() {
+                Some(operator @ ('*' | '/' | '%')) => operator,
+                _ => return Ok(value),
+            };
+            self.chars.next();
+
+            let rhs = self.factor()?;
+            value = match operator {
+                '*' => value * rhs,
+                _ if rhs == 0.0 => return Err("Division by zero".to_string()),
+                '/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+    }
+
+    // Parses a negated value or a power, which is right-associative, so 2^3^2 is 2^9
+    // * Negation applies after powers, so -3^2 is -9 as it is in maths
+    fn factor(&mut self) -> Result<f64, String> {
+        match self.peek_token() {
+            Some('-') => {
+                self.chars.next();
+                return Ok(-self.factor()?);
+            }
+            Some('+') => {
+                self.chars.next();
+                return self.factor();
+            }
+            _ => (),
+        }
+
+        let base = self.primary()?;
+        match self.peek_token() {
+            Some('^') => {
+                self.chars.next();
+                Ok(base.powf(self.factor()?))
+            }
+            _ => Ok(base),
+        }
+    }
+
+    // Parses a parenthesised value or a number
+    fn primary(&mut self) -> Result<f64, String> {
+        match self.peek_token() {
+            Some('(') => {
+                self.chars.next();
+                let value = self.expression()?;
+                match self.next_token() {
+                    Some(')') => Ok(value),
+                    _ => Err("Missing ')'".to_string()),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) => Err(format!("Unexpected '{}'", c)),
+            None => Err("The expression is incomplete".to_string()),
+        }
+    }
+
+    // Parses a decimal number, which may contain underscores or commas as digit separators
+    fn number(&mut self) -> Result<f64, String> {
+        let mut digits = String::new();
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                '0'..='9' | '.' => digits.push(c),
+                '_' | ',' => (),
+                _ => break,
+            }
+            self.chars.next();
+        }
+
+        digits
+            .parse()
+            .map_err(|_| format!("'{}' is not a number", digits))
+    }
+
+    // Gets the next character which is not whitespace without consuming it
+    fn peek_token(&mut self) -> Option<char> {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        self.chars.peek().copied()
+    }
+
+    // Consumes the next character which is not whitespace
+    fn next_token(&mut self) -> Option<char> {
+        self.peek_token();
+        self.chars.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_precedence() {
+        assert_eq!(evaluate("1 + 2 * 3"), Ok(7.0));
+        assert_eq!(evaluate("(1 + 2) * 3"), Ok(9.0));
+        assert_eq!(evaluate("10 - 4 - 3"), Ok(3.0));
+        assert_eq!(evaluate("12 / 3 / 2"), Ok(2.0));
+        assert_eq!(evaluate("7 % 4 * 2"), Ok(6.0));
+        assert_eq!(evaluate("2 * (3 + 4) ^ 2"), Ok(98.0));
+    }
+
+    #[test]
+    fn evaluates_powers_right_to_left() {
+        assert_eq!(evaluate("2 ^ 3 ^ 2"), Ok(512.0));
+        assert_eq!(evaluate("(2 ^ 3) ^ 2"), Ok(64.0));
+        assert_eq!(evaluate("-3 ^ 2"), Ok(-9.0));
+        assert_eq!(evaluate("2 ^ -1"), Ok(0.5));
+    }
+
+    #[test]
+    fn reads_numbers_with_separators() {
+        assert_eq!(evaluate("1,000 + 1_000 + .5"), Ok(2000.5));
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(evaluate("1 / 0"), Err("Division by zero".to_string()));
+        assert_eq!(evaluate("(1 + 2"), Err("Missing ')'".to_string()));
+        assert_eq!(
+            evaluate("1 +"),
+            Err("The expression is incomplete".to_string())
+        );
+        assert_eq!(evaluate("1 2"), Err("Unexpected '2'".to_string()));
+        assert_eq!(
+            evaluate("1.2.3"),
+            Err("'1.2.3' is not a number".to_string())
+        );
+    }
+
+    #[test]
+    fn formats_results() {
+        assert_eq!(format_result(4.0), "4");
+        assert_eq!(format_result(0.1 + 0.2), "0.3");
+        assert_eq!(format_result(-2.5), "-2.5");
+    }
+}
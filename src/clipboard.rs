@@ -0,0 +1,34 @@
+// The characters used by Base64, in order of the values they represent
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Builds the OSC 52 escape sequence which asks the terminal to put text on the system clipboard
+// * The terminal emulator does the copying, so this works over SSH and without X11/Wayland access,
+// * as long as the terminal supports it (some require it to be enabled in their settings)
+pub fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))
+}
+
+// Encodes bytes as standard Base64, with padding
+pub fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        // Pack the chunk into 24 bits, then split it into four 6-bit values
+        let packed = chunk.iter().enumerate().fold(0u32, |packed, (i, &byte)| {
+            packed | (byte as u32) << (16 - i * 8)
+        });
+
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => {
+                    let value = (packed >> (18 - i * 6)) & 0b111111;
+                    encoded.push(BASE64_ALPHABET[value as usize] as char);
+                }
+                false => encoded.push('='),
+            }
+        }
+    }
+
+    encoded
+}
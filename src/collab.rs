@@ -0,0 +1,449 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::crdt::{CharId, Document, Element, Operation, TextChange};
+use crate::runtime;
+use crate::Edit;
+
+// The site number of the host, which also owns the characters that were in the file originally
+const HOST_SITE: u32 = 0;
+
+// Represents a message received from the network by one of the session's background readers
+enum Incoming {
+    // A guest has connected to the host, on the socket for followers if it is read-only
+    Connected(TcpStream, bool),
+    // The host has assigned this guest its site number and sent the current document,
+    // along with whether the guest may only follow the session
+    Welcome(u32, Vec<Element>, bool),
+    // Each message is tagged with the site of the connection it arrived on
+    Operation(u32, Operation),
+    Cursor(u32, u32, Option<CharId>),
+    Disconnected(u32),
+}
+
+// Represents a change to the editor's buffer caused by the session
+pub enum RemoteChange {
+    Edit(Edit),
+    // The whole buffer has been replaced, such as when joining a session
+    Reset(String),
+    // The host has only let this guest follow the session, so the buffer must not be edited
+    Follow,
+    Message(String),
+}
+
+// Represents a connection to another site
+struct Peer {
+    site: u32,
+    stream: TcpStream,
+    // Whether the peer connected on the socket for followers, in which case anything it sends is ignored
+    read_only: bool,
+}
+
+// Represents a collaborative editing session, where the buffer is shared with other rut instances
+// The host accepts connections from guests and relays every change to all of them,
+// so guests only ever talk to the host
+pub struct Session {
+    document: Document,
+    peers: Vec<Peer>,
+    sender: Sender<Incoming>,
+    receiver: Receiver<Incoming>,
+    // The site number assigned to the next guest, which is only used by the host
+    next_site: u32,
+    // The position of each remote site's cursor, anchored to the character before it
+    cursors: BTreeMap<u32, Option<CharId>>,
+    // The anchor of the local cursor that was last sent to the other sites
+    sent_cursor: Option<Option<CharId>>,
+    // Whether the document has been received from the host, which is always true for the host itself
+    joined: bool,
+}
+
+impl Session {
+    // Starts hosting a session sharing the given text, where guests connecting to the first address
+    // can edit it and guests connecting to the second address can only follow along
+    // ! Anyone who can reach the first address can read and edit the buffer, so it should only be on
+    // ! another interface than 127.0.0.1 if everyone on that network is trusted
+    pub fn host(
+        address: Option<&str>,
+        follow_address: Option<&str>,
+        text: &str,
+    ) -> io::Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+
+        // Both sockets are bound before either starts accepting, so a failure leaves nothing behind
+        let listener = address.map(TcpListener::bind).transpose()?;
+        let follow_listener = follow_address.map(TcpListener::bind).transpose()?;
+
+        if let Some(listener) = listener {
+            spawn_listener(listener, false, sender.clone());
+        }
+        if let Some(listener) = follow_listener {
+            spawn_listener(listener, true, sender.clone());
+        }
+
+        Ok(Self {
+            document: Document::new(HOST_SITE, text),
+            peers: Vec::new(),
+            sender,
+            receiver,
+            next_site: HOST_SITE + 1,
+            cursors: BTreeMap::new(),
+            sent_cursor: None,
+            joined: true,
+        })
+    }
+
+    // Joins the session hosted at the given address
+    // * The buffer is replaced once the host has sent its document
+    pub fn join(address: &str) -> io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+
+        let (sender, receiver) = mpsc::channel();
+        spawn_reader(stream.try_clone()?, HOST_SITE, sender.clone());
+
+        Ok(Self {
+            // Until the host has responded, the guest has an empty placeholder document
+            document: Document::new(HOST_SITE, ""),
+            peers: vec![Peer {
+                site: HOST_SITE,
+                stream,
+                read_only: false,
+            }],
+            sender,
+            receiver,
+            next_site: HOST_SITE + 1,
+            cursors: BTreeMap::new(),
+            sent_cursor: None,
+            joined: false,
+        })
+    }
+
+    // Applies an edit made to the local buffer and sends it to the other sites
+    // * Edits made before joining are not sent, because the buffer is replaced once the document arrives
+    pub fn local_edit(&mut self, edit: &Edit) {
+        if !self.joined {
+            return;
+        }
+
+        let operations = match edit {
+            Edit::Insert { index, text } => self.document.insert(*index, text),
+            Edit::Delete { index, text } => {
+                self.document.delete(*index..*index + text.chars().count())
+            }
+        };
+
+        for operation in operations {
+            self.broadcast(&encode_operation(&operation), None);
+        }
+    }
+
+    // Sends the position of the local cursor to the other sites, if it has moved
+    pub fn local_cursor(&mut self, index: usize) {
+        if !self.joined {
+            return;
+        }
+
+        let anchor = match index {
+            0 => None,
+            _ => self.document.visible_id(index - 1),
+        };
+
+        if self.sent_cursor != Some(anchor) {
+            self.sent_cursor = Some(anchor);
+            self.broadcast(&encode_cursor(self.site(), anchor), None);
+        }
+    }
+
+    // Gets the buffer indices of the remote cursors, along with the site each belongs to
+    pub fn remote_cursors(&self) -> Vec<(u32, usize)> {
+        self.cursors
+            .iter()
+            .map(|(&site, &anchor)| (site, self.document.index_after(anchor)))
+            .collect()
+    }
+
+    // Handles any messages received from the network since the last poll, without blocking
+    pub fn poll(&mut self) -> Vec<RemoteChange> {
+        let mut changes = Vec::new();
+
+        while let Ok(incoming) = self.receiver.try_recv() {
+            match incoming {
+                Incoming::Connected(stream, read_only) => {
+                    if let Err(error) = self.welcome(stream, read_only) {
+                        changes.push(RemoteChange::Message(format!(
+                            "A guest failed to join: {}",
+                            error
+                        )));
+                    }
+                }
+                // Only a guest which is still waiting for the document takes it, so a guest cannot
+                // replace the host's document, and the host cannot replace it twice
+                Incoming::Welcome(..) if self.joined => {}
+                Incoming::Welcome(site, elements, read_only) => {
+                    self.document = Document::from_elements(site, elements);
+                    self.sent_cursor = None;
+                    self.joined = true;
+                    changes.push(RemoteChange::Reset(self.document.text()));
+
+                    if read_only {
+                        changes.push(RemoteChange::Follow);
+                        changes.push(RemoteChange::Message("Following the session".to_string()));
+                    } else {
+                        changes.push(RemoteChange::Message("Joined the session".to_string()));
+                    }
+                }
+                Incoming::Operation(from, _) | Incoming::Cursor(from, _, _)
+                    if self.is_read_only(from) => {}
+                Incoming::Operation(from, operation) => {
+                    self.broadcast(&encode_operation(&operation), Some(from));
+
+                    match self.document.apply(&operation) {
+                        Some(TextChange::Insert { index, character }) => {
+                            changes.push(RemoteChange::Edit(Edit::Insert {
+                                index,
+                                text: character.to_string(),
+                            }))
+                        }
+                        Some(TextChange::Delete { index, character }) => {
+                            changes.push(RemoteChange::Edit(Edit::Delete {
+                                index,
+                                text: character.to_string(),
+                            }))
+                        }
+                        None => (),
+                    }
+                }
+                Incoming::Cursor(from, site, anchor) => {
+                    self.broadcast(&encode_cursor(site, anchor), Some(from));
+                    self.cursors.insert(site, anchor);
+                }
+                Incoming::Disconnected(site) => {
+                    self.peers.retain(|peer| peer.site != site);
+                    self.cursors.remove(&site);
+
+                    let message = match site {
+                        HOST_SITE => "Disconnected from the session".to_string(),
+                        _ => format!("Guest {} left the session", site),
+                    };
+                    changes.push(RemoteChange::Message(message));
+                }
+            }
+        }
+
+        changes
+    }
+
+    // Gets the buffer index of the host's cursor, if it is known
+    pub fn host_cursor(&self) -> Option<usize> {
+        self.cursors
+            .get(&HOST_SITE)
+            .map(|&anchor| self.document.index_after(anchor))
+    }
+
+    // Checks whether a peer connected on the socket for followers
+    fn is_read_only(&self, site: u32) -> bool {
+        self.peers
+            .iter()
+            .any(|peer| peer.site == site && peer.read_only)
+    }
+
+    // Sets up a new guest by assigning it a site number and sending it the current document
+    fn welcome(&mut self, mut stream: TcpStream, read_only: bool) -> io::Result<()> {
+        stream.set_nodelay(true)?;
+
+        let site = self.next_site;
+        self.next_site += 1;
+
+        // The whole document is sent at once, including deleted characters that may still be referred to
+        let elements = self.document.elements();
+        let mut message = format!("welcome {} {} {}\n", site, elements.len(), read_only as u8);
+        for element in elements {
+            message.push_str(&format!(
+                "{} {} {} {}\n",
+                element.id.counter,
+                element.id.site,
+                element.deleted as u8,
+                element.character as u32
+            ));
+        }
+
+        // Let the new guest know where everyone's cursor is
+        if let Some(anchor) = self.sent_cursor {
+            message.push_str(&encode_cursor(HOST_SITE, anchor));
+        }
+
+        for (&cursor_site, &anchor) in self.cursors.iter() {
+            message.push_str(&encode_cursor(cursor_site, anchor));
+        }
+
+        stream.write_all(message.as_bytes())?;
+
+        spawn_reader(stream.try_clone()?, site, self.sender.clone());
+        self.peers.push(Peer {
+            site,
+            stream,
+            read_only,
+        });
+
+        Ok(())
+    }
+
+    // Sends a message to every connected site, except for the one it came from
+    // Sites which can no longer be written to are dropped, and their reader reports the disconnection
+    fn broadcast(&mut self, message: &str, except: Option<u32>) {
+        self.peers.retain_mut(|peer| {
+            Some(peer.site) == except || peer.stream.write_all(message.as_bytes()).is_ok()
+        });
+    }
+
+    // Gets the site number of this instance
+    fn site(&self) -> u32 {
+        self.document.site()
+    }
+}
+
+// Accepts connections in the background, handing them to the editor to be set up
+fn spawn_listener(listener: TcpListener, read_only: bool, sender: Sender<Incoming>) {
+    runtime().spawn_blocking(move || {
+        for stream in listener.incoming().flatten() {
+            if sender.send(Incoming::Connected(stream, read_only)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+// Reads messages from a connection in the background, passing them to the editor
+fn spawn_reader(stream: TcpStream, site: u32, sender: Sender<Incoming>) {
+    runtime().spawn_blocking(move || {
+        let mut lines = BufReader::new(stream).lines();
+
+        while let Some(Ok(line)) = lines.next() {
+            let Some(incoming) = decode(&line, site, &mut lines) else {
+                continue;
+            };
+
+            if sender.send(incoming).is_err() {
+                return;
+            }
+        }
+
+        let _ = sender.send(Incoming::Disconnected(site));
+    });
+}
+
+// Parses a message from a connection, reading any extra lines that belong to it
+// Malformed messages are ignored
+// [EXAMPLE]
+// insert 12 1 11:1 97   (site 1 inserted 'a' with ID 12:1 after the character with ID 11:1)
+// delete 12 1           (the character with ID 12:1 was deleted)
+// cursor 1 -            (site 1's cursor is at the start of the document)
+fn decode(
+    line: &str,
+    from: u32,
+    lines: &mut impl Iterator<Item = io::Result<String>>,
+) -> Option<Incoming> {
+    let mut words = line.split_whitespace();
+
+    match words.next()? {
+        // * Only the host sends the document, so it is ignored from anyone else
+        // * The count is not used to allocate the elements up front, since it comes off the network
+        "welcome" if from == HOST_SITE => {
+            let site = words.next()?.parse().ok()?;
+            let count: usize = words.next()?.parse().ok()?;
+            let read_only = words.next()? == "1";
+
+            let mut elements = Vec::new();
+            for _ in 0..count {
+                let line = lines.next()?.ok()?;
+                let mut words = line.split_whitespace();
+
+                elements.push(Element {
+                    id: decode_id(words.next()?, words.next()?)?,
+                    deleted: words.next()? == "1",
+                    character: char::from_u32(words.next()?.parse().ok()?)?,
+                });
+            }
+
+            Some(Incoming::Welcome(site, elements, read_only))
+        }
+        "insert" => {
+            let id = decode_id(words.next()?, words.next()?)?;
+            let origin = decode_anchor(words.next()?)?;
+            let character = char::from_u32(words.next()?.parse().ok()?)?;
+
+            Some(Incoming::Operation(
+                from,
+                Operation::Insert {
+                    id,
+                    origin,
+                    character,
+                },
+            ))
+        }
+        "delete" => {
+            let id = decode_id(words.next()?, words.next()?)?;
+
+            Some(Incoming::Operation(from, Operation::Delete { id }))
+        }
+        "cursor" => {
+            let site = words.next()?.parse().ok()?;
+            let anchor = decode_anchor(words.next()?)?;
+
+            Some(Incoming::Cursor(from, site, anchor))
+        }
+        _ => None,
+    }
+}
+
+// Parses a character ID from its counter and site
+fn decode_id(counter: &str, site: &str) -> Option<CharId> {
+    Some(CharId {
+        counter: counter.parse().ok()?,
+        site: site.parse().ok()?,
+    })
+}
+
+// Parses an optional character ID, which is written as "counter:site" or "-" if there is none
+fn decode_anchor(anchor: &str) -> Option<Option<CharId>> {
+    match anchor {
+        "-" => Some(None),
+        _ => {
+            let (counter, site) = anchor.split_once(':')?;
+            Some(Some(decode_id(counter, site)?))
+        }
+    }
+}
+
+// Writes an optional character ID in the format read by decode_anchor
+fn encode_anchor(anchor: Option<CharId>) -> String {
+    match anchor {
+        Some(id) => format!("{}:{}", id.counter, id.site),
+        None => "-".to_string(),
+    }
+}
+
+// Writes an operation in the format read by decode
+fn encode_operation(operation: &Operation) -> String {
+    match operation {
+        Operation::Insert {
+            id,
+            origin,
+            character,
+        } => format!(
+            "insert {} {} {} {}\n",
+            id.counter,
+            id.site,
+            encode_anchor(*origin),
+            *character as u32
+        ),
+        Operation::Delete { id } => format!("delete {} {}\n", id.counter, id.site),
+    }
+}
+
+// Writes a cursor position in the format read by decode
+fn encode_cursor(site: u32, anchor: Option<CharId>) -> String {
+    format!("cursor {} {}\n", site, encode_anchor(anchor))
+}
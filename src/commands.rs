@@ -0,0 +1,143 @@
+use crate::completion::complete_path;
+
+// Represents a command typed into the command line, in the style of Vim's ex commands
+pub enum Command {
+    // Saves the file
+    Write,
+    // Saves every open file which has unsaved changes
+    WriteAll,
+    // Quits, asking about each buffer with unsaved changes first unless it is forced
+    Quit { force: bool },
+    // Saves the file and then quits
+    WriteQuit,
+    // Opens another file in a new buffer, or browses a directory
+    Edit { path: String },
+    // Switches to the next or previous open file
+    NextBuffer,
+    PreviousBuffer,
+    // Switches to an open file, given by its number in the list of buffers or part of its name
+    Buffer(String),
+    // Lists the open files
+    ListBuffers,
+    // Switches to a scratch buffer with a name, which is never saved, opening it if it is not open yet
+    Scratch(String),
+    // Closes the file being edited, which unsaved changes prevent unless it is forced
+    CloseBuffer { force: bool },
+    // Moves the cursor to a line, counting from one
+    GoToLine(usize),
+    // Stops the editor and returns to the shell, until it is continued with `fg`
+    Suspend,
+}
+
+// Builds a command from its argument and whether it was forced with a !, or describes what is wrong with them
+type CommandParser = fn(&str, bool) -> Result<Command, String>;
+
+// Every command which can be typed, with each of the names it can be typed as
+// * Adding a command only requires adding it here and handling it in Editor::run_command
+const COMMANDS: &[(&[&str], CommandParser)] = &[
+    (&["w", "write"], |argument, _| {
+        no_argument(argument)?;
+        Ok(Command::Write)
+    }),
+    (&["wa", "wall"], |argument, _| {
+        no_argument(argument)?;
+        Ok(Command::WriteAll)
+    }),
+    (&["q", "quit", "qa", "qall"], |argument, force| {
+        no_argument(argument)?;
+        Ok(Command::Quit { force })
+    }),
+    (&["wq", "x"], |argument, _| {
+        no_argument(argument)?;
+        Ok(Command::WriteQuit)
+    }),
+    (&["e", "edit"], |argument, _| match argument {
+        "" => Err("No file name given".to_string()),
+        path => Ok(Command::Edit {
+            path: path.to_string(),
+        }),
+    }),
+    (&["bn", "bnext"], |argument, _| {
+        no_argument(argument)?;
+        Ok(Command::NextBuffer)
+    }),
+    (&["bp", "bprevious"], |argument, _| {
+        no_argument(argument)?;
+        Ok(Command::PreviousBuffer)
+    }),
+    (&["b", "buffer"], |argument, _| match argument {
+        "" => Err("No buffer given".to_string()),
+        name => Ok(Command::Buffer(name.to_string())),
+    }),
+    (&["ls", "buffers"], |argument, _| {
+        no_argument(argument)?;
+        Ok(Command::ListBuffers)
+    }),
+    (&["bd", "bdelete"], |argument, force| {
+        no_argument(argument)?;
+        Ok(Command::CloseBuffer { force })
+    }),
+    (&["scratch"], |argument, _| match argument {
+        "" => Ok(Command::Scratch("scratch".to_string())),
+        name => Ok(Command::Scratch(name.to_string())),
+    }),
+    (&["sus", "suspend", "st", "stop"], |argument, _| {
+        no_argument(argument)?;
+        Ok(Command::Suspend)
+    }),
+];
+
+// The commands whose argument is a file path, which can be completed with Tab
+const PATH_COMMANDS: &[&str] = &["e", "edit"];
+
+// Parses a command typed into the command line, with or without the leading colon
+// A command is a name, optionally followed by ! to force it and then an argument,
+// and a number on its own moves to that line
+// [EXAMPLE] "wq", ":q!", "e src/main.rs", "42"
+pub fn parse(input: &str) -> Result<Command, String> {
+    let input = input.trim().trim_start_matches(':');
+    if let Ok(line) = input.parse() {
+        return Ok(Command::GoToLine(line));
+    }
+
+    let (name, argument) = split_command(input);
+    let (name, force) = match name.strip_suffix('!') {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+
+    let (_, parser) = COMMANDS
+        .iter()
+        .find(|(names, _)| names.contains(&name))
+        .ok_or_else(|| format!("Unknown command: {}", name))?;
+
+    parser(argument, force)
+}
+
+// Completes the file path typed as the argument of a command which takes one,
+// returning None if the command does not take a path or nothing matches
+// [EXAMPLE] "e src/ma" -> "e src/main.rs" (if that is the only match)
+pub fn complete(input: &str) -> Option<String> {
+    let (name, argument) = split_command(input);
+    if !PATH_COMMANDS.contains(&name.trim_start_matches(':').trim_end_matches('!')) {
+        return None;
+    }
+
+    Some(format!("{} {}", name, complete_path(argument)?))
+}
+
+// Splits a command into its name and its argument, which is empty if there is none
+fn split_command(input: &str) -> (&str, &str) {
+    match input.trim_start().split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, argument.trim()),
+        None => (input.trim(), ""),
+    }
+}
+
+// Checks that a command which takes no argument was not given one
+fn no_argument(argument: &str) -> Result<(), String> {
+    match argument {
+        "" => Ok(()),
+        _ => Err(format!("Unexpected argument: {}", argument)),
+    }
+}
@@ -0,0 +1,82 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ignore::WalkBuilder;
+
+// Completes a partially typed file path as far as it can be completed unambiguously,
+// returning None if nothing matches
+// If exactly one directory matches, a slash is added so the next part of the path can be completed
+// [EXAMPLE] "src/ma" -> "src/main.rs" (if that is the only match)
+pub fn complete_path(input: &str) -> Option<String> {
+    // Split the input into the directory to look in and the start of the name to complete
+    let (directory, prefix) = match input.rfind('/') {
+        Some(slash) => (&input[..=slash], &input[slash + 1..]),
+        None => ("", input),
+    };
+
+    let search_directory = match directory {
+        "" => Path::new("."),
+        directory => Path::new(directory),
+    };
+
+    // Hidden files are only completed if the user has started typing their name, and ignored files
+    // are never completed
+    let matches: Vec<(String, bool)> =
+        list_directory(search_directory, prefix.starts_with('.'), false)
+            .ok()?
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(prefix))
+            .collect();
+
+    let completed = match matches.as_slice() {
+        [] => return None,
+        [(name, true)] => format!("{}/", name),
+        [(name, false)] => name.clone(),
+        [(first, _), rest @ ..] => rest.iter().fold(first.clone(), |common, (name, _)| {
+            common_prefix(&common, name)
+        }),
+    };
+
+    Some(format!("{}{}", directory, completed))
+}
+
+// Lists the names of the entries in a directory, along with whether each of them is a directory
+// Hidden files and files matched by .gitignore or .ignore rules are left out unless they are asked for
+// * .gitignore rules only apply inside a git repository, the same as they do for git itself
+pub fn list_directory(
+    directory: &Path,
+    show_hidden: bool,
+    show_ignored: bool,
+) -> io::Result<Vec<(String, bool)>> {
+    // A directory which cannot be read is reported as it is, rather than being listed as empty
+    fs::read_dir(directory)?;
+
+    let entries = WalkBuilder::new(directory)
+        .max_depth(Some(1))
+        .hidden(!show_hidden)
+        .ignore(!show_ignored)
+        .git_ignore(!show_ignored)
+        .git_global(!show_ignored)
+        .git_exclude(!show_ignored)
+        .build()
+        .filter_map(|entry| {
+            let entry = entry.ok().filter(|entry| entry.depth() > 0)?;
+            let name = entry.file_name().to_str()?.to_string();
+
+            // Symbolic links to directories count as directories
+            Some((name, entry.path().is_dir()))
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+// Gets the longest string that both strings start with
+fn common_prefix(a: &str, b: &str) -> String {
+    a.chars()
+        .zip(b.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c)
+        .collect()
+}
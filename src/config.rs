@@ -0,0 +1,324 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::actions::Action;
+use crate::dap::DebugConfig;
+use crate::json::{self, Json};
+use crate::keymap::{parse_key, Key, Keymap, KeymapPreset};
+use crate::statusline::{StatusLine, TabLine};
+use crate::theme::Theme;
+use crate::toml::{self, Entry, Value};
+use crate::words::WordChars;
+use crate::TaskDefinition;
+use crate::{LineNumbers, DEFAULT_DATE_FORMAT, DEFAULT_TAB_WIDTH, DEFAULT_ZEN_WIDTH};
+
+// The options read from the config file, with defaults for anything it does not set
+// Options given on the command line take the place of the ones in the file
+// [EXAMPLE]
+// tab-width = 4
+// line-numbers = "relative"
+// soft-wrap = true
+// current-line-highlight = false
+// ruler = 100
+// scrolloff = 3
+// word-chars = "_-"
+// zen-width = 72
+// autosave = 30
+// keymap = "emacs"
+// theme = "light"
+// date-format = "%d/%m/%Y"
+// status-line = "modified,file:cyan,message|branch:green,position"
+// tab-line = "index,path:cyan,modified"
+// icons = true
+//
+// [keys]
+// "ctrl+e" = "line-end"
+//
+// [tasks]
+// build = "cargo build"
+//
+// [debug]
+// adapter = "lldb-dap"
+// launch = '{"program": "target/debug/app", "args": []}'
+pub struct Config {
+    // The number of columns a tab character takes up
+    pub tab_width: usize,
+    // Whether line numbers are shown next to the text, and how they are counted
+    pub line_numbers: Option<LineNumbers>,
+    // Whether long lines are wrapped onto the next row of the screen
+    pub soft_wrap: bool,
+    // Whether the background of the line the cursor is on is highlighted
+    pub current_line_highlight: bool,
+    // The column that the ruler is drawn at, if there is one
+    pub ruler: Option<u16>,
+    // The minimum number of lines kept visible above and below the cursor
+    pub scrolloff: usize,
+    // Which punctuation counts as part of words, instead of the default for each file's type
+    pub word_chars: Option<WordChars>,
+    // The width of the text column when zen mode is turned on
+    pub zen_width: u16,
+    // How long the buffer has to go unchanged before it is saved automatically, if it is at all
+    pub autosave: Option<Duration>,
+    // The preset which the key bindings start from
+    pub keymap: KeymapPreset,
+    // The keys bound on top of the preset, which bind keys to the identifiers of actions
+    pub keys: Vec<(Key, Action)>,
+    // The styles that everything on the screen is drawn in, which is a built-in theme or a custom one
+    pub theme: Theme,
+    // The strftime-style format used by F5 to insert the date and time
+    pub date_format: String,
+    // Which segments the status bar shows on each side, and their colors
+    pub status_line: StatusLine,
+    // Which segments each buffer's entry in the tab line shows, and their colors
+    pub tab_line: TabLine,
+    // Whether Nerd Font icons are shown next to file names, which needs a patched font
+    pub icons: bool,
+    // The tasks which can be run from the tasks panel, which tasks given on the command line add to
+    pub tasks: Vec<TaskDefinition>,
+    // The debug adapter which debugging starts, if one is set up
+    pub debugger: Option<DebugConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tab_width: DEFAULT_TAB_WIDTH,
+            line_numbers: None,
+            soft_wrap: false,
+            current_line_highlight: true,
+            ruler: None,
+            scrolloff: 0,
+            word_chars: None,
+            zen_width: DEFAULT_ZEN_WIDTH,
+            autosave: None,
+            keymap: KeymapPreset::Default,
+            keys: Vec::new(),
+            theme: Theme::default(),
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            status_line: StatusLine::default(),
+            tab_line: TabLine::default(),
+            icons: false,
+            tasks: Vec::new(),
+            debugger: None,
+        }
+    }
+}
+
+impl Config {
+    // Reads the config file, using the defaults if there is none
+    // ! Any mistake in the file is returned as an error, so that it is not silently ignored
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = config_path() else {
+            return Ok(Self::default());
+        };
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(format!("{}: {}", path.display(), error)),
+        };
+
+        Self::parse(&text).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    // Parses the contents of a config file, describing the first thing wrong with it
+    fn parse(text: &str) -> Result<Self, String> {
+        let document = toml::parse(text)?;
+        if let Some(table) = document
+            .table_names()
+            .find(|&name| !["keys", "tasks", "debug"].contains(&name))
+        {
+            return Err(format!("Unknown table: [{}]", table));
+        }
+
+        let mut config = Self::default();
+        for entry in document.table("") {
+            config
+                .set_option(entry)
+                .map_err(|error| format!("line {}: {}", entry.line, error))?;
+        }
+
+        for entry in document.table("keys") {
+            let binding = match &entry.value {
+                Value::String(action) => {
+                    parse_key(&entry.key).and_then(|key| Ok((key, action.parse()?)))
+                }
+                other => Err(format!(
+                    "Expected an action for {}, found {}",
+                    entry.key, other
+                )),
+            };
+
+            let binding = binding.map_err(|error| format!("line {}: {}", entry.line, error))?;
+            config.keys.push(binding);
+        }
+
+        // Each task is named by its key, in the same way as name=command on the command line
+        for entry in document.table("tasks") {
+            let task = match &entry.value {
+                Value::String(command) if command.trim().is_empty() => {
+                    Err(format!("{} has no command", entry.key))
+                }
+                Value::String(command) => Ok(TaskDefinition {
+                    name: entry.key.clone(),
+                    command: command.trim().to_string(),
+                }),
+                other => Err(format!(
+                    "Expected a command for {}, found {}",
+                    entry.key, other
+                )),
+            };
+
+            let task = task.map_err(|error| format!("line {}: {}", entry.line, error))?;
+            config.tasks.push(task);
+        }
+
+        let debug = document.table("debug");
+        if !debug.is_empty() {
+            config.debugger = Some(parse_debug_config(debug)?);
+        }
+
+        Ok(config)
+    }
+
+    // Sets one of the options at the top of the config file
+    fn set_option(&mut self, entry: &Entry) -> Result<(), String> {
+        let invalid = || format!("Invalid value for {}", entry.key);
+
+        match (entry.key.as_str(), &entry.value) {
+            ("tab-width", &Value::Integer(width)) => {
+                self.tab_width = usize::try_from(width)
+                    .ok()
+                    .filter(|&width| width > 0)
+                    .ok_or_else(invalid)?;
+            }
+            ("line-numbers", Value::String(line_numbers)) => {
+                self.line_numbers = match line_numbers.as_str() {
+                    "absolute" => Some(LineNumbers::Absolute),
+                    "relative" => Some(LineNumbers::Relative),
+                    "off" => None,
+                    _ => return Err(invalid()),
+                };
+            }
+            ("line-numbers", &Value::Boolean(shown)) => {
+                self.line_numbers = shown.then_some(LineNumbers::Absolute);
+            }
+            ("soft-wrap", &Value::Boolean(soft_wrap)) => self.soft_wrap = soft_wrap,
+            ("current-line-highlight", &Value::Boolean(enabled)) => {
+                self.current_line_highlight = enabled;
+            }
+            // Zero turns the ruler off, the same as leaving it out
+            ("ruler", &Value::Integer(column)) => {
+                let column = u16::try_from(column).map_err(|_| invalid())?;
+                self.ruler = (column > 0).then_some(column);
+            }
+            ("scrolloff", &Value::Integer(lines)) => {
+                self.scrolloff = usize::try_from(lines).map_err(|_| invalid())?;
+            }
+            ("word-chars", Value::String(chars)) => self.word_chars = Some(WordChars::new(chars)),
+            ("zen-width", &Value::Integer(width)) => {
+                self.zen_width = u16::try_from(width)
+                    .ok()
+                    .filter(|&width| width > 0)
+                    .ok_or_else(invalid)?;
+            }
+            // The interval is in seconds, and zero turns autosaving off
+            ("autosave", &Value::Integer(seconds)) => {
+                let seconds = u64::try_from(seconds).map_err(|_| invalid())?;
+                self.autosave = (seconds > 0).then(|| Duration::from_secs(seconds));
+            }
+            ("keymap", Value::String(preset)) => {
+                self.keymap = preset.parse().map_err(|_| invalid())?;
+            }
+            ("theme", Value::String(name)) => self.theme = Theme::load(name)?,
+            ("date-format", Value::String(format)) => self.date_format = format.clone(),
+            ("status-line", Value::String(layout)) => {
+                self.status_line = layout.parse().map_err(|_| invalid())?;
+            }
+            ("tab-line", Value::String(layout)) => {
+                self.tab_line = layout.parse().map_err(|_| invalid())?;
+            }
+            ("icons", &Value::Boolean(icons)) => self.icons = icons,
+            (
+                "tab-width"
+                | "line-numbers"
+                | "soft-wrap"
+                | "current-line-highlight"
+                | "ruler"
+                | "scrolloff"
+                | "word-chars"
+                | "zen-width"
+                | "autosave"
+                | "keymap"
+                | "theme"
+                | "date-format"
+                | "status-line"
+                | "tab-line"
+                | "icons",
+                other,
+            ) => {
+                return Err(format!("{} cannot be {}", entry.key, other));
+            }
+            (key, _) => return Err(format!("Unknown option: {}", key)),
+        }
+
+        Ok(())
+    }
+
+    // Builds the keymap from the preset and the keys bound on top of it
+    pub fn keymap(&self) -> Keymap {
+        let mut keymap = Keymap::new(self.keymap);
+        for &(key, action) in &self.keys {
+            keymap.bind(key, action);
+        }
+
+        keymap
+    }
+}
+
+// Parses the [debug] table, which needs both the command that starts the debug adapter and the
+// arguments of the launch request, written as a JSON object
+fn parse_debug_config(entries: &[Entry]) -> Result<DebugConfig, String> {
+    let mut adapter = None;
+    let mut launch = None;
+
+    for entry in entries {
+        let value = match (entry.key.as_str(), &entry.value) {
+            ("adapter", Value::String(command)) if !command.trim().is_empty() => {
+                adapter = Some(command.trim().to_string());
+                Ok(())
+            }
+            ("launch", Value::String(text)) => match json::parse(text) {
+                Ok(arguments @ Json::Object(_)) => {
+                    launch = Some(arguments);
+                    Ok(())
+                }
+                Ok(_) => Err("launch has to be a JSON object".to_string()),
+                Err(error) => Err(format!("Invalid JSON for launch: {}", error)),
+            },
+            ("adapter" | "launch", other) => Err(format!("{} cannot be {}", entry.key, other)),
+            (key, _) => Err(format!("Unknown option: {}", key)),
+        };
+
+        value.map_err(|error| format!("line {}: {}", entry.line, error))?;
+    }
+
+    match (adapter, launch) {
+        (Some(adapter), Some(launch)) => Ok(DebugConfig { adapter, launch }),
+        _ => Err("[debug] needs both an adapter and launch".to_string()),
+    }
+}
+
+// Gets the path of the config file, which is in $XDG_CONFIG_HOME, or ~/.config if that is not set
+// [EXAMPLE] "/home/user/.config/rut/config.toml"
+pub fn config_path() -> Option<PathBuf> {
+    let directory = match env::var_os("XDG_CONFIG_HOME").filter(|path| !path.is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(directory.join("rut").join("config.toml"))
+}
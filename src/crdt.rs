@@ -0,0 +1,320 @@
+use std::ops::Range;
+
+// Uniquely identifies a character across every copy of a shared document
+// IDs are Lamport timestamps, so an ID is always greater than the IDs of every character its
+// site had seen when creating it, and ties between sites are broken by the site number
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CharId {
+    pub counter: u64,
+    pub site: u32,
+}
+
+// Represents a change to a shared document, which can be applied by any copy in any order
+// that respects causality (a character is always inserted before it is deleted or built upon)
+#[derive(Clone, Copy)]
+pub enum Operation {
+    // The character is placed after its origin, or at the start of the document if it has none
+    Insert {
+        id: CharId,
+        origin: Option<CharId>,
+        character: char,
+    },
+    Delete {
+        id: CharId,
+    },
+}
+
+// Represents a change to the visible text caused by applying an operation
+pub enum TextChange {
+    Insert { index: usize, character: char },
+    Delete { index: usize, character: char },
+}
+
+// Represents a character of the document
+// * Deleted characters are kept as tombstones, because other sites may still refer to them
+#[derive(Clone, Copy)]
+pub struct Element {
+    pub id: CharId,
+    pub character: char,
+    pub deleted: bool,
+}
+
+// Represents a sequence CRDT (a Replicated Growable Array), which lets multiple sites edit the
+// same text concurrently and always converge on the same result once they have seen the same edits
+pub struct Document {
+    site: u32,
+    clock: u64,
+    elements: Vec<Element>,
+}
+
+impl Document {
+    // Create a new Document instance containing the given text, owned by the given site
+    // * The elements are built directly rather than inserted one at a time, which would be slow for large texts
+    pub fn new(site: u32, text: &str) -> Self {
+        let elements: Vec<Element> = text
+            .chars()
+            .zip(1..)
+            .map(|(character, counter)| Element {
+                id: CharId { counter, site },
+                character,
+                deleted: false,
+            })
+            .collect();
+
+        Self {
+            site,
+            clock: elements.len() as u64,
+            elements,
+        }
+    }
+
+    // Create a Document instance from another site's elements, such as when joining a session
+    pub fn from_elements(site: u32, elements: Vec<Element>) -> Self {
+        let clock = elements
+            .iter()
+            .map(|element| element.id.counter)
+            .max()
+            .unwrap_or(0);
+
+        Self {
+            site,
+            clock,
+            elements,
+        }
+    }
+
+    // Inserts text at a visible index, returning the operations which other sites need to apply
+    pub fn insert(&mut self, index: usize, text: &str) -> Vec<Operation> {
+        let mut origin = match index {
+            0 => None,
+            _ => self.visible_id(index - 1),
+        };
+
+        let mut operations = Vec::new();
+        for character in text.chars() {
+            self.clock += 1;
+            let id = CharId {
+                counter: self.clock,
+                site: self.site,
+            };
+
+            let operation = Operation::Insert {
+                id,
+                origin,
+                character,
+            };
+            self.apply(&operation);
+            operations.push(operation);
+
+            origin = Some(id);
+        }
+
+        operations
+    }
+
+    // Deletes a range of visible characters, returning the operations which other sites need to apply
+    pub fn delete(&mut self, range: Range<usize>) -> Vec<Operation> {
+        let ids: Vec<CharId> = range.filter_map(|index| self.visible_id(index)).collect();
+
+        ids.into_iter()
+            .map(|id| {
+                let operation = Operation::Delete { id };
+                self.apply(&operation);
+                operation
+            })
+            .collect()
+    }
+
+    // Applies an operation, returning how it changed the visible text
+    // Operations which have already been applied are ignored, so applying one twice is harmless
+    pub fn apply(&mut self, operation: &Operation) -> Option<TextChange> {
+        match *operation {
+            Operation::Insert {
+                id,
+                origin,
+                character,
+            } => {
+                if self.position(id).is_some() {
+                    return None;
+                }
+
+                self.clock = self.clock.max(id.counter);
+
+                // Characters inserted after the same origin are ordered newest first, so every site
+                // skips over any characters with greater IDs (and everything inserted after them,
+                // which always has an even greater ID) to find the same position
+                let mut position = match origin {
+                    Some(origin) => self.position(origin)? + 1,
+                    None => 0,
+                };
+
+                while position < self.elements.len() && self.elements[position].id > id {
+                    position += 1;
+                }
+
+                self.elements.insert(
+                    position,
+                    Element {
+                        id,
+                        character,
+                        deleted: false,
+                    },
+                );
+
+                Some(TextChange::Insert {
+                    index: self.visible_index(position),
+                    character,
+                })
+            }
+            Operation::Delete { id } => {
+                let position = self.position(id)?;
+                if self.elements[position].deleted {
+                    return None;
+                }
+
+                self.elements[position].deleted = true;
+
+                Some(TextChange::Delete {
+                    index: self.visible_index(position),
+                    character: self.elements[position].character,
+                })
+            }
+        }
+    }
+
+    // Gets the site that owns this copy of the document
+    pub fn site(&self) -> u32 {
+        self.site
+    }
+
+    // Gets the ID of the character at a visible index
+    pub fn visible_id(&self, index: usize) -> Option<CharId> {
+        self.elements
+            .iter()
+            .filter(|element| !element.deleted)
+            .nth(index)
+            .map(|element| element.id)
+    }
+
+    // Gets the visible index directly after a character, which is where a cursor anchored to it is
+    // If the character has been deleted, this is the position it would have been at
+    pub fn index_after(&self, id: Option<CharId>) -> usize {
+        match id.and_then(|id| self.position(id)) {
+            Some(position) => self.visible_index(position + 1),
+            None => 0,
+        }
+    }
+
+    // Gets every element of the document, including deleted ones, in order
+    pub fn elements(&self) -> &[Element] {
+        &self.elements
+    }
+
+    // Gets the visible text of the document
+    pub fn text(&self) -> String {
+        self.elements
+            .iter()
+            .filter(|element| !element.deleted)
+            .map(|element| element.character)
+            .collect()
+    }
+
+    // Gets the position of a character in the list of elements
+    fn position(&self, id: CharId) -> Option<usize> {
+        self.elements.iter().position(|element| element.id == id)
+    }
+
+    // Gets the visible index of the element at a given position
+    fn visible_index(&self, position: usize) -> usize {
+        self.elements[..position]
+            .iter()
+            .filter(|element| !element.deleted)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Creates two copies of a document, as the host and a guest which has just joined
+    fn two_sites(text: &str) -> (Document, Document) {
+        let host = Document::new(0, text);
+        let guest = Document::from_elements(1, host.elements().to_vec());
+        (host, guest)
+    }
+
+    // Applies operations made at one site to another
+    fn deliver(operations: &[Operation], document: &mut Document) {
+        for operation in operations {
+            document.apply(operation);
+        }
+    }
+
+    #[test]
+    fn concurrent_inserts_converge() {
+        let (mut host, mut guest) = two_sites("ac");
+
+        let from_host = host.insert(1, "xy");
+        let from_guest = guest.insert(1, "b");
+        deliver(&from_guest, &mut host);
+        deliver(&from_host, &mut guest);
+
+        assert_eq!(host.text(), guest.text());
+        assert_eq!(host.text().len(), 5);
+        assert!(host.text().starts_with('a') && host.text().ends_with('c'));
+    }
+
+    #[test]
+    fn concurrent_deletes_and_inserts_converge() {
+        let (mut host, mut guest) = two_sites("hello");
+
+        let from_host = host.delete(1..4);
+        let mut from_guest = guest.insert(2, "!");
+        from_guest.extend(guest.delete(0..1));
+        deliver(&from_guest, &mut host);
+        deliver(&from_host, &mut guest);
+
+        assert_eq!(host.text(), "!o");
+        assert_eq!(guest.text(), "!o");
+    }
+
+    #[test]
+    fn reports_changes_once() {
+        let (mut host, mut guest) = two_sites("ab");
+
+        let operations = host.insert(2, "c");
+        assert!(matches!(
+            guest.apply(&operations[0]),
+            Some(TextChange::Insert {
+                index: 2,
+                character: 'c'
+            })
+        ));
+        assert!(guest.apply(&operations[0]).is_none());
+
+        let operations = host.delete(0..1);
+        assert!(matches!(
+            guest.apply(&operations[0]),
+            Some(TextChange::Delete {
+                index: 0,
+                character: 'a'
+            })
+        ));
+        assert!(guest.apply(&operations[0]).is_none());
+        assert_eq!(guest.text(), "bc");
+    }
+
+    #[test]
+    fn anchors_follow_their_character() {
+        let (mut host, mut guest) = two_sites("abc");
+
+        let anchor = guest.visible_id(1);
+        deliver(&host.insert(0, "xx"), &mut guest);
+        assert_eq!(guest.index_after(anchor), 4);
+
+        deliver(&host.delete(3..4), &mut guest);
+        assert_eq!(guest.index_after(anchor), 3);
+        assert_eq!(guest.index_after(None), 0);
+    }
+}
@@ -0,0 +1,572 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::json::{self, Json};
+use crate::runtime;
+
+// The debug adapter to start, and the arguments of the launch request which tell it what to debug
+// * The launch arguments differ between adapters, so they are passed on as they were written
+#[derive(Clone)]
+pub struct DebugConfig {
+    pub adapter: String,
+    pub launch: Json,
+}
+
+// Represents something which happened in a debug session, for the editor to show
+pub enum DebugUpdate {
+    // The program has stopped, at a file and line (counting from zero) if the adapter knows where
+    Stopped {
+        reason: String,
+        location: Option<(String, usize)>,
+    },
+    Continued,
+    Output(String),
+    // The lines of the variables panel, listing the variables in each scope of the stopped frame
+    Variables(Vec<String>),
+    Error(String),
+    // The session is over, along with why
+    Ended(String),
+}
+
+// What a request which has not been answered yet was sent for
+enum Pending {
+    Initialize,
+    Threads,
+    StackTrace,
+    Scopes,
+    // The variables of the scope with this position in the list of scopes
+    Variables(usize),
+    Other,
+}
+
+// Represents a running debug adapter, which is talked to with the Debug Adapter Protocol
+// The adapter is a program which runs and controls the program being debugged, and messages are
+// sent to it and read from it by async tasks, so that the editor only ever polls for them
+// [EXAMPLE] An adapter of "lldb-dap" with launch arguments of {"program": "target/debug/app"}
+pub struct DebugSession {
+    child: Child,
+    outgoing: UnboundedSender<String>,
+    incoming: UnboundedReceiver<Json>,
+    // The sequence number of the next message sent to the adapter
+    sequence: i64,
+    pending: HashMap<i64, Pending>,
+    launch: Json,
+    // The lines (counting from zero) with breakpoints in each file, which are sent to the adapter
+    // once it is ready for them, and again whenever they change
+    breakpoints: Vec<(PathBuf, Vec<usize>)>,
+    initialized: bool,
+    // The thread which stopped, along with why, and the frame at the top of its stack once it is known
+    stopped: Option<(i64, String)>,
+    frame: Option<i64>,
+    // The name of each scope of the frame whose variables were asked for, with its variables once
+    // they arrive
+    scopes: Vec<(String, Option<Vec<String>>)>,
+    // Whether the program has ended, and whether the adapter has been asked to end the session
+    terminated: bool,
+    disconnecting: bool,
+}
+
+impl DebugSession {
+    // Starts the debug adapter with the system shell and asks it to start the program
+    pub fn start(
+        config: &DebugConfig,
+        breakpoints: Vec<(PathBuf, Vec<usize>)>,
+    ) -> io::Result<Self> {
+        // * The process has to be spawned from inside the runtime, which watches it for exiting
+        let _runtime = runtime().enter();
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&config.adapter)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(io::Error::other(
+                "The debug adapter's input and output are not piped",
+            ));
+        };
+
+        let (sender, incoming) = mpsc::unbounded_channel();
+        forward_messages(stdout, sender);
+        let (outgoing, receiver) = mpsc::unbounded_channel();
+        write_messages(stdin, receiver);
+
+        let mut session = Self {
+            child,
+            outgoing,
+            incoming,
+            sequence: 1,
+            pending: HashMap::new(),
+            launch: config.launch.clone(),
+            breakpoints,
+            initialized: false,
+            stopped: None,
+            frame: None,
+            scopes: Vec::new(),
+            terminated: false,
+            disconnecting: false,
+        };
+
+        session.request(
+            "initialize",
+            Json::object([
+                ("clientID", "rut".into()),
+                ("clientName", "rut".into()),
+                ("adapterID", "rut".into()),
+                ("linesStartAt1", true.into()),
+                ("columnsStartAt1", true.into()),
+                ("pathFormat", "path".into()),
+            ]),
+            Pending::Initialize,
+        );
+
+        Ok(session)
+    }
+
+    // Sets the lines (counting from zero) with breakpoints in a file, replacing the ones it had
+    pub fn set_breakpoints(&mut self, path: PathBuf, lines: Vec<usize>) {
+        self.breakpoints.retain(|(other, _)| *other != path);
+        self.breakpoints.push((path.clone(), lines.clone()));
+
+        // Until the adapter is ready, the breakpoints are kept to be sent all together
+        if self.initialized {
+            self.send_breakpoints(&path, &lines);
+        }
+    }
+
+    // Lets the stopped program run until it reaches a breakpoint or ends
+    pub fn resume(&mut self) -> Result<(), String> {
+        self.step("continue")
+    }
+
+    // Runs the stopped program until the next line, stepping over function calls
+    pub fn step_over(&mut self) -> Result<(), String> {
+        self.step("next")
+    }
+
+    // Runs the stopped program until the next line, stepping into function calls
+    pub fn step_into(&mut self) -> Result<(), String> {
+        self.step("stepIn")
+    }
+
+    // Runs the stopped program until the function it is in returns
+    pub fn step_out(&mut self) -> Result<(), String> {
+        self.step("stepOut")
+    }
+
+    // Asks for the variables in the frame the program stopped in, which arrive as an update
+    pub fn request_variables(&mut self) -> Result<(), String> {
+        let frame = self
+            .frame
+            .ok_or_else(|| "The program is not stopped".to_string())?;
+
+        self.request(
+            "scopes",
+            Json::object([("frameId", frame.into())]),
+            Pending::Scopes,
+        );
+        Ok(())
+    }
+
+    // Asks the adapter to end the program and the session, which it reports once it has
+    pub fn stop(&mut self) {
+        self.disconnecting = true;
+        self.request(
+            "disconnect",
+            Json::object([("terminateDebuggee", true.into())]),
+            Pending::Other,
+        );
+    }
+
+    // Checks whether the adapter has been asked to end the session, and has not done so yet
+    pub fn is_stopping(&self) -> bool {
+        self.disconnecting
+    }
+
+    // Handles the messages the adapter has sent since the last poll, without blocking
+    // Returns what happened because of them, which ends with Ended once the adapter has exited
+    pub fn poll(&mut self) -> Vec<DebugUpdate> {
+        let mut updates = Vec::new();
+
+        loop {
+            match self.incoming.try_recv() {
+                Ok(message) => self.handle_message(message, &mut updates),
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    let reason = match (self.terminated, self.disconnecting) {
+                        (true, _) => "The program has finished",
+                        (false, true) => "Stopped debugging",
+                        (false, false) => "The debug adapter exited",
+                    };
+                    updates.push(DebugUpdate::Ended(reason.to_string()));
+                    break;
+                }
+            }
+        }
+
+        updates
+    }
+
+    // Sends a request to the adapter, remembering what it was for so its response can be handled
+    fn request(&mut self, command: &str, arguments: Json, pending: Pending) {
+        let sequence = self.sequence;
+        self.sequence += 1;
+        self.pending.insert(sequence, pending);
+
+        self.send(Json::object([
+            ("seq", sequence.into()),
+            ("type", "request".into()),
+            ("command", command.into()),
+            ("arguments", arguments),
+        ]));
+    }
+
+    // Sends a message to the adapter
+    // * If the adapter has exited, the message is dropped, and the next poll reports that it has ended
+    fn send(&mut self, message: Json) {
+        let _ = self.outgoing.send(message.to_string());
+    }
+
+    // Sends a request which makes the stopped thread run again
+    fn step(&mut self, command: &str) -> Result<(), String> {
+        let (thread, _) = self
+            .stopped
+            .take()
+            .ok_or_else(|| "The program is not stopped".to_string())?;
+        self.frame = None;
+
+        self.request(
+            command,
+            Json::object([("threadId", thread.into())]),
+            Pending::Other,
+        );
+        Ok(())
+    }
+
+    fn send_breakpoints(&mut self, path: &Path, lines: &[usize]) {
+        let breakpoints = lines
+            .iter()
+            .map(|&line| Json::object([("line", (line as i64 + 1).into())]))
+            .collect::<Vec<_>>();
+
+        self.request(
+            "setBreakpoints",
+            Json::object([
+                (
+                    "source",
+                    Json::object([("path", path.to_string_lossy().as_ref().into())]),
+                ),
+                ("breakpoints", breakpoints.into()),
+            ]),
+            Pending::Other,
+        );
+    }
+
+    fn handle_message(&mut self, message: Json, updates: &mut Vec<DebugUpdate>) {
+        match message.get("type").and_then(Json::as_str) {
+            Some("response") => self.handle_response(&message, updates),
+            Some("event") => self.handle_event(&message, updates),
+            // The adapter can ask the editor to do things too (such as running the program in a
+            // terminal), which are all turned down so that it does them itself instead
+            Some("request") => {
+                let sequence = self.sequence;
+                self.sequence += 1;
+                self.send(Json::object([
+                    ("seq", sequence.into()),
+                    ("type", "response".into()),
+                    (
+                        "request_seq",
+                        message.get("seq").cloned().unwrap_or(Json::Null),
+                    ),
+                    ("success", false.into()),
+                    (
+                        "command",
+                        message.get("command").cloned().unwrap_or(Json::Null),
+                    ),
+                    ("message", "Not supported".into()),
+                ]));
+            }
+            _ => (),
+        }
+    }
+
+    fn handle_response(&mut self, message: &Json, updates: &mut Vec<DebugUpdate>) {
+        let Some(pending) = message
+            .get("request_seq")
+            .and_then(Json::as_i64)
+            .and_then(|sequence| self.pending.remove(&sequence))
+        else {
+            return;
+        };
+
+        if message.get("success").and_then(Json::as_bool) != Some(true) {
+            let command = message.get("command").and_then(Json::as_str).unwrap_or("");
+            let error = message
+                .get("message")
+                .and_then(Json::as_str)
+                .unwrap_or("it failed");
+            updates.push(DebugUpdate::Error(format!(
+                "The debug adapter could not {}: {}",
+                command, error
+            )));
+            return;
+        }
+
+        let body = message.get("body").cloned().unwrap_or(Json::Null);
+        match pending {
+            // The program is launched straight away, and the adapter then says when it is ready for
+            // the breakpoints with the initialized event
+            Pending::Initialize => {
+                let launch = self.launch.clone();
+                self.request("launch", launch, Pending::Other);
+            }
+            // The first thread is taken to be the one which stopped, if the adapter did not say
+            Pending::Threads => {
+                let thread = body
+                    .get("threads")
+                    .and_then(|threads| threads.items().first())
+                    .and_then(|thread| thread.get("id"))
+                    .and_then(Json::as_i64);
+                if let (Some(thread), Some((_, reason))) = (thread, self.stopped.take()) {
+                    self.stopped = Some((thread, reason));
+                    self.request_stack_trace(thread);
+                }
+            }
+            Pending::StackTrace => {
+                let frame = body
+                    .get("stackFrames")
+                    .and_then(|frames| frames.items().first());
+                self.frame = frame
+                    .and_then(|frame| frame.get("id"))
+                    .and_then(Json::as_i64);
+
+                let location = frame.and_then(|frame| {
+                    let path = frame.get("source")?.get("path")?.as_str()?;
+                    let line = frame.get("line")?.as_i64()?;
+                    Some((path.to_string(), (line.max(1) - 1) as usize))
+                });
+                let reason = self
+                    .stopped
+                    .as_ref()
+                    .map_or(String::new(), |(_, reason)| reason.clone());
+                updates.push(DebugUpdate::Stopped { reason, location });
+            }
+            Pending::Scopes => {
+                self.scopes.clear();
+                for scope in body.get("scopes").map(Json::items).unwrap_or_default() {
+                    let name = scope.get("name").and_then(Json::as_str).unwrap_or("Scope");
+                    let reference = scope
+                        .get("variablesReference")
+                        .and_then(Json::as_i64)
+                        .unwrap_or(0);
+
+                    let index = self.scopes.len();
+                    self.scopes.push((name.to_string(), None));
+                    self.request(
+                        "variables",
+                        Json::object([("variablesReference", reference.into())]),
+                        Pending::Variables(index),
+                    );
+                }
+
+                if self.scopes.is_empty() {
+                    updates.push(DebugUpdate::Variables(Vec::new()));
+                }
+            }
+            Pending::Variables(index) => {
+                let variables = body
+                    .get("variables")
+                    .map(Json::items)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(describe_variable)
+                    .collect();
+                if let Some((_, scope)) = self.scopes.get_mut(index) {
+                    *scope = Some(variables);
+                }
+
+                // The panel is only shown once every scope has arrived, so it does not jump about
+                if self.scopes.iter().all(|(_, variables)| variables.is_some()) {
+                    let lines = self
+                        .scopes
+                        .drain(..)
+                        .flat_map(|(name, variables)| {
+                            std::iter::once(format!("{}:", name))
+                                .chain(variables.unwrap_or_default())
+                        })
+                        .collect();
+                    updates.push(DebugUpdate::Variables(lines));
+                }
+            }
+            Pending::Other => (),
+        }
+    }
+
+    fn handle_event(&mut self, message: &Json, updates: &mut Vec<DebugUpdate>) {
+        let body = message.get("body").cloned().unwrap_or(Json::Null);
+
+        match message.get("event").and_then(Json::as_str) {
+            Some("initialized") => {
+                self.initialized = true;
+                for (path, lines) in self.breakpoints.clone() {
+                    self.send_breakpoints(&path, &lines);
+                }
+                self.request("configurationDone", Json::object([]), Pending::Other);
+            }
+            Some("stopped") => {
+                let reason = body
+                    .get("reason")
+                    .and_then(Json::as_str)
+                    .unwrap_or("paused")
+                    .to_string();
+                let thread = body.get("threadId").and_then(Json::as_i64);
+                let thread = thread.or(self.stopped.as_ref().map(|&(thread, _)| thread));
+
+                match thread {
+                    Some(thread) => {
+                        self.stopped = Some((thread, reason));
+                        self.request_stack_trace(thread);
+                    }
+                    // The thread is found from the list of threads before its stack is asked for
+                    None => {
+                        self.stopped = Some((0, reason));
+                        self.request("threads", Json::object([]), Pending::Threads);
+                    }
+                }
+            }
+            Some("continued") => {
+                self.stopped = None;
+                self.frame = None;
+                updates.push(DebugUpdate::Continued);
+            }
+            Some("output") => {
+                if body.get("category").and_then(Json::as_str) == Some("telemetry") {
+                    return;
+                }
+                if let Some(output) = body.get("output").and_then(Json::as_str) {
+                    updates.extend(
+                        output
+                            .lines()
+                            .filter(|line| !line.trim().is_empty())
+                            .map(|line| DebugUpdate::Output(line.to_string())),
+                    );
+                }
+            }
+            Some("exited") => {
+                if let Some(code) = body.get("exitCode").and_then(Json::as_i64) {
+                    updates.push(DebugUpdate::Output(format!(
+                        "The program exited with code {}",
+                        code
+                    )));
+                }
+            }
+            // The program is over, so the adapter is told it can stop too
+            Some("terminated") => {
+                self.terminated = true;
+                if !self.disconnecting {
+                    self.stop();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn request_stack_trace(&mut self, thread: i64) {
+        self.request(
+            "stackTrace",
+            Json::object([
+                ("threadId", thread.into()),
+                ("startFrame", 0.into()),
+                ("levels", 1.into()),
+            ]),
+            Pending::StackTrace,
+        );
+    }
+}
+
+impl Drop for DebugSession {
+    // Stops the adapter when the session is over or the editor exits, so it does not keep running
+    fn drop(&mut self) {
+        let _ = self.child.start_kill();
+    }
+}
+
+// Describes a variable as a line of the variables panel
+// [EXAMPLE] "  count: i32 = 4"
+fn describe_variable(variable: &Json) -> String {
+    let name = variable.get("name").and_then(Json::as_str).unwrap_or("?");
+    let value = variable.get("value").and_then(Json::as_str).unwrap_or("");
+
+    match variable.get("type").and_then(Json::as_str) {
+        Some(kind) if !kind.is_empty() => format!("  {}: {} = {}", name, kind, value),
+        _ => format!("  {} = {}", name, value),
+    }
+}
+
+// Reads the messages the adapter sends, which each have a Content-Length header, and sends them
+// over a channel from an async task on the runtime
+// * Messages which are not valid JSON are skipped, and the channel is closed once the adapter exits
+fn forward_messages(
+    stream: impl AsyncRead + Unpin + Send + 'static,
+    sender: UnboundedSender<Json>,
+) {
+    runtime().spawn(async move {
+        let mut reader = BufReader::new(stream);
+
+        loop {
+            let mut length = None;
+            loop {
+                let mut header = String::new();
+                match reader.read_line(&mut header).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(_) => (),
+                }
+
+                let header = header.trim();
+                if header.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.trim().eq_ignore_ascii_case("Content-Length") {
+                        length = value.trim().parse::<usize>().ok();
+                    }
+                }
+            }
+
+            let Some(length) = length else {
+                continue;
+            };
+            let mut body = vec![0; length];
+            if reader.read_exact(&mut body).await.is_err() {
+                return;
+            }
+
+            if let Ok(message) = json::parse(&String::from_utf8_lossy(&body)) {
+                if sender.send(message).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+}
+
+// Writes each message sent over a channel to the adapter, with its Content-Length header, from an
+// async task on the runtime
+fn write_messages(mut stdin: ChildStdin, mut receiver: UnboundedReceiver<String>) {
+    runtime().spawn(async move {
+        while let Some(message) = receiver.recv().await {
+            let framed = format!("Content-Length: {}\r\n\r\n{}", message.len(), message);
+            if stdin.write_all(framed.as_bytes()).await.is_err() || stdin.flush().await.is_err() {
+                return;
+            }
+        }
+    });
+}
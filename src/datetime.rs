@@ -0,0 +1,205 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// The format used for inserted dates if no other format is configured
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAY_NAMES: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+// Represents a calendar date and time of day
+pub struct DateTime {
+    year: i64,
+    // The month, from 1 to 12
+    month: u32,
+    // The day of the month, from 1 to 31
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    // The day of the week, from 0 (Sunday) to 6 (Saturday)
+    weekday: u32,
+    // The offset from UTC, in seconds
+    utc_offset: i64,
+}
+
+impl DateTime {
+    // Gets the current date and time in the local time zone
+    // * The time zone comes from the C library, so other platforms fall back to UTC
+    pub fn now() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs() as i64);
+
+        Self::from_timestamp(seconds, local_utc_offset(seconds))
+    }
+
+    // Converts a Unix timestamp to a date and time at the given offset from UTC
+    fn from_timestamp(seconds: i64, utc_offset: i64) -> Self {
+        let local = seconds + utc_offset;
+        let days = local.div_euclid(86400);
+        let time = local.rem_euclid(86400);
+
+        // Convert the number of days since 1970-01-01 to a civil date
+        // * This is Howard Hinnant's days_from_civil algorithm, in reverse
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let day_of_era = z.rem_euclid(146097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        } as u32;
+        let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+        Self {
+            year,
+            month,
+            day,
+            hour: (time / 3600) as u32,
+            minute: (time / 60 % 60) as u32,
+            second: (time % 60) as u32,
+            // 1970-01-01 was a Thursday
+            weekday: (days + 4).rem_euclid(7) as u32,
+            utc_offset,
+        }
+    }
+
+    // Formats the date and time using strftime-style conversions
+    // Supported: %Y %y %m %d %e %H %I %M %S %p %B %b %A %a %z %%
+    // Anything else is copied into the output unchanged
+    // [EXAMPLE] "%a %d %b %Y" -> "Fri 16 Oct 2026"
+    pub fn format(&self, format: &str) -> String {
+        let mut output = String::new();
+        let mut chars = format.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                output.push(c);
+                continue;
+            }
+
+            let month_name = MONTH_NAMES[self.month as usize - 1];
+            let weekday_name = WEEKDAY_NAMES[self.weekday as usize];
+
+            match chars.next() {
+                Some('Y') => output.push_str(&self.year.to_string()),
+                Some('y') => output.push_str(&format!("{:02}", self.year.rem_euclid(100))),
+                Some('m') => output.push_str(&format!("{:02}", self.month)),
+                Some('d') => output.push_str(&format!("{:02}", self.day)),
+                Some('e') => output.push_str(&format!("{:2}", self.day)),
+                Some('H') => output.push_str(&format!("{:02}", self.hour)),
+                Some('I') => output.push_str(&format!("{:02}", (self.hour + 11) % 12 + 1)),
+                Some('M') => output.push_str(&format!("{:02}", self.minute)),
+                Some('S') => output.push_str(&format!("{:02}", self.second)),
+                Some('p') => output.push_str(if self.hour < 12 { "AM" } else { "PM" }),
+                Some('B') => output.push_str(month_name),
+                Some('b') => output.push_str(&month_name[..3]),
+                Some('A') => output.push_str(weekday_name),
+                Some('a') => output.push_str(&weekday_name[..3]),
+                Some('z') => {
+                    let sign = if self.utc_offset < 0 { '-' } else { '+' };
+                    let minutes = self.utc_offset.abs() / 60;
+                    output.push_str(&format!("{}{:02}{:02}", sign, minutes / 60, minutes % 60));
+                }
+                Some('%') => output.push('%'),
+                Some(other) => {
+                    output.push('%');
+                    output.push(other);
+                }
+                None => output.push('%'),
+            }
+        }
+
+        output
+    }
+}
+
+// Gets the local time zone's offset from UTC at the given time, in seconds
+#[cfg(unix)]
+fn local_utc_offset(seconds: i64) -> i64 {
+    let time = seconds as libc::time_t;
+    // SAFETY: tm is plain data, so an all-zero value is valid, and localtime_r only writes to it
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::localtime_r(&time, &mut tm) };
+
+    match result.is_null() {
+        true => 0,
+        false => tm.tm_gmtoff as i64,
+    }
+}
+
+#[cfg(not(unix))]
+fn local_utc_offset(_seconds: i64) -> i64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_timestamps_to_dates() {
+        let format = "%Y-%m-%d %H:%M:%S %a";
+        assert_eq!(
+            DateTime::from_timestamp(0, 0).format(format),
+            "1970-01-01 00:00:00 Thu"
+        );
+        assert_eq!(
+            DateTime::from_timestamp(951782400, 0).format(format),
+            "2000-02-29 00:00:00 Tue"
+        );
+        assert_eq!(
+            DateTime::from_timestamp(-31536000, 0).format(format),
+            "1969-01-01 00:00:00 Wed"
+        );
+    }
+
+    #[test]
+    fn applies_the_utc_offset() {
+        let date = DateTime::from_timestamp(0, -5 * 3600);
+        assert_eq!(
+            date.format("%d %B %y %I:%M %p %z"),
+            "31 December 69 07:00 PM -0500"
+        );
+
+        let date = DateTime::from_timestamp(1792108800, 5 * 3600 + 30 * 60);
+        assert_eq!(
+            date.format("%A %e %b %H:%M %z"),
+            "Friday 16 Oct 05:30 +0530"
+        );
+    }
+
+    #[test]
+    fn copies_unknown_conversions() {
+        let date = DateTime::from_timestamp(0, 0);
+        assert_eq!(date.format("100%% %q %"), "100% %q %");
+        assert_eq!(date.format(DEFAULT_DATE_FORMAT), "1970-01-01 00:00");
+    }
+}
@@ -0,0 +1,328 @@
+use std::cmp::Reverse;
+use std::fmt;
+use std::ops::Range;
+
+use tree_sitter::{Node, Parser, Tree};
+
+use crate::grammar::Grammar;
+use crate::Buffer;
+
+// How serious a diagnostic is
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+// Represents a problem in a file, such as a syntax error or a compiler warning
+// The line and column count from zero, and the column is in characters
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub filename: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+// Finds the syntax errors in a buffer, using the tree-sitter grammar for its language
+// Returns nothing if the language has no grammar
+// * Each error is reported at the start of the text the parser could not make sense of, or where the
+// * token it expected is missing, and the nodes inside an error are not reported again
+pub fn syntax_errors(filename: &str, buffer: &Buffer) -> Vec<Diagnostic> {
+    let Some(grammar) = Grammar::for_filename(filename) else {
+        return Vec::new();
+    };
+    let mut parser = Parser::new();
+    if parser.set_language(&grammar.language()).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse_with_options(
+        &mut |byte, _| buffer.chunk_at_byte(byte).as_bytes(),
+        None,
+        None,
+    ) else {
+        return Vec::new();
+    };
+
+    tree_errors(buffer, &tree)
+        .into_iter()
+        .map(|(range, message)| {
+            let line = buffer.line_of(range.start);
+            Diagnostic {
+                severity: Severity::Error,
+                message,
+                filename: filename.to_string(),
+                line,
+                column: range.start - buffer.line_start(line),
+            }
+        })
+        .collect()
+}
+
+// A syntax error's range in the buffer and its message
+pub type SyntaxError = (Range<usize>, String);
+
+// Finds the syntax errors in a syntax tree which has already been parsed, in order, along with the
+// range of the buffer each one covers
+// * A missing token covers the character where it was expected, so that there is something to mark
+pub fn tree_errors(buffer: &Buffer, tree: &Tree) -> Vec<SyntaxError> {
+    let mut errors = Vec::new();
+    let mut nodes = vec![tree.root_node()];
+    while let Some(node) = nodes.pop() {
+        if node.is_error() || node.is_missing() {
+            let start = buffer.byte_to_char(node.start_byte());
+            let end = buffer.byte_to_char(node.end_byte()).max(start + 1);
+            errors.push((start..end.min(buffer.size()), error_message(buffer, node)));
+            continue;
+        }
+
+        // Only the branches which contain an error need to be looked through
+        let mut cursor = node.walk();
+        nodes.extend(node.children(&mut cursor).filter(|child| child.has_error()));
+    }
+
+    errors.sort_by_key(|(range, _)| range.start);
+    errors
+}
+
+// Describes a node which the parser could not fit into the grammar
+fn error_message(buffer: &Buffer, node: Node) -> String {
+    if node.is_missing() {
+        return format!("Missing {}", node.kind());
+    }
+
+    let start = buffer.byte_to_char(node.start_byte());
+    let end = buffer.byte_to_char(node.end_byte());
+    let text = buffer.text_range(start..end.min(start + 40));
+    match text.lines().next().unwrap_or_default().trim() {
+        "" => "Syntax error".to_string(),
+        text => format!("Unexpected {}", text),
+    }
+}
+
+// Picks out the errors and warnings in the output of a task, such as a build
+// Both the one-line format of C compilers ("file:line:column: error: message") and the
+// format of rustc, where the location is on a later "--> file:line:column" line, are understood
+// [EXAMPLE] "src/main.c:4:2: warning: unused variable 'x'"
+pub fn task_diagnostics(lines: &[String]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    // The rustc diagnostic whose location has not been seen yet
+    let mut pending: Option<(Severity, String)> = None;
+
+    for line in lines {
+        if let Some((severity, message)) = severity_prefix(line) {
+            pending = Some((severity, message.to_string()));
+            continue;
+        }
+
+        if let Some(location) = line.trim_start().strip_prefix("--> ") {
+            if let (Some((severity, message)), Some((filename, line, column))) =
+                (pending.take(), parse_location(location))
+            {
+                diagnostics.push(Diagnostic {
+                    severity,
+                    message,
+                    filename,
+                    line,
+                    column,
+                });
+            }
+            continue;
+        }
+
+        // A location followed by a severity, all on one line
+        let Some((location, rest)) = line.split_once(": ") else {
+            continue;
+        };
+        if let (Some((filename, line, column)), Some((severity, message))) =
+            (parse_location(location), severity_prefix(rest))
+        {
+            diagnostics.push(Diagnostic {
+                severity,
+                message: message.to_string(),
+                filename,
+                line,
+                column,
+            });
+        }
+    }
+
+    diagnostics
+}
+
+// Splits the severity off the start of a compiler message, along with any error code after it
+// [EXAMPLE] "error[E0308]: mismatched types" -> (Error, "mismatched types")
+fn severity_prefix(text: &str) -> Option<(Severity, &str)> {
+    let (severity, rest) = match text {
+        text if text.starts_with("error") => (Severity::Error, &text["error".len()..]),
+        text if text.starts_with("warning") => (Severity::Warning, &text["warning".len()..]),
+        _ => return None,
+    };
+
+    let rest = match rest.strip_prefix('[') {
+        Some(rest) => rest.split_once(']')?.1,
+        None => rest,
+    };
+    let message = rest.strip_prefix(": ")?.trim();
+
+    (!message.is_empty()).then_some((severity, message))
+}
+
+// Parses a location given as "file:line" or "file:line:column", counting from one
+// [EXAMPLE] "src/main.rs:4:5" -> ("src/main.rs", 3, 4)
+fn parse_location(location: &str) -> Option<(String, usize, usize)> {
+    let mut parts = location.trim().rsplitn(3, ':');
+    let last = parts.next()?.parse::<usize>().ok()?;
+
+    let (filename, line, column) = match parts.next()?.parse::<usize>() {
+        Ok(line) => (parts.next()?, line, last),
+        Err(_) => (location.trim().rsplit_once(':')?.0, last, 1),
+    };
+    if filename.is_empty() || filename.contains(' ') {
+        return None;
+    }
+
+    Some((
+        filename.to_string(),
+        line.saturating_sub(1),
+        column.saturating_sub(1),
+    ))
+}
+
+// The diagnostics shown in the diagnostics panel, along with how they are sorted and filtered
+#[derive(Default)]
+pub struct DiagnosticsList {
+    diagnostics: Vec<Diagnostic>,
+    // Whether errors are listed before warnings, rather than everything being listed by location
+    by_severity: bool,
+    // Whether warnings are left out
+    errors_only: bool,
+    // The text which a diagnostic's message or filename has to contain to be listed
+    filter: String,
+}
+
+impl DiagnosticsList {
+    // Replaces the diagnostics, keeping the way they are sorted and filtered
+    pub fn set(&mut self, mut diagnostics: Vec<Diagnostic>) {
+        diagnostics
+            .sort_by(|a, b| (&a.filename, a.line, a.column).cmp(&(&b.filename, b.line, b.column)));
+        self.diagnostics = diagnostics;
+    }
+
+    // Switches between sorting by location and by severity
+    pub fn toggle_sort(&mut self) {
+        self.by_severity = !self.by_severity;
+    }
+
+    // Switches between listing every diagnostic and only the errors
+    pub fn toggle_errors_only(&mut self) {
+        self.errors_only = !self.errors_only;
+    }
+
+    // Gets the text which the listed diagnostics have to contain
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    // Sets the text which the listed diagnostics have to contain, which lists them all if it is empty
+    pub fn set_filter(&mut self, filter: &str) {
+        self.filter = filter.to_string();
+    }
+
+    // Gets the diagnostics which are listed, in the order they are listed
+    pub fn visible(&self) -> Vec<&Diagnostic> {
+        let filter = self.filter.to_lowercase();
+        let mut visible: Vec<_> = self
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| !self.errors_only || diagnostic.severity == Severity::Error)
+            .filter(|diagnostic| {
+                diagnostic.message.to_lowercase().contains(&filter)
+                    || diagnostic.filename.to_lowercase().contains(&filter)
+            })
+            .collect();
+
+        // The sort is stable, so diagnostics of the same severity stay in order of location
+        if self.by_severity {
+            visible.sort_by_key(|diagnostic| Reverse(diagnostic.severity));
+        }
+
+        visible
+    }
+
+    // Gets the lines listing the diagnostics in the panel
+    // [EXAMPLE] "error    src/main.rs:4:5  Missing ;"
+    pub fn items(&self) -> Vec<String> {
+        let visible = self.visible();
+        let locations: Vec<_> = visible
+            .iter()
+            .map(|diagnostic| {
+                format!(
+                    "{}:{}:{}",
+                    diagnostic.filename,
+                    diagnostic.line + 1,
+                    diagnostic.column + 1
+                )
+            })
+            .collect();
+        let width = locations.iter().map(|location| location.len()).max();
+
+        visible
+            .iter()
+            .zip(&locations)
+            .map(|(diagnostic, location)| {
+                format!(
+                    "{:<7}  {:<width$}  {}",
+                    diagnostic.severity.to_string(),
+                    location,
+                    diagnostic.message,
+                    width = width.unwrap_or(0)
+                )
+            })
+            .collect()
+    }
+
+    // Gets the title of the panel, which counts the diagnostics and lists the keys for sorting and
+    // filtering them
+    pub fn title(&self) -> String {
+        let errors = self
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .count();
+        let warnings = self.diagnostics.len() - errors;
+
+        let sort = match self.by_severity {
+            true => "location",
+            false => "severity",
+        };
+        let shown = match self.errors_only {
+            true => "show warnings",
+            false => "errors only",
+        };
+        let filter = match self.filter.is_empty() {
+            true => String::new(),
+            false => format!(" matching \"{}\"", self.filter),
+        };
+
+        format!(
+            "{} error{}, {} warning{}{} - S sort by {}, E {}, / filter",
+            errors,
+            if errors == 1 { "" } else { "s" },
+            warnings,
+            if warnings == 1 { "" } else { "s" },
+            filter,
+            sort,
+            shown
+        )
+    }
+}
@@ -0,0 +1,88 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+// Represents which feature a dialog belongs to, so the user's choice can be handled by the editor
+#[derive(Clone, Copy, PartialEq)]
+#[allow(clippy::enum_variant_names)]
+pub enum DialogKind {
+    ConfirmQuit,
+    ConfirmOverwrite,
+    ConfirmReload,
+    ConfirmDelete,
+}
+
+// Represents one of the answers a dialog can be given
+#[derive(Clone, Copy, PartialEq)]
+pub enum DialogChoice {
+    Yes,
+    No,
+    Cancel,
+}
+
+// Represents a modal question which has to be answered before anything else can be done,
+// used to confirm destructive actions
+// Each option can be chosen by pressing the first letter of its label, or by moving the selection
+// to it and pressing Enter, and Esc always cancels
+pub struct Dialog {
+    kind: DialogKind,
+    message: String,
+    options: Vec<(DialogChoice, &'static str)>,
+    selected: usize,
+}
+
+impl Dialog {
+    // Create a new Dialog instance, with the first option selected
+    pub fn new(
+        kind: DialogKind,
+        message: &str,
+        options: Vec<(DialogChoice, &'static str)>,
+    ) -> Self {
+        Self {
+            kind,
+            message: message.to_string(),
+            options,
+            selected: 0,
+        }
+    }
+
+    // Handles a keypress, returning the user's choice if they have made one
+    pub fn handle_key(&mut self, event: KeyEvent) -> Option<DialogChoice> {
+        match event.code {
+            KeyCode::Esc => Some(DialogChoice::Cancel),
+            KeyCode::Enter => Some(self.options[self.selected].0),
+            KeyCode::Left | KeyCode::BackTab => {
+                self.selected = self.selected.saturating_sub(1);
+                None
+            }
+            KeyCode::Right | KeyCode::Tab => {
+                self.selected = (self.selected + 1).min(self.options.len() - 1);
+                None
+            }
+            KeyCode::Char(key) => self
+                .options
+                .iter()
+                .find(|(_, label)| label.starts_with(key.to_ascii_uppercase()))
+                .map(|&(choice, _)| choice),
+            _ => None,
+        }
+    }
+
+    // Returns the kind of the dialog
+    pub fn kind(&self) -> DialogKind {
+        self.kind
+    }
+
+    // Returns the question the dialog is asking
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    // Returns the labels of the options, in order
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.options.iter().map(|&(_, label)| label)
+    }
+
+    // Returns the index of the selected option
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+}
@@ -0,0 +1,81 @@
+use std::fs::File;
+use std::ops::Range;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+
+use crate::terminal::BufferView;
+use crate::{Buffer, BufferChange, Encoding, Folds, History, Marks, Search};
+use crate::{LoadTask, SaveTask};
+
+// Represents a file which is open in the editor, but is not the one being edited
+// * The editor keeps the state of the file being edited in its own fields, and swaps them with one
+// * of these to switch files, so that everything else only ever deals with the file being edited
+// ! Every field here needs to be swapped in Editor::swap_document
+pub struct Document {
+    pub filename: String,
+    pub file: Option<Arc<Mutex<File>>>,
+    pub read_only: bool,
+    pub scratch: bool,
+    pub buffer: Buffer,
+    pub buffer_changes: Receiver<BufferChange>,
+    pub save_task: SaveTask,
+    pub load_task: Option<LoadTask>,
+    pub saved_version: u64,
+    pub encoding: Encoding,
+    pub saved_encoding: Encoding,
+    pub disk_modified: Option<SystemTime>,
+    pub disk_checked: Instant,
+    pub marks: Marks,
+    pub folds: Folds,
+    pub last_yank: Option<(Range<usize>, u64)>,
+    pub history: History,
+    pub search: Option<Search>,
+    pub selection_anchor: Option<usize>,
+    pub breakpoints: Vec<usize>,
+    pub branch: Option<String>,
+    pub last_change: (u64, Instant),
+    // The cursor, scroll position, and table view, which belong to the terminal while the file is
+    // being edited
+    pub view: BufferView,
+}
+
+impl Document {
+    // Creates a document with an empty buffer and no file, which a file is then opened into
+    pub fn empty() -> Self {
+        let mut buffer = Buffer::empty();
+
+        Self {
+            filename: String::new(),
+            file: None,
+            read_only: false,
+            scratch: false,
+            buffer_changes: buffer.subscribe(),
+            saved_version: buffer.version(),
+            buffer,
+            save_task: SaveTask::default(),
+            load_task: None,
+            encoding: Encoding::default(),
+            saved_encoding: Encoding::default(),
+            disk_modified: None,
+            disk_checked: Instant::now(),
+            marks: Marks::default(),
+            folds: Folds::default(),
+            last_yank: None,
+            history: History::default(),
+            search: None,
+            selection_anchor: None,
+            breakpoints: Vec::new(),
+            branch: None,
+            last_change: (0, Instant::now()),
+            view: BufferView::default(),
+        }
+    }
+
+    // Checks whether the buffer has changed since it was last saved, the same way as
+    // Editor::is_modified does for the file being edited
+    pub fn is_modified(&self) -> bool {
+        !self.scratch
+            && (self.buffer.version() != self.saved_version || self.encoding != self.saved_encoding)
+    }
+}
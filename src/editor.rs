@@ -1,49 +1,560 @@
 use std::{
-    fs::{File, OpenOptions},
+    fs::{self, File, OpenOptions},
+    io, mem,
+    ops::Range,
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    rc::Rc,
+    str::FromStr,
+    sync::mpsc::Receiver,
     sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
 };
 
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    event::{
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+        MouseEventKind,
+    },
     Result,
 };
+use regex::{Regex, RegexBuilder};
+use tree_sitter::Tree;
 
-use crate::Buffer;
+use crate::actions::{self, Action};
+use crate::calc::{evaluate, format_result};
+use crate::collab::{RemoteChange, Session};
+use crate::commands::{self, Command};
+use crate::completion::{complete_path, list_directory};
+use crate::dap::{DebugSession, DebugUpdate};
+use crate::diagnostics::{self, DiagnosticsList};
+use crate::encoding::describe_unmappable;
+use crate::export;
+use crate::fileinfo;
+use crate::filters::{filter_for_key, TextFilter};
+use crate::grammar::Grammar;
+use crate::help;
+use crate::icons::{file_icon, DIRECTORY_ICON};
+use crate::images::{self, image_info};
+use crate::increment::{increment_number, sequence};
+use crate::jumps::Jump;
+use crate::marks::{adjust_index_for_delete, adjust_index_for_insert};
+use crate::statusline::{git_branch, StatusLine};
+use crate::structure;
+use crate::syntax::Syntax;
+use crate::table::detect_delimiter;
+use crate::Config;
+use crate::DateTime;
 use crate::DeletionMode;
-use crate::Terminal;
+use crate::Document;
+use crate::Encoding;
+use crate::Folds;
+use crate::InputReader;
+use crate::JumpList;
+use crate::KillRing;
+use crate::LoadTask;
+use crate::Marks;
+use crate::Profiler;
+use crate::RemoteFile;
+use crate::Search;
+use crate::Table;
+use crate::Theme;
+use crate::WordChars;
+use crate::{Buffer, BufferChange, BufferObserver};
+use crate::{CursorPosition, CursorStyle, LineNumbers, Status, Tab, Terminal, ViewPosition};
+use crate::{Dialog, DialogChoice, DialogKind};
+use crate::{Edit, History, TimeTravel};
+use crate::{Keymap, KeymapPreset};
+use crate::{MessageLevel, Messages};
+use crate::{Panel, PanelKind};
+use crate::{Prompt, PromptAction, PromptKind};
+use crate::{SaveTask, SavedFile};
+use crate::{Signal, SignalListener};
+use crate::{TaskDefinition, TaskRun};
+
+// The width of the text column in zen mode if no other width is configured
+pub const DEFAULT_ZEN_WIDTH: u16 = 80;
 
 // Represents the state of the editor
 // There should only be one instance of this struct at any given point
 pub struct Editor {
-    file: Arc<Mutex<File>>,
+    filename: String,
+    // The file being edited, or None if the editor was opened on a directory and no file has been chosen yet
+    file: Option<Arc<Mutex<File>>>,
+    // Whether the file could only be opened for reading, in which case it is never saved
+    read_only: bool,
+    // Whether the buffer is a scratch buffer, which has no file and is never saved
+    scratch: bool,
     buffer: Buffer,
+    // Receives every change made to the buffer, so that positions in it can be kept up to date
+    buffer_changes: Receiver<BufferChange>,
+    save_task: SaveTask,
+    // The file being read into the buffer when the editor starts, until it has been read
+    load_task: Option<LoadTask>,
+    // The version of the buffer which was last written to the file
+    saved_version: u64,
+    // The encoding the file will be saved in, and the one it was last saved in
+    encoding: Encoding,
+    saved_encoding: Encoding,
+    // The modification time of the file when it was opened or last saved,
+    // used to detect if something else has changed the file in the meantime
+    disk_modified: Option<SystemTime>,
+    // When the file was last checked for changes made by other programs
+    disk_checked: Instant,
+    // The messages shown in the status bar
+    messages: Messages,
     terminal: Terminal,
+    marks: Marks,
+    jumps: JumpList,
+    folds: Folds,
+    // The text deleted by word, subword, and sentence deletions, and where the last yank put it
+    // along with the buffer's version afterwards, so that yanking again straight away can cycle
+    kill_ring: KillRing,
+    last_yank: Option<(Range<usize>, u64)>,
+    history: History,
+    panel: Option<Panel>,
+    prompt: Option<Prompt>,
+    dialog: Option<Dialog>,
+    // The most recent search, which F3 moves between the matches of
+    search: Option<Search>,
+    // Whether the current search's matches are highlighted, which Esc turns off until the next search
+    show_search_matches: bool,
+    // The regex being replaced while the replacement is being typed, and the selection to replace
+    // it in (or None for the whole buffer)
+    pending_replace: Option<(Regex, Option<Range<usize>>)>,
+    pending_key: Option<PendingKey>,
+    // The number typed with Alt+digits before a command, which tells it how many times to act
+    count: Option<usize>,
+    // When a bare Esc was pressed, if it has not been handled yet
+    // * It is held back briefly, since it may be the start of an Esc-prefixed Alt key
+    pending_escape: Option<Instant>,
+    // When and where the mouse was last clicked, and how many clicks in a row it was part of
+    last_click: Option<(Instant, usize, u8)>,
+    // The file whose path was just pasted, along with the buffer's version after it was pasted
+    // * The offer to open it lapses once anything else changes the buffer
+    pasted_path: Option<(PathBuf, u64)>,
+    // When and where the mouse came to rest, until a tooltip has been looked for there
+    hover: Option<(Instant, u16, u16)>,
+    // The buffer index that the selection started at, if text is being selected
+    // The selection covers the text between this and the cursor
+    selection_anchor: Option<usize>,
+    // The buffer indices of the cursors added besides the main one, which typing and deleting act at too
+    // * Only the main cursor can be moved, so the others are dropped as soon as it moves
+    extra_cursors: Vec<usize>,
+    // The file on another machine that the buffer is uploaded to on save, if it is a remote file
+    // * The file opened by the editor is then a temporary local copy of it
+    remote: Option<RemoteFile>,
+    // Whether the remote file's host could be reached the last time it was saved to
+    remote_connected: bool,
+    // The collaborative editing session the buffer is shared in, if there is one
+    collab: Option<Session>,
+    // Whether the session is only being followed, in which case the buffer is read-only
+    // and the cursor follows the host's cursor
+    following: bool,
+    // Whether typed characters replace the characters under the cursor (toggled with Insert)
+    overwrite: bool,
+    // Whether pasted text is inserted exactly as it is, rather than reindented to fit the cursor's line
+    raw_paste: bool,
+    // Whether changes are announced in the status bar for screen readers
+    screen_reader: bool,
+    // The line that was last announced to the screen reader, so each line is only announced once
+    announced_line: Option<usize>,
+    // Receives signals such as SIGTSTP, if they could be listened for
+    signals: Option<SignalListener>,
+    // What Ctrl+C does
+    interrupt_action: InterruptAction,
+    // The options from the config file, and the command line options which take their place
+    config: Config,
+    // The keys which run actions, which are built from the preset and keys in the config
+    keymap: Keymap,
+    // The version of the buffer the last time it was checked for autosaving, and when it became that version
+    last_change: (u64, Instant),
+    // The git branch of the repository the file is in, found when the editor starts
+    branch: Option<String>,
+    // Every file which is open, in the order they were opened, and which of them is being edited
+    // * The state of the file being edited is kept in the editor's own fields, so its document is empty
+    documents: Vec<Document>,
+    document_index: usize,
+    // The directory shown in the file picker, until a file has been chosen from it, and the names
+    // of the entries listed in it, which may be shown with icons in the panel
+    picker_directory: Option<PathBuf>,
+    picker_entries: Vec<String>,
+    // The diagnostics listed in the diagnostics panel, which are gathered again each time it is opened
+    diagnostics: DiagnosticsList,
+    // The lines of the buffer with breakpoints, as the indices of their starts so they move with edits
+    breakpoints: Vec<usize>,
+    // The program being debugged, if there is one, and the file and line it has stopped at
+    debug: Option<DebugSession>,
+    debug_stop: Option<(PathBuf, usize)>,
+    // Whether the file picker lists hidden files and files ignored by git, which . toggles
+    show_ignored: bool,
+    // The tasks which can be run from the tasks panel, and the one which was run last
+    task_definitions: Vec<TaskDefinition>,
+    task_run: Option<TaskRun>,
+    // Measures how long frames take to draw, for the performance overlay
+    profiler: Profiler,
+    show_profiler: bool,
+    // The most frames drawn per second, if frames are limited, and when the last frame was drawn
+    max_fps: Option<u32>,
+    last_frame: Instant,
+    // Whether the editor should exit once the save in progress has finished
+    quit_after_save: bool,
+    // While quitting, the buffer to start looking from for the next one with unsaved changes to ask about
+    quit_index: Option<usize>,
+    // Whether the editor has exited, which ends the event loop
+    quitting: bool,
+    // Whether something has changed since the last frame was drawn
+    frame_outdated: bool,
+}
+
+// The longest amount of time the event loop waits for input before checking on background tasks
+const TICK_RATE: Duration = Duration::from_millis(100);
+
+// How often the file is checked for changes made by other programs
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// The longest amount of time between an Esc and the next key for them to be treated as Alt+key
+const ESCAPE_TIMEOUT: Duration = Duration::from_millis(50);
+
+// How often the highlighter is checked on while it is still working on the visible lines
+const HIGHLIGHT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// The longest amount of time between clicks for them to count as a double or triple click
+const MULTI_CLICK_TIMEOUT: Duration = Duration::from_millis(400);
+
+// The number of lines the cursor moves for each step of the mouse wheel
+const WHEEL_LINES: usize = 3;
+
+// How long the mouse has to rest in one place before a tooltip is shown for what is under it
+const HOVER_DELAY: Duration = Duration::from_millis(500);
+
+// Represents what Ctrl+C does
+// * Ctrl+C used to exit the editor immediately, which made it far too easy to lose work
+// * by reflex, so quitting is done with Ctrl+Q instead
+#[derive(Clone, Copy)]
+pub enum InterruptAction {
+    Copy,
+    Ignore,
+}
+
+impl FromStr for InterruptAction {
+    type Err = ();
+
+    // Parses an action given on the command line, such as "copy"
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "copy" => Ok(Self::Copy),
+            "ignore" => Ok(Self::Ignore),
+            _ => Err(()),
+        }
+    }
+}
+
+// Represents a key combination that is waiting for another keypress to complete it
+#[derive(Clone, Copy)]
+enum PendingKey {
+    SetMark,
+    View,
+    Filter,
+}
+
+impl PendingKey {
+    // Gets the title and the keys which can complete the combination, for the key hints popup
+    fn hints(self) -> (&'static str, &'static [(&'static str, &'static str)]) {
+        match self {
+            Self::SetMark => ("Ctrl+B", help::MARK_KEYS),
+            Self::View => ("Ctrl+W", help::VIEW_KEYS),
+            Self::Filter => ("Ctrl+G", help::FILTER_KEYS),
+        }
+    }
 }
 
 impl Editor {
     // Create a new Editor instance
+    // The editor starts on an empty buffer, which the file is read into in the background once the
+    // editor is running, or which a file is chosen for if the filename is a directory
     pub fn new(filename: &str) -> Self {
-        // Open the file
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .open(filename)
-            .expect("[INTERNAL ERROR] Failed to open file");
+        let directory = Path::new(filename).is_dir();
+
+        // Open the file and start reading it
+        // * A file which cannot be opened at all, such as one in a directory that does not exist,
+        // * leaves the editor on an empty buffer which is never written, and the error is reported
+        let (file, read_only, open_error) = match directory {
+            true => (None, false, None),
+            false => match open_for_editing(Path::new(filename)) {
+                Ok((file, read_only)) => (Some(file), read_only, None),
+                Err(error) => (None, false, Some(error)),
+            },
+        };
+        let load_task = file.as_ref().map(|file| {
+            LoadTask::start(
+                file.try_clone()
+                    .expect("[INTERNAL ERROR] Failed to duplicate file handle"),
+            )
+        });
+
+        // A config file with mistakes in it is reported once the editor has started, and ignored
+        let (config, config_error) = match Config::load() {
+            Ok(config) => (config, None),
+            Err(error) => (Config::default(), Some(error)),
+        };
 
-        // Read the file into the buffer
-        let buffer = Buffer::new(&file);
+        let mut buffer = Buffer::empty();
+        buffer.set_word_chars(
+            config
+                .word_chars
+                .clone()
+                .unwrap_or_else(|| WordChars::for_filename(filename)),
+        );
+        buffer.set_tab_width(config.tab_width);
+        let buffer_changes = buffer.subscribe();
+        let disk_modified = file.as_ref().and_then(modification_time);
 
         // Store the file in an Arc<Mutex> so it can be shared between threads
-        let file = Arc::new(Mutex::new(file));
+        let file = file.map(|file| Arc::new(Mutex::new(file)));
 
         // Create the terminal
-        let terminal = Terminal::new();
+        let mut terminal = Terminal::new();
+        terminal.set_line_numbers(config.line_numbers);
+        terminal.set_soft_wrap(config.soft_wrap);
+        terminal.set_current_line_highlight(config.current_line_highlight);
+        terminal.set_ruler(config.ruler);
+        terminal.set_scrolloff(config.scrolloff);
+        terminal.set_syntax(Syntax::for_filename(filename));
+        terminal.set_grammar(Grammar::for_filename(filename));
+        terminal.set_theme(config.theme.clone());
+        terminal.set_status_line(config.status_line.clone());
+        terminal.set_tab_line(config.tab_line.clone());
 
-        Self {
+        let mut editor = Self {
+            filename: filename.to_string(),
             file,
+            read_only,
+            scratch: false,
+            saved_version: buffer.version(),
+            encoding: Encoding::default(),
+            saved_encoding: Encoding::default(),
+            disk_modified,
+            disk_checked: Instant::now(),
             buffer,
+            buffer_changes,
+            save_task: SaveTask::default(),
+            load_task,
+            messages: Messages::default(),
             terminal,
+            marks: Marks::default(),
+            jumps: JumpList::default(),
+            kill_ring: KillRing::default(),
+            last_yank: None,
+            folds: Folds::default(),
+            history: History::default(),
+            panel: None,
+            prompt: None,
+            dialog: None,
+            search: None,
+            show_search_matches: false,
+            pending_replace: None,
+            pending_key: None,
+            count: None,
+            pending_escape: None,
+            last_click: None,
+            pasted_path: None,
+            hover: None,
+            selection_anchor: None,
+            extra_cursors: Vec::new(),
+            remote: None,
+            remote_connected: true,
+            collab: None,
+            following: false,
+            overwrite: false,
+            raw_paste: false,
+            screen_reader: false,
+            announced_line: None,
+            signals: SignalListener::new().ok(),
+            interrupt_action: InterruptAction::Copy,
+            keymap: config.keymap(),
+            task_definitions: config.tasks.clone(),
+            last_change: (0, Instant::now()),
+            config,
+            branch: git_branch(filename),
+            documents: vec![Document::empty()],
+            document_index: 0,
+            task_run: None,
+            picker_directory: None,
+            picker_entries: Vec::new(),
+            diagnostics: DiagnosticsList::default(),
+            breakpoints: Vec::new(),
+            debug: None,
+            debug_stop: None,
+            show_ignored: false,
+            profiler: Profiler::default(),
+            show_profiler: false,
+            max_fps: None,
+            last_frame: Instant::now(),
+            quit_after_save: false,
+            quit_index: None,
+            quitting: false,
+            frame_outdated: false,
+        };
+
+        if directory {
+            editor.browse(PathBuf::from(filename));
+        }
+
+        if read_only {
+            editor.set_warning(format!(
+                "Opened {} read-only, since it cannot be written to",
+                editor.display_name()
+            ));
+        }
+
+        if let Some(error) = open_error {
+            editor.set_error(format!(
+                "Failed to open {}: {}",
+                editor.display_name(),
+                error
+            ));
+        }
+
+        if let Some(error) = config_error {
+            editor.set_error(error);
+        }
+
+        editor
+    }
+
+    // Sets the number of columns a tab character takes up
+    pub fn set_tab_width(&mut self, tab_width: usize) {
+        self.config.tab_width = tab_width;
+        self.buffer.set_tab_width(tab_width);
+        self.terminal.invalidate_lines();
+    }
+
+    // Sets which punctuation counts as part of words, instead of the default for the file's type
+    pub fn set_word_chars(&mut self, word_chars: WordChars) {
+        self.buffer.set_word_chars(word_chars.clone());
+        self.config.word_chars = Some(word_chars);
+    }
+
+    // Sets the column that the ruler is drawn at, or disables it
+    pub fn set_ruler(&mut self, column: Option<u16>) {
+        self.terminal.set_ruler(column);
+        self.config.ruler = column;
+    }
+
+    // Sets what Ctrl+C does
+    pub fn set_interrupt_action(&mut self, action: InterruptAction) {
+        self.interrupt_action = action;
+    }
+
+    // Sets the preset which the key bindings start from, keeping the keys bound in the config file
+    pub fn set_keymap_preset(&mut self, preset: KeymapPreset) {
+        self.config.keymap = preset;
+        self.keymap = self.config.keymap();
+    }
+
+    // Sets the styles that everything on the screen is drawn in, instead of the config file's theme
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.terminal.set_theme(theme.clone());
+        self.config.theme = theme;
+        self.queue_frame_update();
+    }
+
+    // Sets the format used to insert the date and time, such as "%Y-%m-%d", instead of the config
+    // file's format
+    pub fn set_date_format(&mut self, format: String) {
+        self.config.date_format = format;
+    }
+
+    // Adds a task which can be run from the tasks panel, replacing any task with the same name
+    pub fn add_task(&mut self, task: TaskDefinition) {
+        self.task_definitions
+            .retain(|definition| definition.name != task.name);
+        self.task_definitions.push(task);
+    }
+
+    // Sets which segments the status bar shows on each side, instead of the config file's layout
+    pub fn set_status_line(&mut self, status_line: StatusLine) {
+        self.terminal.set_status_line(status_line.clone());
+        self.config.status_line = status_line;
+    }
+
+    // Sets the most frames drawn per second, or removes the limit
+    pub fn set_max_fps(&mut self, fps: Option<u32>) {
+        self.max_fps = fps;
+    }
+
+    // Sets the width of the text column when zen mode is turned on
+    pub fn set_zen_width(&mut self, width: u16) {
+        self.config.zen_width = width.max(1);
+    }
+
+    // Turns the screen reader mode on or off
+    pub fn set_screen_reader(&mut self, enabled: bool) {
+        self.screen_reader = enabled;
+        self.terminal.set_screen_reader(enabled);
+    }
+
+    // Sets the minimum number of lines kept visible above and below the cursor
+    pub fn set_scrolloff(&mut self, lines: usize) {
+        self.terminal.set_scrolloff(lines);
+        self.config.scrolloff = lines;
+    }
+
+    // Sets how the line numbers to the left of the text are counted, or hides them
+    pub fn set_line_numbers(&mut self, line_numbers: Option<LineNumbers>) {
+        self.terminal.set_line_numbers(line_numbers);
+    }
+
+    // Sets the shape of the cursor and whether it blinks, instead of using the terminal's default
+    pub fn set_cursor_style(&mut self, style: Option<CursorStyle>) {
+        self.terminal.set_cursor_style(style);
+    }
+
+    // Sets the remote file that the buffer is uploaded to on save
+    pub fn set_remote(&mut self, remote: RemoteFile) {
+        self.remote = Some(remote);
+    }
+
+    // Shares the buffer with other rut instances, which can join at the first address to edit it,
+    // or at the second address to only follow along
+    pub fn host_session(&mut self, address: Option<&str>, follow_address: Option<&str>) {
+        self.finish_loading();
+
+        match Session::host(address, follow_address, &self.buffer.to_string()) {
+            Ok(session) => {
+                self.collab = Some(session);
+                self.set_message(match (address, follow_address) {
+                    (Some(address), Some(follow_address)) => format!(
+                        "Hosting a session on {}, followed on {}",
+                        address, follow_address
+                    ),
+                    (Some(address), None) => format!("Hosting a session on {}", address),
+                    (None, Some(follow_address)) => {
+                        format!("Sharing a read-only session on {}", follow_address)
+                    }
+                    (None, None) => {
+                        unreachable!("[INTERNAL ERROR] A session was hosted without a socket")
+                    }
+                });
+            }
+            Err(error) => self.set_error(format!("Failed to host a session: {}", error)),
+        }
+    }
+
+    // Joins a session hosted by another rut instance, replacing the buffer with the shared one
+    // If following, the buffer cannot be edited and the view follows the host's cursor
+    // * The host also makes guests follow when they join on its socket for followers
+    pub fn join_session(&mut self, address: &str, following: bool) {
+        self.finish_loading();
+
+        match Session::join(address) {
+            Ok(session) => {
+                self.collab = Some(session);
+                self.following = following;
+                self.set_message(format!("Joining the session at {}...", address));
+            }
+            Err(error) => self.set_error(format!("Failed to join {}: {}", address, error)),
         }
     }
 
@@ -53,81 +564,385 @@ impl Editor {
         self.terminal.open()?;
 
         // Draw the initial buffer
-        self.terminal.update_frame(&self.buffer)?;
+        self.update_frame()?;
 
         // Start the event loop
         self.start_event_loop()
     }
 
     // Enters the event loop for the editor
+    // The loop ends once the editor has exited
     fn start_event_loop(&mut self) -> Result<()> {
+        let input = InputReader::start();
+
         loop {
-            // Wait for the next event
-            // * This is a blocking call
-            let event = event::read()?;
+            // Wait for the next event, waking up periodically to check on background tasks
+            // If a frame is being held back by the frame limit, only wait until it can be drawn
+            // A held back Esc is handled as soon as it is clear that no key is coming with it
+            let mut timeout = match self.frame_outdated {
+                true => self.time_until_next_frame().min(TICK_RATE),
+                false => TICK_RATE,
+            };
+            if let Some(time) = self.pending_escape {
+                timeout = timeout.min(ESCAPE_TIMEOUT.saturating_sub(time.elapsed()));
+            }
+            if let Some((time, _, _)) = self.hover {
+                timeout = timeout.min(HOVER_DELAY.saturating_sub(time.elapsed()));
+            }
+            // Lines which were drawn before they were highlighted are redrawn as soon as they are
+            if self.terminal.highlights_pending() {
+                timeout = timeout.min(HIGHLIGHT_POLL_INTERVAL);
+            }
+
+            if let Some(event) = input.next(timeout) {
+                // Dispatch the event to the appropriate handler
+                self.handle_event(event?)?;
+                self.profiler.record_event();
+
+                // Handle any other events which are already waiting before drawing, so that holding
+                // a key or pasting text results in one frame per batch instead of one per character
+                while !self.quitting {
+                    let Some(event) = input.next(Duration::ZERO) else {
+                        break;
+                    };
+
+                    self.handle_event(event?)?;
+                    self.profiler.record_event();
+                }
+            }
+
+            if self.quitting {
+                return Ok(());
+            }
+
+            self.check_pending_escape()?;
+            self.check_hover();
+            self.check_background_tasks()?;
+            self.handle_signals()?;
+
+            if self.quitting {
+                return Ok(());
+            }
+
+            if self.frame_outdated && self.time_until_next_frame().is_zero() {
+                let started = Instant::now();
+                self.update_frame()?;
+                self.frame_outdated = false;
+
+                self.profiler.record_frame(started.elapsed());
+                self.last_frame = started;
+            }
+        }
+    }
+
+    // Gets how long to wait before the next frame can be drawn without going over the frame limit
+    fn time_until_next_frame(&self) -> Duration {
+        match self.max_fps {
+            Some(fps) => (Duration::from_secs(1) / fps).saturating_sub(self.last_frame.elapsed()),
+            None => Duration::ZERO,
+        }
+    }
+
+    // Responds to any signals which have been sent to the process
+    fn handle_signals(&mut self) -> Result<()> {
+        let Some(signals) = self.signals.as_mut() else {
+            return Ok(());
+        };
+
+        for signal in signals.poll() {
+            match signal {
+                Signal::Suspend => self.suspend()?,
+                Signal::Terminate => self.terminate(),
+            }
+        }
+
+        Ok(())
+    }
+
+    // Restores the terminal and stops the process, then takes over the terminal again once the
+    // process is continued (such as with `fg`) and redraws everything
+    // * Raw mode stops the terminal from sending SIGTSTP itself, so the editor does it
+    // * Ctrl+Z undoes rather than suspending, so this is run with :suspend or the command palette,
+    // * or by sending SIGTSTP to the process
+    fn suspend(&mut self) -> Result<()> {
+        self.terminal.exit()?;
+        crate::signals::stop_process()?;
 
-            // Dispatch the event to the appropriate handler
-            self.handle_event(event)?;
+        // The process has been continued at this point
+        self.terminal.resume()?;
+        if self.overwrite {
+            self.terminal.set_cursor_shape(true)?;
         }
+
+        self.queue_frame_update();
+
+        Ok(())
     }
 
     // Handles a generic Event by dispatching it to the appropriate handler function
+    // * Anything but the mouse moving hides the tooltip, since it is about what was under the mouse
     fn handle_event(&mut self, event: Event) -> Result<()> {
+        let moved = matches!(&event, Event::Mouse(mouse) if mouse.kind == MouseEventKind::Moved);
+        if !moved {
+            self.hover = None;
+            if self.terminal.hide_tooltip() {
+                self.queue_frame_update();
+            }
+        }
+
         match event {
-            Event::Key(key_event) => self.handle_key_event(key_event)?,
+            // * Key releases are only reported by some terminals, and nothing is bound to them
+            Event::Key(key_event) if key_event.kind != KeyEventKind::Release => {
+                self.handle_key_event(key_event)?
+            }
+            // Pasted text, and text committed by some input methods, arrives all at once
+            Event::Paste(text) => self.insert_text(&text)?,
+            Event::Mouse(mouse_event) => self.handle_mouse_event(mouse_event)?,
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    // Handles a MouseEvent, where clicking places the cursor, double-clicking selects a word,
+    // triple-clicking selects a line, dragging selects the text between, and clicking the gutter
+    // toggles a breakpoint
+    // * Mouse events are ignored while a panel, prompt, or dialog is open, since they take the keyboard
+    fn handle_mouse_event(&mut self, event: MouseEvent) -> Result<()> {
+        if self.panel.is_some() || self.prompt.is_some() || self.dialog.is_some() {
+            return Ok(());
+        }
+
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                // Clicking the gutter toggles a breakpoint on the line next to it
+                let gutter_line =
+                    self.terminal
+                        .gutter_line_at(&self.buffer, event.column, event.row);
+                if let Some(line) = gutter_line {
+                    self.toggle_breakpoint(line);
+                    return Ok(());
+                }
+
+                let Some(index) = self.index_at(event.column, event.row) else {
+                    return Ok(());
+                };
+
+                let clicks = match self.last_click {
+                    Some((time, last_index, clicks))
+                        if time.elapsed() < MULTI_CLICK_TIMEOUT && last_index == index =>
+                    {
+                        clicks % 3 + 1
+                    }
+                    _ => 1,
+                };
+                self.last_click = Some((Instant::now(), index, clicks));
+
+                match clicks {
+                    1 => self.move_cursor(|cursor, buffer| cursor.move_to(index, buffer))?,
+                    2 => self.select_range(self.buffer.word_range(index)),
+                    _ => self.select_line(self.buffer.line_of(index)),
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                if let Some(index) = self.index_at(event.column, event.row) {
+                    self.extend_selection(|editor| {
+                        editor.terminal.cursor_mut().move_to(index, &editor.buffer);
+                        editor.queue_frame_update();
+                        Ok(())
+                    })?;
+                }
+            }
+            MouseEventKind::ScrollUp => {
+                for _ in 0..WHEEL_LINES {
+                    self.move_row(false)?;
+                }
+            }
+            MouseEventKind::ScrollDown => {
+                for _ in 0..WHEEL_LINES {
+                    self.move_row(true)?;
+                }
+            }
+            // Moving the mouse hides the tooltip until it comes to rest again
+            MouseEventKind::Moved => {
+                if self.terminal.hide_tooltip() {
+                    self.queue_frame_update();
+                }
+                self.hover = Some((Instant::now(), event.column, event.row));
+            }
             _ => (),
         }
 
         Ok(())
     }
 
+    // Gets the buffer index under a position on the screen, if there is text there
+    fn index_at(&self, x: u16, y: u16) -> Option<usize> {
+        self.terminal.buffer_index_at(&self.buffer, x, y)
+    }
+
     // Handles a KeyEvent using its code and modifiers
-    fn handle_key_event(&mut self, event: KeyEvent) -> Result<()> {
+    // Some terminals send Alt+key as Esc followed by the key, which is treated the same way
+    // * A bare Esc is held back until it is clear that no key is coming with it, so that the Esc of
+    // * an Alt key never reaches a prompt, dialog, or panel, and is never mistaken for a real Esc
+    fn handle_key_event(&mut self, mut event: KeyEvent) -> Result<()> {
+        if let Some(time) = self.pending_escape.take() {
+            match time.elapsed() < ESCAPE_TIMEOUT
+                && event.modifiers == KeyModifiers::NONE
+                && event.code != KeyCode::Esc
+            {
+                true => event.modifiers = KeyModifiers::ALT,
+                false => self.dispatch_key_event(escape_event())?,
+            }
+        }
+
+        if (event.code, event.modifiers) == (KeyCode::Esc, KeyModifiers::NONE) {
+            self.pending_escape = Some(Instant::now());
+            return Ok(());
+        }
+
+        self.dispatch_key_event(event)
+    }
+
+    // Handles a KeyEvent once any Esc prefix has been resolved, by sending it to whatever has the keyboard
+    fn dispatch_key_event(&mut self, event: KeyEvent) -> Result<()> {
+        // If a key combination is in progress, this keypress completes it
+        if let Some(pending_key) = self.pending_key.take() {
+            return self.handle_pending_key(pending_key, event);
+        }
+
+        // If a dialog, prompt, or panel is open, it receives all keypresses
+        if self.dialog.is_some() {
+            return self.handle_dialog_key(event);
+        }
+
+        if self.prompt.is_some() {
+            return self.handle_prompt_key(event);
+        }
+
+        if self.panel.is_some() {
+            return self.handle_panel_key(event);
+        }
+
+        // A count only applies to the command directly after it
+        let count = self.count.take();
+
+        // Keys bound in the keymap take priority over the ones handled here,
+        // so that a preset can take over keys like Ctrl+A
+        if let Some(action) = self.keymap.get((event.code, event.modifiers)) {
+            return self.run_action(action);
+        }
+
         match (event.code, event.modifiers) {
-            // Exit the program on Ctrl+C
-            (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
-                self.exit()?;
+            // Build up a count by typing digits with Alt held, like in Emacs
+            (KeyCode::Char(digit @ '0'..='9'), KeyModifiers::ALT) => {
+                let count = count.unwrap_or(0) * 10 + digit.to_digit(10).unwrap_or(0) as usize;
+                self.count = Some(count);
+                self.set_message(format!("Count: {}", count));
             }
-            // Save the file on Ctrl+S
-            (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
-                self.save()?;
+            // Increment or decrement the number under the cursor on Ctrl+A and Ctrl+X
+            (KeyCode::Char('a'), KeyModifiers::CONTROL) => {
+                self.increment(count.unwrap_or(1) as i64)
+            }
+            (KeyCode::Char('x'), KeyModifiers::CONTROL) => {
+                self.increment(-(count.unwrap_or(1) as i64))
             }
+            // Copy or do nothing on Ctrl+C, depending on the interrupt action
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => match self.interrupt_action {
+                InterruptAction::Copy => self.copy()?,
+                InterruptAction::Ignore => (),
+            },
             // Handle arrow keypresses
-            // TODO: Deduplicate and find some way to not pass in the buffer to the cursor methods
-            (KeyCode::Up, KeyModifiers::NONE) => {
-                self.terminal.cursor_mut().move_up(&self.buffer);
-                self.terminal.update_cursor();
+            (KeyCode::Up, KeyModifiers::NONE) => self.move_row(false)?,
+            (KeyCode::Down, KeyModifiers::NONE) => self.move_row(true)?,
+            (KeyCode::Left, KeyModifiers::NONE) => self.move_cursor(CursorPosition::move_left)?,
+            (KeyCode::Right, KeyModifiers::NONE) => self.move_cursor(CursorPosition::move_right)?,
+            // Handle Shift+arrow keypresses, which select text
+            (KeyCode::Up, KeyModifiers::SHIFT) => {
+                self.extend_selection(|editor| editor.move_row(false))?
+            }
+            (KeyCode::Down, KeyModifiers::SHIFT) => {
+                self.extend_selection(|editor| editor.move_row(true))?
+            }
+            (KeyCode::Left, KeyModifiers::SHIFT) => {
+                self.extend_selection(|editor| editor.move_cursor(CursorPosition::move_left))?
+            }
+            (KeyCode::Right, KeyModifiers::SHIFT) => {
+                self.extend_selection(|editor| editor.move_cursor(CursorPosition::move_right))?
+            }
+            // Move by subword (camelCase humps and snake_case parts) on Alt+LEFT and Alt+RIGHT,
+            // and delete by subword on Alt+BACKSPACE and Alt+DELETE
+            (KeyCode::Left, KeyModifiers::ALT) => {
+                self.move_cursor(CursorPosition::move_subword_left)?
+            }
+            (KeyCode::Right, KeyModifiers::ALT) => {
+                self.move_cursor(CursorPosition::move_subword_right)?
+            }
+            (KeyCode::Backspace, KeyModifiers::ALT) => {
+                self.remove_subword(DeletionMode::Backspace)?
+            }
+            (KeyCode::Delete, KeyModifiers::ALT) => self.remove_subword(DeletionMode::Delete)?,
+            // Move by paragraph on Ctrl+UP and Ctrl+DOWN
+            (KeyCode::Up, KeyModifiers::CONTROL) => {
+                self.move_cursor(CursorPosition::move_paragraph_up)?
+            }
+            (KeyCode::Down, KeyModifiers::CONTROL) => {
+                self.move_cursor(CursorPosition::move_paragraph_down)?
             }
-            (KeyCode::Down, KeyModifiers::NONE) => {
-                self.terminal.cursor_mut().move_down(&self.buffer);
-                self.terminal.update_cursor();
+            // Move to the start or end of the indentation block on Alt+HOME and Alt+END
+            (KeyCode::Home, KeyModifiers::ALT) => {
+                self.move_cursor(CursorPosition::move_block_start)?
             }
-            (KeyCode::Left, KeyModifiers::NONE) => {
-                self.terminal.cursor_mut().move_left(&self.buffer);
-                self.terminal.update_cursor();
+            (KeyCode::End, KeyModifiers::ALT) => {
+                self.move_cursor(CursorPosition::move_block_end)?
             }
-            (KeyCode::Right, KeyModifiers::NONE) => {
-                self.terminal.cursor_mut().move_right(&self.buffer);
-                self.terminal.update_cursor();
+            // Move by sentence on Alt+A and Alt+E, like Emacs
+            (KeyCode::Char('a'), KeyModifiers::ALT) => {
+                self.move_cursor(CursorPosition::move_sentence_left)?
             }
+            (KeyCode::Char('e'), KeyModifiers::ALT) => {
+                self.move_cursor(CursorPosition::move_sentence_right)?
+            }
+            // Clear the selection, extra cursors, and search highlights, and cancel any background
+            // task in progress, on Esc
+            (KeyCode::Esc, KeyModifiers::NONE) => self.handle_escape(),
             // Handle Ctrl+LEFT and Ctrl+RIGHT
             (KeyCode::Left, KeyModifiers::CONTROL) => {
-                self.terminal.cursor_mut().move_word_left(&self.buffer);
-                self.terminal.update_cursor();
+                self.move_cursor(CursorPosition::move_word_left)?
             }
             (KeyCode::Right, KeyModifiers::CONTROL) => {
-                self.terminal.cursor_mut().move_word_right(&self.buffer);
-                self.terminal.update_cursor();
+                self.move_cursor(CursorPosition::move_word_right)?
             }
             // Handle backspace
             (KeyCode::Backspace, KeyModifiers::NONE) => {
                 self.remove_char(DeletionMode::Backspace)?
             }
             // Handle Ctrl+BACKSPACE
-            // ! This is bound to Ctrl+L for now because Ctrl+BACKSPACE does not seem to work
-            (KeyCode::Char('l'), KeyModifiers::CONTROL) => {
-                self.remove_word()?
+            // ! Ctrl+BACKSPACE is only reported by terminals which support the enhanced keyboard
+            // ! protocol, so Ctrl+L is also bound for the others
+            (KeyCode::Backspace, KeyModifiers::CONTROL) => self.remove_word()?,
+            (KeyCode::Char('l'), KeyModifiers::CONTROL) => self.remove_word()?,
+            // Set a mark on Ctrl+B followed by a letter
+            (KeyCode::Char('b'), KeyModifiers::CONTROL) => {
+                self.pending_key = Some(PendingKey::SetMark);
+                self.queue_frame_update();
+            }
+            // Change the view on Ctrl+W followed by another key
+            // Scroll the view around the cursor with C (center), T (top), or B (bottom),
+            // fold with Z (toggle the fold at the cursor), M (fold all), or R (unfold all),
+            // and line up the columns of a table with A (toggle) or H (toggle the header)
+            (KeyCode::Char('w'), KeyModifiers::CONTROL) => {
+                self.pending_key = Some(PendingKey::View);
+                self.queue_frame_update();
             }
+            // Transform the selection on Ctrl+G followed by the key of a filter
+            (KeyCode::Char('g'), KeyModifiers::CONTROL) => {
+                self.pending_key = Some(PendingKey::Filter);
+                self.queue_frame_update();
+            }
+            // Move between the cells of the table view on Tab and Shift+Tab
+            (KeyCode::Tab, KeyModifiers::NONE) => self.move_to_cell(true)?,
+            (KeyCode::BackTab, _) => self.move_to_cell(false)?,
             // Handle delete
             (KeyCode::Delete, KeyModifiers::NONE) => self.remove_char(DeletionMode::Delete)?,
             // Handle enter
@@ -140,109 +955,4010 @@ impl Editor {
         Ok(())
     }
 
-    // Inserts a character into the buffer at the cursor position
-    fn insert_char(&mut self, character: char) -> Result<()> {
-        // Get the index at which the character should be inserted
-        let buffer_index = self.terminal.cursor().index();
+    // Clears the selection, extra cursors, and search highlights, and cancels any background task in
+    // progress
+    fn handle_escape(&mut self) {
+        self.selection_anchor = None;
+        self.extra_cursors.clear();
+        self.show_search_matches = false;
+        self.save_task.cancel();
+        self.queue_frame_update();
+    }
 
-        // Insert the character into the buffer
-        self.buffer.insert(buffer_index, character);
+    // Shows a tooltip once the mouse has rested over a syntax error or the name of a function for long
+    // enough, with the error's message or the function's signature
+    fn check_hover(&mut self) {
+        let Some((time, x, y)) = self.hover else {
+            return;
+        };
+        if time.elapsed() < HOVER_DELAY {
+            return;
+        }
+        self.hover = None;
 
-        // Adjust the cursor position
-        self.terminal.cursor_mut().move_right(&self.buffer);
+        if self.panel.is_some() || self.prompt.is_some() || self.dialog.is_some() {
+            return;
+        }
+        let Some(index) = self.index_at(x, y) else {
+            return;
+        };
 
-        // Update the terminal
-        self.terminal.update_frame(&self.buffer)?;
-        self.terminal.update_cursor();
+        let text = match self.terminal.syntax_error_at(&self.buffer, index) {
+            Some(message) => message.to_string(),
+            None => match self.function_signature_at(index) {
+                Some((line, signature)) => format!("{}  (line {})", signature, line + 1),
+                None => return,
+            },
+        };
 
-        Ok(())
+        self.terminal.show_tooltip(x, y, text);
+        self.queue_frame_update();
     }
 
-    // Deletes the character in the buffer immediately preceding the cursor,
-    // or alternatively immediately after the cursor (delete_mode)
-    fn remove_char(&mut self, deletion_mode: DeletionMode) -> Result<()> {
-        use DeletionMode::*;
+    // Gets the signature of the function defined in the buffer whose name is at a buffer index,
+    // along with the line it is defined on
+    fn function_signature_at(&mut self, index: usize) -> Option<(usize, String)> {
+        let range = self.buffer.word_range(index);
+        if range.is_empty() {
+            return None;
+        }
+        let name = self.buffer.text_range(range);
 
-        // Get the index at which the character should be deleted, adjusting for the deletion mode
-        let mut buffer_index = self.terminal.cursor().index();
+        let tree = self.syntax_tree()?;
+        let grammar = self.terminal.grammar()?;
+        structure::function_signature(&self.buffer, &tree, grammar, &name)
+    }
 
-        // Avoid backspacing characters preceding the start of the buffer
-        if buffer_index == 0 && deletion_mode == Backspace {
-            return Ok(());
+    // Handles an Esc which has been held back for long enough that no key is coming with it
+    fn check_pending_escape(&mut self) -> Result<()> {
+        match self.pending_escape {
+            Some(time) if time.elapsed() >= ESCAPE_TIMEOUT => {
+                self.pending_escape = None;
+                self.dispatch_key_event(escape_event())
+            }
+            _ => Ok(()),
         }
+    }
 
-        // If a backspace is being performed, delete the character before the cursor instead of after
-        if let Backspace = deletion_mode {
-            buffer_index -= 1;
+    // Handles the keypress that completes a pending key combination
+    fn handle_pending_key(&mut self, pending_key: PendingKey, event: KeyEvent) -> Result<()> {
+        match (pending_key, event.code) {
+            (PendingKey::SetMark, KeyCode::Char(name)) if Marks::is_valid_name(name) => {
+                self.marks.set(name, self.terminal.cursor().index());
+            }
+            (PendingKey::View, KeyCode::Char('c')) => self.run_action(Action::CenterView)?,
+            (PendingKey::View, KeyCode::Char('t')) => self.run_action(Action::ScrollToTop)?,
+            (PendingKey::View, KeyCode::Char('b')) => self.run_action(Action::ScrollToBottom)?,
+            (PendingKey::View, KeyCode::Char('z')) => self.run_action(Action::ToggleFold)?,
+            (PendingKey::View, KeyCode::Char('m')) => self.run_action(Action::FoldAll)?,
+            (PendingKey::View, KeyCode::Char('r')) => self.run_action(Action::UnfoldAll)?,
+            (PendingKey::View, KeyCode::Char('a')) => self.run_action(Action::ToggleTable)?,
+            (PendingKey::View, KeyCode::Char('h')) => self.run_action(Action::ToggleTableHeader)?,
+            (PendingKey::View, KeyCode::Char('w')) => self.run_action(Action::ToggleSoftWrap)?,
+            (PendingKey::Filter, KeyCode::Char(key)) => {
+                if let Some(filter) = filter_for_key(key) {
+                    self.apply_filter(filter);
+                }
+            }
+            // Any other key cancels the combination
+            _ => (),
         }
 
-        // Delete the character from the buffer
-        self.buffer.delete(buffer_index..buffer_index + 1);
+        // Hide the key hints popup
+        self.queue_frame_update();
 
-        // Adjust the cursor position depending on the deletion mode
-        if let Backspace = deletion_mode {
-            self.terminal.cursor_mut().move_left(&self.buffer);
+        Ok(())
+    }
+
+    // Runs an action, either from its key binding or from the command palette
+    fn run_action(&mut self, action: Action) -> Result<()> {
+        match action {
+            Action::Save => self.save()?,
+            Action::SaveAll => self.save_all()?,
+            Action::Quit => self.quit()?,
+            Action::Suspend => self.suspend()?,
+            Action::ShowHelp => self.open_help_panel()?,
+            Action::OpenCommandPalette => self.open_command_palette(),
+            Action::OpenCommandLine => self.open_prompt(PromptKind::ExCommand, ":")?,
+            Action::MoveWordLeft => self.move_cursor(CursorPosition::move_word_left)?,
+            Action::MoveWordRight => self.move_cursor(CursorPosition::move_word_right)?,
+            Action::LineStart => self.move_cursor(CursorPosition::move_line_start)?,
+            Action::LineEnd => self.move_cursor(CursorPosition::move_line_end)?,
+            Action::Copy => self.copy()?,
+            Action::Undo => self.undo()?,
+            Action::Redo => self.redo()?,
+            Action::OpenUndoTree => self.open_undo_tree_panel()?,
+            Action::TimeTravel => {
+                self.open_prompt(PromptKind::TimeTravel, "Time travel (e.g. -10s, +3): ")?
+            }
+            Action::Search => self.open_prompt(PromptKind::Search, "Search: ")?,
+            Action::NextMatch => self.next_match(true),
+            Action::PreviousMatch => self.next_match(false),
+            Action::ReplaceRegex => {
+                self.open_prompt(PromptKind::ReplacePattern, "Replace regex: ")?
+            }
+            Action::InsertFile => self.open_prompt(PromptKind::InsertFile, "Insert file: ")?,
+            Action::InsertDate => {
+                let date = DateTime::now().format(&self.config.date_format);
+                self.insert_text(&date)?
+            }
+            Action::Yank => self.yank()?,
+            Action::KillLine => self.kill_line()?,
+            Action::KillRegion => self.kill_selection()?,
+            Action::DeleteSentence => self.remove_sentence()?,
+            Action::MoveLineUp => self.move_line_up()?,
+            Action::MoveLineDown => self.move_line_down()?,
+            Action::SelectParagraph => self.select_paragraph(),
+            Action::SelectEnclosingBlock => self.select_enclosing_block(),
+            Action::NextFunction => self.go_to_function(true),
+            Action::PreviousFunction => self.go_to_function(false),
+            Action::Increment => self.increment(1),
+            Action::Decrement => self.increment(-1),
+            Action::Calculate => self.calculate(),
+            Action::ToggleOverwrite => self.toggle_overwrite()?,
+            Action::ToggleRawPaste => self.toggle_raw_paste(),
+            Action::OpenMarks => self.open_marks_panel()?,
+            Action::JumpBack => self.jump_back()?,
+            Action::JumpForward => self.jump_forward()?,
+            Action::CenterView => self.recenter(ViewPosition::Center)?,
+            Action::ScrollToTop => self.recenter(ViewPosition::Top)?,
+            Action::ScrollToBottom => self.recenter(ViewPosition::Bottom)?,
+            Action::ToggleFold => {
+                let line = self.terminal.cursor().line();
+                let tree = self.syntax_tree();
+                let syntax = tree.as_ref().zip(self.terminal.grammar());
+                self.folds.toggle(&self.buffer, syntax, line);
+            }
+            Action::FoldAll => {
+                let tree = self.syntax_tree();
+                let syntax = tree.as_ref().zip(self.terminal.grammar());
+                self.folds.fold_all(&self.buffer, syntax);
+            }
+            Action::UnfoldAll => self.folds.unfold_all(),
+            Action::ToggleTable => self.toggle_table(),
+            Action::ToggleTableHeader => self.terminal.toggle_table_header(),
+            Action::ToggleSoftWrap => self.toggle_soft_wrap(),
+            Action::ShowLineNumbers => self.set_line_numbers(Some(LineNumbers::Absolute)),
+            Action::ShowRelativeLineNumbers => self.set_line_numbers(Some(LineNumbers::Relative)),
+            Action::HideLineNumbers => self.set_line_numbers(None),
+            Action::ToggleInvisibles => self.terminal.toggle_invisibles(),
+            Action::ToggleCurrentLineHighlight => self.terminal.toggle_current_line_highlight(),
+            Action::ToggleZenMode => self.toggle_zen_mode(),
+            Action::TogglePerformanceOverlay => self.toggle_profiler(),
+            Action::ChangeEncoding => self.open_prompt(
+                PromptKind::Encoding,
+                "Save as encoding (utf-8, latin-1, utf-16le, utf-16be, ascii): ",
+            )?,
+            Action::ExportHtml => self.open_export_prompt(),
+            Action::RunTask => self.open_tasks_panel(),
+            Action::ShowTaskOutput => self.open_task_output_panel(),
+            Action::PreviewImage => self.preview_image()?,
+            Action::ShowFileInfo => {
+                let info = fileinfo::describe(
+                    &self.filename,
+                    &self.display_name(),
+                    &self.buffer,
+                    self.encoding,
+                    self.is_modified(),
+                );
+                self.set_message(info);
+            }
+            Action::NextBuffer => self.cycle_document(true),
+            Action::PreviousBuffer => self.cycle_document(false),
+            Action::ListBuffers => self.open_buffers_panel(),
+            Action::CloseBuffer => self.close_document(false),
+            Action::OpenPastedPath => self.open_pasted_path()?,
+            Action::ShowDiagnostics => self.open_diagnostics_panel(),
+            Action::ToggleBreakpoint => self.toggle_breakpoint(self.terminal.cursor().line()),
+            Action::StartDebugging => self.start_debugging(),
+            Action::StopDebugging => self.stop_debugging(),
+            Action::StepOver => self.debug_step(DebugSession::step_over),
+            Action::StepInto => self.debug_step(DebugSession::step_into),
+            Action::StepOut => self.debug_step(DebugSession::step_out),
+            Action::ShowVariables => self.show_variables(),
+            Action::AddCursorAbove => self.add_cursor(false),
+            Action::AddCursorBelow => self.add_cursor(true),
+            Action::InsertSequence => self.open_sequence_prompt(),
         }
 
-        // Update the terminal
-        self.terminal.update_frame(&self.buffer)?;
-        self.terminal.update_cursor();
+        // Most actions change something on screen, so the frame is always updated afterwards
+        self.queue_frame_update();
 
         Ok(())
     }
 
-    // Deletes the word immediately preceding the cursor
-    fn remove_word(&mut self) -> Result<()> {
-        // Get the index range of the word that should be deleted
-        let word_end = self.terminal.cursor().index();
-        let word_start = self.buffer.start_of_word(word_end);
+    // Runs a command typed into the command line
+    fn run_command(&mut self, command: Command) -> Result<()> {
+        match command {
+            Command::Write => self.save()?,
+            Command::WriteAll => self.save_all()?,
+            Command::Quit { force: false } => self.quit()?,
+            Command::Quit { force: true } => self.exit()?,
+            // Only the file being edited is saved, so the others must not have anything to lose
+            Command::WriteQuit if self.modified_documents() > usize::from(self.is_modified()) => {
+                self.set_error(
+                    "Other buffers have unsaved changes (save them with :wa first)".to_string(),
+                );
+            }
+            // There is nothing to save if the buffer is unchanged, so the editor quits straight away
+            Command::WriteQuit if !self.is_modified() => self.exit()?,
+            Command::WriteQuit => {
+                self.quit_after_save = true;
+                self.save()?;
+            }
+            Command::Edit { path } => self.edit_file(&path),
+            Command::NextBuffer => self.cycle_document(true),
+            Command::PreviousBuffer => self.cycle_document(false),
+            Command::Buffer(name) => self.go_to_document(&name),
+            Command::ListBuffers => self.open_buffers_panel(),
+            Command::Scratch(name) => self.open_scratch(&name),
+            Command::CloseBuffer { force } => self.close_document(force),
+            Command::GoToLine(line) => {
+                let line = line.clamp(1, self.buffer.line_count()) - 1;
+                self.jump_to(self.buffer.line_start(line));
+            }
+            Command::Suspend => self.suspend()?,
+        }
 
-        // Delete the word from the buffer
-        self.buffer.delete(word_start..word_end);
+        self.queue_frame_update();
 
-        // Adjust the cursor position
-        // TODO: Probably not the most efficient way to do this, maybe add a parameter to cursor move methods
-        for _ in word_start..word_end {
-            self.terminal.cursor_mut().move_left(&self.buffer);
+        Ok(())
+    }
+
+    // Opens another file in a new buffer, or lists the files in a directory to choose one from
+    fn edit_file(&mut self, path: &str) {
+        if self.collab.is_some() || self.remote.is_some() {
+            self.set_error(
+                "Another file cannot be opened in a shared or remote session".to_string(),
+            );
+            return;
         }
 
-        // Update the terminal
-        self.terminal.update_frame(&self.buffer)?;
-        self.terminal.update_cursor();
+        let path = Path::new(path);
+        match path.is_dir() {
+            true => self.browse(path.to_path_buf()),
+            false => self.open_file(path),
+        }
+    }
+
+    // Opens the command palette, which is a prompt for the name of an action above a panel listing
+    // the actions which match it
+    fn open_command_palette(&mut self) {
+        self.panel = Some(Panel::new(PanelKind::Commands, "Commands", Vec::new()));
+        self.prompt = Some(Prompt::new(PromptKind::Command, "Command: "));
+        self.update_command_palette();
+    }
+
+    // Lists the actions which match the command palette's input, selecting the best match
+    fn update_command_palette(&mut self) {
+        let (Some(prompt), Some(panel)) = (self.prompt.as_ref(), self.panel.as_mut()) else {
+            return;
+        };
+
+        let matches = actions::search(prompt.input());
+        let width = matches
+            .iter()
+            .map(|(_, name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        let items = matches
+            .iter()
+            .map(|&(action, name, key)| {
+                let key = self.keymap.key_for(action, key).unwrap_or_default();
+                format!("{:<width$}  {}", name, key)
+            })
+            .collect();
+
+        panel.set_items(items);
+        panel.select(0);
+        self.queue_frame_update();
+    }
+
+    // Handles a keypress while a prompt is open
+    fn handle_prompt_key(&mut self, event: KeyEvent) -> Result<()> {
+        let Some(prompt) = self.prompt.as_mut() else {
+            return Ok(());
+        };
+
+        // The command palette's list is moved through with the arrow keys while typing
+        if let (PromptKind::Command, Some(panel)) = (prompt.kind(), self.panel.as_mut()) {
+            match event.code {
+                KeyCode::Up => panel.select_previous(),
+                KeyCode::Down => panel.select_next(),
+                _ => (),
+            }
+
+            if matches!(event.code, KeyCode::Up | KeyCode::Down) {
+                self.queue_frame_update();
+                return Ok(());
+            }
+        }
+
+        // File paths can be completed with Tab, including the paths given to commands
+        if event.code == KeyCode::Tab {
+            let completed = match prompt.kind() {
+                PromptKind::InsertFile => complete_path(prompt.input()),
+                PromptKind::ExCommand => commands::complete(prompt.input()),
+                _ => None,
+            };
+            if let Some(completed) = completed {
+                prompt.set_input(&completed);
+            }
+
+            self.queue_frame_update();
+            return Ok(());
+        }
+
+        match prompt.handle_key(event) {
+            PromptAction::Submit => {
+                if let Some(prompt) = self.prompt.take() {
+                    self.submit_prompt(prompt)?;
+                }
+            }
+            PromptAction::Cancel => match self.prompt.take().map(|prompt| prompt.kind()) {
+                Some(PromptKind::Command) => self.panel = None,
+                // Cancelling the filter lists every diagnostic again
+                Some(PromptKind::DiagnosticsFilter) => {
+                    self.diagnostics.set_filter("");
+                    self.update_diagnostics_panel();
+                }
+                _ => (),
+            },
+            PromptAction::Continue if prompt.kind() == PromptKind::Command => {
+                self.update_command_palette()
+            }
+            // The diagnostics are filtered as the filter is typed
+            PromptAction::Continue if prompt.kind() == PromptKind::DiagnosticsFilter => {
+                let filter = prompt.input().to_string();
+                self.diagnostics.set_filter(&filter);
+                self.update_diagnostics_panel();
+            }
+            PromptAction::Continue => (),
+        }
+
+        self.queue_frame_update();
 
         Ok(())
     }
 
-    // Saves the buffer to the file
-    // ! This might crash the program if the file is being saved twice at the same time
-    fn save(&mut self) -> Result<()> {
-        // Clone the buffer so it can be used in a separate thread
-        let buffer = self.buffer.clone();
-
-        // Get a copy of the File reference to use it in the thread
-        let file = self.file.clone();
-
-        std::thread::spawn(move || {
-            // Acquire a lock on the file so it can be written to
-            let mut file = file
-                .lock()
-                .expect("[INTERNAL ERROR] Failed to acquire lock on file");
-
-            // Write the buffer to the file
-            buffer
-                .write_to_file(&mut *file)
-                .expect("[INTERNAL ERROR] Failed to write to file");
-        });
+    // Opens a prompt at the bottom of the screen
+    fn open_prompt(&mut self, kind: PromptKind, label: &str) -> Result<()> {
+        self.prompt = Some(Prompt::new(kind, label));
+
+        self.queue_frame_update();
 
         Ok(())
     }
 
-    // Closes the terminal and exits the program
-    fn exit(&mut self) -> Result<()> {
-        // Close the terminal
-        self.terminal.exit()?;
+    // Opens a prompt for the path to export the buffer to, which defaults to the filename with .html added
+    fn open_export_prompt(&mut self) {
+        let path = format!("{}.html", self.filename);
+        self.prompt =
+            Some(Prompt::new(PromptKind::ExportHtml, "Export HTML to: ").with_input(&path));
+
+        self.queue_frame_update();
+    }
+
+    // Changes the encoding the file will be saved in, as long as every character in the buffer
+    // can be represented in it
+    fn set_encoding(&mut self, name: &str) {
+        let Ok(encoding) = name.parse::<Encoding>() else {
+            self.set_error(format!("Unknown encoding: {}", name));
+            return;
+        };
+
+        let unmappable = encoding.unmappable(&self.buffer.to_string());
+        if !unmappable.is_empty() {
+            self.set_warning(format!(
+                "{} cannot represent: {}",
+                encoding,
+                describe_unmappable(&unmappable)
+            ));
+            return;
+        }
+
+        self.encoding = encoding;
+        self.set_message(format!("The file will be saved as {}", encoding));
+    }
+
+    // Writes the buffer to the given path as an HTML page
+    fn export_html(&mut self, path: &str) {
+        if path.is_empty() {
+            return;
+        }
+
+        let html = export::to_html(&self.buffer, &self.filename);
+        match fs::write(path, html) {
+            Ok(()) => self.set_message(format!("Exported to {}", path)),
+            Err(error) => self.set_error(format!("Failed to export: {}", error)),
+        }
+    }
+
+    // Handles the input of a prompt once the user has submitted it
+    fn submit_prompt(&mut self, prompt: Prompt) -> Result<()> {
+        match prompt.kind() {
+            PromptKind::TimeTravel => {
+                if let Ok(travel) = prompt.input().parse::<TimeTravel>() {
+                    let edits = self.history.time_travel(travel);
+                    self.apply_history_edits(edits);
+                }
+            }
+            PromptKind::ExportHtml => self.export_html(prompt.input().trim()),
+            PromptKind::InsertFile => self.insert_file(prompt.input().trim())?,
+            PromptKind::Encoding => self.set_encoding(prompt.input().trim()),
+            PromptKind::NewFile => self.create_file(prompt.input().trim()),
+            PromptKind::RenameFile => self.rename_file(prompt.input().trim()),
+            PromptKind::Sequence => self.insert_sequence(prompt.input()),
+            PromptKind::DiagnosticsFilter => {
+                self.diagnostics.set_filter(prompt.input());
+                self.update_diagnostics_panel();
+            }
+            // * ^ and $ match at the start and end of every line, since the whole buffer is searched at once
+            PromptKind::ReplacePattern => {
+                match RegexBuilder::new(prompt.input()).multi_line(true).build() {
+                    Ok(regex) => {
+                        self.pending_replace = Some((regex, self.selection()));
+                        self.open_prompt(PromptKind::ReplaceWith, "Replace with: ")?;
+                    }
+                    Err(error) => self.set_error(format!("Invalid regex: {}", error)),
+                }
+            }
+            PromptKind::ReplaceWith => {
+                if let Some((regex, range)) = self.pending_replace.take() {
+                    self.replace_all(&regex, range, prompt.input());
+                }
+            }
+            PromptKind::ExCommand => match commands::parse(prompt.input()) {
+                Ok(command) => self.run_command(command)?,
+                Err(error) => self.set_error(error),
+            },
+            PromptKind::Command => {
+                let selected = self.panel.take().map_or(0, |panel| panel.selected());
+                match actions::search(prompt.input()).get(selected) {
+                    Some(&(action, _, _)) => self.run_action(action)?,
+                    None => self.set_warning(format!("No command matches {}", prompt.input())),
+                }
+            }
+            PromptKind::Search => {
+                self.search = Some(Search::new(prompt.input()));
+                self.show_search_matches = true;
+                self.next_match(true);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Handles a keypress while a dialog is open
+    fn handle_dialog_key(&mut self, event: KeyEvent) -> Result<()> {
+        let Some(dialog) = self.dialog.as_mut() else {
+            return Ok(());
+        };
+
+        if let Some(choice) = dialog.handle_key(event) {
+            let kind = dialog.kind();
+            self.dialog = None;
+
+            match (kind, choice) {
+                (DialogKind::ConfirmQuit, DialogChoice::Yes) => self.save_before_quitting()?,
+                (DialogKind::ConfirmQuit, DialogChoice::No) => self.continue_quit()?,
+                (DialogKind::ConfirmQuit, _) => self.quit_index = None,
+                (DialogKind::ConfirmOverwrite, DialogChoice::Yes) => {
+                    self.write_file();
+                    if self.quit_index.is_some() {
+                        self.continue_quit()?;
+                    }
+                }
+                (DialogKind::ConfirmOverwrite, _) => {
+                    self.quit_after_save = false;
+                    self.quit_index = None;
+                    self.set_message("Save cancelled".to_string());
+                }
+                (DialogKind::ConfirmDelete, DialogChoice::Yes) => self.delete_file(),
+                (DialogKind::ConfirmDelete, _) => (),
+                (DialogKind::ConfirmReload, DialogChoice::Yes) => self.reload(),
+                (DialogKind::ConfirmReload, _) => {
+                    // The user has chosen their version, so they are not asked again until the file
+                    // changes again, and saving overwrites it without another warning
+                    self.disk_modified = disk_modification_time(&self.filename);
+                    self.set_message("Kept your changes".to_string());
+                }
+            }
+        }
+
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Opens a dialog in the middle of the screen
+    fn open_dialog(
+        &mut self,
+        kind: DialogKind,
+        message: &str,
+        options: Vec<(DialogChoice, &'static str)>,
+    ) {
+        self.dialog = Some(Dialog::new(kind, message, options));
+
+        self.queue_frame_update();
+    }
+
+    // Handles a keypress while a panel is open
+    fn handle_panel_key(&mut self, event: KeyEvent) -> Result<()> {
+        let Some(panel) = self.panel.as_mut() else {
+            return Ok(());
+        };
+
+        match event.code {
+            KeyCode::Up => panel.select_previous(),
+            KeyCode::Down => panel.select_next(),
+            KeyCode::Esc => self.panel = None,
+            KeyCode::Enter => {
+                let kind = panel.kind();
+                let selected = panel.selected();
+                let name = self.picker_entries.get(selected).cloned();
+                self.panel = None;
+
+                match kind {
+                    PanelKind::Marks => {
+                        if let Some((name, _)) = self.marks.nth(selected) {
+                            self.jump_to_mark(name);
+                        }
+                    }
+                    PanelKind::UndoTree => {
+                        if let Some(&(id, _)) = self.history.tree_rows().get(selected) {
+                            let edits = self.history.checkout(id);
+                            self.apply_history_edits(edits);
+                        }
+                    }
+                    PanelKind::Tasks => {
+                        if let Some(task) = self.task_definitions.get(selected) {
+                            self.run_task(task.clone());
+                        }
+                    }
+                    PanelKind::Files => {
+                        if let (Some(directory), Some(name)) = (self.picker_directory.clone(), name)
+                        {
+                            let path = directory.join(&name);
+                            match name.ends_with('/') {
+                                true => self.browse(path),
+                                false => self.open_file(&path),
+                            }
+                        }
+                    }
+                    PanelKind::Buffers => self.switch_document(selected),
+                    PanelKind::Diagnostics => {
+                        let location = self.diagnostics.visible().get(selected).map(|diagnostic| {
+                            (
+                                diagnostic.filename.clone(),
+                                diagnostic.line,
+                                diagnostic.column,
+                            )
+                        });
+                        if let Some((filename, line, column)) = location {
+                            self.go_to_location(&filename, line, column);
+                        }
+                    }
+                    // The command palette's panel is driven by its prompt, which has the keyboard
+                    PanelKind::Help
+                    | PanelKind::Image
+                    | PanelKind::TaskOutput
+                    | PanelKind::Commands
+                    | PanelKind::Variables => (),
+                }
+            }
+            // In the task output panel, S stops the task and R runs it again
+            KeyCode::Char('s') if panel.kind() == PanelKind::TaskOutput => {
+                // Stopping the task records its status straight away, so it is reported here
+                // rather than when the background tasks are next checked
+                if let Some(run) = self.task_run.as_mut().filter(|run| run.status().is_none()) {
+                    run.stop();
+                    let name = run.definition().name.clone();
+                    self.set_warning(format!("{} was stopped", name));
+                }
+                self.update_task_output();
+            }
+            KeyCode::Char('r') if panel.kind() == PanelKind::TaskOutput => {
+                if let Some(run) = self.task_run.as_ref() {
+                    self.run_task(run.definition().clone());
+                }
+            }
+            // In the file picker, . shows or hides hidden files and files ignored by git
+            // The selection stays on the same entry, as long as it is still listed
+            KeyCode::Char('.') if panel.kind() == PanelKind::Files => {
+                let name = self.picker_entries.get(panel.selected()).cloned();
+                self.show_ignored = !self.show_ignored;
+                self.refresh_picker(name.as_deref());
+            }
+            // In the file picker, N creates a file, R renames or moves the selected entry, and D deletes it
+            KeyCode::Char('n') if panel.kind() == PanelKind::Files => self.open_prompt(
+                PromptKind::NewFile,
+                "New file (end with / for a directory): ",
+            )?,
+            KeyCode::Char('r') if panel.kind() == PanelKind::Files => {
+                if let Some(name) = self
+                    .picker_selection()
+                    .and_then(|path| Some(path.file_name()?.to_string_lossy().to_string()))
+                {
+                    self.prompt = Some(
+                        Prompt::new(PromptKind::RenameFile, "Rename or move to: ")
+                            .with_input(&name),
+                    );
+                }
+            }
+            KeyCode::Char('d') if panel.kind() == PanelKind::Files => self.confirm_delete(),
+            // In the diagnostics panel, S changes how they are sorted, E hides or shows the warnings,
+            // and / filters them by their message or file
+            KeyCode::Char('s') if panel.kind() == PanelKind::Diagnostics => {
+                self.diagnostics.toggle_sort();
+                self.update_diagnostics_panel();
+            }
+            KeyCode::Char('e') if panel.kind() == PanelKind::Diagnostics => {
+                self.diagnostics.toggle_errors_only();
+                self.update_diagnostics_panel();
+            }
+            KeyCode::Char('/') if panel.kind() == PanelKind::Diagnostics => {
+                let filter = self.diagnostics.filter().to_string();
+                self.prompt = Some(
+                    Prompt::new(PromptKind::DiagnosticsFilter, "Filter: ").with_input(&filter),
+                );
+            }
+            // In the marks panel, typing the name of a mark jumps straight to it
+            KeyCode::Char(name)
+                if panel.kind() == PanelKind::Marks && self.marks.get(name).is_some() =>
+            {
+                self.panel = None;
+                self.jump_to_mark(name);
+            }
+            _ => (),
+        }
+
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Opens the help screen, which lists every key binding
+    fn open_help_panel(&mut self) -> Result<()> {
+        self.panel = Some(Panel::new(
+            PanelKind::Help,
+            "Help (Esc to close)",
+            help::help_lines(&self.keymap),
+        ));
+
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Opens a panel listing the files in a directory, so that one can be chosen to edit
+    // Directories are listed first, and hidden and ignored files are left out unless . has shown them
+    // Files can also be created, renamed, moved, and deleted from the panel
+    fn browse(&mut self, directory: PathBuf) {
+        // The path is made absolute so that going up from the starting directory works
+        let directory = fs::canonicalize(&directory).unwrap_or(directory);
+
+        let entries = match list_directory(&directory, self.show_ignored, self.show_ignored) {
+            Ok(entries) => entries,
+            Err(error) => {
+                self.set_error(format!("Failed to read {}: {}", directory.display(), error));
+                return;
+            }
+        };
+
+        let mut names: Vec<String> = entries
+            .into_iter()
+            .map(|(name, is_directory)| match is_directory {
+                true => format!("{}/", name),
+                false => name,
+            })
+            .collect();
+        names.sort_by_key(|name| (!name.ends_with('/'), name.to_lowercase()));
+
+        if directory.parent().is_some() {
+            names.insert(0, "../".to_string());
+        }
+
+        // * Without icons, directories are still told apart from files by their trailing slash
+        let items = names
+            .iter()
+            .map(|name| match (self.config.icons, name.ends_with('/')) {
+                (true, true) => format!("{} {}", DIRECTORY_ICON, name),
+                (true, false) => format!("{} {}", file_icon(name), name),
+                (false, _) => name.clone(),
+            })
+            .collect();
+
+        let title = match self.show_ignored {
+            true => "hide",
+            false => "show",
+        };
+        let title = format!(
+            "{} - N new, R rename, D delete, . {} ignored",
+            directory.display(),
+            title
+        );
+        self.panel = Some(Panel::new(PanelKind::Files, &title, items));
+        self.picker_directory = Some(directory);
+        self.picker_entries = names;
+        self.queue_frame_update();
+    }
+
+    // Gets the path of the entry selected in the file picker, unless it is the parent directory
+    fn picker_selection(&self) -> Option<PathBuf> {
+        let directory = self.picker_directory.as_ref()?;
+        let panel = self
+            .panel
+            .as_ref()
+            .filter(|panel| panel.kind() == PanelKind::Files)?;
+        let name = self.picker_entries.get(panel.selected())?;
+
+        (name != "../").then(|| directory.join(name.trim_end_matches('/')))
+    }
+
+    // Lists the file picker's directory again once its files have changed, selecting an entry by name
+    fn refresh_picker(&mut self, name: Option<&str>) {
+        let Some(directory) = self.picker_directory.clone() else {
+            return;
+        };
+        self.browse(directory);
+
+        let index = name.and_then(|name| {
+            let name = name.trim_end_matches('/');
+            self.picker_entries
+                .iter()
+                .position(|entry| entry.trim_end_matches('/') == name)
+        });
+        if let (Some(panel), Some(index)) = (self.panel.as_mut(), index) {
+            panel.select(index);
+        }
+    }
+
+    // Creates a file in the file picker's directory, or a directory if the name ends with a slash
+    // * The name can include directories, which are created along with it
+    fn create_file(&mut self, name: &str) {
+        let Some(directory) = self.picker_directory.clone() else {
+            return;
+        };
+        if name.is_empty() {
+            return;
+        }
+
+        let path = directory.join(name);
+        if fs::symlink_metadata(&path).is_ok() {
+            self.set_error(format!("{} already exists", path.display()));
+            return;
+        }
+
+        let created = match name.ends_with('/') {
+            true => fs::create_dir_all(&path),
+            false => path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| OpenOptions::new().write(true).create_new(true).open(&path))
+                .map(|_| ()),
+        };
+
+        match created {
+            Ok(()) => {
+                self.refresh_picker(name.split('/').next());
+                self.set_message(format!("Created {}", path.display()));
+            }
+            Err(error) => self.set_error(format!("Failed to create {}: {}", path.display(), error)),
+        }
+    }
+
+    // Renames or moves the entry selected in the file picker to a path relative to the picker's
+    // directory, and points any buffers open on it (or on files inside it) at its new location
+    // * Moving an entry into an existing directory keeps its name, the same as mv
+    fn rename_file(&mut self, target: &str) {
+        let (Some(source), Some(directory)) =
+            (self.picker_selection(), self.picker_directory.clone())
+        else {
+            return;
+        };
+        if target.is_empty() {
+            return;
+        }
+
+        let mut target = directory.join(target);
+        if let (true, Some(name)) = (target.is_dir(), source.file_name()) {
+            target.push(name);
+        }
+        if fs::symlink_metadata(&target).is_ok() {
+            self.set_error(format!("{} already exists", target.display()));
+            return;
+        }
+
+        // The open files are found before the rename, while their paths still exist
+        let affected = self.documents_within(&source);
+        if let Err(error) = fs::rename(&source, &target) {
+            self.set_error(format!(
+                "Failed to move {} to {}: {}",
+                source.display(),
+                target.display(),
+                error
+            ));
+            return;
+        }
+
+        // The open file handles follow the file, so only the names need to change
+        let renamed = fs::canonicalize(&target).unwrap_or(target.clone());
+        for (index, inside) in affected {
+            let path = match inside.as_os_str().is_empty() {
+                true => renamed.clone(),
+                false => renamed.join(inside),
+            };
+            let filename = relative_filename(&path);
+            match index == self.document_index {
+                true => self.filename = filename,
+                false => self.documents[index].filename = filename,
+            }
+        }
+
+        let name = target
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+        self.refresh_picker(
+            name.as_deref()
+                .filter(|_| target.parent() == Some(&directory)),
+        );
+        self.set_message(format!(
+            "Moved {} to {}",
+            source.display(),
+            target.display()
+        ));
+    }
+
+    // Asks whether to delete the entry selected in the file picker
+    // * Files which are open are not deleted, since saving them would bring them back
+    fn confirm_delete(&mut self) {
+        let Some(path) = self.picker_selection() else {
+            return;
+        };
+
+        if !self.documents_within(&path).is_empty() {
+            self.set_error(format!(
+                "{} is open in a buffer (close it with :bd first)",
+                path.display()
+            ));
+            return;
+        }
+
+        let message = match fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.is_dir()) {
+            true => format!("Delete {} and everything in it?", path.display()),
+            false => format!("Delete {}?", path.display()),
+        };
+        self.open_dialog(
+            DialogKind::ConfirmDelete,
+            &message,
+            vec![
+                (DialogChoice::Yes, "Delete"),
+                (DialogChoice::Cancel, "Cancel"),
+            ],
+        );
+    }
+
+    // Deletes the entry selected in the file picker, once the user has confirmed it
+    // * Symbolic links are removed themselves, rather than what they point to
+    fn delete_file(&mut self) {
+        let Some(path) = self.picker_selection() else {
+            return;
+        };
+
+        let deleted = match fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.is_dir()) {
+            true => fs::remove_dir_all(&path),
+            false => fs::remove_file(&path),
+        };
+
+        match deleted {
+            Ok(()) => {
+                self.refresh_picker(None);
+                self.set_message(format!("Deleted {}", path.display()));
+            }
+            Err(error) => self.set_error(format!("Failed to delete {}: {}", path.display(), error)),
+        }
+    }
+
+    // Checks whether the file being edited is the empty buffer the editor starts with when it is opened
+    // on a directory, which the first file to be opened takes the place of
+    fn is_placeholder(&self) -> bool {
+        self.file.is_none() && self.load_task.is_none() && !self.scratch && !self.is_modified()
+    }
+
+    // Switches to the scratch buffer with a name, or opens a new one
+    // * The name is only used to tell scratch buffers apart and pick their syntax highlighting, so it
+    // * can be the same as a file's without ever touching it
+    fn open_scratch(&mut self, name: &str) {
+        if self.collab.is_some() || self.remote.is_some() {
+            self.set_error(
+                "A scratch buffer cannot be opened in a shared or remote session".to_string(),
+            );
+            return;
+        }
+
+        let open = (0..self.documents.len())
+            .find(|&index| self.is_scratch(index) && self.document_filename(index) == name);
+        if let Some(index) = open {
+            self.switch_document(index);
+            return;
+        }
+
+        if !self.is_placeholder() {
+            self.documents.push(Document::empty());
+            self.switch_document(self.documents.len() - 1);
+        }
+
+        self.scratch = true;
+        self.filename = name.to_string();
+        self.picker_directory = None;
+        self.terminal.set_syntax(Syntax::for_filename(name));
+        self.terminal.set_grammar(Grammar::for_filename(name));
+        self.install_buffer(Buffer::empty());
+        self.set_message(format!(
+            "Opened scratch buffer {}, which is never saved",
+            name
+        ));
+    }
+
+    // Opens a file chosen from the file picker or the command line in a new buffer, or switches to
+    // it if it is already open
+    // * The buffer the editor started with is used instead if no file has been chosen for it yet
+    fn open_file(&mut self, path: &Path) {
+        if let Some(index) = self.find_document(path) {
+            self.picker_directory = None;
+            self.switch_document(index);
+            return;
+        }
+
+        let opened = open_for_editing(path)
+            .and_then(|(file, read_only)| Buffer::read(&file).map(|read| (file, read_only, read)));
+
+        let (file, read_only, (buffer, encoding)) = match opened {
+            Ok(opened) => opened,
+            Err(error) => {
+                if let Some(directory) = self.picker_directory.clone() {
+                    self.browse(directory);
+                }
+                self.set_error(format!("Failed to open {}: {}", path.display(), error));
+                return;
+            }
+        };
+
+        let filename = relative_filename(path);
+
+        let placeholder = self.is_placeholder();
+        if !placeholder {
+            self.documents.push(Document::empty());
+            self.switch_document(self.documents.len() - 1);
+        }
+
+        self.disk_modified = modification_time(&file);
+        self.file = Some(Arc::new(Mutex::new(file)));
+        self.read_only = read_only;
+        self.branch = git_branch(&filename);
+        self.terminal.set_syntax(Syntax::for_filename(&filename));
+        self.terminal.set_grammar(Grammar::for_filename(&filename));
+        self.filename = filename;
+        self.picker_directory = None;
+        self.jumps.clear_document(self.document_index);
+
+        // Everything which refers to positions in the placeholder's buffer is thrown away with it
+        self.history = History::default();
+        self.marks = Marks::default();
+        self.folds = Folds::default();
+        self.search = None;
+        self.show_search_matches = false;
+        self.selection_anchor = None;
+        self.extra_cursors.clear();
+        self.breakpoints.clear();
+        self.last_yank = None;
+        self.encoding = encoding;
+        self.saved_encoding = encoding;
+        self.terminal.set_table(None);
+
+        self.install_buffer(buffer);
+
+        if read_only {
+            self.set_warning(format!(
+                "Opened {} read-only, since it cannot be written to",
+                self.display_name()
+            ));
+        }
+    }
+
+    // Replaces the empty buffer the editor started with by one read from the file, keeping the
+    // tab width and word characters which were set for it
+    fn install_buffer(&mut self, mut buffer: Buffer) {
+        buffer.set_tab_width(self.config.tab_width);
+        buffer.set_word_chars(
+            self.config
+                .word_chars
+                .clone()
+                .unwrap_or_else(|| WordChars::for_filename(&self.filename)),
+        );
+
+        self.buffer_changes = buffer.subscribe();
+        self.saved_version = buffer.version();
+        self.buffer = buffer;
+
+        *self.terminal.cursor_mut() = CursorPosition::default();
+        self.terminal.invalidate_lines();
+        self.queue_frame_update();
+    }
+
+    // Handles the result of reading the file the editor was started with
+    // ! If the file could not be read, it is never written to either, since that would replace
+    // ! its contents with the empty buffer
+    fn finish_load(&mut self, result: Result<(Buffer, Encoding)>) {
+        match result {
+            // The file is saved in the encoding it was read in, unless another one is chosen
+            Ok((buffer, encoding)) => {
+                self.encoding = encoding;
+                self.saved_encoding = encoding;
+                self.install_buffer(buffer);
+            }
+            Err(error) => {
+                self.file = None;
+                self.set_error(format!("Failed to read {}: {}", self.display_name(), error));
+            }
+        }
+    }
+
+    // Waits for the file to be read, for features which need its contents straight away
+    fn finish_loading(&mut self) {
+        if let Some(task) = self.load_task.take() {
+            self.finish_load(task.wait());
+        }
+    }
+
+    // Checks whether the file is ready to be edited, reopening the file picker if one has not been
+    // chosen yet
+    fn check_file_ready(&mut self) -> bool {
+        if self.load_task.is_some() {
+            self.set_warning(format!("{} is still loading", self.display_name()));
+            return false;
+        }
+
+        if self.file.is_some() || self.scratch {
+            return true;
+        }
+
+        match self.picker_directory.clone() {
+            Some(directory) => {
+                self.browse(directory);
+                self.set_warning("Choose a file to edit first".to_string());
+            }
+            None => {
+                self.set_warning("The file could not be read, so it cannot be edited".to_string())
+            }
+        }
+
+        false
+    }
+
+    // Swaps the state of the file being edited with the document at an index, leaving the view alone
+    // * Swapping with the same document again undoes this, so a file in the background can be saved
+    // * or checked on without switching to it
+    fn swap_document(&mut self, index: usize) {
+        let document = &mut self.documents[index];
+
+        mem::swap(&mut self.filename, &mut document.filename);
+        mem::swap(&mut self.file, &mut document.file);
+        mem::swap(&mut self.read_only, &mut document.read_only);
+        mem::swap(&mut self.scratch, &mut document.scratch);
+        mem::swap(&mut self.buffer, &mut document.buffer);
+        mem::swap(&mut self.buffer_changes, &mut document.buffer_changes);
+        mem::swap(&mut self.save_task, &mut document.save_task);
+        mem::swap(&mut self.load_task, &mut document.load_task);
+        mem::swap(&mut self.saved_version, &mut document.saved_version);
+        mem::swap(&mut self.encoding, &mut document.encoding);
+        mem::swap(&mut self.saved_encoding, &mut document.saved_encoding);
+        mem::swap(&mut self.disk_modified, &mut document.disk_modified);
+        mem::swap(&mut self.disk_checked, &mut document.disk_checked);
+        mem::swap(&mut self.marks, &mut document.marks);
+        mem::swap(&mut self.folds, &mut document.folds);
+        mem::swap(&mut self.last_yank, &mut document.last_yank);
+        mem::swap(&mut self.history, &mut document.history);
+        mem::swap(&mut self.search, &mut document.search);
+        mem::swap(&mut self.selection_anchor, &mut document.selection_anchor);
+        mem::swap(&mut self.breakpoints, &mut document.breakpoints);
+        mem::swap(&mut self.branch, &mut document.branch);
+        mem::swap(&mut self.last_change, &mut document.last_change);
+    }
+
+    // Switches to editing another open file, recording the jump away from the current one
+    fn switch_document(&mut self, index: usize) {
+        if index == self.document_index || index >= self.documents.len() {
+            return;
+        }
+
+        self.jumps.record(self.current_jump());
+        self.show_document(index);
+    }
+
+    // Switches to editing another open file, keeping the state of the current one in its document
+    fn show_document(&mut self, index: usize) {
+        if index == self.document_index || index >= self.documents.len() {
+            return;
+        }
+
+        // A file which is still being read is finished first, since it is installed into the editor
+        self.finish_loading();
+
+        // The current file's state goes into its empty document, and the other file's document is
+        // left empty in its place
+        let current = self.document_index;
+        self.swap_document(current);
+        self.terminal.swap_view(&mut self.documents[current].view);
+        self.swap_document(index);
+        self.terminal.swap_view(&mut self.documents[index].view);
+        self.document_index = index;
+        self.jumps.set_document(index);
+
+        // Every line is redrawn and parsed again, so the changes made before the switch are not needed
+        self.buffer.take_line_changes();
+        self.buffer.take_syntax_edits();
+        self.pending_replace = None;
+        self.last_click = None;
+        self.announced_line = None;
+        self.pasted_path = None;
+        self.extra_cursors.clear();
+
+        self.queue_frame_update();
+    }
+
+    // Finds the open file at a path, if it is open
+    // * Scratch buffers are never found, since their names are not paths
+    fn find_document(&self, path: &Path) -> Option<usize> {
+        let path = fs::canonicalize(path).ok()?;
+
+        (0..self.documents.len()).find(|&index| {
+            !self.is_scratch(index)
+                && fs::canonicalize(self.document_filename(index)).is_ok_and(|other| other == path)
+        })
+    }
+
+    // Finds the open files which are at a path or inside it, along with where they are inside it
+    fn documents_within(&self, path: &Path) -> Vec<(usize, PathBuf)> {
+        let Ok(path) = fs::canonicalize(path) else {
+            return Vec::new();
+        };
+
+        (0..self.documents.len())
+            .filter(|&index| !self.is_scratch(index))
+            .filter_map(|index| {
+                let filename = fs::canonicalize(self.document_filename(index)).ok()?;
+                let inside = filename.strip_prefix(&path).ok()?;
+                Some((index, inside.to_path_buf()))
+            })
+            .collect()
+    }
+
+    // Checks whether an open buffer is a scratch buffer
+    fn is_scratch(&self, index: usize) -> bool {
+        match index == self.document_index {
+            true => self.scratch,
+            false => self.documents[index].scratch,
+        }
+    }
+
+    // Gets the filename of an open file, which is kept in the editor itself for the file being edited
+    fn document_filename(&self, index: usize) -> &str {
+        match index == self.document_index {
+            true => &self.filename,
+            false => &self.documents[index].filename,
+        }
+    }
+
+    // Gets the name of every open file in order, along with whether it has unsaved changes
+    fn document_names(&self) -> Vec<(String, bool)> {
+        self.documents
+            .iter()
+            .enumerate()
+            .map(|(index, document)| match index == self.document_index {
+                true => (self.display_name(), self.is_modified()),
+                false => (document.filename.clone(), document.is_modified()),
+            })
+            .collect()
+    }
+
+    // Counts the open files which have unsaved changes
+    fn modified_documents(&self) -> usize {
+        self.document_names()
+            .iter()
+            .filter(|(_, modified)| *modified)
+            .count()
+    }
+
+    // Switches to the next or previous open file, wrapping around at the ends of the list
+    fn cycle_document(&mut self, forwards: bool) {
+        let count = self.documents.len();
+        if count == 1 {
+            self.set_message("No other buffers are open".to_string());
+            return;
+        }
+
+        let index = match forwards {
+            true => (self.document_index + 1) % count,
+            false => (self.document_index + count - 1) % count,
+        };
+        self.switch_document(index);
+        self.set_message(format!(
+            "Buffer {} of {}: {}",
+            index + 1,
+            count,
+            self.display_name()
+        ));
+    }
+
+    // Switches to an open file given by its number in the list of buffers, or by part of its name
+    // [EXAMPLE] ":b 2", ":b main.rs"
+    fn go_to_document(&mut self, name: &str) {
+        let names = self.document_names();
+        let matching: Vec<usize> = match name.parse::<usize>() {
+            Ok(number) => (1..=names.len())
+                .filter(|&other| other == number)
+                .map(|number| number - 1)
+                .collect(),
+            Err(_) => (0..names.len())
+                .filter(|&index| names[index].0.contains(name))
+                .collect(),
+        };
+
+        match matching.as_slice() {
+            [index] => self.switch_document(*index),
+            [] => self.set_error(format!("No buffer matches {}", name)),
+            _ => self.set_error(format!("More than one buffer matches {}", name)),
+        }
+    }
+
+    // Opens a panel listing the open files, where Enter switches to the selected one
+    fn open_buffers_panel(&mut self) {
+        let items = self
+            .document_names()
+            .into_iter()
+            .enumerate()
+            .map(|(index, (name, modified))| {
+                let icon = match self.config.icons {
+                    true => format!("{} ", file_icon(&name)),
+                    false => String::new(),
+                };
+                let marker = if modified { "[+] " } else { "" };
+                format!("{:>2}  {}{}{}", index + 1, marker, icon, name)
+            })
+            .collect();
+
+        let mut panel = Panel::new(PanelKind::Buffers, "Buffers", items);
+        panel.select(self.document_index);
+        self.panel = Some(panel);
+        self.queue_frame_update();
+    }
+
+    // Closes the file being edited and switches to the one after it, unless that would lose changes
+    fn close_document(&mut self, force: bool) {
+        if self.documents.len() == 1 {
+            self.set_warning("This is the only open buffer (quit with :q instead)".to_string());
+            return;
+        }
+
+        if self.is_modified() && !force {
+            self.set_error(format!(
+                "{} has unsaved changes (add ! to discard them)",
+                self.display_name()
+            ));
+            return;
+        }
+
+        if self.save_task.is_running() {
+            self.set_warning(format!("{} is still being saved", self.display_name()));
+            return;
+        }
+
+        let name = self.display_name();
+        let closed = self.document_index;
+        let next = match closed + 1 < self.documents.len() {
+            true => closed + 1,
+            false => closed - 1,
+        };
+        self.show_document(next);
+        self.documents.remove(closed);
+        self.jumps.close_document(closed);
+        if self.document_index > closed {
+            self.document_index -= 1;
+        }
+
+        self.set_message(format!("Closed {}", name));
+    }
+
+    // Opens a panel listing the syntax errors in every open buffer, along with the errors and warnings
+    // in the output of the last task
+    // * Buffers which are still loading are skipped, since their text is not there yet
+    fn open_diagnostics_panel(&mut self) {
+        let mut found = Vec::new();
+        for index in 0..self.documents.len() {
+            let (filename, buffer, loading) = match index == self.document_index {
+                true => (&self.filename, &self.buffer, self.load_task.is_some()),
+                false => {
+                    let document = &self.documents[index];
+                    (
+                        &document.filename,
+                        &document.buffer,
+                        document.load_task.is_some(),
+                    )
+                }
+            };
+            if !loading {
+                found.extend(diagnostics::syntax_errors(filename, buffer));
+            }
+        }
+        if let Some(run) = self.task_run.as_ref() {
+            found.extend(diagnostics::task_diagnostics(run.lines()));
+        }
+
+        if found.is_empty() {
+            self.set_message("No errors or warnings were found".to_string());
+            return;
+        }
+
+        self.diagnostics.set(found);
+        self.panel = Some(Panel::new(PanelKind::Diagnostics, "", Vec::new()));
+        self.update_diagnostics_panel();
+    }
+
+    // Lists the diagnostics in the diagnostics panel again once they have been sorted or filtered
+    fn update_diagnostics_panel(&mut self) {
+        let Some(panel) = self
+            .panel
+            .as_mut()
+            .filter(|panel| panel.kind() == PanelKind::Diagnostics)
+        else {
+            return;
+        };
+
+        panel.set_title(&self.diagnostics.title());
+        panel.set_items(self.diagnostics.items());
+        panel.select(0);
+        self.queue_frame_update();
+    }
+
+    // Moves the cursor to a line and column of a file, switching to it or opening it first
+    // * Scratch buffers are matched by their name, since it is not a path
+    fn go_to_location(&mut self, filename: &str, line: usize, column: usize) {
+        let open = (0..self.documents.len())
+            .find(|&index| self.document_filename(index) == filename)
+            .or_else(|| self.find_document(Path::new(filename)));
+        match open {
+            Some(index) => self.switch_document(index),
+            None => {
+                self.edit_file(filename);
+                if self.find_document(Path::new(filename)) != Some(self.document_index) {
+                    return;
+                }
+            }
+        }
+        self.finish_loading();
+
+        let line = line.min(self.buffer.line_count() - 1);
+        let column = column.min(self.buffer.line_contents(line).chars().count());
+        self.jump_to(self.buffer.line_start(line) + column);
+    }
+
+    // Sets a breakpoint on a line, or removes the one it has, and lets the debug adapter know if a
+    // program is being debugged
+    fn toggle_breakpoint(&mut self, line: usize) {
+        if self.scratch {
+            self.set_warning("Breakpoints cannot be set in a scratch buffer".to_string());
+            return;
+        }
+
+        let count = self.breakpoints.len();
+        let buffer = &self.buffer;
+        self.breakpoints
+            .retain(|&index| buffer.line_of(index.min(buffer.size())) != line);
+        match self.breakpoints.len() == count {
+            true => {
+                self.breakpoints.push(self.buffer.line_start(line));
+                self.set_message(format!("Set a breakpoint on line {}", line + 1));
+            }
+            false => self.set_message(format!("Removed the breakpoint on line {}", line + 1)),
+        }
+
+        if let (Some(session), Ok(path)) = (self.debug.as_mut(), fs::canonicalize(&self.filename)) {
+            session.set_breakpoints(path, breakpoint_lines(&self.buffer, &self.breakpoints));
+        }
+    }
+
+    // Gets the lines with breakpoints in every open file, which are sent to the debug adapter when
+    // debugging starts
+    fn all_breakpoints(&self) -> Vec<(PathBuf, Vec<usize>)> {
+        (0..self.documents.len())
+            .filter_map(|index| {
+                let (buffer, breakpoints) = match index == self.document_index {
+                    true => (&self.buffer, &self.breakpoints),
+                    false => (
+                        &self.documents[index].buffer,
+                        &self.documents[index].breakpoints,
+                    ),
+                };
+                if breakpoints.is_empty() || self.is_scratch(index) {
+                    return None;
+                }
+
+                let path = fs::canonicalize(self.document_filename(index)).ok()?;
+                Some((path, breakpoint_lines(buffer, breakpoints)))
+            })
+            .collect()
+    }
+
+    // Starts the debug adapter from the config file, or lets the program continue if it has stopped
+    fn start_debugging(&mut self) {
+        if let Some(session) = self.debug.as_mut() {
+            match session.resume() {
+                Ok(()) => self.debug_stop = None,
+                Err(error) => self.set_warning(error),
+            }
+            return;
+        }
+
+        let Some(config) = self.config.debugger.clone() else {
+            self.set_warning(
+                "No debug adapter is set up (add a [debug] table to the config file)".to_string(),
+            );
+            return;
+        };
+
+        match DebugSession::start(&config, self.all_breakpoints()) {
+            Ok(session) => {
+                self.debug = Some(session);
+                self.set_message(format!("Started debugging with {}", config.adapter));
+            }
+            Err(error) => self.set_error(format!(
+                "{} could not be started: {}",
+                config.adapter, error
+            )),
+        }
+    }
+
+    // Asks the debug adapter to end the program, or stops the adapter itself if it was already asked
+    fn stop_debugging(&mut self) {
+        let Some(session) = self.debug.as_mut() else {
+            self.set_warning("Nothing is being debugged".to_string());
+            return;
+        };
+
+        match session.is_stopping() {
+            true => {
+                self.debug = None;
+                self.debug_stop = None;
+                self.set_message("Stopped debugging".to_string());
+            }
+            false => {
+                session.stop();
+                self.set_message("Stopping the program".to_string());
+            }
+        }
+    }
+
+    // Steps through the stopped program in one of the ways the debug adapter can
+    fn debug_step(&mut self, step: fn(&mut DebugSession) -> std::result::Result<(), String>) {
+        let Some(session) = self.debug.as_mut() else {
+            self.set_warning("Nothing is being debugged".to_string());
+            return;
+        };
+
+        match step(session) {
+            Ok(()) => self.debug_stop = None,
+            Err(error) => self.set_warning(error),
+        }
+    }
+
+    // Asks for the variables where the program has stopped, which are shown in a panel once they arrive
+    fn show_variables(&mut self) {
+        let result = match self.debug.as_mut() {
+            Some(session) => session.request_variables(),
+            None => Err("Nothing is being debugged".to_string()),
+        };
+
+        if let Err(error) = result {
+            self.set_warning(error);
+        }
+    }
+
+    // Handles what the debug adapter has reported since it was last checked
+    fn check_debug_session(&mut self) {
+        let Some(session) = self.debug.as_mut() else {
+            return;
+        };
+
+        for update in session.poll() {
+            match update {
+                DebugUpdate::Stopped { reason, location } => {
+                    match location {
+                        Some((path, line)) => {
+                            self.go_to_location(&path, line, 0);
+                            self.debug_stop = fs::canonicalize(&path).ok().map(|path| (path, line));
+                            self.set_message(format!(
+                                "Stopped at line {} of {} ({})",
+                                line + 1,
+                                relative_filename(Path::new(&path)),
+                                reason
+                            ));
+                        }
+                        None => self.set_message(format!("Stopped ({})", reason)),
+                    }
+
+                    // The variables panel is kept up to date while it is open
+                    let variables_shown = self
+                        .panel
+                        .as_ref()
+                        .is_some_and(|panel| panel.kind() == PanelKind::Variables);
+                    if let (true, Some(session)) = (variables_shown, self.debug.as_mut()) {
+                        let _ = session.request_variables();
+                    }
+                }
+                DebugUpdate::Continued => self.debug_stop = None,
+                // * The program's output replaces itself in the status bar, so it does not queue up
+                DebugUpdate::Output(line) => self.messages.replace(MessageLevel::Info, line),
+                DebugUpdate::Variables(lines) if lines.is_empty() => {
+                    self.set_message("There are no variables here".to_string());
+                }
+                DebugUpdate::Variables(lines) => {
+                    // Another panel which was opened in the meantime is left alone
+                    if self
+                        .panel
+                        .as_ref()
+                        .is_none_or(|panel| panel.kind() == PanelKind::Variables)
+                    {
+                        self.panel = Some(Panel::new(
+                            PanelKind::Variables,
+                            "Variables - Esc to close",
+                            lines,
+                        ));
+                    }
+                }
+                DebugUpdate::Error(error) => self.set_error(error),
+                DebugUpdate::Ended(reason) => {
+                    self.debug = None;
+                    self.debug_stop = None;
+                    self.set_message(reason);
+                }
+            }
+
+            self.queue_frame_update();
+        }
+    }
+
+    // Gets the line of the buffer being edited which the program being debugged has stopped on
+    fn debug_line(&self) -> Option<usize> {
+        let (path, line) = self.debug_stop.as_ref()?;
+        let filename = fs::canonicalize(&self.filename).ok()?;
+
+        (filename == *path).then_some(*line)
+    }
+
+    // Opens a panel listing the tasks which can be run
+    fn open_tasks_panel(&mut self) {
+        if self.task_definitions.is_empty() {
+            self.set_warning(
+                "There are no tasks (they can be added with --task or in the config file)"
+                    .to_string(),
+            );
+            return;
+        }
+
+        let width = self
+            .task_definitions
+            .iter()
+            .map(|task| task.name.len())
+            .max()
+            .unwrap_or(0);
+        let items = self
+            .task_definitions
+            .iter()
+            .map(|task| format!("{:<width$}  {}", task.name, task.command))
+            .collect();
+
+        self.panel = Some(Panel::new(PanelKind::Tasks, "Tasks", items));
+        self.queue_frame_update();
+    }
+
+    // Starts running a task, stopping the last one if it is still running, and shows its output
+    fn run_task(&mut self, task: TaskDefinition) {
+        let name = task.name.clone();
+        self.task_run = None;
+
+        match TaskRun::start(task) {
+            Ok(run) => {
+                self.task_run = Some(run);
+                self.open_task_output_panel();
+            }
+            Err(error) => self.set_error(format!("Failed to start {}: {}", name, error)),
+        }
+    }
+
+    // Opens a panel showing the output of the task which was run last
+    fn open_task_output_panel(&mut self) {
+        if self.task_run.is_none() {
+            self.set_warning("No task has been run yet".to_string());
+            return;
+        }
+
+        self.panel = Some(Panel::new(PanelKind::TaskOutput, "", Vec::new()));
+        self.update_task_output();
+    }
+
+    // Updates the task output panel, if it is open, with the output and state of the last task
+    fn update_task_output(&mut self) {
+        let (Some(run), Some(panel)) = (self.task_run.as_ref(), self.panel.as_mut()) else {
+            return;
+        };
+        if panel.kind() != PanelKind::TaskOutput {
+            return;
+        }
+
+        let state = match run.status() {
+            None => "running".to_string(),
+            Some(status) => describe_exit(status),
+        };
+        panel.set_title(&format!(
+            "{} ({}) - S to stop, R to run again, Esc to close",
+            run.definition().name,
+            state
+        ));
+        panel.set_items(run.lines().to_vec());
+
+        self.queue_frame_update();
+    }
+
+    // Opens a panel showing the image whose path is under the cursor
+    // The image itself is only shown if it is a PNG and the terminal supports the Kitty graphics protocol,
+    // and otherwise only its format, dimensions, and size are shown
+    fn preview_image(&mut self) -> Result<()> {
+        let Some(path) = self.path_at_cursor() else {
+            self.set_warning("There is no file path under the cursor".to_string());
+            return Ok(());
+        };
+
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                self.set_error(format!("Failed to read {}: {}", path, error));
+                return Ok(());
+            }
+        };
+
+        let Some(info) = image_info(&bytes) else {
+            self.set_warning(format!("{} is not an image", path));
+            return Ok(());
+        };
+
+        let mut items = vec![images::describe(&info, bytes.len())];
+        let image = match (info.format, images::graphics_supported()) {
+            ("PNG", true) => Some(bytes),
+            (_, true) => {
+                items.push("Only PNG images can be previewed".to_string());
+                None
+            }
+            (_, false) => {
+                items.push("This terminal cannot show images".to_string());
+                None
+            }
+        };
+
+        self.terminal.set_image(image)?;
+        self.panel = Some(Panel::new(PanelKind::Image, &path, items));
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Gets the file path under the cursor, if it names a file which exists
+    // Relative paths are looked up next to the file being edited first, and then in the current directory
+    // [EXAMPLE] with the cursor in "![logo](images/logo.png)", the path is "images/logo.png"
+    fn path_at_cursor(&self) -> Option<String> {
+        let line = self.terminal.cursor().line();
+        let contents: Vec<char> = self.buffer.line_contents(line).chars().collect();
+        let column = self.terminal.cursor().index() - self.buffer.line_start(line);
+
+        let is_path_char = |c: char| !c.is_whitespace() && !"\"'`()<>[]{}".contains(c);
+        let start = (0..column.min(contents.len()))
+            .rev()
+            .take_while(|&i| is_path_char(contents[i]))
+            .last()
+            .unwrap_or(column);
+        let end = (column..contents.len())
+            .find(|&i| !is_path_char(contents[i]))
+            .unwrap_or(contents.len());
+
+        let path: String = contents.get(start..end)?.iter().collect();
+        let path = path.trim_end_matches([',', '.', ';', ':']);
+        if path.is_empty() {
+            return None;
+        }
+
+        let beside_file = Path::new(&self.filename)
+            .parent()
+            .map(|directory| directory.join(path));
+
+        [beside_file, Some(PathBuf::from(path))]
+            .into_iter()
+            .flatten()
+            .find(|candidate| candidate.is_file())
+            .map(|candidate| candidate.to_string_lossy().to_string())
+    }
+
+    // Opens a panel listing all of the marks in the buffer
+    fn open_marks_panel(&mut self) -> Result<()> {
+        let items = self
+            .marks
+            .iter()
+            .map(|(name, index)| {
+                let (x, y) = self
+                    .buffer
+                    .cursor_coord(index)
+                    .expect("[INTERNAL ERROR] Mark position was out of bounds");
+
+                format!(
+                    "{}  {}:{}  {}",
+                    name,
+                    y + 1,
+                    x + 1,
+                    self.buffer.line_contents(y as usize).trim()
+                )
+            })
+            .collect();
+
+        self.panel = Some(Panel::new(PanelKind::Marks, "Marks", items));
+
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Opens a panel visualizing the undo tree, with the current state selected
+    fn open_undo_tree_panel(&mut self) -> Result<()> {
+        let rows = self.history.tree_rows();
+        let current = rows
+            .iter()
+            .position(|&(id, _)| id == self.history.current())
+            .unwrap_or(0);
+
+        let items = rows.into_iter().map(|(_, row)| row).collect();
+
+        let mut panel = Panel::new(PanelKind::UndoTree, "Undo Tree", items);
+        panel.select(current);
+        self.panel = Some(panel);
+
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Moves the cursor to the position of a mark
+    fn jump_to_mark(&mut self, name: char) {
+        if let Some(index) = self.marks.get(name) {
+            self.jump_to(index);
+        }
+    }
+
+    // Moves the cursor using one of the CursorPosition movement methods
+    // * Moving the cursor clears the selection, so extend_selection is used to select text instead
+    fn move_cursor(&mut self, movement: impl FnOnce(&mut CursorPosition, &Buffer)) -> Result<()> {
+        movement(self.terminal.cursor_mut(), &self.buffer);
+        self.selection_anchor = None;
+        self.extra_cursors.clear();
+
+        // Typing after moving the cursor should be undone separately
+        self.history.break_group();
+
+        // The whole frame is updated so that the status bar reflects the new position
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Moves the cursor while keeping the selection, starting a new selection at the cursor if needed
+    fn extend_selection(&mut self, movement: impl FnOnce(&mut Self) -> Result<()>) -> Result<()> {
+        let anchor = self
+            .selection_anchor
+            .unwrap_or(self.terminal.cursor().index());
+        movement(self)?;
+        self.selection_anchor = Some(anchor);
+
+        Ok(())
+    }
+
+    // Selects the paragraph the cursor is in, including the line break at the end of its last line
+    fn select_paragraph(&mut self) {
+        let lines = self.buffer.paragraph_lines(self.terminal.cursor().line());
+
+        let start = self.buffer.line_start(lines.start);
+        let end = match lines.end < self.buffer.line_count() {
+            true => self.buffer.line_start(lines.end),
+            false => self.buffer.size(),
+        };
+
+        self.select_range(start..end);
+    }
+
+    // Selects the innermost syntax node around the selection, or around the cursor if nothing is
+    // selected, so that doing it again selects the node around that
+    // Without a syntax tree, the innermost pair of brackets is selected instead
+    fn select_enclosing_block(&mut self) {
+        let cursor = self.terminal.cursor().index();
+        let range = self.selection().unwrap_or(cursor..cursor);
+
+        let (block, warning) = match self.syntax_tree() {
+            Some(tree) => (
+                structure::enclosing_node(&self.buffer, &tree, range),
+                "Nothing encloses the selection",
+            ),
+            None => (
+                structure::enclosing_block(&self.buffer, self.terminal.syntax(), range),
+                "Not inside of any brackets",
+            ),
+        };
+
+        match block {
+            Some(block) => self.select_range(block),
+            None => self.set_warning(warning.to_string()),
+        }
+    }
+
+    // Jumps to the start of the next or previous function definition
+    // Functions are found from the syntax tree if the file has a grammar, and otherwise by the
+    // keywords which start them
+    fn go_to_function(&mut self, forward: bool) {
+        let cursor = self.terminal.cursor().index();
+        let function = match (self.syntax_tree(), self.terminal.grammar()) {
+            (Some(tree), Some(grammar)) => {
+                structure::adjacent_function_node(&self.buffer, &tree, grammar, cursor, forward)
+            }
+            _ => match self.terminal.syntax() {
+                Some(syntax) => structure::adjacent_function(&self.buffer, syntax, cursor, forward),
+                None => {
+                    self.set_warning(
+                        "Functions can only be found in files which are highlighted".to_string(),
+                    );
+                    return;
+                }
+            },
+        };
+
+        match function {
+            Some(index) => self.jump_to(index),
+            None if forward => self.set_warning("No functions after the cursor".to_string()),
+            None => self.set_warning("No functions before the cursor".to_string()),
+        }
+    }
+
+    // Gets the syntax tree of the buffer as it is now, if the file's language has a grammar
+    fn syntax_tree(&mut self) -> Option<Tree> {
+        self.terminal
+            .apply_syntax_edits(self.buffer.take_syntax_edits());
+        self.terminal.syntax_tree(&self.buffer)
+    }
+
+    // Selects a range of the buffer, leaving the cursor at its end
+    fn select_range(&mut self, range: Range<usize>) {
+        self.selection_anchor = Some(range.start);
+        self.terminal.cursor_mut().move_to(range.end, &self.buffer);
+        self.history.break_group();
+
+        self.queue_frame_update();
+    }
+
+    // Selects a whole line, including the line break at its end
+    fn select_line(&mut self, line: usize) {
+        let start = self.buffer.line_start(line);
+        let end = match line + 1 < self.buffer.line_count() {
+            true => self.buffer.line_start(line + 1),
+            false => self.buffer.size(),
+        };
+
+        self.select_range(start..end);
+    }
+
+    // Gets the range of buffer indices which is selected, if any text is selected
+    fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.selection_anchor?;
+        let cursor = self.terminal.cursor().index();
+
+        Some(anchor.min(cursor)..anchor.max(cursor)).filter(|range| !range.is_empty())
+    }
+
+    // Copies the selection to the system clipboard, or the current line if nothing is selected
+    fn copy(&mut self) -> Result<()> {
+        let range = self.selection().unwrap_or_else(|| {
+            let line = self.terminal.cursor().line();
+            let start = self.buffer.line_start(line);
+
+            start..start + self.buffer.line_len(line)
+        });
+
+        let text = self.buffer.text_range(range);
+        self.terminal.copy_to_clipboard(&text)?;
+        self.set_message(format!("Copied {} characters", text.chars().count()));
+
+        Ok(())
+    }
+
+    // Moves the cursor up or down using one of the CursorPosition movement methods,
+    // skipping over any lines which are hidden by folds
+    fn move_vertically(&mut self, movement: fn(&mut CursorPosition, &Buffer)) -> Result<()> {
+        let hidden_lines = self.folds.hidden_lines(&self.buffer);
+
+        self.move_cursor(|cursor, buffer| {
+            let start_line = cursor.line();
+            movement(cursor, buffer);
+
+            let line = cursor.line();
+            if let Some(range) = hidden_lines.iter().find(|range| range.contains(&line)) {
+                // If the fold reaches the end of the buffer, there is nothing below it to move to
+                let line = match line > start_line && range.end < buffer.line_count() {
+                    true => range.end,
+                    false => range.start - 1,
+                };
+
+                cursor.move_to_line(line, buffer);
+            }
+        })
+    }
+
+    // Moves the cursor down or up by one row of the text area, which is a whole line unless soft
+    // wrap has split the line into several rows
+    fn move_row(&mut self, down: bool) -> Result<()> {
+        match down {
+            _ if self.terminal.soft_wrap() && self.terminal.table().is_none() => {
+                self.terminal.move_cursor_row(&self.buffer, down);
+                // The cursor has already been moved, so this only does the usual bookkeeping
+                self.move_cursor(|_, _| ())
+            }
+            true => self.move_vertically(CursorPosition::move_down),
+            false => self.move_vertically(CursorPosition::move_up),
+        }
+    }
+
+    // Scrolls the view so that the cursor's line is at the given position in the text area
+    fn recenter(&mut self, position: ViewPosition) -> Result<()> {
+        self.sync_folds();
+        self.terminal.recenter(&self.buffer, position)?;
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Moves the cursor to the given buffer index, recording the jump in the jump list
+    fn jump_to(&mut self, index: usize) {
+        self.jumps.record(self.current_jump());
+        self.terminal.cursor_mut().move_to(index, &self.buffer);
+        self.extra_cursors.clear();
+        self.history.break_group();
+    }
+
+    // Moves the cursor to the next match of the most recent search after (or before) the cursor,
+    // wrapping around the end of the buffer
+    fn next_match(&mut self, forwards: bool) {
+        let Some(search) = self.search.as_mut() else {
+            self.set_message("Nothing has been searched for yet".to_string());
+            return;
+        };
+
+        let cursor = self.terminal.cursor().index();
+        let matches = search.matches(&self.buffer);
+        self.show_search_matches = true;
+        let position = match forwards {
+            true => matches
+                .iter()
+                .position(|range| range.start > cursor)
+                .or((!matches.is_empty()).then_some(0)),
+            false => matches
+                .iter()
+                .rposition(|range| range.start < cursor)
+                .or(matches.len().checked_sub(1)),
+        };
+
+        let Some(position) = position else {
+            let message = format!("No matches for \"{}\"", search.pattern());
+            self.set_warning(message);
+            return;
+        };
+
+        let index = matches[position].start;
+        let message = format!("Match {} of {}", position + 1, matches.len());
+        self.jump_to(index);
+
+        // * The count is replaced rather than queued, so it keeps up when moving through matches quickly
+        self.messages.replace(MessageLevel::Info, message);
+        self.queue_frame_update();
+    }
+
+    // Replaces every match of a regex in a range of the buffer (or the whole buffer) as a single edit
+    // Capture groups can be used in the replacement as $1 or ${1}, and named groups as ${name}
+    fn replace_all(&mut self, regex: &Regex, range: Option<Range<usize>>, replacement: &str) {
+        if !self.check_writable() {
+            return;
+        }
+
+        let range = range.unwrap_or(0..self.buffer.size());
+        let text = self.buffer.text_range(range.clone());
+
+        // The regex reports byte offsets, which are turned into character indices as the matches are
+        // walked in order, since they are all after the previous one
+        let mut matches = Vec::new();
+        let (mut byte_index, mut index) = (0, range.start);
+        for captures in regex.captures_iter(&text) {
+            let found = captures
+                .get(0)
+                .expect("[INTERNAL ERROR] A match always has a whole-match group");
+            index += text[byte_index..found.start()].chars().count();
+            byte_index = found.start();
+
+            let mut replaced = String::new();
+            captures.expand(replacement, &mut replaced);
+            matches.push((index, found.as_str(), replaced));
+        }
+
+        if matches.is_empty() {
+            self.set_warning("No matches to replace".to_string());
+            return;
+        }
+
+        // The matches are replaced from last to first, so each edit leaves the earlier indices alone
+        let mut edits = Vec::new();
+        for (index, found, replaced) in matches.iter().rev() {
+            let (index, replaced) = (*index, replaced.clone());
+
+            if !found.is_empty() {
+                edits.push(Edit::Delete {
+                    index,
+                    text: found.to_string(),
+                });
+            }
+            if !replaced.is_empty() {
+                edits.push(Edit::Insert {
+                    index,
+                    text: replaced,
+                });
+            }
+        }
+
+        let cursor = self.terminal.cursor().index();
+        for edit in edits.iter() {
+            self.apply_edit(edit);
+        }
+        self.history.record_together(edits);
+
+        self.terminal
+            .cursor_mut()
+            .move_to(cursor.min(self.buffer.size()), &self.buffer);
+        self.set_message(format!(
+            "Replaced {} match{}",
+            matches.len(),
+            if matches.len() == 1 { "" } else { "es" }
+        ));
+    }
+
+    // Gets the position of the cursor in the file being edited, as it is recorded in the jump list
+    fn current_jump(&self) -> Jump {
+        Jump {
+            document: self.document_index,
+            index: self.terminal.cursor().index(),
+        }
+    }
+
+    // Moves the cursor to the previous position in the jump list, which may be in another file
+    fn jump_back(&mut self) -> Result<()> {
+        match self.jumps.back(self.current_jump()) {
+            Some(jump) => self.return_to_jump(jump),
+            None => Ok(()),
+        }
+    }
+
+    // Moves the cursor to the next position in the jump list, which may be in another file
+    fn jump_forward(&mut self) -> Result<()> {
+        match self.jumps.forward() {
+            Some(jump) => self.return_to_jump(jump),
+            None => Ok(()),
+        }
+    }
+
+    // Moves the cursor to a position from the jump list, without recording it as another jump
+    fn return_to_jump(&mut self, jump: Jump) -> Result<()> {
+        self.show_document(jump.document);
+        self.move_cursor(|cursor, buffer| cursor.move_to(jump.index, buffer))
+    }
+
+    // Inserts a character into the buffer at the cursor position
+    // * Combining characters (such as accents) join the grapheme before the cursor,
+    // * so the cursor is moved by exactly one char rather than by one grapheme
+    fn insert_char(&mut self, character: char) -> Result<()> {
+        if !self.check_writable() {
+            return Ok(());
+        }
+
+        // * With extra cursors, the character is inserted at each of them even in overwrite mode
+        if !self.extra_cursors.is_empty() {
+            let text = character.to_string();
+            self.edit_at_cursors(|_, _, index| {
+                vec![Edit::Insert {
+                    index,
+                    text: text.clone(),
+                }]
+            });
+            return Ok(());
+        }
+
+        // Get the index at which the character should be inserted
+        let buffer_index = self.terminal.cursor().index();
+
+        // In overwrite mode, the character replaces the one under the cursor,
+        // unless the cursor is at the end of the line
+        let next_boundary = self.buffer.next_grapheme_boundary(buffer_index);
+        let replaced = self.buffer.text_range(buffer_index..next_boundary);
+        if self.overwrite && character != '\n' && !replaced.is_empty() && replaced != "\n" {
+            let edits = vec![
+                Edit::Delete {
+                    index: buffer_index,
+                    text: replaced,
+                },
+                Edit::Insert {
+                    index: buffer_index,
+                    text: character.to_string(),
+                },
+            ];
+
+            for edit in edits.iter() {
+                self.apply_edit(edit);
+            }
+            self.history.record_together(edits);
+        } else {
+            // Insert the character into the buffer
+            self.insert_at(buffer_index, character);
+        }
+
+        // Adjust the cursor position
+        self.terminal
+            .cursor_mut()
+            .move_to(buffer_index + 1, &self.buffer);
+
+        // Update the terminal
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Inserts a string of text at the cursor as a single edit, such as pasted text or text
+    // committed by an input method (which may be several characters making up one grapheme)
+    // * Inserting the characters one at a time would split composed characters across edits
+    // * Pasting the path of an existing file (as terminals do when a file is dropped onto them) offers
+    // * to open the file instead, with the key to accept shown in the status bar
+    fn insert_text(&mut self, text: &str) -> Result<()> {
+        // Text sent to a prompt only keeps its first line, since prompts are a single line
+        if let Some(prompt) = self.prompt.as_mut() {
+            text.lines()
+                .next()
+                .unwrap_or_default()
+                .chars()
+                .filter(|c| !c.is_control())
+                .for_each(|c| prompt.insert(c));
+            self.queue_frame_update();
+            return Ok(());
+        }
+
+        if self.panel.is_some()
+            || self.dialog.is_some()
+            || text.is_empty()
+            || !self.check_writable()
+        {
+            return Ok(());
+        }
+
+        // Terminals send line breaks as carriage returns, which the buffer does not use
+        let mut text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+        // With extra cursors, the text is pasted at each of them as it is
+        if !self.extra_cursors.is_empty() {
+            self.edit_at_cursors(|_, _, index| {
+                vec![Edit::Insert {
+                    index,
+                    text: text.clone(),
+                }]
+            });
+            return Ok(());
+        }
+
+        // Multi-line text is reindented to line up with the line it is pasted into
+        let index = self.terminal.cursor().index();
+        if !self.raw_paste && text.contains('\n') {
+            let line = self.buffer.line_of(index);
+            let line_start = self.buffer.line_start(line);
+            let indentation: String = self
+                .buffer
+                .line_contents(line)
+                .chars()
+                .take_while(|&c| c == ' ' || c == '\t')
+                .collect();
+
+            // If the cursor is right after the line's indentation, the first line of the pasted
+            // text is already indented by it, so its own indentation is dropped
+            let at_indentation = index - line_start == indentation.chars().count();
+            text = reindent(&text, &indentation, at_indentation);
+        }
+
+        // The path is pasted as its own edit, so that undoing it takes back nothing else
+        let path = pasted_path(&text);
+        if path.is_some() {
+            self.history.break_group();
+        }
+
+        let edit = Edit::Insert { index, text };
+
+        self.apply_edit(&edit);
+        let cursor_index = edit.cursor_after();
+        self.history.record(edit);
+
+        self.terminal
+            .cursor_mut()
+            .move_to(cursor_index, &self.buffer);
+
+        if let Some(path) = path {
+            self.history.break_group();
+            let key = self
+                .keymap
+                .key_for(Action::OpenPastedPath, Some("Alt+Enter"))
+                .unwrap_or_else(|| "the command palette".to_string());
+            self.set_message(format!(
+                "Pasted the path of {} (open it instead with {})",
+                path.display(),
+                key
+            ));
+            self.pasted_path = Some((path, self.buffer.version()));
+        }
+
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Takes back the path which was just pasted and opens the file it leads to in a new buffer
+    fn open_pasted_path(&mut self) -> Result<()> {
+        let Some((path, version)) = self.pasted_path.take() else {
+            self.set_warning("No file path has just been pasted".to_string());
+            return Ok(());
+        };
+
+        if version != self.buffer.version() {
+            self.set_warning(format!(
+                "The buffer has changed since {} was pasted",
+                path.display()
+            ));
+            return Ok(());
+        }
+
+        self.undo()?;
+        self.edit_file(&path.to_string_lossy());
+
+        Ok(())
+    }
+
+    // Deletes the grapheme in the buffer immediately preceding the cursor,
+    // or alternatively immediately after the cursor (delete_mode)
+    // * A whole grapheme is deleted so that, for example, an accented letter made of a letter
+    // * and a combining accent is deleted as one character
+    fn remove_char(&mut self, deletion_mode: DeletionMode) -> Result<()> {
+        use DeletionMode::*;
+
+        if !self.check_writable() {
+            return Ok(());
+        }
+
+        // With extra cursors, a grapheme is deleted at each of them
+        if !self.extra_cursors.is_empty() {
+            self.edit_at_cursors(|buffer, _, index| {
+                let range = match deletion_mode {
+                    Backspace => buffer.prev_grapheme_boundary(index)..index,
+                    Delete => index..buffer.next_grapheme_boundary(index),
+                };
+
+                match range.is_empty() {
+                    true => Vec::new(),
+                    false => vec![Edit::Delete {
+                        index: range.start,
+                        text: buffer.text_range(range),
+                    }],
+                }
+            });
+            return Ok(());
+        }
+
+        let buffer_index = self.terminal.cursor().index();
+
+        // Get the range of the grapheme that should be deleted, adjusting for the deletion mode
+        let range = match deletion_mode {
+            Backspace => self.buffer.prev_grapheme_boundary(buffer_index)..buffer_index,
+            Delete => buffer_index..self.buffer.next_grapheme_boundary(buffer_index),
+        };
+
+        // Avoid deleting past either end of the buffer
+        if range.is_empty() {
+            return Ok(());
+        }
+
+        // Delete the grapheme from the buffer
+        if self.screen_reader {
+            let text = self.buffer.text_range(range.clone());
+            self.set_message(format!("Deleted {}", spoken_text(&text)));
+        }
+
+        self.delete_range(range.clone());
+
+        // Adjust the cursor position depending on the deletion mode
+        if let Backspace = deletion_mode {
+            self.terminal
+                .cursor_mut()
+                .move_to(range.start, &self.buffer);
+        }
+
+        // Update the terminal
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Deletes the word immediately preceding the cursor
+    fn remove_word(&mut self) -> Result<()> {
+        if !self.check_writable() {
+            return Ok(());
+        }
+
+        // Get the index range of the word that should be deleted
+        let word_end = self.terminal.cursor().index();
+        let word_start = self.buffer.start_of_word(word_end);
+
+        // Delete the word from the buffer
+        self.kill_range(word_start..word_end);
+
+        // Adjust the cursor position
+        self.terminal.cursor_mut().move_to(word_start, &self.buffer);
+
+        // Update the terminal
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Deletes the subword before the cursor, or alternatively after the cursor (deletion_mode)
+    fn remove_subword(&mut self, deletion_mode: DeletionMode) -> Result<()> {
+        if !self.check_writable() {
+            return Ok(());
+        }
+
+        let cursor = self.terminal.cursor().index();
+        let range = match deletion_mode {
+            DeletionMode::Backspace => self.buffer.start_of_subword(cursor)..cursor,
+            DeletionMode::Delete => cursor..self.buffer.end_of_subword(cursor),
+        };
+
+        if !range.is_empty() {
+            self.kill_range(range.clone());
+        }
+
+        self.terminal
+            .cursor_mut()
+            .move_to(range.start, &self.buffer);
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Deletes the text between the cursor and the end of the sentence
+    // TODO: Once there is a modal mode, make sentences (and the other motions) usable as the targets
+    // TODO: of delete and change operators, rather than having a separate command for each
+    fn remove_sentence(&mut self) -> Result<()> {
+        if !self.check_writable() {
+            return Ok(());
+        }
+
+        let start = self.terminal.cursor().index();
+        let end = self.buffer.end_of_sentence(start);
+        if end > start {
+            self.kill_range(start..end);
+        }
+
+        self.terminal.cursor_mut().move_to(start, &self.buffer);
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Deletes from the cursor to the end of the line, keeping the text in the kill ring
+    // At the end of a line, the line break is deleted instead, joining the next line onto it, like Emacs
+    fn kill_line(&mut self) -> Result<()> {
+        if !self.check_writable() {
+            return Ok(());
+        }
+
+        let start = self.terminal.cursor().index();
+        let line = self.terminal.cursor().line();
+        let end = match self.buffer.line_end(line) {
+            end if end > start => end,
+            _ => self.buffer.line_start(line) + self.buffer.line_len(line),
+        };
+        if end > start {
+            self.kill_range(start..end);
+        }
+
+        self.terminal.cursor_mut().move_to(start, &self.buffer);
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Deletes the selection, keeping the text in the kill ring
+    fn kill_selection(&mut self) -> Result<()> {
+        if !self.check_writable() {
+            return Ok(());
+        }
+
+        let Some(range) = self.selection() else {
+            self.set_warning("Nothing is selected".to_string());
+            return Ok(());
+        };
+
+        self.kill_range(range.clone());
+        self.selection_anchor = None;
+        self.terminal
+            .cursor_mut()
+            .move_to(range.start, &self.buffer);
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Deletes a range of characters from the buffer, keeping the text in the kill ring
+    fn kill_range(&mut self, range: Range<usize>) {
+        self.kill_ring.push(self.buffer.text_range(range.clone()));
+        self.delete_range(range);
+    }
+
+    // Inserts the most recently deleted text at the cursor
+    // If the last thing done was a yank, the yanked text is replaced with the text deleted before it
+    fn yank(&mut self) -> Result<()> {
+        if !self.check_writable() {
+            return Ok(());
+        }
+
+        let cursor = self.terminal.cursor().index();
+        let repeated = self
+            .last_yank
+            .take()
+            .filter(|(range, version)| *version == self.buffer.version() && range.end == cursor)
+            .map(|(range, _)| range);
+
+        let text = match repeated {
+            Some(_) => self.kill_ring.cycle(),
+            None => self.kill_ring.yank(),
+        };
+        let Some(text) = text.map(str::to_string) else {
+            self.set_message("Nothing to yank".to_string());
+            return Ok(());
+        };
+
+        let mut edits = Vec::new();
+        let index = match &repeated {
+            Some(range) => {
+                edits.push(Edit::Delete {
+                    index: range.start,
+                    text: self.buffer.text_range(range.clone()),
+                });
+                range.start
+            }
+            None => cursor,
+        };
+        edits.push(Edit::Insert { index, text });
+
+        for edit in edits.iter() {
+            self.apply_edit(edit);
+        }
+        let end = edits
+            .last()
+            .expect("[INTERNAL ERROR] A yank always inserts text")
+            .cursor_after();
+        self.history.record_together(edits);
+
+        self.terminal.cursor_mut().move_to(end, &self.buffer);
+        self.last_yank = Some((index..end, self.buffer.version()));
+
+        if repeated.is_some() {
+            self.set_message(format!(
+                "Yanked deleted text {} of {}",
+                self.kill_ring.position(),
+                self.kill_ring.size()
+            ));
+        }
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Reads a file and inserts its contents at the cursor as a single edit
+    fn insert_file(&mut self, path: &str) -> Result<()> {
+        if !self.check_writable() {
+            return Ok(());
+        }
+
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) => {
+                self.set_error(format!("Failed to read {}: {}", path, error));
+                return Ok(());
+            }
+        };
+
+        // * The file is inserted as it is, rather than being reindented like pasted text
+        let edit = Edit::Insert {
+            index: self.terminal.cursor().index(),
+            text,
+        };
+
+        // * Recorded on its own so it is never grouped with any typing around it
+        self.apply_edit(&edit);
+        let cursor_index = edit.cursor_after();
+        self.history.record_together(vec![edit]);
+
+        self.terminal
+            .cursor_mut()
+            .move_to(cursor_index, &self.buffer);
+        self.set_message(format!("Inserted {}", path));
+
+        Ok(())
+    }
+
+    // Adds an amount to the number under or after the cursor on the current line
+    fn increment(&mut self, amount: i64) {
+        if !self.check_writable() {
+            return;
+        }
+
+        let line = self.terminal.cursor().line();
+        let line_start = self.buffer.line_start(line);
+        let column = self.terminal.cursor().index() - line_start;
+
+        let Some((range, replacement)) =
+            increment_number(&self.buffer.line_contents(line), column, amount)
+        else {
+            self.set_warning("There is no number to increment".to_string());
+            return;
+        };
+
+        let index = line_start + range.start;
+        let edits = vec![
+            Edit::Delete {
+                index,
+                text: self.buffer.text_range(index..line_start + range.end),
+            },
+            Edit::Insert {
+                index,
+                text: replacement.clone(),
+            },
+        ];
+
+        for edit in edits.iter() {
+            self.apply_edit(edit);
+        }
+        self.history.record_together(edits);
+
+        // The cursor ends up on the last character of the number
+        let cursor_index = index + replacement.chars().count() - 1;
+        self.terminal
+            .cursor_mut()
+            .move_to(cursor_index, &self.buffer);
+
+        self.queue_frame_update();
+    }
+
+    // Turns the table view, which lines up the columns of delimiter-separated files, on or off
+    fn toggle_table(&mut self) {
+        let table = match self.terminal.table() {
+            Some(_) => None,
+            None => {
+                let delimiter = detect_delimiter(&self.filename, &self.buffer.line_contents(0));
+                let name = match delimiter {
+                    '\t' => "tab".to_string(),
+                    delimiter => format!("'{}'", delimiter),
+                };
+                self.set_message(format!("Lining up columns separated by {}", name));
+
+                Some(Table::new(delimiter))
+            }
+        };
+
+        self.terminal.set_table(table);
+        self.queue_frame_update();
+    }
+
+    // Turns soft wrap on or off, which wraps long lines onto several rows without changing the buffer
+    fn toggle_soft_wrap(&mut self) {
+        self.terminal.toggle_soft_wrap();
+        match self.terminal.soft_wrap() {
+            true => self.set_message("Soft wrap on".to_string()),
+            false => self.set_message("Soft wrap off".to_string()),
+        }
+
+        self.queue_frame_update();
+    }
+
+    // Moves the cursor to the start of the next or previous cell of the table view,
+    // continuing onto the next or previous line at the end of a row
+    fn move_to_cell(&mut self, forwards: bool) -> Result<()> {
+        let Some(table) = self.terminal.table() else {
+            return Ok(());
+        };
+
+        let cursor = self.terminal.cursor();
+        let line = cursor.line();
+        let line_start = self.buffer.line_start(line);
+        let contents = self.buffer.line_contents(line);
+        let column = cursor.index() - line_start;
+
+        let index = match table.adjacent_cell(&contents, column, forwards) {
+            Some(cell_start) => line_start + cell_start,
+            None if forwards && line + 1 < self.buffer.line_count() => {
+                self.buffer.line_start(line + 1)
+            }
+            None if !forwards && line > 0 => {
+                let previous = self.buffer.line_contents(line - 1);
+                let last_cell = table.cells(&previous).last().map_or(0, |cell| cell.start);
+
+                self.buffer.line_start(line - 1) + last_cell
+            }
+            None => return Ok(()),
+        };
+
+        self.move_cursor(|cursor, buffer| cursor.move_to(index, buffer))
+    }
+
+    // Replaces the selection with the result of passing it through a filter
+    fn apply_filter(&mut self, filter: &dyn TextFilter) {
+        if !self.check_writable() {
+            return;
+        }
+
+        let Some(range) = self.selection() else {
+            self.set_warning("Select the text to transform first".to_string());
+            return;
+        };
+
+        let text = self.buffer.text_range(range.clone());
+        let transformed = match filter.apply(&text) {
+            Ok(transformed) => transformed,
+            Err(error) => {
+                self.set_warning(format!("Could not transform the selection: {}", error));
+                return;
+            }
+        };
+
+        let edits = vec![
+            Edit::Delete {
+                index: range.start,
+                text,
+            },
+            Edit::Insert {
+                index: range.start,
+                text: transformed.clone(),
+            },
+        ];
+
+        for edit in edits.iter() {
+            self.apply_edit(edit);
+        }
+        self.history.record_together(edits);
+
+        // The transformed text stays selected, so that it can be transformed again
+        self.selection_anchor = Some(range.start);
+        self.terminal
+            .cursor_mut()
+            .move_to(range.start + transformed.chars().count(), &self.buffer);
+        self.set_message(format!("Selection {}", filter.description()));
+    }
+
+    // Evaluates the selection as arithmetic and replaces it with the result
+    // If nothing is selected, the current line is evaluated instead and the result is appended to it
+    // [EXAMPLE] "12 * 4 =" -> "12 * 4 = 48"
+    fn calculate(&mut self) {
+        if !self.check_writable() {
+            return;
+        }
+
+        let selection = self.selection();
+        let range = selection.clone().unwrap_or_else(|| {
+            let line = self.terminal.cursor().line();
+            let start = self.buffer.line_start(line);
+
+            start..start + self.buffer.line_len(line)
+        });
+
+        let text = self.buffer.text_range(range.clone());
+        let expression = match selection {
+            Some(_) => text.trim(),
+            None => text.trim().trim_end_matches('='),
+        };
+
+        let result = match evaluate(expression) {
+            Ok(value) => format_result(value),
+            Err(error) => {
+                self.set_warning(format!("Could not calculate: {}", error));
+                return;
+            }
+        };
+
+        let edits = match selection {
+            Some(_) => vec![
+                Edit::Delete {
+                    index: range.start,
+                    text,
+                },
+                Edit::Insert {
+                    index: range.start,
+                    text: result.clone(),
+                },
+            ],
+            None => {
+                let separator = match text.trim_end().ends_with('=') {
+                    true => " ",
+                    false => " = ",
+                };
+                let trailing_whitespace =
+                    text.chars().rev().take_while(|c| c.is_whitespace()).count();
+
+                vec![Edit::Insert {
+                    index: range.end - trailing_whitespace,
+                    text: format!("{}{}", separator, result),
+                }]
+            }
+        };
+
+        for edit in edits.iter() {
+            self.apply_edit(edit);
+        }
+        let cursor_index = edits
+            .last()
+            .expect("[INTERNAL ERROR] Calculation produced no edits")
+            .cursor_after();
+        self.history.record_together(edits);
+
+        self.selection_anchor = None;
+        self.terminal
+            .cursor_mut()
+            .move_to(cursor_index, &self.buffer);
+        self.set_message(format!("= {}", result));
+    }
+
+    // Shows or hides the performance overlay, which shows how long frames take to draw
+    fn toggle_profiler(&mut self) {
+        self.show_profiler = !self.show_profiler;
+        self.queue_frame_update();
+    }
+
+    // Switches zen mode on or off, which centers the text in a column and hides the status bar
+    fn toggle_zen_mode(&mut self) {
+        let width = match self.terminal.zen_width() {
+            Some(_) => None,
+            None => Some(self.config.zen_width),
+        };
+
+        self.terminal.set_zen_width(width);
+        self.queue_frame_update();
+    }
+
+    // Switches between reindenting pasted text and pasting it exactly as it is
+    fn toggle_raw_paste(&mut self) {
+        self.raw_paste = !self.raw_paste;
+
+        let message = match self.raw_paste {
+            true => "Pasted text is inserted as it is",
+            false => "Pasted text is reindented to match the cursor's line",
+        };
+        self.set_message(message.to_string());
+    }
+
+    // Switches between inserting typed characters and overwriting the characters under the cursor
+    fn toggle_overwrite(&mut self) -> Result<()> {
+        self.overwrite = !self.overwrite;
+        self.terminal.set_cursor_shape(self.overwrite)?;
+
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Swaps the line the cursor is on with the line above it
+    fn move_line_up(&mut self) -> Result<()> {
+        let line = self.terminal.cursor().line();
+        if line > 0 {
+            self.swap_lines(line - 1, line);
+        }
+
+        Ok(())
+    }
+
+    // Swaps the line the cursor is on with the line below it
+    fn move_line_down(&mut self) -> Result<()> {
+        let line = self.terminal.cursor().line();
+        self.swap_lines(line, line + 1);
+
+        Ok(())
+    }
+
+    // Swaps two adjacent lines, keeping the cursor on the same line of text
+    // * The empty line after a final newline is not a real line, so it is never swapped
+    fn swap_lines(&mut self, first: usize, second: usize) {
+        let last_line = self.buffer.line_count() - 1 - self.buffer.ends_with_newline() as usize;
+        if second > last_line || !self.check_writable() {
+            return;
+        }
+
+        let first_contents = self.buffer.line_contents(first);
+        let second_contents = self.buffer.line_contents(second);
+        let index = self.buffer.line_start(first);
+
+        let cursor = self.terminal.cursor();
+        let cursor_line = cursor.line();
+        let column = cursor.index() - self.buffer.line_start(cursor_line);
+
+        let edits = vec![
+            Edit::Delete {
+                index,
+                text: format!("{}\n{}", first_contents, second_contents),
+            },
+            Edit::Insert {
+                index,
+                text: format!("{}\n{}", second_contents, first_contents),
+            },
+        ];
+
+        for edit in edits.iter() {
+            self.apply_edit(edit);
+        }
+        self.history.record_together(edits);
+
+        // The cursor moves with its line, which is now where the other line was
+        let new_line = if cursor_line == first { second } else { first };
+        let index = self.buffer.line_start(new_line) + column;
+        self.terminal.cursor_mut().move_to(index, &self.buffer);
+
+        self.queue_frame_update();
+    }
+
+    // Reverts the most recent edit
+    fn undo(&mut self) -> Result<()> {
+        if let Some(edits) = self.history.undo() {
+            self.apply_history_edits(edits);
+        }
+
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Reapplies the most recently undone edit
+    fn redo(&mut self) -> Result<()> {
+        if let Some(edits) = self.history.redo() {
+            self.apply_history_edits(edits);
+        }
+
+        self.queue_frame_update();
+
+        Ok(())
+    }
+
+    // Checks whether the buffer can be edited, letting the user know if it cannot
+    // * Undo, redo, and time travel need no check, because a read-only buffer never has any history
+    fn check_writable(&mut self) -> bool {
+        if !self.check_file_ready() {
+            return false;
+        }
+
+        if self.following {
+            self.set_warning("The buffer is read-only while following a session".to_string());
+        }
+
+        !self.following
+    }
+
+    // Adds a cursor on the line below the lowest cursor, or above the highest one, in the same column as
+    // the main cursor (or at the end of the line, if the line is shorter)
+    fn add_cursor(&mut self, below: bool) {
+        let main = self.terminal.cursor().index();
+        let column = main - self.buffer.line_start(self.buffer.line_of(main));
+        let lines = self
+            .extra_cursors
+            .iter()
+            .chain([&main])
+            .map(|&index| self.buffer.line_of(index));
+
+        let line = match below {
+            true => lines.max().map(|line| line + 1),
+            false => lines.min().and_then(|line| line.checked_sub(1)),
+        };
+        let Some(line) = line.filter(|&line| line < self.buffer.line_count()) else {
+            self.set_warning("There is no line to add a cursor on".to_string());
+            return;
+        };
+
+        let length = self.buffer.line_contents(line).chars().count();
+        self.extra_cursors
+            .push(self.buffer.line_start(line) + column.min(length));
+        self.set_message(format!(
+            "{} cursors (Esc to go back to one)",
+            self.extra_cursors.len() + 1
+        ));
+    }
+
+    // Makes an edit at the main cursor and at each extra cursor, as one step in the undo history
+    // The edits for each cursor are given its place among the cursors in order and its buffer index
+    // * The cursors are edited from last to first, so each edit leaves the cursors before it where
+    // * they are, and each cursor ends up after its own edit
+    fn edit_at_cursors(&mut self, edits_at: impl Fn(&Buffer, usize, usize) -> Vec<Edit>) {
+        let main = self.terminal.cursor().index();
+        let mut cursors = mem::take(&mut self.extra_cursors);
+        cursors.push(main);
+        cursors.sort_unstable();
+        cursors.dedup();
+        let main_position = cursors.partition_point(|&index| index < main);
+
+        let mut edits = Vec::new();
+        for nth in (0..cursors.len()).rev() {
+            for edit in edits_at(&self.buffer, nth, cursors[nth]) {
+                self.apply_edit(&edit);
+
+                // The cursors after this one have already been edited, and move along with this edit
+                for cursor in &mut cursors[nth + 1..] {
+                    *cursor = match &edit {
+                        Edit::Insert { index, text } => {
+                            adjust_index_for_insert(*cursor, *index, text.chars().count())
+                        }
+                        Edit::Delete { index, text } => {
+                            adjust_index_for_delete(*cursor, *index..*index + text.chars().count())
+                        }
+                    };
+                }
+                cursors[nth] = edit.cursor_after();
+                edits.push(edit);
+            }
+        }
+        self.history.record_together(edits);
+
+        self.terminal
+            .cursor_mut()
+            .move_to(cursors.remove(main_position), &self.buffer);
+        self.extra_cursors = cursors;
+        self.queue_frame_update();
+    }
+
+    // Opens a prompt for the value to start a sequence from, which is inserted at every cursor
+    fn open_sequence_prompt(&mut self) {
+        if self.extra_cursors.is_empty() {
+            let key = self
+                .keymap
+                .key_for(Action::AddCursorBelow, Some("Ctrl+Alt+Down"))
+                .unwrap_or_else(|| "the command palette".to_string());
+            self.set_warning(format!("Add more cursors first (with {})", key));
+            return;
+        }
+
+        self.prompt = Some(
+            Prompt::new(
+                PromptKind::Sequence,
+                "Start the sequence from (such as 1, 01, or a): ",
+            )
+            .with_input("1"),
+        );
+    }
+
+    // Inserts a sequence counting up from a starting value at the cursors, in order through the buffer
+    // [EXAMPLE] With three cursors, "9" inserts 9, 10, and 11
+    fn insert_sequence(&mut self, start: &str) {
+        if !self.check_writable() {
+            return;
+        }
+
+        let Some(values) = sequence(start, self.extra_cursors.len() + 1) else {
+            self.set_warning("A sequence starts from a number or letters".to_string());
+            return;
+        };
+
+        self.history.break_group();
+        self.edit_at_cursors(|_, nth, index| {
+            vec![Edit::Insert {
+                index,
+                text: values[nth].clone(),
+            }]
+        });
+    }
+
+    // Inserts a character into the buffer at the given index and records it in the undo history
+    fn insert_at(&mut self, index: usize, character: char) {
+        let edit = Edit::Insert {
+            index,
+            text: character.to_string(),
+        };
+
+        self.apply_edit(&edit);
+        self.history.record(edit);
+    }
+
+    // Deletes a range of characters from the buffer and records it in the undo history
+    fn delete_range(&mut self, range: Range<usize>) {
+        let edit = Edit::Delete {
+            index: range.start,
+            text: self.buffer.text_range(range),
+        };
+
+        self.apply_edit(&edit);
+        self.history.record(edit);
+    }
+
+    // Applies edits produced by the undo history, then moves the cursor to the last edit
+    // * The extra cursors are dropped, since the main cursor moves
+    fn apply_history_edits(&mut self, edits: Vec<Edit>) {
+        for edit in edits.iter() {
+            self.apply_edit(edit);
+        }
+        self.extra_cursors.clear();
+
+        if let Some(edit) = edits.last() {
+            self.terminal
+                .cursor_mut()
+                .move_to(edit.cursor_after(), &self.buffer);
+        }
+    }
+
+    // Applies an edit made by the user to the buffer, sharing it with the session if there is one
+    // * Editing clears the selection
+    fn apply_edit(&mut self, edit: &Edit) {
+        self.edit_buffer(edit);
+        self.selection_anchor = None;
+
+        if let Some(session) = self.collab.as_mut() {
+            session.local_edit(edit);
+        }
+    }
+
+    // Applies an edit to the buffer, keeping the marks, jumps, folds, and selection in place
+    fn edit_buffer(&mut self, edit: &Edit) {
+        match edit {
+            Edit::Insert { index, text } => self.buffer.insert_str(*index, text),
+            Edit::Delete { index, text } => {
+                self.buffer.delete(*index..*index + text.chars().count())
+            }
+        }
+
+        self.handle_buffer_changes();
+    }
+
+    // Shifts everything which refers to positions in the buffer to account for the buffer's changes
+    fn handle_buffer_changes(&mut self) {
+        while let Ok(change) = self.buffer_changes.try_recv() {
+            self.marks.buffer_changed(&change);
+            self.jumps.buffer_changed(&change);
+            self.folds.buffer_changed(&change);
+            self.selection_anchor = self.selection_anchor.map(|anchor| {
+                let anchor = adjust_index_for_delete(anchor, change.deleted_range());
+                adjust_index_for_insert(anchor, change.index, change.inserted_len())
+            });
+            for cursor in &mut self.extra_cursors {
+                let index = adjust_index_for_delete(*cursor, change.deleted_range());
+                *cursor = adjust_index_for_insert(index, change.index, change.inserted_len());
+            }
+            for breakpoint in &mut self.breakpoints {
+                let index = adjust_index_for_delete(*breakpoint, change.deleted_range());
+                *breakpoint = adjust_index_for_insert(index, change.index, change.inserted_len());
+            }
+        }
+    }
+
+    // Lets the terminal know which lines are hidden by folds
+    // * If the cursor has ended up inside of a fold (by jumping, editing, etc.), the fold is opened
+    fn sync_folds(&mut self) {
+        self.folds
+            .reveal(&self.buffer, self.terminal.cursor().line());
+        self.terminal
+            .set_hidden_lines(self.folds.hidden_lines(&self.buffer));
+    }
+
+    // Reads out the line the cursor is on through the status bar when the cursor moves to a new line
+    // * Screen readers follow changes to the terminal's text, and the status bar is always in the
+    // * same place, so this is more reliable than hoping the screen reader notices the new line
+    fn announce_line(&mut self) {
+        let line = self.terminal.cursor().line();
+        if self.announced_line == Some(line) {
+            return;
+        }
+
+        self.announced_line = Some(line);
+        self.messages.replace(
+            MessageLevel::Info,
+            format!(
+                "Line {}: {}",
+                line + 1,
+                spoken_text(&self.buffer.line_contents(line))
+            ),
+        );
+    }
+
+    // Marks the frame as needing to be redrawn once all pending events have been handled
+    fn queue_frame_update(&mut self) {
+        self.frame_outdated = true;
+    }
+
+    // Redraws the buffer and any open panel or prompt, then updates the cursor
+    fn update_frame(&mut self) -> Result<()> {
+        // Let the terminal know which lines need to be redrawn
+        let line_changes = self.buffer.take_line_changes();
+        self.terminal
+            .apply_line_changes(line_changes, self.buffer.line_count());
+        self.terminal
+            .apply_syntax_edits(self.buffer.take_syntax_edits());
+        self.sync_folds();
+
+        // Let the other users in the session know where the cursor is, and find out where theirs are
+        // * Followers are not shown to anyone, so their cursor is never sent
+        if let Some(session) = self.collab.as_mut() {
+            if !self.following {
+                session.local_cursor(self.terminal.cursor().index());
+            }
+
+            self.terminal.set_remote_cursors(session.remote_cursors());
+        }
+
+        self.terminal.set_selection(self.selection());
+        self.terminal.set_extra_cursors(self.extra_cursors.clone());
+
+        let search_matches = match self.search.as_mut() {
+            Some(search) if self.show_search_matches => search.matches(&self.buffer),
+            _ => Rc::default(),
+        };
+        self.terminal.set_search_matches(search_matches);
+
+        self.terminal
+            .set_breakpoints(breakpoint_lines(&self.buffer, &self.breakpoints));
+        self.terminal.set_debug_line(self.debug_line());
+
+        if self.screen_reader {
+            self.announce_line();
+        }
+
+        let tabs = self
+            .document_names()
+            .into_iter()
+            .map(|(path, modified)| Tab {
+                icon: self.config.icons.then(|| file_icon(&path)),
+                path,
+                modified,
+            })
+            .collect();
+        self.terminal.set_tabs(tabs, self.document_index);
+
+        let display_name = self.display_name();
+        let status = Status {
+            message: self
+                .messages
+                .current()
+                .map(|message| (message.text.as_str(), message.level)),
+            modified: self.is_modified(),
+            progress: self
+                .save_task
+                .progress()
+                .or_else(|| {
+                    self.documents
+                        .iter()
+                        .find_map(|document| document.save_task.progress())
+                })
+                .map(|progress| ("Saving", progress.percentage()))
+                .or_else(|| {
+                    let task = self.load_task.as_ref()?;
+                    Some(("Loading", task.progress().percentage()))
+                })
+                .or_else(|| {
+                    let run = self.task_run.as_ref()?;
+                    run.status()
+                        .is_none()
+                        .then_some((run.definition().name.as_str(), None))
+                }),
+            overwrite: self.overwrite,
+            remote: self
+                .remote
+                .as_ref()
+                .map(|remote| (remote.host(), self.remote_connected)),
+            file: &display_name,
+            icon: self.config.icons.then(|| file_icon(&display_name)),
+            branch: self.branch.as_deref(),
+        };
+
+        let overlay = self.show_profiler.then(|| {
+            self.profiler
+                .overlay(&self.buffer, self.terminal.cached_lines(), self.max_fps)
+        });
+        self.terminal.set_overlay(overlay);
+
+        self.terminal.update_frame(
+            &self.buffer,
+            status,
+            self.panel.as_ref(),
+            self.prompt.as_ref(),
+            self.pending_key.map(PendingKey::hints),
+            self.dialog.as_ref(),
+        )?;
+
+        // The prompt places the cursor itself, and dialogs hide it
+        if self.prompt.is_none() && self.dialog.is_none() {
+            self.terminal.update_cursor(&self.buffer);
+        }
+
+        Ok(())
+    }
+
+    // Saves the buffer to the file in the background
+    // If the file has been changed on disk since it was opened or last saved,
+    // the user is asked to confirm before it is overwritten
+    // * Nothing is written if the buffer has not changed, so the file's modification time is left
+    // * alone and anything watching the file (such as a build tool) is not set off for no reason
+    // ! Every return which does not start a save has to clear quit_after_save, or the next save would quit
+    fn save(&mut self) -> Result<()> {
+        if self.scratch {
+            self.quit_after_save = false;
+            self.set_warning(format!(
+                "{} is a scratch buffer, so it is never saved",
+                self.display_name()
+            ));
+            return Ok(());
+        }
+
+        if !self.check_file_ready() {
+            self.quit_after_save = false;
+            return Ok(());
+        }
+
+        if self.read_only {
+            self.quit_after_save = false;
+            self.set_error(format!(
+                "{} is read-only, so it cannot be saved",
+                self.display_name()
+            ));
+            return Ok(());
+        }
+
+        if !self.is_modified() {
+            self.quit_after_save = false;
+            self.set_message("No changes to save".to_string());
+            return Ok(());
+        }
+
+        // Characters typed since the encoding was chosen may not fit in it either
+        let unmappable = self.encoding.unmappable(&self.buffer.to_string());
+        if !unmappable.is_empty() {
+            self.quit_after_save = false;
+            self.set_error(format!(
+                "Cannot save as {}, which cannot represent: {}",
+                self.encoding,
+                describe_unmappable(&unmappable)
+            ));
+            return Ok(());
+        }
+
+        let disk_modified = disk_modification_time(&self.filename);
+
+        if disk_modified != self.disk_modified {
+            self.open_dialog(
+                DialogKind::ConfirmOverwrite,
+                "The file has changed on disk since it was opened. Overwrite it?",
+                vec![
+                    (DialogChoice::Yes, "Overwrite"),
+                    (DialogChoice::Cancel, "Cancel"),
+                ],
+            );
+
+            return Ok(());
+        }
+
+        self.write_file();
+
+        Ok(())
+    }
+
+    // Saves every open file which has unsaved changes, each in its own background task
+    // Files which cannot be saved are reported without stopping the others from being saved
+    fn save_all(&mut self) -> Result<()> {
+        if self.modified_documents() == 0 {
+            self.quit_after_save = false;
+            self.set_message("No changes to save".to_string());
+            return Ok(());
+        }
+
+        // The file being edited is saved as usual, so it can still ask about overwriting the file
+        // * save() clears quit_after_save itself if it does not start a save
+        if self.is_modified() {
+            self.save()?;
+        }
+
+        let mut failed = false;
+        for index in 0..self.documents.len() {
+            if index == self.document_index || !self.documents[index].is_modified() {
+                continue;
+            }
+
+            self.swap_document(index);
+            if let Err(error) = self.start_save() {
+                failed = true;
+                self.set_error(error);
+            }
+            self.swap_document(index);
+        }
+
+        if failed {
+            self.quit_after_save = false;
+        }
+
+        Ok(())
+    }
+
+    // Starts saving a file in the background without asking anything, or describes why it cannot be
+    // * A file which has changed on disk is left alone, since the user is not looking at it to decide
+    // * whether to overwrite it
+    fn start_save(&mut self) -> std::result::Result<(), String> {
+        let name = self.display_name();
+
+        if self.file.is_none() || self.load_task.is_some() {
+            return Err(format!("{} could not be read, so it cannot be saved", name));
+        }
+
+        if self.read_only {
+            return Err(format!("{} is read-only, so it cannot be saved", name));
+        }
+
+        let unmappable = self.encoding.unmappable(&self.buffer.to_string());
+        if !unmappable.is_empty() {
+            return Err(format!(
+                "Cannot save {} as {}, which cannot represent: {}",
+                name,
+                self.encoding,
+                describe_unmappable(&unmappable)
+            ));
+        }
+
+        if disk_modification_time(&self.filename) != self.disk_modified {
+            return Err(format!(
+                "{} has changed on disk, so it was not saved (save it on its own to overwrite it)",
+                name
+            ));
+        }
+
+        self.write_file();
+
+        Ok(())
+    }
+
+    // Checks whether any open file is being saved
+    fn saving(&self) -> bool {
+        self.save_task.is_running()
+            || self
+                .documents
+                .iter()
+                .any(|document| document.save_task.is_running())
+    }
+
+    // Writes the buffer to the file in the background
+    // The buffer is only marked as unmodified once the write has actually finished
+    fn write_file(&mut self) {
+        // Clone the buffer so it can be used in the background, and get a copy of the File reference
+        let file = self
+            .file
+            .clone()
+            .expect("[INTERNAL ERROR] Tried to save before a file was chosen");
+
+        if self.save_task.start(
+            file,
+            self.buffer.clone(),
+            self.encoding,
+            self.remote.clone(),
+        ) {
+            self.set_message(format!("Saving {}...", self.display_name()));
+        } else {
+            // The save in progress does not include the latest changes, so it must not quit the editor
+            self.quit_after_save = false;
+            self.set_warning("A save is already in progress".to_string());
+        }
+    }
+
+    // Saves the file once the buffer has gone unchanged for the autosave interval, if there is one
+    fn check_autosave(&mut self) -> Result<()> {
+        let Some(interval) = self.config.autosave else {
+            return Ok(());
+        };
+
+        let version = self.buffer.version();
+        if self.last_change.0 != version {
+            self.last_change = (version, Instant::now());
+            return Ok(());
+        }
+
+        // Saving can ask about overwriting the file, so it waits until nothing else is open
+        if !self.is_modified()
+            || self.last_change.1.elapsed() < interval
+            || self.file.is_none()
+            || self.read_only
+            || self.load_task.is_some()
+            || self.save_task.is_running()
+            || self.dialog.is_some()
+            || self.prompt.is_some()
+        {
+            return Ok(());
+        }
+
+        // If the save does not go through, it is tried again after another interval
+        self.last_change.1 = Instant::now();
+        self.save()
+    }
+
+    // Checks whether any background tasks have finished, and reports their results
+    fn check_background_tasks(&mut self) -> Result<()> {
+        self.apply_remote_changes();
+        self.check_autosave()?;
+
+        if self.messages.expire() {
+            self.queue_frame_update();
+        }
+
+        if self.terminal.poll_highlights() {
+            self.queue_frame_update();
+        }
+
+        // Keep the progress indicator moving while a task is running, and the clock up to date
+        // * The performance overlay is kept up to date too, though it always lags a frame behind,
+        // * since a frame's time is only known once it has been drawn
+        if self.saving()
+            || self.load_task.is_some()
+            || self.show_profiler
+            || self.terminal.clock_outdated()
+        {
+            self.queue_frame_update();
+        }
+
+        if let Some(result) = self.load_task.as_mut().and_then(LoadTask::poll) {
+            self.load_task = None;
+            self.finish_load(result);
+        }
+
+        self.check_debug_session();
+
+        // Collect the running task's output, and report how it exited once it has
+        if let Some(run) = self.task_run.as_mut() {
+            let was_running = run.status().is_none();
+            if run.poll() {
+                self.update_task_output();
+            }
+
+            let run = self
+                .task_run
+                .as_ref()
+                .expect("[INTERNAL ERROR] Task run disappeared");
+            match run.status() {
+                Some(status) if was_running => {
+                    let message = format!("{} {}", run.definition().name, describe_exit(status));
+                    match status.success() {
+                        true => self.set_message(message),
+                        false => self.set_error(message),
+                    }
+                }
+                // Keep the spinner in the status bar moving
+                None => self.queue_frame_update(),
+                _ => (),
+            }
+        }
+
+        // Files in the background are swapped in briefly so that their saves are reported the same way
+        let mut saved = self.check_save();
+        for index in 0..self.documents.len() {
+            if index != self.document_index && self.documents[index].save_task.is_running() {
+                self.swap_document(index);
+                saved |= self.check_save();
+                self.swap_document(index);
+            }
+        }
+
+        // When quitting after saving, the editor waits for every save to finish (or to be confirmed)
+        if saved && self.quit_after_save && !self.saving() && self.dialog.is_none() {
+            return self.exit();
+        }
+
+        self.check_disk_changes();
+
+        Ok(())
+    }
+
+    // Reports the result of the file's save once it has finished, returning whether it succeeded
+    fn check_save(&mut self) -> bool {
+        match self.save_task.poll() {
+            Some(Ok(SavedFile {
+                version,
+                modified,
+                encoding,
+            })) => {
+                self.saved_version = version;
+                self.saved_encoding = encoding;
+                self.disk_modified = modified;
+                self.remote_connected = true;
+                self.set_message(format!("Saved {}", self.display_name()));
+                true
+            }
+            Some(Err(error)) => {
+                self.quit_after_save = false;
+                self.remote_connected = self.remote.is_none();
+                self.set_error(format!("Failed to save {}: {}", self.display_name(), error));
+                false
+            }
+            None => false,
+        }
+    }
+
+    // Reloads the file if another program has changed it, as long as that would not lose any edits,
+    // and otherwise asks whether to reload it
+    fn check_disk_changes(&mut self) {
+        if self.disk_checked.elapsed() < DISK_CHECK_INTERVAL
+            || self.file.is_none()
+            || self.load_task.is_some()
+            || self.dialog.is_some()
+            || self.save_task.is_running()
+        {
+            return;
+        }
+        self.disk_checked = Instant::now();
+
+        let disk_modified = disk_modification_time(&self.filename);
+        if disk_modified.is_none() || disk_modified == self.disk_modified {
+            return;
+        }
+
+        match self.is_modified() {
+            false => self.reload(),
+            true => self.open_dialog(
+                DialogKind::ConfirmReload,
+                "The file has changed on disk. Reload it and lose your changes?",
+                vec![(DialogChoice::Yes, "Reload"), (DialogChoice::No, "Keep")],
+            ),
+        }
+    }
+
+    // Replaces the buffer's text with the file's contents on disk, keeping the cursor where it was
+    // Only the part of the text which differs is replaced, so the view, marks, and folds elsewhere
+    // in the file stay put, and the reload can be undone like any other edit
+    // * The file is decoded in the encoding it was last read or saved in, which is what is on disk
+    fn reload(&mut self) {
+        let disk_modified = disk_modification_time(&self.filename);
+        let text = match fs::read(&self.filename) {
+            Ok(bytes) => self
+                .saved_encoding
+                .decode(&bytes)
+                .ok_or_else(|| format!("the file is no longer valid {}", self.saved_encoding)),
+            Err(error) => Err(error.to_string()),
+        };
+        let text = match text {
+            Ok(text) => text,
+            Err(error) => {
+                self.disk_modified = disk_modified;
+                self.set_error(format!(
+                    "Failed to reload {}: {}",
+                    self.display_name(),
+                    error
+                ));
+                return;
+            }
+        };
+
+        let old: Vec<char> = self.buffer.to_string().chars().collect();
+        let new: Vec<char> = text.chars().collect();
+
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let mut edits = Vec::new();
+        if prefix + suffix < old.len() {
+            edits.push(Edit::Delete {
+                index: prefix,
+                text: old[prefix..old.len() - suffix].iter().collect(),
+            });
+        }
+        if prefix + suffix < new.len() {
+            edits.push(Edit::Insert {
+                index: prefix,
+                text: new[prefix..new.len() - suffix].iter().collect(),
+            });
+        }
+
+        let mut cursor = self.terminal.cursor().index();
+        for edit in &edits {
+            cursor = match edit {
+                Edit::Insert { index, text } => {
+                    adjust_index_for_insert(cursor, *index, text.chars().count())
+                }
+                Edit::Delete { index, text } => {
+                    adjust_index_for_delete(cursor, *index..*index + text.chars().count())
+                }
+            };
+            self.apply_edit(edit);
+        }
+        self.history.record_together(edits);
+        self.terminal.cursor_mut().move_to(cursor, &self.buffer);
+
+        self.saved_version = self.buffer.version();
+        self.disk_modified = disk_modified;
+        self.set_message(format!(
+            "Reloaded {}, which changed on disk",
+            self.display_name()
+        ));
+    }
+
+    // Applies the changes made by other users in the collaborative session
+    // ! Remote edits shift the text that the undo history refers to, so the history is cleared
+    // ! rather than letting an undo apply edits in the wrong places
+    fn apply_remote_changes(&mut self) {
+        let Some(session) = self.collab.as_mut() else {
+            return;
+        };
+
+        for change in session.poll() {
+            match change {
+                RemoteChange::Edit(edit) => {
+                    let cursor = self.terminal.cursor().index();
+                    let cursor = match &edit {
+                        Edit::Insert { index, text } => {
+                            adjust_index_for_insert(cursor, *index, text.chars().count())
+                        }
+                        Edit::Delete { index, text } => {
+                            adjust_index_for_delete(cursor, *index..*index + text.chars().count())
+                        }
+                    };
+
+                    self.edit_buffer(&edit);
+                    self.terminal.cursor_mut().move_to(cursor, &self.buffer);
+                    self.history = History::default();
+                }
+                RemoteChange::Reset(text) => {
+                    self.edit_buffer(&Edit::Delete {
+                        index: 0,
+                        text: self.buffer.to_string(),
+                    });
+                    self.edit_buffer(&Edit::Insert { index: 0, text });
+                    self.terminal.cursor_mut().move_to(0, &self.buffer);
+                    self.history = History::default();
+                }
+                RemoteChange::Follow => self.following = true,
+                RemoteChange::Message(message) => self.set_message(message),
+            }
+
+            self.queue_frame_update();
+        }
+
+        // Keep the view on whatever the host is looking at
+        if self.following {
+            if let Some(index) = self.collab.as_ref().and_then(Session::host_cursor) {
+                if index != self.terminal.cursor().index() {
+                    self.terminal.cursor_mut().move_to(index, &self.buffer);
+                    self.queue_frame_update();
+                }
+            }
+        }
+    }
+
+    // Gets the name of the file being edited, which is its remote location if it is a remote file
+    fn display_name(&self) -> String {
+        match &self.remote {
+            Some(remote) => remote.to_string(),
+            None => self.filename.clone(),
+        }
+    }
+
+    // Checks whether the buffer has changed since it was last saved
+    // * Changing the encoding counts as a change, since the file on disk no longer matches
+    // * Scratch buffers are never modified, so they are left out of saving everything and quitting
+    fn is_modified(&self) -> bool {
+        !self.scratch
+            && (self.buffer.version() != self.saved_version || self.encoding != self.saved_encoding)
+    }
+
+    // Sets the message shown in the status bar
+    fn set_message(&mut self, message: String) {
+        self.messages.push(MessageLevel::Info, message);
+        self.queue_frame_update();
+    }
+
+    // Sets a message shown in the status bar which warns the user about something
+    fn set_warning(&mut self, message: String) {
+        self.messages.push(MessageLevel::Warning, message);
+        self.queue_frame_update();
+    }
+
+    // Sets a message shown in the status bar which tells the user that something has failed
+    fn set_error(&mut self, message: String) {
+        self.messages.push(MessageLevel::Error, message);
+        self.queue_frame_update();
+    }
+
+    // Exits the editor, first asking whether to save each buffer which has unsaved changes in turn
+    // The editor only exits once every buffer has been saved or discarded, and any of them can cancel
+    fn quit(&mut self) -> Result<()> {
+        self.quit_index = Some(0);
+        self.continue_quit()
+    }
+
+    // Asks about the next buffer with unsaved changes while quitting, or exits if there are none left
+    // * Buffers which are being saved are waited for, and the editor does not exit if any of them fail
+    fn continue_quit(&mut self) -> Result<()> {
+        let Some(start) = self.quit_index else {
+            return Ok(());
+        };
+
+        let names = self.document_names();
+        let Some(index) = (start..names.len()).find(|&index| names[index].1) else {
+            self.quit_index = None;
+            return match self.saving() {
+                true => {
+                    self.quit_after_save = true;
+                    Ok(())
+                }
+                false => self.exit(),
+            };
+        };
+
+        self.switch_document(index);
+        self.quit_index = Some(index + 1);
+
+        let message = match names.len() {
+            1 => "Save the changes to the buffer before quitting?".to_string(),
+            _ => format!("Save the changes to {} before quitting?", names[index].0),
+        };
+        self.open_dialog(
+            DialogKind::ConfirmQuit,
+            &message,
+            vec![
+                (DialogChoice::Yes, "Save"),
+                (DialogChoice::No, "Don't save"),
+                (DialogChoice::Cancel, "Cancel"),
+            ],
+        );
+
+        Ok(())
+    }
+
+    // Saves the buffer the user chose to save while quitting, then moves on to the next one
+    // If the save cannot be started, quitting is cancelled so the user can see why
+    fn save_before_quitting(&mut self) -> Result<()> {
+        if self.save_task.is_running() {
+            self.quit_index = None;
+            self.set_warning(format!("{} is still being saved", self.display_name()));
+            return Ok(());
+        }
+
+        self.save()?;
+
+        // Saving may be waiting for the user to confirm overwriting the file, which continues quitting
+        if self.dialog.is_some() {
+            return Ok(());
+        }
+
+        match self.save_task.is_running() {
+            true => self.continue_quit(),
+            false => {
+                self.quit_index = None;
+                Ok(())
+            }
+        }
+    }
+
+    // Closes the terminal and stops the event loop, which lets the program exit
+    fn exit(&mut self) -> Result<()> {
+        // Close the terminal
+        self.terminal.exit()?;
+
+        // Remove the local copy of a remote file
+        if self.remote.is_some() {
+            let _ = fs::remove_file(&self.filename);
+        }
+
+        self.quitting = true;
+
+        Ok(())
+    }
+
+    // Exits the program without being asked to by the user, such as when the terminal is closed
+    // Unsaved changes are written to a recovery file next to the file rather than being lost
+    // * The file itself is left alone, since the user never chose to overwrite it
+    fn terminate(&mut self) -> ! {
+        // The files in the background are recovered the same way as the file being edited
+        let modified = self.is_modified().then_some((&self.filename, &self.buffer));
+        let recoveries: Vec<_> = modified
+            .into_iter()
+            .chain(
+                self.documents
+                    .iter()
+                    .filter(|document| document.is_modified())
+                    .map(|document| (&document.filename, &document.buffer)),
+            )
+            .map(|(filename, buffer)| {
+                let path = format!("{}.rut-recovery", filename);
+                fs::write(&path, buffer.to_string()).map(|_| path)
+            })
+            .collect();
+
+        // * If the terminal has hung up, it cannot be restored, so any errors are ignored
+        let _ = self.terminal.exit();
+
+        for recovery in &recoveries {
+            match recovery {
+                Ok(path) => eprintln!("rut: unsaved changes were written to {}", path),
+                Err(error) => eprintln!("rut: failed to write unsaved changes: {}", error),
+            }
+        }
+
+        // The local copy of a remote file is only removed if nothing was recovered next to it
+        if self.remote.is_some() && recoveries.is_empty() {
+            let _ = fs::remove_file(&self.filename);
+        }
+
+        std::process::exit(1);
+    }
+}
+
+// Describes how a process exited, for the status bar
+// [EXAMPLE] "finished successfully", "failed with exit status 2", "was stopped by signal 9"
+fn describe_exit(status: ExitStatus) -> String {
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return format!("was stopped by signal {}", signal);
+    }
+
+    match status.code() {
+        Some(0) => "finished successfully".to_string(),
+        Some(code) => format!("failed with exit status {}", code),
+        None => "was stopped".to_string(),
+    }
+}
+
+// Gets the name a file is shown and opened by, which is relative to the working directory if it is
+// inside it, the same as if it had been given on the command line
+fn relative_filename(path: &Path) -> String {
+    std::env::current_dir()
+        .ok()
+        .and_then(|current| path.strip_prefix(current).ok())
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
+}
+
+// Gets the lines with breakpoints, in order, from the buffer indices of their starts
+// * Breakpoints whose lines were joined together end up on the same line, which is only listed once
+fn breakpoint_lines(buffer: &Buffer, breakpoints: &[usize]) -> Vec<usize> {
+    let mut lines: Vec<_> = breakpoints
+        .iter()
+        .map(|&index| buffer.line_of(index.min(buffer.size())))
+        .collect();
+    lines.sort_unstable();
+    lines.dedup();
+    lines
+}
+
+// Gets the file which pasted text is the path of, if it is the absolute path of an existing file
+// Terminals paste the paths of dropped files quoted, with spaces escaped, or as file:// URLs
+// * Relative paths are not offered, since an ordinary word could be the name of a file
+// [EXAMPLE] "'/home/user/My Notes.txt'" -> "/home/user/My Notes.txt"
+fn pasted_path(text: &str) -> Option<PathBuf> {
+    let text = text.trim();
+    if text.contains('\n') {
+        return None;
+    }
+
+    let text = text.strip_prefix("file://").unwrap_or(text);
+    let text = ['\'', '"']
+        .iter()
+        .find_map(|&quote| text.strip_prefix(quote)?.strip_suffix(quote))
+        .unwrap_or(text);
+
+    [text.to_string(), text.replace("\\ ", " ")]
+        .into_iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_absolute() && path.is_file())
+}
+
+// Builds the event of a bare Esc keypress
+fn escape_event() -> KeyEvent {
+    KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)
+}
+
+// Gets when the file at a path was last modified, or None if it cannot be found out
+fn disk_modification_time(filename: &str) -> Option<SystemTime> {
+    fs::metadata(filename)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// Opens a file for reading and writing, creating it if it does not exist yet
+// If the file cannot be written to, it is opened for reading only, which is returned alongside it
+fn open_for_editing(path: &Path) -> io::Result<(File, bool)> {
+    let opened = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path);
+
+    match opened {
+        Ok(file) => Ok((file, false)),
+        Err(error) if error.kind() == io::ErrorKind::PermissionDenied => {
+            File::open(path).map(|file| (file, true))
+        }
+        Err(error) => Err(error),
+    }
+}
+
+// Gets the modification time of a file, if the platform supports it
+fn modification_time(file: &File) -> Option<SystemTime> {
+    file.metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// Reindents multi-line text so that its lines keep their indentation relative to each other,
+// but are indented by the given indentation instead of whatever they were indented by before
+// The first line is placed at the cursor, so its indentation is only dropped if asked to
+// [EXAMPLE] "a\n        b\n    c" with "  " -> "a\n      b\n  c"
+fn reindent(text: &str, indentation: &str, strip_first: bool) -> String {
+    let mut lines = text.split('\n');
+    let first = lines.next().unwrap_or_default();
+    let rest: Vec<&str> = lines.collect();
+
+    let leading_whitespace =
+        |line: &str| line.chars().take_while(|&c| c == ' ' || c == '\t').count();
+
+    // Blank lines are ignored, since they often have no indentation at all
+    let common = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| leading_whitespace(line))
+        .min()
+        .unwrap_or(0);
+
+    let mut reindented = match strip_first {
+        true => first.trim_start_matches([' ', '\t']).to_string(),
+        false => first.to_string(),
+    };
+
+    for line in rest {
+        reindented.push('\n');
+
+        if !line.trim().is_empty() {
+            reindented.push_str(indentation);
+            reindented.extend(line.chars().skip(common));
+        }
+    }
+
+    reindented
+}
 
-        // Exit the program
-        std::process::exit(0);
+// Describes text for a screen reader, naming whitespace which would otherwise be read as silence
+fn spoken_text(text: &str) -> String {
+    match text {
+        "" => "blank".to_string(),
+        " " => "space".to_string(),
+        "\t" => "tab".to_string(),
+        "\n" => "line break".to_string(),
+        _ => text.to_string(),
     }
 }
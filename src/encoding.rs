@@ -0,0 +1,159 @@
+use std::fmt;
+use std::str::FromStr;
+
+// The most unmappable characters listed in a warning, so that it still fits in the status bar
+const MAX_LISTED_CHARACTERS: usize = 8;
+
+// Represents a character encoding which the buffer can be read from and saved in
+// * ASCII is never detected when a file is read, since any ASCII file is also valid UTF-8
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Ascii,
+    Latin1,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    // Guesses the encoding of a file from its contents, or returns None if it does not look like text
+    // Files which start with a UTF-16 byte order mark are UTF-16, and the rest are UTF-8 if they are
+    // valid UTF-8, or otherwise Latin-1 (which any bytes are valid in) unless they contain null bytes
+    // * Null bytes are almost never found in text, but are in nearly every binary file
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        let encoding = match bytes {
+            [0xff, 0xfe, ..] => Self::Utf16Le,
+            [0xfe, 0xff, ..] => Self::Utf16Be,
+            _ if std::str::from_utf8(bytes).is_ok() => Self::Utf8,
+            _ if bytes.contains(&0) => return None,
+            _ => Self::Latin1,
+        };
+
+        Some(encoding)
+    }
+
+    // Gets the byte order mark which files in this encoding start with, which is empty if there is none
+    // * UTF-16 files are given one so that they are recognized as UTF-16 when they are opened again
+    pub fn byte_order_mark(self) -> &'static [u8] {
+        match self {
+            Self::Utf16Le => &[0xff, 0xfe],
+            Self::Utf16Be => &[0xfe, 0xff],
+            Self::Utf8 | Self::Ascii | Self::Latin1 => &[],
+        }
+    }
+
+    // Decodes the contents of a file in this encoding, leaving out its byte order mark,
+    // or returns None if they are not valid in it
+    pub fn decode(self, bytes: &[u8]) -> Option<String> {
+        let bytes = bytes.strip_prefix(self.byte_order_mark()).unwrap_or(bytes);
+
+        match self {
+            Self::Utf8 => String::from_utf8(bytes.to_vec()).ok(),
+            Self::Ascii => bytes
+                .is_ascii()
+                .then(|| bytes.iter().map(|&byte| byte as char).collect()),
+            Self::Latin1 => Some(bytes.iter().map(|&byte| byte as char).collect()),
+            Self::Utf16Le | Self::Utf16Be => {
+                if !bytes.len().is_multiple_of(2) {
+                    return None;
+                }
+
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|pair| match self {
+                        Self::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                        _ => u16::from_be_bytes([pair[0], pair[1]]),
+                    })
+                    .collect();
+
+                String::from_utf16(&units).ok()
+            }
+        }
+    }
+
+    // Checks whether a character can be written in this encoding
+    pub fn can_encode(self, character: char) -> bool {
+        match self {
+            Self::Utf8 | Self::Utf16Le | Self::Utf16Be => true,
+            Self::Ascii => character.is_ascii(),
+            Self::Latin1 => (character as u32) < 0x100,
+        }
+    }
+
+    // Gets every distinct character in the text which cannot be written in this encoding, in the
+    // order they first appear
+    pub fn unmappable(self, text: &str) -> Vec<char> {
+        let mut unmappable = Vec::new();
+        for character in text.chars() {
+            if !self.can_encode(character) && !unmappable.contains(&character) {
+                unmappable.push(character);
+            }
+        }
+
+        unmappable
+    }
+
+    // Encodes text in this encoding
+    // ! The text should only contain characters which can be encoded, and any others are replaced with '?'
+    pub fn encode(self, text: &str) -> Vec<u8> {
+        match self {
+            Self::Utf8 => text.as_bytes().to_vec(),
+            Self::Ascii | Self::Latin1 => text
+                .chars()
+                .map(|c| match self.can_encode(c) {
+                    true => c as u8,
+                    false => b'?',
+                })
+                .collect(),
+            Self::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            Self::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+        }
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = ();
+
+    // Parses the name of an encoding, ignoring case
+    // [EXAMPLE] "utf-8", "latin1", "ISO-8859-1", "utf-16le"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('_', "-").as_str() {
+            "utf-8" | "utf8" => Ok(Self::Utf8),
+            "ascii" | "us-ascii" => Ok(Self::Ascii),
+            "latin-1" | "latin1" | "iso-8859-1" => Ok(Self::Latin1),
+            "utf-16le" | "utf16le" => Ok(Self::Utf16Le),
+            "utf-16be" | "utf16be" => Ok(Self::Utf16Be),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Utf8 => "UTF-8",
+            Self::Ascii => "ASCII",
+            Self::Latin1 => "Latin-1",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16Be => "UTF-16BE",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+// Lists unmappable characters for a warning, leaving out any beyond the first few
+// [EXAMPLE] "€ ✓ → (and 2 more)"
+pub fn describe_unmappable(characters: &[char]) -> String {
+    let listed: Vec<String> = characters
+        .iter()
+        .take(MAX_LISTED_CHARACTERS)
+        .map(|character| character.to_string())
+        .collect();
+
+    match characters.len().saturating_sub(MAX_LISTED_CHARACTERS) {
+        0 => listed.join(" "),
+        more => format!("{} (and {} more)", listed.join(" "), more),
+    }
+}
@@ -0,0 +1,239 @@
+use crossterm::style::{Attribute, ContentStyle};
+use tui::style::{Color, Modifier, Style};
+
+use crate::terminal::build_line;
+use crate::theme::Theme;
+use crate::Buffer;
+
+// The colors used for the page itself, matching a typical dark terminal
+const BACKGROUND: &str = "#1c1c1c";
+const FOREGROUND: &str = "#d0d0d0";
+
+// Renders the buffer as a standalone HTML page, styled the same way it is drawn in the terminal
+// * The lines are built by the terminal's renderer, so any styling it applies is kept
+pub fn to_html(buffer: &Buffer, title: &str) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape(title)));
+    html.push_str("</head>\n");
+    html.push_str(&format!(
+        "<body style=\"background: {}; color: {}\">\n",
+        BACKGROUND, FOREGROUND
+    ));
+    html.push_str("<pre style=\"font-family: monospace\">\n");
+
+    // The buffer's last line comes after its final newline, so it is left out if it is empty
+    let mut line_count = buffer.line_count();
+    if buffer.ends_with_newline() {
+        line_count -= 1;
+    }
+
+    for line in 0..line_count {
+        for span in build_line(buffer, line, false, &[], &Theme::default()).0 {
+            let content = escape(&span.content);
+
+            match style_to_css(span.style) {
+                css if css.is_empty() => html.push_str(&content),
+                css => html.push_str(&format!("<span style=\"{}\">{}</span>", css, content)),
+            }
+        }
+
+        html.push('\n');
+    }
+
+    html.push_str("</pre>\n</body>\n</html>\n");
+
+    html
+}
+
+// Renders the buffer as text for printing to a terminal, optionally styled with ANSI escape codes
+// and preceded by line numbers
+pub fn to_ansi(buffer: &Buffer, styled: bool, line_numbers: bool) -> String {
+    let mut text = String::new();
+
+    // The buffer's last line comes after its final newline, so it is left out if it is empty
+    let mut line_count = buffer.line_count();
+    if buffer.ends_with_newline() {
+        line_count -= 1;
+    }
+
+    let number_width = line_count.to_string().len();
+
+    for line in 0..line_count {
+        if line_numbers {
+            text.push_str(&format!("{:>width$} ", line + 1, width = number_width));
+        }
+
+        for span in build_line(buffer, line, false, &[], &Theme::default()).0 {
+            match styled {
+                true => text.push_str(&style_to_ansi(span.style).apply(span.content).to_string()),
+                false => text.push_str(&span.content),
+            }
+        }
+
+        text.push('\n');
+    }
+
+    text
+}
+
+// Escapes the characters which have a special meaning in HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Converts a terminal style into the equivalent CSS declarations
+fn style_to_css(style: Style) -> String {
+    let mut declarations = Vec::new();
+
+    if let Some(color) = style.fg.and_then(color_to_css) {
+        declarations.push(format!("color: {}", color));
+    }
+
+    if let Some(color) = style.bg.and_then(color_to_css) {
+        declarations.push(format!("background: {}", color));
+    }
+
+    if style.add_modifier.contains(Modifier::BOLD) {
+        declarations.push("font-weight: bold".to_string());
+    }
+
+    if style.add_modifier.contains(Modifier::DIM) {
+        declarations.push("opacity: 0.6".to_string());
+    }
+
+    if style.add_modifier.contains(Modifier::ITALIC) {
+        declarations.push("font-style: italic".to_string());
+    }
+
+    if style.add_modifier.contains(Modifier::UNDERLINED) {
+        declarations.push("text-decoration: underline".to_string());
+    }
+
+    declarations.join("; ")
+}
+
+// Converts a terminal style into the equivalent crossterm style, which can be printed as escape codes
+fn style_to_ansi(style: Style) -> ContentStyle {
+    let mut ansi_style = ContentStyle::new();
+    ansi_style.foreground_color = style.fg.map(tui_color_to_crossterm);
+    ansi_style.background_color = style.bg.map(tui_color_to_crossterm);
+
+    let attributes = [
+        (Modifier::BOLD, Attribute::Bold),
+        (Modifier::DIM, Attribute::Dim),
+        (Modifier::ITALIC, Attribute::Italic),
+        (Modifier::UNDERLINED, Attribute::Underlined),
+        (Modifier::REVERSED, Attribute::Reverse),
+    ];
+
+    for (modifier, attribute) in attributes {
+        if style.add_modifier.contains(modifier) {
+            ansi_style.attributes.set(attribute);
+        }
+    }
+
+    ansi_style
+}
+
+// Converts a terminal color into the equivalent crossterm color, in the same way tui's backend does
+// * tui's conversion is for the version of crossterm it depends on, which is not the one used here
+// * tui's basic colors are the dark variants, and its light colors are crossterm's plain ones
+fn tui_color_to_crossterm(color: Color) -> crossterm::style::Color {
+    use crossterm::style::Color as CColor;
+
+    match color {
+        Color::Reset => CColor::Reset,
+        Color::Black => CColor::Black,
+        Color::Red => CColor::DarkRed,
+        Color::Green => CColor::DarkGreen,
+        Color::Yellow => CColor::DarkYellow,
+        Color::Blue => CColor::DarkBlue,
+        Color::Magenta => CColor::DarkMagenta,
+        Color::Cyan => CColor::DarkCyan,
+        Color::Gray => CColor::Grey,
+        Color::DarkGray => CColor::DarkGrey,
+        Color::LightRed => CColor::Red,
+        Color::LightGreen => CColor::Green,
+        Color::LightYellow => CColor::Yellow,
+        Color::LightBlue => CColor::Blue,
+        Color::LightMagenta => CColor::Magenta,
+        Color::LightCyan => CColor::Cyan,
+        Color::White => CColor::White,
+        Color::Rgb(r, g, b) => CColor::Rgb { r, g, b },
+        Color::Indexed(index) => CColor::AnsiValue(index),
+    }
+}
+
+// Converts a terminal color into a CSS color, using the standard xterm palette
+// The terminal's default color has no equivalent, so the page's color is used instead
+fn color_to_css(color: Color) -> Option<String> {
+    let index = match color {
+        Color::Reset => return None,
+        Color::Rgb(r, g, b) => return Some(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+        Color::Indexed(index) => index,
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+    };
+
+    let (r, g, b) = match index {
+        // The 16 basic colors
+        0..=15 => BASIC_COLORS[index as usize],
+        // A 6x6x6 color cube
+        16..=231 => {
+            let level = |value: u8| match value {
+                0 => 0,
+                _ => 55 + value * 40,
+            };
+            let index = index - 16;
+
+            (level(index / 36), level(index / 6 % 6), level(index % 6))
+        }
+        // A grayscale ramp
+        232..=255 => {
+            let level = 8 + (index - 232) * 10;
+
+            (level, level, level)
+        }
+    };
+
+    Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+}
+
+// The RGB values of the 16 basic colors in the xterm palette
+const BASIC_COLORS: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
@@ -0,0 +1,100 @@
+use std::fs::{self, Metadata};
+
+use crate::Buffer;
+use crate::Encoding;
+
+// Describes the file being edited in one line, for the file info command
+// [EXAMPLE] "/home/user/notes.txt" 1.2 KB, rw-r--r--, UTF-8, LF, 48 lines, modified
+pub fn describe(
+    path: &str,
+    name: &str,
+    buffer: &Buffer,
+    encoding: Encoding,
+    modified: bool,
+) -> String {
+    let metadata = fs::metadata(path).ok();
+
+    // Local files are shown with their full path, and remote files with their remote location
+    let full_path = match name == path {
+        true => fs::canonicalize(path).map_or(path.to_string(), |full_path| {
+            full_path.to_string_lossy().to_string()
+        }),
+        false => name.to_string(),
+    };
+
+    let line_count = match buffer.ends_with_newline() {
+        true => buffer.line_count() - 1,
+        false => buffer.line_count(),
+    };
+
+    format!(
+        "\"{}\" {}, {}, {}, {}, {} line{}, {}",
+        full_path,
+        format_size(buffer.size_bytes()),
+        metadata
+            .as_ref()
+            .map_or("not saved yet".to_string(), permissions),
+        encoding,
+        line_endings(buffer),
+        line_count,
+        if line_count == 1 { "" } else { "s" },
+        if modified { "modified" } else { "unmodified" },
+    )
+}
+
+// Formats a number of bytes with the largest unit that keeps it at least 1
+// [EXAMPLE] 1536 -> "1.5 KB"
+pub fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{} bytes", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+// Formats a file's permissions like ls does
+// [EXAMPLE] 0o644 -> "rw-r--r--"
+#[cfg(unix)]
+fn permissions(metadata: &Metadata) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = metadata.permissions().mode();
+    (0..9)
+        .map(|bit| match mode & (0o400 >> bit) != 0 {
+            true => ['r', 'w', 'x'][bit % 3],
+            false => '-',
+        })
+        .collect()
+}
+
+// Formats a file's permissions, which are only whether it is read-only outside of unix
+#[cfg(not(unix))]
+fn permissions(metadata: &Metadata) -> String {
+    match metadata.permissions().readonly() {
+        true => "read-only".to_string(),
+        false => "writable".to_string(),
+    }
+}
+
+// Gets the name of the line endings the buffer uses, which are mixed if it uses more than one kind
+fn line_endings(buffer: &Buffer) -> &'static str {
+    let lines = buffer.line_count().saturating_sub(1);
+    let crlf = (0..lines)
+        .filter(|&line| buffer.line_contents(line).ends_with('\r'))
+        .count();
+
+    match crlf {
+        0 => "LF",
+        crlf if crlf == lines => "CRLF",
+        _ => "mixed line endings",
+    }
+}
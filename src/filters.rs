@@ -0,0 +1,209 @@
+use crate::clipboard::base64_encode;
+
+// Represents a transformation which can be applied to the selected text
+// * Filters are looked up by the key that follows Ctrl+G, so adding one only requires adding it
+// * to FILTERS (and its key to help::FILTER_KEYS)
+pub trait TextFilter: Sync {
+    // Gets the key which applies the filter after Ctrl+G
+    fn key(&self) -> char;
+
+    // Gets a short description of what the filter does, for status messages
+    fn description(&self) -> &'static str;
+
+    // Transforms the text, returning a description of the problem if it cannot be transformed
+    fn apply(&self, text: &str) -> Result<String, String>;
+}
+
+// Every available filter, in the order they are listed
+pub const FILTERS: &[&dyn TextFilter] = &[
+    &Base64Encode,
+    &Base64Decode,
+    &UrlEncode,
+    &UrlDecode,
+    &HexEncode,
+    &HexDecode,
+];
+
+// Gets the filter which is applied by the given key, if there is one
+pub fn filter_for_key(key: char) -> Option<&'static dyn TextFilter> {
+    FILTERS.iter().copied().find(|filter| filter.key() == key)
+}
+
+struct Base64Encode;
+
+impl TextFilter for Base64Encode {
+    fn key(&self) -> char {
+        'b'
+    }
+
+    fn description(&self) -> &'static str {
+        "Base64 encoded"
+    }
+
+    fn apply(&self, text: &str) -> Result<String, String> {
+        Ok(base64_encode(text.as_bytes()))
+    }
+}
+
+struct Base64Decode;
+
+impl TextFilter for Base64Decode {
+    fn key(&self) -> char {
+        'B'
+    }
+
+    fn description(&self) -> &'static str {
+        "Base64 decoded"
+    }
+
+    // * Whitespace is ignored so that wrapped Base64 can be decoded, and padding is optional
+    fn apply(&self, text: &str) -> Result<String, String> {
+        let mut bytes = Vec::new();
+        let mut packed = 0u32;
+        let mut bits = 0;
+
+        for c in text.chars().filter(|c| !c.is_whitespace()) {
+            let value = match c {
+                'A'..='Z' => c as u32 - 'A' as u32,
+                'a'..='z' => c as u32 - 'a' as u32 + 26,
+                '0'..='9' => c as u32 - '0' as u32 + 52,
+                '+' | '-' => 62,
+                '/' | '_' => 63,
+                '=' => break,
+                _ => return Err(format!("'{}' is not a Base64 character", c)),
+            };
+
+            packed = packed << 6 | value;
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push((packed >> bits) as u8);
+            }
+        }
+
+        utf8(bytes)
+    }
+}
+
+struct UrlEncode;
+
+impl TextFilter for UrlEncode {
+    fn key(&self) -> char {
+        'u'
+    }
+
+    fn description(&self) -> &'static str {
+        "URL encoded"
+    }
+
+    // Percent-encodes everything except the characters which never need to be escaped in a URL
+    fn apply(&self, text: &str) -> Result<String, String> {
+        Ok(text
+            .bytes()
+            .map(|byte| match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                    (byte as char).to_string()
+                }
+                _ => format!("%{:02X}", byte),
+            })
+            .collect())
+    }
+}
+
+struct UrlDecode;
+
+impl TextFilter for UrlDecode {
+    fn key(&self) -> char {
+        'U'
+    }
+
+    fn description(&self) -> &'static str {
+        "URL decoded"
+    }
+
+    // * Plus signs are decoded as spaces, as they are in query strings
+    fn apply(&self, text: &str) -> Result<String, String> {
+        let mut bytes = Vec::new();
+        let mut remaining = text.bytes();
+
+        while let Some(byte) = remaining.next() {
+            match byte {
+                b'%' => {
+                    let digits = [remaining.next(), remaining.next()];
+                    let value = match digits {
+                        [Some(high), Some(low)] => hex_value(high).zip(hex_value(low)),
+                        _ => None,
+                    };
+
+                    match value {
+                        Some((high, low)) => bytes.push(high << 4 | low),
+                        None => return Err("'%' must be followed by two hex digits".to_string()),
+                    }
+                }
+                b'+' => bytes.push(b' '),
+                _ => bytes.push(byte),
+            }
+        }
+
+        utf8(bytes)
+    }
+}
+
+struct HexEncode;
+
+impl TextFilter for HexEncode {
+    fn key(&self) -> char {
+        'h'
+    }
+
+    fn description(&self) -> &'static str {
+        "hex encoded"
+    }
+
+    fn apply(&self, text: &str) -> Result<String, String> {
+        Ok(text.bytes().map(|byte| format!("{:02x}", byte)).collect())
+    }
+}
+
+struct HexDecode;
+
+impl TextFilter for HexDecode {
+    fn key(&self) -> char {
+        'H'
+    }
+
+    fn description(&self) -> &'static str {
+        "hex decoded"
+    }
+
+    // * Whitespace between bytes is ignored, so "de ad be ef" can be decoded as well as "deadbeef"
+    fn apply(&self, text: &str) -> Result<String, String> {
+        let digits: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+        if !digits.len().is_multiple_of(2) {
+            return Err("There is an odd number of hex digits".to_string());
+        }
+
+        let bytes = digits
+            .chunks(2)
+            .map(|pair| match (hex_value(pair[0]), hex_value(pair[1])) {
+                (Some(high), Some(low)) => Ok(high << 4 | low),
+                _ => Err(format!(
+                    "'{}' is not a hex byte",
+                    String::from_utf8_lossy(pair)
+                )),
+            })
+            .collect::<Result<Vec<u8>, String>>()?;
+
+        utf8(bytes)
+    }
+}
+
+// Gets the value of a hex digit
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+// Converts decoded bytes back into text, since the buffer can only hold valid UTF-8
+fn utf8(bytes: Vec<u8>) -> Result<String, String> {
+    String::from_utf8(bytes).map_err(|_| "The decoded bytes are not valid UTF-8 text".to_string())
+}
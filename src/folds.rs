@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use tree_sitter::{Node, Tree};
+
+use crate::grammar::Grammar;
+use crate::marks::{adjust_index_for_delete, adjust_index_for_insert};
+use crate::{Buffer, BufferObserver};
+
+// Represents the folded regions of the buffer, whose lines are hidden behind their first line
+// Each fold is stored as a range of buffer indices, from the start of its first line to the
+// newline at the end of its last line, so that it can be shifted whenever the buffer is edited
+#[derive(Default)]
+pub struct Folds {
+    folds: Vec<Range<usize>>,
+}
+
+impl Folds {
+    // Folds the region at the given line, or unfolds it if it is already folded
+    // Regions come from the syntax tree if there is one, and from the indentation otherwise
+    pub fn toggle(&mut self, buffer: &Buffer, syntax: Option<(&Tree, &Grammar)>, line: usize) {
+        let folds_before = self.folds.len();
+        self.folds.retain(|fold| buffer.line_of(fold.start) != line);
+
+        // If no fold started on the line, fold the region that the line belongs to
+        if self.folds.len() == folds_before {
+            let region = match syntax {
+                Some((tree, grammar)) => {
+                    syntax_region_containing(&syntax_regions(tree, grammar), line)
+                }
+                None => region_containing(buffer, line),
+            };
+
+            if let Some(region) = region {
+                self.add(buffer, region);
+            }
+        }
+    }
+
+    // Folds every region in the buffer, including nested ones
+    pub fn fold_all(&mut self, buffer: &Buffer, syntax: Option<(&Tree, &Grammar)>) {
+        self.folds.clear();
+
+        if let Some((tree, grammar)) = syntax {
+            for (start, end) in syntax_regions(tree, grammar) {
+                self.add(buffer, start..end);
+            }
+            return;
+        }
+
+        for line in 0..buffer.line_count() {
+            if let Some(region) = region_starting_at(buffer, line) {
+                self.add(buffer, region);
+            }
+        }
+    }
+
+    // Unfolds every region in the buffer
+    pub fn unfold_all(&mut self) {
+        self.folds.clear();
+    }
+
+    // Unfolds any regions which hide the given line, so that the cursor can be placed on it
+    pub fn reveal(&mut self, buffer: &Buffer, line: usize) {
+        self.folds
+            .retain(|fold| !hidden_lines(buffer, fold).contains(&line));
+    }
+
+    // Gets the ranges of lines which are hidden by folds, sorted and without any overlaps
+    // * The first line of a fold is never hidden, because it is shown in place of the whole fold
+    pub fn hidden_lines(&self, buffer: &Buffer) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = self
+            .folds
+            .iter()
+            .map(|fold| hidden_lines(buffer, fold))
+            .filter(|range| !range.is_empty())
+            .collect();
+        ranges.sort_by_key(|range| range.start);
+
+        // Nested folds are merged into the folds containing them
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+
+        merged
+    }
+
+    // Adds a fold covering the given range of lines
+    fn add(&mut self, buffer: &Buffer, lines: Range<usize>) {
+        // The fold ends on the newline of its last line, or at the end of the buffer if there is none
+        let end = match lines.end < buffer.line_count() {
+            true => buffer.line_start(lines.end) - 1,
+            false => buffer.size(),
+        };
+
+        self.folds.push(buffer.line_start(lines.start)..end);
+    }
+}
+
+impl BufferObserver for Folds {
+    // Shifts the folds after an insertion of `len` characters at the given index
+    fn adjust_for_insert(&mut self, index: usize, len: usize) {
+        for fold in self.folds.iter_mut() {
+            fold.start = adjust_index_for_insert(fold.start, index, len);
+            fold.end = adjust_index_for_insert(fold.end, index, len);
+        }
+    }
+
+    // Shifts the folds after the given range of characters has been deleted
+    fn adjust_for_delete(&mut self, range: Range<usize>) {
+        for fold in self.folds.iter_mut() {
+            fold.start = adjust_index_for_delete(fold.start, range.clone());
+            fold.end = adjust_index_for_delete(fold.end, range.clone());
+        }
+    }
+}
+
+// Gets the lines hidden by a fold, which are all of its lines after the first
+fn hidden_lines(buffer: &Buffer, fold: &Range<usize>) -> Range<usize> {
+    let start = buffer.line_of(fold.start.min(buffer.size()));
+    let end = buffer.line_of(fold.end.min(buffer.size()));
+
+    start + 1..end + 1
+}
+
+// Gets the lines of the innermost indented region containing the given line
+// A line which starts a region belongs to that region rather than the one around it
+fn region_containing(buffer: &Buffer, line: usize) -> Option<Range<usize>> {
+    if let Some(region) = region_starting_at(buffer, line) {
+        return Some(region);
+    }
+
+    // Blank lines belong to the same region as the next line with text on it
+    let indentation = (line..buffer.line_count()).find_map(|line| buffer.indentation(line))?;
+
+    // The region starts at the nearest line above which is indented less
+    (0..line)
+        .rev()
+        .find(|&start| {
+            buffer
+                .indentation(start)
+                .is_some_and(|start| start < indentation)
+        })
+        .and_then(|start| region_starting_at(buffer, start))
+        .filter(|region| region.contains(&line))
+}
+
+// Gets the lines of the indented region which starts at the given line, if there is one
+// A region is made up of a line followed by the lines which are indented further than it
+// [EXAMPLE]
+// fn main() {             <- region start
+//     let x = 1;
+//
+//     println!("{}", x);   <- region end
+// }
+fn region_starting_at(buffer: &Buffer, line: usize) -> Option<Range<usize>> {
+    let indentation = buffer.indentation(line)?;
+
+    // Trailing blank lines are left outside of the region
+    let mut end = line + 1;
+    for next in line + 1..buffer.line_count() {
+        match buffer.indentation(next) {
+            Some(next_indentation) if next_indentation > indentation => end = next + 1,
+            Some(_) => break,
+            None => (),
+        }
+    }
+
+    (end > line + 1).then_some(line..end)
+}
+
+// Gets the regions of the buffer from its syntax tree, as the first line of each region mapped to
+// the line after its last one
+// Every node which spans several lines is a region (such as a function or a block), along with each
+// run of imports, and where several regions start on the same line only the biggest one is kept
+// * Unlike indented regions, these are right however the code is indented
+fn syntax_regions(tree: &Tree, grammar: &Grammar) -> BTreeMap<usize, usize> {
+    let mut regions = BTreeMap::new();
+
+    let root = tree.root_node();
+    let mut children = root.walk();
+    for child in root.children(&mut children) {
+        add_node_regions(child, grammar, &mut regions);
+    }
+    add_import_regions(root, grammar, &mut regions);
+
+    regions
+}
+
+// Adds the regions of a node and everything inside of it
+fn add_node_regions(node: Node, grammar: &Grammar, regions: &mut BTreeMap<usize, usize>) {
+    let (start, end) = (node.start_position().row, last_row(node));
+    if node.is_named() && end > start {
+        add_region(regions, start, end + 1);
+    }
+
+    let mut children = node.walk();
+    for child in node.children(&mut children) {
+        add_node_regions(child, grammar, regions);
+    }
+    add_import_regions(node, grammar, regions);
+}
+
+// Adds a region for each run of imports among the children of a node, which is usually the root
+fn add_import_regions(node: Node, grammar: &Grammar, regions: &mut BTreeMap<usize, usize>) {
+    let mut run: Option<(usize, usize)> = None;
+    let mut children = node.walk();
+
+    for child in node.named_children(&mut children) {
+        run = match (run, grammar.is_import(child.kind())) {
+            (Some((start, _)), true) => Some((start, last_row(child))),
+            (None, true) => Some((child.start_position().row, last_row(child))),
+            (Some((start, end)), false) => {
+                if end > start {
+                    add_region(regions, start, end + 1);
+                }
+                None
+            }
+            (None, false) => None,
+        };
+    }
+
+    if let Some((start, end)) = run.filter(|(start, end)| end > start) {
+        add_region(regions, start, end + 1);
+    }
+}
+
+// Adds a region, unless a bigger one already starts on the same line
+fn add_region(regions: &mut BTreeMap<usize, usize>, start: usize, end: usize) {
+    let region_end = regions.entry(start).or_insert(end);
+    *region_end = (*region_end).max(end);
+}
+
+// Gets the last line which a node has any text on
+// * A node which ends with a line break ends at the start of the next line, which it has no text on
+fn last_row(node: Node) -> usize {
+    let end = node.end_position();
+    match end.column == 0 && end.row > node.start_position().row {
+        true => end.row - 1,
+        false => end.row,
+    }
+}
+
+// Gets the lines of the innermost syntax region containing the given line
+// A line which starts a region belongs to that region rather than the one around it
+fn syntax_region_containing(regions: &BTreeMap<usize, usize>, line: usize) -> Option<Range<usize>> {
+    if let Some(&end) = regions.get(&line) {
+        return Some(line..end);
+    }
+
+    regions
+        .range(..line)
+        .map(|(&start, &end)| start..end)
+        .filter(|region| region.contains(&line))
+        .min_by_key(|region| region.len())
+}
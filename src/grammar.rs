@@ -0,0 +1,128 @@
+use tree_sitter::{Language, Query};
+use tree_sitter_language::LanguageFn;
+
+// Describes how the text of a language is parsed into a syntax tree with tree-sitter
+// * Languages with a grammar are highlighted from their syntax tree, which is more accurate than
+// * the lexer in syntax.rs, and the tree is also used for structural motions
+pub struct Grammar {
+    extensions: &'static [&'static str],
+    language: LanguageFn,
+    // The query which picks out the parts of the text to highlight, which comes with the grammar
+    highlights: &'static str,
+    // The kinds of nodes which are function definitions, which are jumped between
+    functions: &'static [&'static str],
+    // The kinds of nodes which import other code, each run of which is folded together
+    imports: &'static [&'static str],
+}
+
+// Every language which has a grammar
+// * Adding a language only requires adding its grammar here, along with its crate
+static GRAMMARS: &[Grammar] = &[
+    Grammar {
+        extensions: &["rs"],
+        language: tree_sitter_rust::LANGUAGE,
+        highlights: tree_sitter_rust::HIGHLIGHTS_QUERY,
+        functions: &["function_item"],
+        imports: &["use_declaration", "extern_crate_declaration"],
+    },
+    Grammar {
+        extensions: &["c", "h"],
+        language: tree_sitter_c::LANGUAGE,
+        highlights: tree_sitter_c::HIGHLIGHT_QUERY,
+        functions: &["function_definition"],
+        imports: &["preproc_include"],
+    },
+    Grammar {
+        extensions: &["go"],
+        language: tree_sitter_go::LANGUAGE,
+        highlights: tree_sitter_go::HIGHLIGHTS_QUERY,
+        functions: &["function_declaration", "method_declaration"],
+        imports: &["import_declaration"],
+    },
+    Grammar {
+        extensions: &["java"],
+        language: tree_sitter_java::LANGUAGE,
+        highlights: tree_sitter_java::HIGHLIGHTS_QUERY,
+        functions: &["method_declaration", "constructor_declaration"],
+        imports: &["import_declaration"],
+    },
+    Grammar {
+        extensions: &["js", "mjs", "cjs", "jsx"],
+        language: tree_sitter_javascript::LANGUAGE,
+        highlights: tree_sitter_javascript::HIGHLIGHT_QUERY,
+        functions: &[
+            "function_declaration",
+            "generator_function_declaration",
+            "method_definition",
+        ],
+        imports: &["import_statement"],
+    },
+    Grammar {
+        extensions: &["py", "pyi"],
+        language: tree_sitter_python::LANGUAGE,
+        highlights: tree_sitter_python::HIGHLIGHTS_QUERY,
+        functions: &["function_definition"],
+        imports: &[
+            "import_statement",
+            "import_from_statement",
+            "future_import_statement",
+        ],
+    },
+    Grammar {
+        extensions: &["sh", "bash"],
+        language: tree_sitter_bash::LANGUAGE,
+        highlights: tree_sitter_bash::HIGHLIGHT_QUERY,
+        functions: &["function_definition"],
+        imports: &[],
+    },
+    Grammar {
+        extensions: &["json"],
+        language: tree_sitter_json::LANGUAGE,
+        highlights: tree_sitter_json::HIGHLIGHTS_QUERY,
+        functions: &[],
+        imports: &[],
+    },
+];
+
+impl Grammar {
+    // Gets the grammar for a file based on its extension, if it is a language which has one
+    // [EXAMPLE] "src/main.rs" -> Rust
+    pub fn for_filename(filename: &str) -> Option<&'static Self> {
+        let (_, extension) = filename.rsplit_once('.')?;
+        let extension = extension.to_lowercase();
+
+        GRAMMARS
+            .iter()
+            .find(|grammar| grammar.extensions.contains(&extension.as_str()))
+    }
+
+    // Gets the language that the parser is set to in order to parse text with this grammar
+    pub fn language(&self) -> Language {
+        self.language.into()
+    }
+
+    // Builds the query which picks out the parts of the text to highlight
+    // Returns None if the query does not match the grammar, in which case the lexer is used instead
+    pub fn highlights_query(&self) -> Option<Query> {
+        Query::new(&self.language(), self.highlights).ok()
+    }
+
+    // Checks whether a kind of node is a function definition
+    pub fn is_function(&self, kind: &str) -> bool {
+        self.functions.contains(&kind)
+    }
+
+    // Checks whether a kind of node imports other code
+    pub fn is_import(&self, kind: &str) -> bool {
+        self.imports.contains(&kind)
+    }
+}
+
+// Checks whether two grammars are the same one
+// * Grammars are only ever used from GRAMMARS, so they can be told apart by their addresses
+pub fn same_grammar(a: Option<&Grammar>, b: Option<&Grammar>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => std::ptr::eq(a, b),
+        (a, b) => a.is_none() && b.is_none(),
+    }
+}
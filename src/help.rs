@@ -0,0 +1,164 @@
+use crate::actions;
+use crate::keymap::{key_name, Keymap};
+
+// The default key bindings listed on the help screen, along with what they do
+// ! This needs to be kept in sync with keymap::DEFAULT_BINDINGS, Editor::handle_key_event,
+// ! and Editor::handle_pending_key
+pub const KEY_BINDINGS: &[(&str, &str)] = &[
+    ("F1", "Show this help screen"),
+    ("Ctrl+Shift+P", "Run any command by name"),
+    (
+        "Alt+;",
+        "Type a command, such as :w, :wa, :q, :wq, :e <file>, :b <n>, :bd, :scratch <name>, :suspend, or a line number",
+    ),
+    ("Ctrl+Q", "Quit"),
+    ("Ctrl+S", "Save"),
+    ("Ctrl+C", "Copy (or nothing, depending on --ctrl-c)"),
+    ("Arrows", "Move the cursor"),
+    ("Home/End", "Move to the start or end of the line"),
+    ("Ctrl+Left/Right", "Move by word"),
+    ("Alt+B/F", "Move by word"),
+    (
+        "Alt+Left/Right",
+        "Move by subword (camelCase or snake_case part)",
+    ),
+    ("Ctrl+Up/Down", "Move by paragraph"),
+    ("Alt+A/E", "Move by sentence"),
+    (
+        "Alt+Home/End",
+        "Move to the start or end of the indented block",
+    ),
+    ("Shift+Arrows", "Select text"),
+    ("Alt+H", "Select the paragraph"),
+    (
+        "Alt+S",
+        "Select the enclosing syntax node (again to expand)",
+    ),
+    ("Alt+PageDown/Up", "Go to the next or previous function"),
+    (
+        "Esc",
+        "Clear the selection and search highlights, and cancel background tasks",
+    ),
+    ("Ctrl+K", "Copy the selection or line"),
+    ("Alt+Up/Down", "Move the line up or down"),
+    ("Backspace", "Delete the previous character"),
+    ("Delete", "Delete the next character"),
+    ("Ctrl+Backspace", "Delete the previous word"),
+    ("Ctrl+L", "Delete the previous word"),
+    ("Alt+Backspace", "Delete the previous subword"),
+    ("Alt+Delete", "Delete the next subword"),
+    ("Alt+K", "Delete to the end of the sentence"),
+    ("Alt+Y", "Yank deleted text (again for older text)"),
+    ("Insert", "Toggle overwrite mode"),
+    ("Alt+=", "Calculate the selection or current line"),
+    ("Alt+P", "Toggle reindenting pasted text"),
+    ("Ctrl+A", "Increment the number under the cursor"),
+    ("Ctrl+X", "Decrement the number under the cursor"),
+    ("Alt+<digits>", "Set the amount for Ctrl+A and Ctrl+X"),
+    ("Ctrl+Z", "Undo"),
+    ("Ctrl+Y", "Redo"),
+    ("Ctrl+U", "Show the undo tree"),
+    ("Ctrl+T", "Travel to an earlier or later state"),
+    ("Ctrl+R", "Insert the contents of a file"),
+    ("Ctrl+F", "Search"),
+    ("Alt+R", "Replace with a regex in the selection or buffer"),
+    ("F3", "Go to the next match"),
+    ("Shift+F3", "Go to the previous match"),
+    ("Ctrl+B <letter>", "Set a mark"),
+    ("Ctrl+J", "Show the marks"),
+    ("Ctrl+O", "Jump back"),
+    ("Ctrl+I", "Jump forward"),
+    ("Ctrl+W C", "Center the view on the cursor"),
+    ("Ctrl+W T", "Scroll the cursor to the top"),
+    ("Ctrl+W B", "Scroll the cursor to the bottom"),
+    ("Ctrl+W Z", "Toggle the fold at the cursor"),
+    ("Ctrl+W M", "Fold everything"),
+    ("Ctrl+W R", "Unfold everything"),
+    ("Ctrl+W A", "Line up the columns of a CSV or TSV file"),
+    ("Ctrl+W H", "Highlight the header of the lined up table"),
+    ("Ctrl+W W", "Toggle soft wrap"),
+    ("Tab", "Go to the next cell of the lined up table"),
+    ("Shift+Tab", "Go to the previous cell of the lined up table"),
+    ("Ctrl+G <key>", "Transform the selection (see below)"),
+    ("Alt+I", "Show information about the file"),
+    ("Alt+V", "Preview the image whose path is under the cursor"),
+    ("Alt+T", "Run a task (from --task or the config file)"),
+    ("Alt+O", "Show the output of the last task"),
+    ("Alt+N", "Change the encoding the file is saved in"),
+    ("Ctrl+E", "Export as HTML"),
+    ("F2", "Toggle invisible characters"),
+    ("F4", "Toggle the current line highlight"),
+    ("F11", "Toggle zen mode (see --zen-width)"),
+    ("F12", "Toggle the performance overlay (see --max-fps)"),
+    ("F5", "Insert the date and time (see --date-format)"),
+    ("Ctrl+PageDown/Up", "Switch to the next or previous buffer"),
+    ("Alt+L", "Show the open buffers"),
+    ("Alt+D", "Show the errors and warnings in every buffer and from the last task"),
+    ("Alt+Enter", "Open a file whose path was just pasted, instead of the path"),
+    ("F9", "Toggle a breakpoint on the line (or click the gutter)"),
+    ("F6", "Start debugging (see [debug] in the config file), or continue"),
+    ("Shift+F6", "Stop debugging"),
+    ("F10/F7/F8", "Step over the line, into the function, or out of it"),
+    ("Alt+X", "Show the variables where the program stopped"),
+    ("Ctrl+Alt+Up/Down", "Add a cursor on the line above or below (Esc to go back to one)"),
+    ("Alt+C", "Insert a sequence (1, 2, 3... or a, b, c...) at the cursors"),
+];
+
+// The keys which can follow Ctrl+W, shown in a popup while it is waiting for one
+pub const VIEW_KEYS: &[(&str, &str)] = &[
+    ("c", "Center the view on the cursor"),
+    ("t", "Scroll the cursor to the top"),
+    ("b", "Scroll the cursor to the bottom"),
+    ("z", "Toggle the fold at the cursor"),
+    ("m", "Fold everything"),
+    ("r", "Unfold everything"),
+    ("a", "Line up the columns of a CSV or TSV file"),
+    ("h", "Highlight the header of the lined up table"),
+    ("w", "Toggle soft wrap"),
+];
+
+// The keys which can follow Ctrl+G, shown in a popup while it is waiting for one
+// ! This needs to be kept in sync with filters::FILTERS
+pub const FILTER_KEYS: &[(&str, &str)] = &[
+    ("b", "Base64 encode"),
+    ("B", "Base64 decode"),
+    ("u", "URL encode"),
+    ("U", "URL decode"),
+    ("h", "Hex encode"),
+    ("H", "Hex decode"),
+];
+
+// The keys which can follow Ctrl+B, shown in a popup while it is waiting for one
+pub const MARK_KEYS: &[(&str, &str)] = &[("a-z, A-Z", "Set the mark with that name")];
+
+// Builds the lines of the help screen, with the keys lined up in a column
+// The keys which a keymap binds differently are listed first, in place of what they do by default
+pub fn help_lines(keymap: &Keymap) -> Vec<String> {
+    let overrides: Vec<(String, &str)> = keymap
+        .overrides()
+        .iter()
+        .map(|&(key, action)| (key_name(key), actions::name(action)))
+        .collect();
+
+    let bindings: Vec<(&str, &str)> = overrides
+        .iter()
+        .map(|(key, action)| (key.as_str(), *action))
+        .chain(
+            KEY_BINDINGS
+                .iter()
+                .copied()
+                .filter(|(keys, _)| !keymap.is_overridden(keys)),
+        )
+        .collect();
+
+    let width = bindings
+        .iter()
+        .map(|(keys, _)| keys.len())
+        .max()
+        .unwrap_or(0);
+
+    bindings
+        .iter()
+        .map(|(keys, action)| format!("{:<width$}  {}", keys, action))
+        .collect()
+}
@@ -0,0 +1,433 @@
+use std::collections::HashSet;
+use std::ops::Range;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+use tree_sitter::{InputEdit, Node, Parser, Query, QueryCursor, StreamingIterator, Tree};
+
+use crate::grammar::{same_grammar, Grammar};
+use crate::runtime;
+use crate::syntax::{Highlight, LineState, Syntax};
+use crate::Buffer;
+
+// The highlighted parts of a line, as byte ranges of the line in order
+pub type LineHighlights = Vec<(Range<usize>, Highlight)>;
+
+// The most lines above the requested ones whose highlighting states are worked out when jumping
+// far into a file
+const MAX_STATE_LINES: usize = 1000;
+
+// Represents a request for the worker to highlight some lines of a snapshot of the buffer
+struct Request {
+    // The snapshot is a cheap copy, since the text of a buffer is shared between its copies
+    buffer: Buffer,
+    syntax: Option<&'static Syntax>,
+    grammar: Option<&'static Grammar>,
+    // Counts how many times the highlighting has been reset, which tells apart buffers that
+    // happen to have the same version
+    generation: u64,
+    lines: Range<usize>,
+    // The first line which has changed since the previous request, if any have
+    changed_from: Option<usize>,
+    // The edits made to the buffer since the previous request, which the syntax tree is updated with
+    edits: Vec<InputEdit>,
+}
+
+// Represents the highlighting of a group of lines, as produced by the worker
+struct Response {
+    generation: u64,
+    version: u64,
+    start: usize,
+    lines: Vec<LineHighlights>,
+    // The syntax tree of the snapshot, if its language has a grammar
+    tree: Option<Tree>,
+}
+
+// Highlights the text of the buffer on a worker, so that highlighting large files never holds up keypresses
+// The terminal asks for the lines it is about to draw, and draws any line that the worker has not
+// highlighted yet as plain text
+// * Languages with a grammar are parsed into a syntax tree, which is kept up to date incrementally
+// * from the buffer's edits, so typing only reparses the part of the tree around each edit
+pub struct Highlighter {
+    requests: Sender<Request>,
+    responses: Receiver<Response>,
+    generation: u64,
+    // The version of the buffer and the lines which were last asked for, so that the same request
+    // is not sent again for every frame
+    requested: Option<(u64, Range<usize>)>,
+    // Whether the worker has not answered the last request yet
+    pending: bool,
+    // The first line which has changed since the last request
+    changed_from: Option<usize>,
+    // The edits made to the buffer since the last request
+    edits: Vec<InputEdit>,
+    // The latest syntax tree from the worker, along with the version of the buffer it was parsed from
+    tree: Option<(u64, Tree)>,
+}
+
+impl Highlighter {
+    // Starts the worker, which runs until the highlighter is dropped
+    pub fn start() -> Self {
+        let (requests, worker_requests) = mpsc::channel();
+        let (worker_responses, responses) = mpsc::channel();
+        runtime().spawn_blocking(move || Worker::default().run(worker_requests, worker_responses));
+
+        Self {
+            requests,
+            responses,
+            generation: 0,
+            requested: None,
+            pending: false,
+            changed_from: None,
+            edits: Vec::new(),
+            tree: None,
+        }
+    }
+
+    // Asks the worker to highlight some lines of the buffer, unless it has already been asked to
+    pub fn request(
+        &mut self,
+        buffer: &Buffer,
+        syntax: Option<&'static Syntax>,
+        grammar: Option<&'static Grammar>,
+        lines: Range<usize>,
+    ) {
+        let requested = Some((buffer.version(), lines.clone()));
+        if self.requested == requested {
+            return;
+        }
+
+        let request = Request {
+            buffer: buffer.clone(),
+            syntax,
+            grammar,
+            generation: self.generation,
+            lines,
+            changed_from: self.changed_from.take(),
+            edits: std::mem::take(&mut self.edits),
+        };
+
+        self.requested = requested;
+        self.pending = self.requests.send(request).is_ok();
+    }
+
+    // Gets the highlighted lines which the worker has finished for the latest request, along with
+    // the first line they start at, waiting up to the given time for them if they are not ready
+    // Answers to earlier requests are dropped, since the lines they are for may have changed since
+    pub fn receive(&mut self, timeout: Duration) -> Option<(usize, Vec<LineHighlights>)> {
+        let deadline = Instant::now() + timeout;
+        let (version, _) = self.requested.clone()?;
+
+        while self.pending {
+            let response = match self.responses.try_recv() {
+                Ok(response) => response,
+                Err(_) => match self
+                    .responses
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                {
+                    Ok(response) => response,
+                    Err(RecvTimeoutError::Timeout) => return None,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        self.pending = false;
+                        return None;
+                    }
+                },
+            };
+
+            if response.generation == self.generation && response.version == version {
+                self.pending = false;
+                self.tree = response.tree.map(|tree| (version, tree));
+                return Some((response.start, response.lines));
+            }
+        }
+
+        None
+    }
+
+    // Checks whether the worker has not answered the last request yet
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    // Gets the lines which were last asked for
+    pub fn requested_lines(&self) -> Option<Range<usize>> {
+        self.requested.as_ref().map(|(_, lines)| lines.clone())
+    }
+
+    // Gets the syntax tree of the given version of the buffer, if the worker has parsed it
+    pub fn tree(&self, version: u64) -> Option<Tree> {
+        self.tree
+            .as_ref()
+            .filter(|(tree_version, _)| *tree_version == version)
+            .map(|(_, tree)| tree.clone())
+    }
+
+    // Lets the worker know that the lines from the given one onwards have changed
+    pub fn lines_changed(&mut self, line: usize) {
+        self.changed_from = Some(self.changed_from.map_or(line, |changed| changed.min(line)));
+    }
+
+    // Passes on the edits made to the buffer to the worker with the next request
+    pub fn edit(&mut self, edits: Vec<InputEdit>) {
+        self.edits.extend(edits);
+    }
+
+    // Forgets everything which has been highlighted, such as when the whole buffer is replaced
+    pub fn reset(&mut self) {
+        self.generation += 1;
+        self.requested = None;
+        self.pending = false;
+        self.changed_from = Some(0);
+        self.edits.clear();
+        self.tree = None;
+    }
+}
+
+// Represents what the worker keeps between requests
+// * For the lexer, this is what each line starts inside of, so after an edit only the lines from
+// * the edit onwards have to be looked at again, and for a grammar it is the syntax tree
+#[derive(Default)]
+struct Worker {
+    states: Vec<LineState>,
+    valid_states: usize,
+    parser: Parser,
+    grammar: Option<&'static Grammar>,
+    // The grammar's highlights query, along with the highlight given by each of its captures
+    query: Option<(Query, Vec<Option<Highlight>>)>,
+    // The syntax tree of the snapshot from the last request, along with its generation
+    tree: Option<(u64, Tree)>,
+}
+
+impl Worker {
+    // Answers requests to highlight lines until the highlighter is dropped
+    fn run(mut self, requests: Receiver<Request>, responses: Sender<Response>) {
+        while let Ok(mut request) = requests.recv() {
+            // Only the latest request is worth answering, but the earlier ones still say what
+            // changed, and their edits are needed to keep the syntax tree in step with the buffer
+            while let Ok(mut newer) = requests.try_recv() {
+                let changed_from = match (request.changed_from, newer.changed_from) {
+                    (Some(older), Some(newer)) => Some(older.min(newer)),
+                    (older, newer) => older.or(newer),
+                };
+                request.edits.append(&mut newer.edits);
+
+                request = Request {
+                    changed_from,
+                    edits: request.edits,
+                    ..newer
+                };
+            }
+
+            let lines = request.lines.start.min(request.buffer.line_count())
+                ..request.lines.end.min(request.buffer.line_count());
+            let tree = self.parse(&request);
+
+            let highlighted = match (&tree, &self.query) {
+                (Some(tree), Some((query, highlights))) => {
+                    highlight_tree(&request.buffer, tree, query, highlights, lines.clone())
+                }
+                _ => self.highlight_lines(&request, lines.clone()),
+            };
+
+            let response = Response {
+                generation: request.generation,
+                version: request.buffer.version(),
+                start: lines.start,
+                lines: highlighted,
+                tree,
+            };
+            if responses.send(response).is_err() {
+                return;
+            }
+        }
+    }
+
+    // Brings the syntax tree up to date with the request's snapshot, reparsing only the parts of
+    // the previous tree which its edits touched
+    // Returns None if the language has no grammar
+    fn parse(&mut self, request: &Request) -> Option<Tree> {
+        if !same_grammar(self.grammar, request.grammar) {
+            self.grammar = request.grammar;
+            self.tree = None;
+            self.query = request.grammar.and_then(|grammar| {
+                let query = grammar.highlights_query()?;
+                let highlights = query
+                    .capture_names()
+                    .iter()
+                    .map(|name| capture_highlight(name))
+                    .collect();
+                Some((query, highlights))
+            });
+
+            let language = request.grammar.map(Grammar::language);
+            if language.is_some_and(|language| self.parser.set_language(&language).is_err()) {
+                self.grammar = None;
+                self.query = None;
+            }
+        }
+        self.grammar?;
+
+        // A tree from before the highlighting was reset is for a different buffer entirely
+        let mut old_tree = self
+            .tree
+            .take()
+            .filter(|(generation, _)| *generation == request.generation)
+            .map(|(_, tree)| tree);
+        if let Some(tree) = old_tree.as_mut() {
+            for edit in &request.edits {
+                tree.edit(edit);
+            }
+        }
+
+        let buffer = &request.buffer;
+        let tree = self.parser.parse_with_options(
+            &mut |byte, _| buffer.chunk_at_byte(byte).as_bytes(),
+            old_tree.as_ref(),
+            None,
+        )?;
+
+        self.tree = Some((request.generation, tree.clone()));
+        Some(tree)
+    }
+
+    // Highlights some lines with the lexer, working out what they start inside of first
+    fn highlight_lines(&mut self, request: &Request, lines: Range<usize>) -> Vec<LineHighlights> {
+        let Some(syntax) = request.syntax else {
+            return vec![Vec::new(); lines.len()];
+        };
+
+        let buffer = &request.buffer;
+        if let Some(line) = request.changed_from {
+            self.valid_states = self.valid_states.min(line + 1);
+        }
+
+        self.states
+            .resize(buffer.line_count(), LineState::default());
+        self.update_states(buffer, syntax, lines.start);
+
+        let mut highlighted = Vec::with_capacity(lines.len());
+        for line in lines.clone() {
+            let (highlights, state) =
+                syntax.highlight_line(&buffer.line_contents(line), self.states[line]);
+            highlighted.push(highlights);
+
+            if line + 1 < self.states.len() {
+                self.states[line + 1] = state;
+            }
+        }
+        self.valid_states = self.valid_states.max(lines.end);
+
+        highlighted
+    }
+
+    // Brings the highlighting states up to date as far as a line
+    // * Only the states are worked out for the lines above the requested ones, which is much cheaper
+    // * than highlighting them
+    // ! To keep jumping far into a large file fast, lines more than MAX_STATE_LINES above the one
+    // ! being highlighted are skipped, and the first line after them is assumed not to start inside of anything
+    fn update_states(&mut self, buffer: &Buffer, syntax: &Syntax, line: usize) {
+        if line < self.valid_states || line >= self.states.len() {
+            return;
+        }
+
+        if line - self.valid_states > MAX_STATE_LINES {
+            self.valid_states = line - MAX_STATE_LINES;
+            self.states[self.valid_states] = LineState::default();
+        }
+
+        for next in self.valid_states.max(1)..=line {
+            let contents = buffer.line_contents(next - 1);
+            let (_, state) = syntax.highlight_line(&contents, self.states[next - 1]);
+            self.states[next] = state;
+        }
+
+        self.valid_states = line + 1;
+    }
+}
+
+// Highlights some lines from their syntax tree, using the grammar's highlights query
+// * Where several patterns give the same node a highlight, the first one wins, and a node inside of
+// * another is drawn on top of it, the same as tree-sitter's own highlighter
+// * Captures which are not highlighted (such as a catch-all for variables) are skipped over
+fn highlight_tree(
+    buffer: &Buffer,
+    tree: &Tree,
+    query: &Query,
+    capture_highlights: &[Option<Highlight>],
+    lines: Range<usize>,
+) -> Vec<LineHighlights> {
+    let start = buffer.line_start_byte(lines.start);
+    let end = match lines.end < buffer.line_count() {
+        true => buffer.line_start_byte(lines.end),
+        false => buffer.size_bytes(),
+    };
+
+    // Find the highlighted nodes first, since they have to be drawn outermost first
+    let mut nodes = Vec::new();
+    let mut captured = HashSet::new();
+    let mut cursor = QueryCursor::new();
+    cursor.set_byte_range(start..end);
+
+    let text = |node: Node| std::iter::once(buffer.byte_range(node.byte_range()));
+    let mut captures = cursor.captures(query, tree.root_node(), text);
+    while let Some((query_match, index)) = captures.next() {
+        let capture = query_match.captures[*index];
+        let Some(highlight) = capture_highlights[capture.index as usize] else {
+            continue;
+        };
+
+        if captured.insert(capture.node.id()) {
+            nodes.push((capture.node.byte_range(), highlight));
+        }
+    }
+    nodes.sort_by_key(|(range, _)| (range.start, std::cmp::Reverse(range.end)));
+
+    // Paint each byte of the lines with the highlight of the innermost node over it
+    let mut painted = vec![None; end - start];
+    for (range, highlight) in nodes {
+        let range = range.start.max(start) - start..range.end.min(end).max(start) - start;
+        painted[range].fill(Some(highlight));
+    }
+
+    lines
+        .map(|line| {
+            let line_start = buffer.line_start_byte(line) - start;
+            let line_end = match line + 1 < buffer.line_count() {
+                true => buffer.line_start_byte(line + 1) - start,
+                false => end - start,
+            };
+
+            let mut highlights: LineHighlights = Vec::new();
+            for (offset, highlight) in painted[line_start..line_end].iter().enumerate() {
+                let Some(highlight) = *highlight else {
+                    continue;
+                };
+
+                match highlights.last_mut() {
+                    Some((range, last)) if range.end == offset && *last == highlight => {
+                        range.end += 1
+                    }
+                    _ => highlights.push((offset..offset + 1, highlight)),
+                }
+            }
+
+            highlights
+        })
+        .collect()
+}
+
+// Gets the highlight for a capture in a grammar's highlights query, from its name
+// Captures for things which are not highlighted, such as variables and punctuation, have none
+// [EXAMPLE] "function.method" -> Function
+fn capture_highlight(name: &str) -> Option<Highlight> {
+    match name.split('.').next()? {
+        "comment" => Some(Highlight::Comment),
+        "string" | "escape" | "character" => Some(Highlight::String),
+        "number" | "float" => Some(Highlight::Number),
+        "keyword" | "include" | "conditional" | "repeat" | "exception" => Some(Highlight::Keyword),
+        "type" | "constructor" => Some(Highlight::Type),
+        "function" | "method" => Some(Highlight::Function),
+        "constant" | "boolean" => Some(Highlight::Constant),
+        _ => None,
+    }
+}
@@ -0,0 +1,454 @@
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+// The amount of time after an edit in which the next edit can be grouped with it
+const GROUP_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Represents a single change to the buffer
+#[derive(Clone)]
+pub enum Edit {
+    Insert { index: usize, text: String },
+    Delete { index: usize, text: String },
+}
+
+impl Edit {
+    // Gets the edit which reverts this edit
+    pub fn inverse(&self) -> Self {
+        match self {
+            Self::Insert { index, text } => Self::Delete {
+                index: *index,
+                text: text.clone(),
+            },
+            Self::Delete { index, text } => Self::Insert {
+                index: *index,
+                text: text.clone(),
+            },
+        }
+    }
+
+    // Gets the buffer index that the cursor should be placed at after this edit is applied
+    pub fn cursor_after(&self) -> usize {
+        match self {
+            Self::Insert { index, text } => index + text.chars().count(),
+            Self::Delete { index, .. } => *index,
+        }
+    }
+
+    // Attempts to combine the next edit into this one, returning whether it was successful
+    // Only edits that continue where this edit left off can be combined
+    fn merge(&mut self, next: &Edit) -> bool {
+        match (self, next) {
+            // Typing forwards
+            (
+                Self::Insert { index, text },
+                Self::Insert {
+                    index: next_index,
+                    text: next_text,
+                },
+            ) if *next_index == *index + text.chars().count() => {
+                text.push_str(next_text);
+                true
+            }
+            // Deleting backwards (backspace)
+            (
+                Self::Delete { index, text },
+                Self::Delete {
+                    index: next_index,
+                    text: next_text,
+                },
+            ) if *next_index + next_text.chars().count() == *index => {
+                text.insert_str(0, next_text);
+                *index = *next_index;
+                true
+            }
+            // Deleting forwards (delete)
+            (
+                Self::Delete { index, text },
+                Self::Delete {
+                    index: next_index,
+                    text: next_text,
+                },
+            ) if *next_index == *index => {
+                text.push_str(next_text);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Checks whether the edit should be the last one in its group
+    fn ends_group(&self) -> bool {
+        match self {
+            Self::Insert { text, .. } | Self::Delete { text, .. } => text.contains('\n'),
+        }
+    }
+
+    // Gets a short description of the edit for display purposes
+    fn describe(&self) -> String {
+        let (sign, text) = match self {
+            Self::Insert { text, .. } => ('+', text),
+            Self::Delete { text, .. } => ('-', text),
+        };
+
+        let mut text = text.escape_default().to_string();
+        if text.chars().count() > 20 {
+            text = text.chars().take(20).collect::<String>() + "...";
+        }
+
+        format!("{}\"{}\"", sign, text)
+    }
+}
+
+// Represents a request to move through the history chronologically, like Vim's :earlier/:later
+pub enum TimeTravel {
+    Earlier(TimeStep),
+    Later(TimeStep),
+}
+
+// Represents an amount of history, either as a number of states or an amount of time
+pub enum TimeStep {
+    Edits(usize),
+    Time(Duration),
+}
+
+impl FromStr for TimeTravel {
+    type Err = ();
+
+    // Parses a time travel request such as "-10s" (10 seconds earlier) or "+3" (3 edits later)
+    // If no sign is given, the request is assumed to be for an earlier state
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        let (earlier, s) = match s.strip_prefix('+') {
+            Some(rest) => (false, rest),
+            None => (true, s.strip_prefix('-').unwrap_or(s)),
+        };
+
+        // Split the number from its unit, if it has one
+        let unit_start = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (amount, unit) = s.split_at(unit_start);
+        let amount: u64 = amount.parse().map_err(|_| ())?;
+
+        // * Amounts too large to represent are capped, which reaches the first or latest state anyway
+        let step = match unit {
+            "" => TimeStep::Edits(amount as usize),
+            "s" => TimeStep::Time(Duration::from_secs(amount)),
+            "m" => TimeStep::Time(Duration::from_secs(amount.saturating_mul(60))),
+            "h" => TimeStep::Time(Duration::from_secs(amount.saturating_mul(60 * 60))),
+            "d" => TimeStep::Time(Duration::from_secs(amount.saturating_mul(60 * 60 * 24))),
+            _ => return Err(()),
+        };
+
+        Ok(match earlier {
+            true => Self::Earlier(step),
+            false => Self::Later(step),
+        })
+    }
+}
+
+// Represents a state of the buffer in the undo tree
+struct Node {
+    parent: Option<usize>,
+    children: Vec<usize>,
+    // The edits that lead from the parent state to this state, in the order they were applied
+    edits: Vec<Edit>,
+    // The child that was most recently visited, which is the one that redo moves to
+    last_child: Option<usize>,
+    // The time at which the state was last edited
+    time: Instant,
+}
+
+// Represents the undo history of the buffer
+// History is stored as a tree rather than a stack so that making an edit after undoing
+// starts a new branch instead of discarding the undone edits
+pub struct History {
+    nodes: Vec<Node>,
+    current: usize,
+    // Whether the next edit can be grouped with the current state's edits
+    group_open: bool,
+    last_record: Option<Instant>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        // The root node represents the original state of the buffer
+        let root = Node {
+            parent: None,
+            children: Vec::new(),
+            edits: Vec::new(),
+            last_child: None,
+            time: Instant::now(),
+        };
+
+        Self {
+            nodes: vec![root],
+            current: 0,
+            group_open: false,
+            last_record: None,
+        }
+    }
+}
+
+impl History {
+    // Records an edit which has been applied to the buffer
+    // Consecutive edits are grouped into a single state until the group is broken by a pause,
+    // a newline, or the editor calling break_group (on cursor movement, etc.)
+    pub fn record(&mut self, edit: Edit) {
+        let now = Instant::now();
+        let paused = self
+            .last_record
+            .is_none_or(|last_record| now.duration_since(last_record) > GROUP_TIMEOUT);
+        self.last_record = Some(now);
+
+        let ends_group = edit.ends_group();
+
+        // States which have already been branched from are never added to
+        let node = &mut self.nodes[self.current];
+        if self.group_open && !paused && node.children.is_empty() {
+            if let Some(last_edit) = node.edits.last_mut() {
+                if last_edit.merge(&edit) {
+                    node.time = now;
+                    self.group_open = !ends_group;
+                    return;
+                }
+            }
+        }
+
+        let id = self.nodes.len();
+
+        self.nodes.push(Node {
+            parent: Some(self.current),
+            children: Vec::new(),
+            edits: vec![edit],
+            last_child: None,
+            time: now,
+        });
+
+        let parent = &mut self.nodes[self.current];
+        parent.children.push(id);
+        parent.last_child = Some(id);
+
+        self.current = id;
+        self.group_open = !ends_group;
+    }
+
+    // Records several edits which have been applied to the buffer together, such as moving a line,
+    // as a single state so that they are undone together
+    pub fn record_together(&mut self, edits: Vec<Edit>) {
+        if edits.is_empty() {
+            return;
+        }
+
+        let id = self.nodes.len();
+
+        self.nodes.push(Node {
+            parent: Some(self.current),
+            children: Vec::new(),
+            edits,
+            last_child: None,
+            time: Instant::now(),
+        });
+
+        let parent = &mut self.nodes[self.current];
+        parent.children.push(id);
+        parent.last_child = Some(id);
+
+        self.current = id;
+        self.group_open = false;
+        self.last_record = None;
+    }
+
+    // Prevents the next edit from being grouped with the previous ones
+    pub fn break_group(&mut self) {
+        self.group_open = false;
+    }
+
+    // Moves to the parent state, returning the edits which must be applied to the buffer
+    pub fn undo(&mut self) -> Option<Vec<Edit>> {
+        let parent = self.nodes[self.current].parent?;
+        let edits = self.revert_edits(self.current);
+        self.group_open = false;
+
+        self.nodes[parent].last_child = Some(self.current);
+        self.current = parent;
+
+        Some(edits)
+    }
+
+    // Moves to the most recently visited child state, returning the edits which must be applied to the buffer
+    pub fn redo(&mut self) -> Option<Vec<Edit>> {
+        let child = self.nodes[self.current].last_child?;
+        self.current = child;
+        self.group_open = false;
+
+        Some(self.nodes[child].edits.clone())
+    }
+
+    // Moves to any state in the tree, returning the edits which must be applied to the buffer
+    pub fn checkout(&mut self, target: usize) -> Vec<Edit> {
+        if target >= self.nodes.len() {
+            return Vec::new();
+        }
+
+        // Find the path from the target up to the root, then walk up from the current
+        // state until reaching a state on that path (the common ancestor)
+        let target_path = self.path_to_root(target);
+
+        let mut edits = Vec::new();
+        while !target_path.contains(&self.current) {
+            edits.extend(
+                self.undo()
+                    .expect("[INTERNAL ERROR] Undo tree is missing its root"),
+            );
+        }
+
+        // Walk down from the common ancestor to the target
+        let ancestor_position = target_path
+            .iter()
+            .position(|&id| id == self.current)
+            .expect("[INTERNAL ERROR] Undo tree path is missing the common ancestor");
+
+        for &id in target_path[..ancestor_position].iter().rev() {
+            self.nodes[self.current].last_child = Some(id);
+            edits.extend(
+                self.redo()
+                    .expect("[INTERNAL ERROR] Undo tree path is broken"),
+            );
+        }
+
+        edits
+    }
+
+    // Moves to the state which was current at an earlier or later point, returning the edits
+    // which must be applied to the buffer
+    // * States are numbered in the order they were created, so this can cross between branches
+    pub fn time_travel(&mut self, travel: TimeTravel) -> Vec<Edit> {
+        let last = self.nodes.len() - 1;
+        let current_time = self.nodes[self.current].time;
+
+        let target = match travel {
+            TimeTravel::Earlier(TimeStep::Edits(count)) => self.current.saturating_sub(count),
+            TimeTravel::Later(TimeStep::Edits(count)) => {
+                self.current.saturating_add(count).min(last)
+            }
+            TimeTravel::Earlier(TimeStep::Time(duration)) => {
+                match current_time.checked_sub(duration) {
+                    Some(time) => self.newest_state_before(time),
+                    None => 0,
+                }
+            }
+            TimeTravel::Later(TimeStep::Time(duration)) => {
+                match current_time.checked_add(duration) {
+                    Some(time) => self.newest_state_before(time),
+                    None => last,
+                }
+            }
+        };
+
+        self.checkout(target)
+    }
+
+    // Gets the ID of the newest state which was last edited at or before the given time
+    fn newest_state_before(&self, time: Instant) -> usize {
+        self.nodes
+            .iter()
+            .rposition(|node| node.time <= time)
+            .unwrap_or(0)
+    }
+
+    // Gets the ID of the current state
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    // Gets the states of the tree in display order, along with a line visualizing each one
+    // Linear stretches of history stay in the same column, and only branches are indented
+    // [EXAMPLE]
+    // o 0 (original) (2m ago)
+    // o 1 +"a" (1m ago)
+    // ├─o 2 +"b" (45s ago)
+    // └─@ 3 +"c" (3s ago)
+    pub fn tree_rows(&self) -> Vec<(usize, String)> {
+        let mut rows = Vec::new();
+
+        // The tree is walked iteratively because linear histories can be very deep
+        let mut stack = vec![(0, String::new(), String::new())];
+        while let Some((id, prefix, child_prefix)) = stack.pop() {
+            let node = &self.nodes[id];
+            let marker = if id == self.current { '@' } else { 'o' };
+            let description = match node.edits.as_slice() {
+                [] => "(original)".to_string(),
+                edits => edits
+                    .iter()
+                    .map(Edit::describe)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            };
+
+            rows.push((
+                id,
+                format!(
+                    "{}{} {} {} ({} ago)",
+                    prefix,
+                    marker,
+                    id,
+                    description,
+                    format_age(node.time.elapsed())
+                ),
+            ));
+
+            // Children are pushed in reverse so that they are displayed oldest first
+            let child_count = node.children.len();
+            for (i, &child) in node.children.iter().enumerate().rev() {
+                let (branch, continuation) = match (child_count, i == child_count - 1) {
+                    (1, _) => ("", ""),
+                    (_, true) => ("└─", "  "),
+                    (_, false) => ("├─", "│ "),
+                };
+
+                stack.push((
+                    child,
+                    format!("{}{}", child_prefix, branch),
+                    format!("{}{}", child_prefix, continuation),
+                ));
+            }
+        }
+
+        rows
+    }
+
+    // Gets the edits which revert a node to its parent state
+    fn revert_edits(&self, id: usize) -> Vec<Edit> {
+        self.nodes[id]
+            .edits
+            .iter()
+            .rev()
+            .map(Edit::inverse)
+            .collect()
+    }
+
+    // Gets the IDs of a node and all of its ancestors, starting from the node itself
+    fn path_to_root(&self, id: usize) -> Vec<usize> {
+        let mut path = vec![id];
+        let mut node = id;
+
+        while let Some(parent) = self.nodes[node].parent {
+            path.push(parent);
+            node = parent;
+        }
+
+        path
+    }
+}
+
+// Formats an amount of time in its largest whole unit, e.g. "5m"
+fn format_age(age: Duration) -> String {
+    let seconds = age.as_secs();
+
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m", seconds / 60),
+        3600..=86399 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86400),
+    }
+}
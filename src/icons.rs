@@ -0,0 +1,43 @@
+// The Nerd Font glyph shown next to each kind of file, by extension
+// * These are only shown when icons are turned on in the config, since they need a patched font
+// * and show up as boxes without one
+const FILE_ICONS: &[(&[&str], char)] = &[
+    (&["rs"], '\u{e7a8}'),
+    (&["c", "h"], '\u{e61e}'),
+    (&["cpp", "cc", "hpp"], '\u{e61d}'),
+    (&["go"], '\u{e627}'),
+    (&["java"], '\u{e738}'),
+    (&["js", "mjs", "cjs", "jsx"], '\u{e74e}'),
+    (&["ts", "tsx"], '\u{e628}'),
+    (&["py", "pyi"], '\u{e73c}'),
+    (&["sh", "bash", "zsh"], '\u{f489}'),
+    (&["json"], '\u{e60b}'),
+    (&["toml", "yaml", "yml", "ini", "cfg"], '\u{e615}'),
+    (&["md", "markdown"], '\u{e73e}'),
+    (&["html", "htm"], '\u{e736}'),
+    (&["css"], '\u{e749}'),
+    (&["csv", "tsv"], '\u{f1c3}'),
+    (&["png", "jpg", "jpeg", "gif", "bmp", "webp"], '\u{f1c5}'),
+    (&["txt"], '\u{f15c}'),
+    (&["lock"], '\u{f023}'),
+];
+
+// The glyph shown for files which have no icon of their own
+const DEFAULT_FILE_ICON: char = '\u{f15b}';
+
+// The glyph shown for directories
+pub const DIRECTORY_ICON: char = '\u{f07b}';
+
+// Gets the glyph for a file based on its extension
+// [EXAMPLE] "src/main.rs" -> the Rust logo
+pub fn file_icon(filename: &str) -> char {
+    let Some((_, extension)) = filename.rsplit_once('.') else {
+        return DEFAULT_FILE_ICON;
+    };
+    let extension = extension.to_lowercase();
+
+    FILE_ICONS
+        .iter()
+        .find(|(extensions, _)| extensions.contains(&extension.as_str()))
+        .map_or(DEFAULT_FILE_ICON, |&(_, icon)| icon)
+}
@@ -0,0 +1,133 @@
+use std::env;
+
+use crate::clipboard::base64_encode;
+use crate::fileinfo::format_size;
+
+// The number of Base64 characters sent in each chunk of a Kitty graphics command, which is the most allowed
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+// Deletes every image the Kitty graphics protocol has drawn, such as when the preview is closed
+pub const KITTY_DELETE_ALL: &str = "\x1b_Ga=d,q=2\x1b\\";
+
+// Represents what the header of an image file says about it
+pub struct ImageInfo {
+    pub format: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Reads the format and dimensions of an image from the start of its file, returning None if the
+// bytes are not a PNG, GIF, JPEG, or BMP image
+// * Only the header is read, so this works with just the first few kilobytes of a large file
+pub fn image_info(bytes: &[u8]) -> Option<ImageInfo> {
+    let be_u16 =
+        |at: usize| Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32);
+    let le_u16 =
+        |at: usize| Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32);
+    let be_u32 = |at: usize| Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    let le_i32 = |at: usize| Some(i32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+
+    let (format, width, height) = if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        ("PNG", be_u32(16)?, be_u32(20)?)
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        ("GIF", le_u16(6)?, le_u16(8)?)
+    } else if bytes.starts_with(b"BM") {
+        // Bottom-up bitmaps are stored with a negative height
+        (
+            "BMP",
+            le_i32(18)?.unsigned_abs(),
+            le_i32(22)?.unsigned_abs(),
+        )
+    } else if bytes.starts_with(b"\xff\xd8") {
+        // The dimensions are in the first start-of-frame segment, so the segments before it are skipped
+        let mut at = 2;
+        loop {
+            let marker = *bytes.get(at + 1)?;
+            if bytes[at] != 0xff {
+                return None;
+            }
+
+            match marker {
+                0xc0..=0xcf if !matches!(marker, 0xc4 | 0xc8 | 0xcc) => {
+                    break ("JPEG", be_u16(at + 7)?, be_u16(at + 5)?);
+                }
+                _ => at += 2 + be_u16(at + 2)? as usize,
+            }
+        }
+    } else {
+        return None;
+    };
+
+    Some(ImageInfo {
+        format,
+        width,
+        height,
+    })
+}
+
+// Describes an image in one line, such as for when it cannot be shown
+// [EXAMPLE] "PNG image, 640×480, 12.3 KB"
+pub fn describe(info: &ImageInfo, size: usize) -> String {
+    format!(
+        "{} image, {}×{}, {}",
+        info.format,
+        info.width,
+        info.height,
+        format_size(size)
+    )
+}
+
+// Checks whether the terminal supports the Kitty graphics protocol, based on the environment it sets
+// TODO: Support Sixel graphics as well, which needs the image to be decoded into pixels first
+pub fn graphics_supported() -> bool {
+    let term = env::var("TERM").unwrap_or_default();
+    let program = env::var("TERM_PROGRAM").unwrap_or_default();
+
+    env::var_os("KITTY_WINDOW_ID").is_some()
+        || term.contains("kitty")
+        || term.contains("ghostty")
+        || program == "WezTerm"
+        || program == "ghostty"
+}
+
+// Gets the number of columns and rows an image should take up to fit in the given space
+// without being stretched, assuming that terminal cells are twice as tall as they are wide
+pub fn fit(info: &ImageInfo, max_columns: u16, max_rows: u16) -> (u16, u16) {
+    let (width, height) = (info.width.max(1) as f64, info.height.max(1) as f64);
+
+    let columns = (max_columns as f64).min(width / 8.0).max(1.0);
+    let rows = columns * height / width / 2.0;
+
+    match rows > max_rows as f64 {
+        true => (
+            (max_rows as f64 * 2.0 * width / height).max(1.0) as u16,
+            max_rows,
+        ),
+        false => (columns as u16, (rows.ceil() as u16).max(1)),
+    }
+}
+
+// Builds the Kitty graphics command which draws a PNG image at the cursor, scaled to the given
+// number of columns and rows, without moving the cursor
+// * The image data is split into chunks, since terminals limit how long each command can be
+pub fn kitty_sequence(png: &[u8], columns: u16, rows: u16) -> String {
+    let encoded = base64_encode(png);
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(KITTY_CHUNK_SIZE).collect();
+
+    let mut sequence = String::with_capacity(encoded.len() + chunks.len() * 16);
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        let chunk = std::str::from_utf8(chunk).expect("[INTERNAL ERROR] Base64 was not ASCII");
+
+        // Only the first chunk carries the options, and q=2 stops the terminal from replying
+        match i {
+            0 => sequence.push_str(&format!(
+                "\x1b_Gf=100,a=T,q=2,C=1,c={},r={},m={};{}\x1b\\",
+                columns, rows, more, chunk
+            )),
+            _ => sequence.push_str(&format!("\x1b_Gm={};{}\x1b\\", more, chunk)),
+        }
+    }
+
+    sequence
+}
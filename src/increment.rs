@@ -0,0 +1,124 @@
+use std::ops::Range;
+
+// Finds the number under or after the given column of a line and adds an amount to it,
+// returning the range of characters the number takes up and the text to replace it with
+// Decimal numbers can be negative, and hexadecimal numbers start with 0x and keep their case and width
+// [EXAMPLE] ("x = 0x0f;", 0, 1) -> (4..8, "0x10")
+pub fn increment_number(line: &str, column: usize, amount: i64) -> Option<(Range<usize>, String)> {
+    let chars: Vec<char> = line.chars().collect();
+
+    let mut start = 0;
+    while start < chars.len() {
+        let (range, replacement) = number_at(&chars, start, amount);
+
+        match range {
+            Some(range) if range.end > column => return replacement.map(|text| (range, text)),
+            Some(range) => start = range.end,
+            None => start += 1,
+        }
+    }
+
+    None
+}
+
+// Reads the number starting at the given position, if there is one, along with its replacement
+// The replacement is None if the number is too large to be incremented
+fn number_at(chars: &[char], start: usize, amount: i64) -> (Option<Range<usize>>, Option<String>) {
+    let digits_end = |from: usize, radix: u32| {
+        (from..chars.len())
+            .find(|&i| !chars[i].is_digit(radix))
+            .unwrap_or(chars.len())
+    };
+
+    // Hexadecimal numbers
+    if chars[start] == '0'
+        && matches!(chars.get(start + 1), Some('x' | 'X'))
+        && chars.get(start + 2).is_some_and(|c| c.is_ascii_hexdigit())
+    {
+        let end = digits_end(start + 2, 16);
+        let digits: String = chars[start + 2..end].iter().collect();
+        let uppercase = digits.chars().any(|c| c.is_ascii_uppercase());
+
+        let replacement = u64::from_str_radix(&digits, 16).ok().map(|value| {
+            let value = value.wrapping_add_signed(amount);
+            let width = digits.len();
+            let prefix: String = chars[start..start + 2].iter().collect();
+
+            match uppercase {
+                true => format!("{}{:0width$X}", prefix, value),
+                false => format!("{}{:0width$x}", prefix, value),
+            }
+        });
+
+        return (Some(start..end), replacement);
+    }
+
+    // Decimal numbers, including a minus sign directly before them
+    if chars[start].is_ascii_digit()
+        || (chars[start] == '-' && chars.get(start + 1).is_some_and(char::is_ascii_digit))
+    {
+        let end = digits_end(start + 1, 10);
+        let number: String = chars[start..end].iter().collect();
+
+        let replacement = number
+            .parse::<i64>()
+            .ok()
+            .and_then(|value| value.checked_add(amount))
+            .map(|value| value.to_string());
+
+        return (Some(start..end), replacement);
+    }
+
+    (None, None)
+}
+
+// Builds a sequence which counts up from a starting value, with one value for each of `count` places
+// Numbers keep the width of the start if it has leading zeros, and letters carry on from z to aa,
+// like the columns of a spreadsheet
+// [EXAMPLE] ("08", 3) -> ["08", "09", "10"], ("y", 3) -> ["y", "z", "aa"]
+pub fn sequence(start: &str, count: usize) -> Option<Vec<String>> {
+    let start = start.trim();
+
+    if let Ok(number) = start.parse::<i64>() {
+        let digits = start.trim_start_matches('-');
+        let width = match digits.len() > 1 && digits.starts_with('0') {
+            true => start.len(),
+            false => 0,
+        };
+
+        return (0..count as i64)
+            .map(|offset| {
+                let value = number.checked_add(offset)?;
+                Some(format!("{:0width$}", value, width = width))
+            })
+            .collect();
+    }
+
+    let uppercase = start.chars().all(|c| c.is_ascii_uppercase());
+    if start.is_empty() || !(uppercase || start.chars().all(|c| c.is_ascii_lowercase())) {
+        return None;
+    }
+
+    // Letters count in base 26 without a zero, so that a comes after z in the next column
+    let value = start.chars().try_fold(0u64, |value, c| {
+        let digit = (c.to_ascii_lowercase() as u8 - b'a') as u64 + 1;
+        value.checked_mul(26)?.checked_add(digit)
+    })?;
+
+    (0..count as u64)
+        .map(|offset| Some(letters(value.checked_add(offset)?, uppercase)))
+        .collect()
+}
+
+// Writes a number counted in letters, where 1 is a, 26 is z, and 27 is aa
+fn letters(mut value: u64, uppercase: bool) -> String {
+    let base = if uppercase { b'A' } else { b'a' };
+    let mut letters = Vec::new();
+    while value > 0 {
+        value -= 1;
+        letters.push((base + (value % 26) as u8) as char);
+        value /= 26;
+    }
+
+    letters.iter().rev().collect()
+}
@@ -0,0 +1,44 @@
+use std::io;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use crossterm::event::{self, Event};
+use crossterm::Result;
+
+// Reads events from the terminal on a background thread and passes them to the event loop
+// ! crossterm keeps retrying forever when reading from a terminal which has been closed, so if
+// ! events were read on the main thread, the editor would never get to handle the SIGHUP and exit
+pub struct InputReader {
+    receiver: Receiver<Result<Event>>,
+}
+
+impl InputReader {
+    // Starts reading events, which should only be done once the terminal is in raw mode
+    pub fn start() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || loop {
+            let event = event::read();
+            let failed = event.is_err();
+
+            // * If the editor has stopped listening, there is nobody to pass the events to
+            if sender.send(event).is_err() || failed {
+                return;
+            }
+        });
+
+        Self { receiver }
+    }
+
+    // Waits up to the given amount of time for the next event, returning None if there is none yet
+    pub fn next(&self, timeout: Duration) -> Option<Result<Event>> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(event) => Some(event),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => {
+                Some(Err(io::Error::other("the input thread stopped")))
+            }
+        }
+    }
+}
@@ -0,0 +1,409 @@
+use std::fmt;
+
+// Represents a JSON value, as sent to and from debug adapters
+// * Objects keep their members in the order they were written, and there are few enough of them
+// * that looking one up by going through them all is fine
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    // Builds an object from its members
+    // [EXAMPLE] Json::object([("line", 4.into())]) -> {"line":4}
+    pub fn object<const N: usize>(members: [(&str, Json); N]) -> Self {
+        Self::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    // Gets a member of an object, or None if this is not an object or it has no such member
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Self::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    // Gets a number which is a whole number, such as an id or a line number
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Self::Number(number) if number.fract() == 0.0 => Some(number as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Self::Boolean(boolean) => Some(boolean),
+            _ => None,
+        }
+    }
+
+    // Gets the items of an array, which are empty if this is not an array
+    pub fn items(&self) -> &[Json] {
+        match self {
+            Self::Array(items) => items,
+            _ => &[],
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(string: &str) -> Self {
+        Self::String(string.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(string: String) -> Self {
+        Self::String(string)
+    }
+}
+
+impl From<i64> for Json {
+    fn from(number: i64) -> Self {
+        Self::Number(number as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(boolean: bool) -> Self {
+        Self::Boolean(boolean)
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(items: Vec<Json>) -> Self {
+        Self::Array(items)
+    }
+}
+
+impl fmt::Display for Json {
+    // Writes the value as compact JSON text
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Boolean(boolean) => write!(f, "{}", boolean),
+            // Whole numbers are written without a fraction, since ids and line numbers have to be
+            Self::Number(number) if number.fract() == 0.0 && number.abs() < 1e15 => {
+                write!(f, "{}", *number as i64)
+            }
+            Self::Number(number) => write!(f, "{}", number),
+            Self::String(string) => write_string(f, string),
+            Self::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Self::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+// Writes a string in quotes, escaping the characters which JSON does not allow in one
+fn write_string(f: &mut fmt::Formatter, string: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in string.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+// Parses a JSON document, or describes the first thing wrong with it
+// [EXAMPLE] "{\"program\": \"target/debug/app\", \"args\": []}"
+pub fn parse(text: &str) -> Result<Json, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        position: 0,
+    };
+
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    match parser.peek() {
+        None => Ok(value),
+        Some(c) => Err(format!("Unexpected {} after the value", c)),
+    }
+}
+
+// Reads a JSON document one character at a time
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    // Consumes a character which has to come next
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("Expected {}, found {}", expected, c)),
+            None => Err(format!("Expected {}, found the end of the text", expected)),
+        }
+    }
+
+    // Consumes a keyword such as true, returning the value it stands for
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        for expected in keyword.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Json::String),
+            Some('t') => self.keyword("true", Json::Boolean(true)),
+            Some('f') => self.keyword("false", Json::Boolean(false)),
+            Some('n') => self.keyword("null", Json::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) => Err(format!("Unexpected {}", c)),
+            None => Err("Expected a value, found the end of the text".to_string()),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            members.push((key, self.value()?));
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Json::Object(members)),
+                _ => return Err("Expected , or } in an object".to_string()),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Json::Array(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Json::Array(items)),
+                _ => return Err("Expected , or ] in an array".to_string()),
+            }
+        }
+    }
+
+    // Parses a quoted string, including its escapes
+    // * Characters outside the Basic Multilingual Plane are escaped as a pair of surrogates
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => match self.next() {
+                    Some('"') => string.push('"'),
+                    Some('\\') => string.push('\\'),
+                    Some('/') => string.push('/'),
+                    Some('b') => string.push('\u{8}'),
+                    Some('f') => string.push('\u{c}'),
+                    Some('n') => string.push('\n'),
+                    Some('r') => string.push('\r'),
+                    Some('t') => string.push('\t'),
+                    Some('u') => {
+                        let high = self.hex_escape()?;
+                        let code = match (0xd800..0xdc00).contains(&high) {
+                            true => {
+                                self.expect('\\')?;
+                                self.expect('u')?;
+                                let low = self.hex_escape()?;
+                                0x10000
+                                    + ((high - 0xd800) << 10)
+                                    + (low.wrapping_sub(0xdc00) & 0x3ff)
+                            }
+                            false => high,
+                        };
+                        string.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    _ => return Err("Invalid escape in a string".to_string()),
+                },
+                Some(c) => string.push(c),
+                None => return Err("Unterminated string".to_string()),
+            }
+        }
+    }
+
+    // Parses the four hex digits of a \u escape
+    fn hex_escape(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| "Invalid \\u escape in a string".to_string())?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            self.position += 1;
+        }
+
+        let text: String = self.chars[start..self.position].iter().collect();
+        text.parse()
+            .map(Json::Number)
+            .map_err(|_| format!("Invalid number: {}", text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_values() {
+        let json =
+            parse(r#" {"id": 4, "ok": true, "body": {"items": [null, -1.5e2, "x"]}} "#).unwrap();
+
+        assert_eq!(json.get("id").and_then(Json::as_i64), Some(4));
+        assert_eq!(json.get("ok").and_then(Json::as_bool), Some(true));
+        assert_eq!(
+            json.get("body")
+                .map(|body| body.get("items").unwrap().items()),
+            Some(&[Json::Null, Json::Number(-150.0), Json::from("x")][..])
+        );
+        assert_eq!(json.get("missing"), None);
+    }
+
+    #[test]
+    fn decodes_escapes_and_surrogate_pairs() {
+        let json = parse(r#""a\"\\\/\n\u00e9\ud83d\ude00""#).unwrap();
+        assert_eq!(json.as_str(), Some("a\"\\/\né😀"));
+
+        // A lone low surrogate is not a character
+        let json = parse(r#""\udc00""#).unwrap();
+        assert_eq!(json.as_str(), Some("\u{fffd}"));
+    }
+
+    #[test]
+    fn writes_what_it_parses() {
+        let json = Json::object([
+            ("text", "tab\there \"😀\"\u{1}".into()),
+            ("line", 4.into()),
+            ("args", vec![true.into(), Json::Number(0.5)].into()),
+        ]);
+
+        let written = json.to_string();
+        assert_eq!(
+            written,
+            r#"{"text":"tab\there \"😀\"\u0001","line":4,"args":[true,0.5]}"#
+        );
+        assert_eq!(parse(&written), Ok(json));
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(parse(r#"{"a" 1}"#), Err("Expected :, found 1".to_string()));
+        assert_eq!(
+            parse("[1 2]"),
+            Err("Expected , or ] in an array".to_string())
+        );
+        assert_eq!(parse(r#""open"#), Err("Unterminated string".to_string()));
+        assert_eq!(
+            parse(r#""\x""#),
+            Err("Invalid escape in a string".to_string())
+        );
+        assert_eq!(
+            parse("nul"),
+            Err("Expected l, found the end of the text".to_string())
+        );
+        assert_eq!(
+            parse("1 2"),
+            Err("Unexpected 2 after the value".to_string())
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use std::ops::Range;
+
+use crate::marks::{adjust_index_for_delete, adjust_index_for_insert};
+use crate::BufferObserver;
+
+// The maximum amount of jumps that are remembered
+const MAX_JUMPS: usize = 100;
+
+// Represents a position the cursor jumped away from, in one of the open files
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Jump {
+    // The index of the file in the editor's list of open files
+    pub document: usize,
+    pub index: usize,
+}
+
+// Represents the history of significant cursor jumps (marks, search, goto-line, switching files, etc.)
+// so the user can navigate backwards and forwards through them
+// * There is one list for the whole editor, so navigating it can move between files
+#[derive(Default)]
+pub struct JumpList {
+    jumps: Vec<Jump>,
+    // The position in the list that the user has navigated to
+    // If this is equal to the length of the list, the user is not navigating the list
+    position: usize,
+    // The file being edited, whose buffer changes the jumps are adjusted for
+    document: usize,
+}
+
+impl JumpList {
+    // Records the position that the cursor is jumping away from
+    pub fn record(&mut self, jump: Jump) {
+        // Jumping from the middle of the list discards the jumps after it
+        self.jumps.truncate(self.position);
+
+        // Avoid storing the same position twice in a row
+        if self.jumps.last() != Some(&jump) {
+            self.jumps.push(jump);
+        }
+
+        if self.jumps.len() > MAX_JUMPS {
+            self.jumps.remove(0);
+        }
+
+        self.position = self.jumps.len();
+    }
+
+    // Gets the position of the previous jump, if there is one
+    // The current cursor position is stored so that the user can return to it
+    pub fn back(&mut self, current: Jump) -> Option<Jump> {
+        if self.jumps.is_empty() {
+            return None;
+        }
+
+        if self.position == self.jumps.len() {
+            if self.jumps.last() != Some(&current) {
+                self.jumps.push(current);
+            }
+
+            self.position = self.jumps.len() - 1;
+        }
+
+        if self.position == 0 {
+            return None;
+        }
+
+        self.position -= 1;
+        self.jumps.get(self.position).copied()
+    }
+
+    // Gets the position of the next jump, if there is one
+    pub fn forward(&mut self) -> Option<Jump> {
+        if self.position + 1 >= self.jumps.len() {
+            return None;
+        }
+
+        self.position += 1;
+        self.jumps.get(self.position).copied()
+    }
+
+    // Sets the file being edited, so that only its jumps are shifted by changes to the buffer
+    pub fn set_document(&mut self, document: usize) {
+        self.document = document;
+    }
+
+    // Forgets the jumps in a file, such as when its buffer is replaced by another file's
+    pub fn clear_document(&mut self, document: usize) {
+        let before_position = self.jumps[..self.position]
+            .iter()
+            .filter(|jump| jump.document == document)
+            .count();
+
+        self.jumps.retain(|jump| jump.document != document);
+        self.position -= before_position;
+    }
+
+    // Forgets the jumps in a file which has been closed, and renumbers the files after it
+    pub fn close_document(&mut self, document: usize) {
+        self.clear_document(document);
+
+        for jump in self.jumps.iter_mut() {
+            if jump.document > document {
+                jump.document -= 1;
+            }
+        }
+
+        if self.document > document {
+            self.document -= 1;
+        }
+    }
+}
+
+impl BufferObserver for JumpList {
+    // Shifts the jumps after an insertion of `len` characters at the given index
+    fn adjust_for_insert(&mut self, index: usize, len: usize) {
+        for jump in self.jumps.iter_mut() {
+            if jump.document == self.document {
+                jump.index = adjust_index_for_insert(jump.index, index, len);
+            }
+        }
+    }
+
+    // Shifts the jumps after the given range of characters has been deleted
+    fn adjust_for_delete(&mut self, range: Range<usize>) {
+        for jump in self.jumps.iter_mut() {
+            if jump.document == self.document {
+                jump.index = adjust_index_for_delete(jump.index, range.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::actions::Action;
+
+// A key along with the modifiers which have to be held for it
+pub type Key = (KeyCode, KeyModifiers);
+
+const NONE: KeyModifiers = KeyModifiers::NONE;
+const CONTROL: KeyModifiers = KeyModifiers::CONTROL;
+const ALT: KeyModifiers = KeyModifiers::ALT;
+const SHIFT: KeyModifiers = KeyModifiers::SHIFT;
+const CONTROL_SHIFT: KeyModifiers = KeyModifiers::CONTROL.union(KeyModifiers::SHIFT);
+const ALT_SHIFT: KeyModifiers = KeyModifiers::ALT.union(KeyModifiers::SHIFT);
+const CONTROL_ALT: KeyModifiers = KeyModifiers::CONTROL.union(KeyModifiers::ALT);
+
+// The keys which run an action in every keymap, unless a preset binds them to something else
+// * Keys which do more than run an action, such as the arrows, prefixes like Ctrl+W, and Ctrl+A and
+// * Ctrl+X (which use the count), are handled directly by Editor::handle_key_event instead
+// ! This needs to be kept in sync with help::KEY_BINDINGS
+const DEFAULT_BINDINGS: &[(Key, Action)] = &[
+    ((KeyCode::F(1), NONE), Action::ShowHelp),
+    // ! Ctrl+Shift+P is only reported by terminals which support the enhanced keyboard
+    // ! protocol, and the others send it as Ctrl+P, so that is bound as well
+    ((KeyCode::Char('p'), CONTROL), Action::OpenCommandPalette),
+    ((KeyCode::Char('P'), CONTROL), Action::OpenCommandPalette),
+    (
+        (KeyCode::Char('p'), CONTROL_SHIFT),
+        Action::OpenCommandPalette,
+    ),
+    (
+        (KeyCode::Char('P'), CONTROL_SHIFT),
+        Action::OpenCommandPalette,
+    ),
+    ((KeyCode::Char(';'), ALT), Action::OpenCommandLine),
+    ((KeyCode::Char(':'), ALT), Action::OpenCommandLine),
+    ((KeyCode::Char(':'), ALT_SHIFT), Action::OpenCommandLine),
+    ((KeyCode::Char('q'), CONTROL), Action::Quit),
+    ((KeyCode::Char('s'), CONTROL), Action::Save),
+    ((KeyCode::Home, NONE), Action::LineStart),
+    ((KeyCode::End, NONE), Action::LineEnd),
+    ((KeyCode::Char('b'), ALT), Action::MoveWordLeft),
+    ((KeyCode::Char('f'), ALT), Action::MoveWordRight),
+    ((KeyCode::Char('h'), ALT), Action::SelectParagraph),
+    ((KeyCode::Char('s'), ALT), Action::SelectEnclosingBlock),
+    ((KeyCode::PageDown, ALT), Action::NextFunction),
+    ((KeyCode::PageUp, ALT), Action::PreviousFunction),
+    ((KeyCode::Up, ALT), Action::MoveLineUp),
+    ((KeyCode::Down, ALT), Action::MoveLineDown),
+    ((KeyCode::Char('k'), CONTROL), Action::Copy),
+    ((KeyCode::Char('k'), ALT), Action::DeleteSentence),
+    ((KeyCode::Char('y'), ALT), Action::Yank),
+    ((KeyCode::Insert, NONE), Action::ToggleOverwrite),
+    ((KeyCode::Char('='), ALT), Action::Calculate),
+    ((KeyCode::Char('p'), ALT), Action::ToggleRawPaste),
+    ((KeyCode::Char('z'), CONTROL), Action::Undo),
+    ((KeyCode::Char('y'), CONTROL), Action::Redo),
+    ((KeyCode::Char('u'), CONTROL), Action::OpenUndoTree),
+    ((KeyCode::Char('t'), CONTROL), Action::TimeTravel),
+    ((KeyCode::Char('r'), CONTROL), Action::InsertFile),
+    ((KeyCode::Char('f'), CONTROL), Action::Search),
+    ((KeyCode::Char('r'), ALT), Action::ReplaceRegex),
+    ((KeyCode::F(3), NONE), Action::NextMatch),
+    ((KeyCode::F(3), SHIFT), Action::PreviousMatch),
+    ((KeyCode::Char('j'), CONTROL), Action::OpenMarks),
+    // ! Terminals without the enhanced keyboard protocol send Ctrl+I as Tab,
+    // ! so it may not be distinguishable
+    ((KeyCode::Char('o'), CONTROL), Action::JumpBack),
+    ((KeyCode::Char('i'), CONTROL), Action::JumpForward),
+    ((KeyCode::Char('i'), ALT), Action::ShowFileInfo),
+    ((KeyCode::Char('v'), ALT), Action::PreviewImage),
+    ((KeyCode::Char('t'), ALT), Action::RunTask),
+    ((KeyCode::Char('o'), ALT), Action::ShowTaskOutput),
+    ((KeyCode::Char('n'), ALT), Action::ChangeEncoding),
+    ((KeyCode::Char('e'), CONTROL), Action::ExportHtml),
+    ((KeyCode::F(2), NONE), Action::ToggleInvisibles),
+    ((KeyCode::F(4), NONE), Action::ToggleCurrentLineHighlight),
+    ((KeyCode::F(11), NONE), Action::ToggleZenMode),
+    ((KeyCode::F(12), NONE), Action::TogglePerformanceOverlay),
+    ((KeyCode::F(5), NONE), Action::InsertDate),
+    ((KeyCode::PageDown, CONTROL), Action::NextBuffer),
+    ((KeyCode::PageUp, CONTROL), Action::PreviousBuffer),
+    ((KeyCode::Char('l'), ALT), Action::ListBuffers),
+    ((KeyCode::Char('d'), ALT), Action::ShowDiagnostics),
+    ((KeyCode::Enter, ALT), Action::OpenPastedPath),
+    ((KeyCode::F(9), NONE), Action::ToggleBreakpoint),
+    ((KeyCode::F(6), NONE), Action::StartDebugging),
+    ((KeyCode::F(6), SHIFT), Action::StopDebugging),
+    ((KeyCode::F(10), NONE), Action::StepOver),
+    ((KeyCode::F(7), NONE), Action::StepInto),
+    ((KeyCode::F(8), NONE), Action::StepOut),
+    ((KeyCode::Char('x'), ALT), Action::ShowVariables),
+    ((KeyCode::Up, CONTROL_ALT), Action::AddCursorAbove),
+    ((KeyCode::Down, CONTROL_ALT), Action::AddCursorBelow),
+    ((KeyCode::Char('c'), ALT), Action::InsertSequence),
+];
+
+// The keys which the Emacs preset binds on top of the default ones
+// * This takes over Ctrl+W, so the view commands can only be run from the command palette
+// * Alt+F and Alt+B already move by word by default, but are listed so the preset is complete
+const EMACS_BINDINGS: &[(Key, Action)] = &[
+    ((KeyCode::Char('a'), CONTROL), Action::LineStart),
+    ((KeyCode::Char('e'), CONTROL), Action::LineEnd),
+    ((KeyCode::Char('k'), CONTROL), Action::KillLine),
+    ((KeyCode::Char('f'), ALT), Action::MoveWordRight),
+    ((KeyCode::Char('b'), ALT), Action::MoveWordLeft),
+    ((KeyCode::Char('w'), CONTROL), Action::KillRegion),
+    ((KeyCode::Char('w'), ALT), Action::Copy),
+];
+
+// Represents a set of key bindings which can be chosen instead of the default ones
+#[derive(Clone, Copy)]
+pub enum KeymapPreset {
+    Default,
+    Emacs,
+}
+
+impl KeymapPreset {
+    // Gets the bindings the preset adds on top of the default ones
+    fn bindings(self) -> &'static [(Key, Action)] {
+        match self {
+            Self::Default => &[],
+            Self::Emacs => EMACS_BINDINGS,
+        }
+    }
+}
+
+impl FromStr for KeymapPreset {
+    type Err = ();
+
+    // Parses the name of a preset given on the command line, such as "emacs"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(Self::Default),
+            "emacs" => Ok(Self::Emacs),
+            _ => Err(()),
+        }
+    }
+}
+
+// Maps keys to the actions they run
+pub struct Keymap {
+    bindings: HashMap<Key, Action>,
+    // The bindings which differ from the default ones, in the order they were added
+    overrides: Vec<(Key, Action)>,
+}
+
+impl Keymap {
+    // Creates a keymap with the default bindings and the bindings of a preset on top of them
+    pub fn new(preset: KeymapPreset) -> Self {
+        let mut keymap = Self {
+            bindings: DEFAULT_BINDINGS.iter().copied().collect(),
+            overrides: Vec::new(),
+        };
+
+        for &(key, action) in preset.bindings() {
+            keymap.bind(key, action);
+        }
+
+        keymap
+    }
+
+    // Binds a key to an action, replacing whatever it was bound to before
+    pub fn bind(&mut self, key: Key, action: Action) {
+        self.bindings.insert(key, action);
+        self.overrides.retain(|&(other, _)| other != key);
+        self.overrides.push((key, action));
+    }
+
+    // Gets the action bound to a key, if there is one
+    pub fn get(&self, key: Key) -> Option<Action> {
+        self.bindings.get(&key).copied()
+    }
+
+    // Gets the bindings which differ from the default ones
+    pub fn overrides(&self) -> &[(Key, Action)] {
+        &self.overrides
+    }
+
+    // Gets the name of the key which runs an action, given the key it is bound to by default
+    // The default key is only given if it, or the first key of its combination, has not been rebound
+    pub fn key_for(&self, action: Action, default_key: Option<&str>) -> Option<String> {
+        if let Some(&(key, _)) = self.overrides.iter().find(|&&(_, other)| other == action) {
+            return Some(key_name(key));
+        }
+
+        let default_key = default_key?;
+        (!self.is_overridden(default_key)).then(|| default_key.to_string())
+    }
+
+    // Checks whether a key written as it is on the help screen, or the first key of a combination
+    // such as "Ctrl+W C", has been bound to something other than its default action
+    pub fn is_overridden(&self, keys: &str) -> bool {
+        self.overrides.iter().any(|&(key, _)| {
+            let name = key_name(key);
+            keys == name || keys.starts_with(&format!("{} ", name))
+        })
+    }
+}
+
+// Parses a key written in the config file, as modifiers and a key joined by +, ignoring case
+// Letters are lowercase unless Shift is held, which matches how terminals report them
+// [EXAMPLE] "ctrl+a", "Alt+Shift+F", "f5", "ctrl+home", "alt+;"
+pub fn parse_key(s: &str) -> Result<Key, String> {
+    let unknown = || format!("Unknown key: {}", s);
+
+    // The key itself can be +, so it is split off before the modifiers
+    let (modifier_names, name) = match s.strip_suffix("++") {
+        Some(modifier_names) => (modifier_names, "+"),
+        None => s.rsplit_once('+').unwrap_or(("", s)),
+    };
+
+    let mut modifiers = NONE;
+    for modifier in modifier_names.split('+').filter(|name| !name.is_empty()) {
+        modifiers |= match modifier.to_lowercase().as_str() {
+            "ctrl" | "control" => CONTROL,
+            "alt" | "meta" => ALT,
+            "shift" => SHIFT,
+            _ => return Err(format!("Unknown modifier: {}", modifier)),
+        };
+    }
+
+    let mut chars = name.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_alphabetic() && modifiers.contains(SHIFT) => {
+            KeyCode::Char(c.to_uppercase().next().unwrap_or(c))
+        }
+        (Some(c), None) => KeyCode::Char(c.to_lowercase().next().unwrap_or(c)),
+        _ => match name.to_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "insert" => KeyCode::Insert,
+            "delete" => KeyCode::Delete,
+            "backspace" => KeyCode::Backspace,
+            "enter" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "esc" | "escape" => KeyCode::Esc,
+            function => match function.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                Some(n @ 1..=12) => KeyCode::F(n),
+                _ => return Err(unknown()),
+            },
+        },
+    };
+
+    Ok((code, modifiers))
+}
+
+// Gets the name of a key as it is written on the help screen
+// [EXAMPLE] "Ctrl+A", "Alt+Up", "Shift+F3"
+pub fn key_name((code, modifiers): Key) -> String {
+    let mut name = String::new();
+    for (modifier, prefix) in [(CONTROL, "Ctrl+"), (ALT, "Alt+"), (SHIFT, "Shift+")] {
+        if modifiers.contains(modifier) {
+            name.push_str(prefix);
+        }
+    }
+
+    match code {
+        KeyCode::Char(' ') => name.push_str("Space"),
+        KeyCode::Char(c) => name.extend(c.to_uppercase()),
+        KeyCode::F(n) => name.push_str(&format!("F{}", n)),
+        KeyCode::Up => name.push_str("Up"),
+        KeyCode::Down => name.push_str("Down"),
+        KeyCode::Left => name.push_str("Left"),
+        KeyCode::Right => name.push_str("Right"),
+        KeyCode::Home => name.push_str("Home"),
+        KeyCode::End => name.push_str("End"),
+        KeyCode::PageUp => name.push_str("PageUp"),
+        KeyCode::PageDown => name.push_str("PageDown"),
+        KeyCode::Insert => name.push_str("Insert"),
+        KeyCode::Delete => name.push_str("Delete"),
+        KeyCode::Backspace => name.push_str("Backspace"),
+        KeyCode::Enter => name.push_str("Enter"),
+        KeyCode::Tab => name.push_str("Tab"),
+        KeyCode::Esc => name.push_str("Esc"),
+        _ => name.push('?'),
+    }
+
+    name
+}
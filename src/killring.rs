@@ -0,0 +1,53 @@
+use std::collections::VecDeque;
+
+// The maximum amount of deleted text that is remembered
+const MAX_KILLS: usize = 60;
+
+// Represents the text deleted by word, subword, and sentence deletions, newest first,
+// so that it can be yanked back like in Emacs
+// * This is separate from the system clipboard, which the editor can only write to
+#[derive(Default)]
+pub struct KillRing {
+    kills: VecDeque<String>,
+    // The position in the ring of the text which was yanked last, so that repeated yanks can cycle
+    position: usize,
+}
+
+impl KillRing {
+    // Records deleted text as the newest kill
+    pub fn push(&mut self, text: String) {
+        if text.is_empty() {
+            return;
+        }
+
+        self.kills.push_front(text);
+        self.kills.truncate(MAX_KILLS);
+        self.position = 0;
+    }
+
+    // Gets the newest kill, starting a new round of yanks
+    pub fn yank(&mut self) -> Option<&str> {
+        self.position = 0;
+        self.kills.front().map(String::as_str)
+    }
+
+    // Gets the kill before the one which was yanked last, wrapping around to the newest after the oldest
+    pub fn cycle(&mut self) -> Option<&str> {
+        if self.kills.is_empty() {
+            return None;
+        }
+
+        self.position = (self.position + 1) % self.kills.len();
+        self.kills.get(self.position).map(String::as_str)
+    }
+
+    // Gets the position of the kill which was yanked last, counting from 1 for the newest
+    pub fn position(&self) -> usize {
+        self.position + 1
+    }
+
+    // Gets the number of kills in the ring
+    pub fn size(&self) -> usize {
+        self.kills.len()
+    }
+}
@@ -0,0 +1,71 @@
+use std::fs::File;
+use std::io::{self, Read};
+
+use crate::Buffer;
+use crate::Encoding;
+use crate::Progress;
+use crate::{Task, TaskStatus};
+
+// Represents a file being read into a buffer in the background
+// * The editor opens the terminal and draws its first frame straight away instead of waiting,
+// * so large files don't leave the user looking at their shell until they have been read
+pub struct LoadTask {
+    task: Task<io::Result<(Buffer, Encoding)>>,
+}
+
+impl LoadTask {
+    // Starts reading the file into a new buffer, working out which encoding it is in
+    pub fn start(file: File) -> Self {
+        let task = Task::spawn_blocking(move |progress: &Progress| {
+            progress.set_total(
+                file.metadata()
+                    .map_or(0, |metadata| metadata.len() as usize),
+            );
+
+            Buffer::read(ProgressReader {
+                inner: &file,
+                progress,
+            })
+        });
+
+        Self { task }
+    }
+
+    // Gets how much of the file has been read
+    pub fn progress(&self) -> &Progress {
+        self.task.progress()
+    }
+
+    // Gets the buffer and its encoding if the file has been read, without blocking
+    pub fn poll(&mut self) -> Option<io::Result<(Buffer, Encoding)>> {
+        match self.task.poll() {
+            TaskStatus::Finished(result) => Some(result),
+            TaskStatus::Running => None,
+            TaskStatus::Stopped => Some(Err(stopped_error())),
+        }
+    }
+
+    // Blocks until the file has been read, for when its contents are needed straight away
+    pub fn wait(self) -> io::Result<(Buffer, Encoding)> {
+        self.task.wait().unwrap_or_else(|| Err(stopped_error()))
+    }
+}
+
+// Wraps a reader to report how many bytes have been read through it
+struct ProgressReader<'a, R> {
+    inner: R,
+    progress: &'a Progress,
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.progress.advance(read);
+
+        Ok(read)
+    }
+}
+
+fn stopped_error() -> io::Error {
+    io::Error::other("reading the file stopped unexpectedly")
+}
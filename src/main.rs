@@ -1,25 +1,393 @@
+mod actions;
 mod buffer;
+mod calc;
+mod clipboard;
+mod collab;
+mod commands;
+mod completion;
+mod config;
+mod crdt;
+mod dap;
+mod datetime;
+mod diagnostics;
+mod dialog;
+mod document;
 mod editor;
+mod encoding;
+mod export;
+mod fileinfo;
+mod filters;
+mod folds;
+mod grammar;
+mod help;
+mod highlighter;
+mod history;
+mod icons;
+mod images;
+mod increment;
+mod input;
+mod json;
+mod jumps;
+mod keymap;
+mod killring;
+mod load;
+mod marks;
+mod message;
+mod panel;
+mod profiler;
+mod progress;
+mod prompt;
+mod remote;
+mod runner;
+mod save;
+mod search;
+mod signals;
+mod statusline;
+mod structure;
+mod syntax;
+mod table;
+mod task;
 mod terminal;
+mod theme;
+mod toml;
+mod words;
+mod wrap;
+
+use std::fs::File;
+use std::io::{self, IsTerminal, Read};
 
 use crossterm::Result;
 
-use buffer::Buffer;
 use buffer::DeletionMode;
-use editor::Editor;
-use terminal::Terminal;
+use buffer::{Buffer, BufferChange, BufferObserver, DEFAULT_TAB_WIDTH};
+use config::Config;
+use datetime::{DateTime, DEFAULT_DATE_FORMAT};
+use dialog::{Dialog, DialogChoice, DialogKind};
+use document::Document;
+use editor::{Editor, InterruptAction, DEFAULT_ZEN_WIDTH};
+use encoding::Encoding;
+use folds::Folds;
+use history::{Edit, History, TimeTravel};
+use input::InputReader;
+use jumps::JumpList;
+use keymap::{Keymap, KeymapPreset};
+use killring::KillRing;
+use load::LoadTask;
+use marks::Marks;
+use message::{MessageLevel, Messages};
+use panel::{Panel, PanelKind};
+use profiler::Profiler;
+use progress::Progress;
+use prompt::{Prompt, PromptAction, PromptKind};
+use remote::RemoteFile;
+use runner::{TaskDefinition, TaskRun};
+use save::{SaveTask, SavedFile};
+use search::Search;
+use signals::{Signal, SignalListener};
+use table::Table;
+use task::{runtime, Task, TaskStatus};
+use terminal::{CursorPosition, CursorStyle, LineNumbers, Status, Tab, Terminal, ViewPosition};
+use theme::Theme;
+use words::WordChars;
 
 fn main() -> Result<()> {
-    // Make sure the user has provided one argument (filename to open)
-    if std::env::args().len() != 2 {
-        println!("Usage: rut <filename>");
-        std::process::exit(1);
+    let mut filename = None;
+    let mut tab_width = None;
+    let mut ruler = None;
+    let mut scrolloff = None;
+    let mut cat = false;
+    let mut line_numbers = None;
+    let mut host_address = None;
+    let mut serve_address = None;
+    let mut join_address = None;
+    let mut following = false;
+    let mut screen_reader = false;
+    let mut interrupt_action = InterruptAction::Copy;
+    let mut keymap = None;
+    let mut theme = None;
+    let mut date_format = None;
+    let mut word_chars = None;
+    let mut zen_width = None;
+    let mut status_line = None;
+    let mut tasks = Vec::new();
+    let mut max_fps = None;
+    let mut cursor_shape = None;
+    let mut cursor_blink = None;
+
+    // Parse the command line, which should contain a filename and optionally some options
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tab-width" => match args.next().and_then(|width| width.parse().ok()) {
+                Some(width) if width > 0 => tab_width = Some(width),
+                _ => exit_with_usage(),
+            },
+            "--ruler" => match args.next().and_then(|column| column.parse().ok()) {
+                Some(column) => ruler = Some(column),
+                None => exit_with_usage(),
+            },
+            "--scrolloff" => match args.next().and_then(|lines| lines.parse().ok()) {
+                Some(lines) => scrolloff = Some(lines),
+                None => exit_with_usage(),
+            },
+            "--ctrl-c" => match args.next().and_then(|action| action.parse().ok()) {
+                Some(action) => interrupt_action = action,
+                None => exit_with_usage(),
+            },
+            "--keymap" => match args.next().and_then(|preset| preset.parse().ok()) {
+                Some(preset) => keymap = Some(preset),
+                None => exit_with_usage(),
+            },
+            "--theme" => match args.next() {
+                Some(name) => theme = Some(name),
+                None => exit_with_usage(),
+            },
+            "--date-format" => match args.next() {
+                Some(format) => date_format = Some(format),
+                None => exit_with_usage(),
+            },
+            "--zen-width" => match args.next().and_then(|width| width.parse().ok()) {
+                Some(width) if width > 0 => zen_width = Some(width),
+                _ => exit_with_usage(),
+            },
+            "--status-line" => match args.next().and_then(|layout| layout.parse().ok()) {
+                Some(layout) => status_line = Some(layout),
+                None => exit_with_usage(),
+            },
+            "--max-fps" => match args.next().and_then(|fps| fps.parse().ok()) {
+                Some(fps) if fps > 0 => max_fps = Some(fps),
+                _ => exit_with_usage(),
+            },
+            "--cursor-style" => match args.next().and_then(|shape| shape.parse().ok()) {
+                Some(shape) => cursor_shape = Some(shape),
+                None => exit_with_usage(),
+            },
+            "--cursor-blink" => match args.next().as_deref() {
+                Some("on") => cursor_blink = Some(true),
+                Some("off") => cursor_blink = Some(false),
+                _ => exit_with_usage(),
+            },
+            "--task" => match args.next().as_deref().and_then(TaskDefinition::parse) {
+                Some(task) => tasks.push(task),
+                None => exit_with_usage(),
+            },
+            "--word-chars" => match args.next() {
+                Some(chars) => word_chars = Some(WordChars::new(&chars)),
+                None => exit_with_usage(),
+            },
+            "--host" => match args.next().as_deref().and_then(parse_host_address) {
+                Some(address) => host_address = Some(address),
+                None => exit_with_usage(),
+            },
+            "--serve" => match args.next().as_deref().and_then(parse_host_address) {
+                Some(address) => serve_address = Some(address),
+                None => exit_with_usage(),
+            },
+            "--join" | "--follow" => match args.next() {
+                Some(address) => {
+                    following = arg == "--follow";
+                    join_address = Some(address);
+                }
+                None => exit_with_usage(),
+            },
+            "--cat" => cat = true,
+            "--line-numbers" => line_numbers = Some(LineNumbers::Absolute),
+            "--relative-line-numbers" => line_numbers = Some(LineNumbers::Relative),
+            "--screen-reader" => screen_reader = true,
+            _ if filename.is_none() => filename = Some(arg),
+            _ => exit_with_usage(),
+        }
     }
 
-    // Get the filename from the command line
-    let filename = std::env::args().nth(1).unwrap();
+    // Make sure the user has provided a filename to open
+    let Some(filename) = filename else {
+        exit_with_usage();
+    };
+
+    // A theme given on the command line which cannot be loaded is reported before the editor starts
+    let theme = theme.map(|name| match Theme::load(&name) {
+        Ok(theme) => theme,
+        Err(error) => {
+            eprintln!("rut: {}", error);
+            std::process::exit(1);
+        }
+    });
+
+    // Files on other machines are downloaded to a temporary local copy, which is edited instead
+    let remote = RemoteFile::parse(&filename);
+    let filename = match &remote {
+        Some(remote) => match remote.download() {
+            Ok(local_path) => local_path,
+            Err(error) => {
+                eprintln!("rut: {}: {}", remote, error);
+                std::process::exit(1);
+            }
+        },
+        None => filename,
+    };
+
+    // Images cannot be edited as text, so they are shown instead (or described, if the terminal cannot)
+    if show_image(&filename) {
+        if remote.is_some() {
+            let _ = std::fs::remove_file(&filename);
+        }
+
+        return Ok(());
+    }
+
+    // Print the file instead of opening the editor if requested
+    if cat {
+        let tab_width = tab_width.unwrap_or(DEFAULT_TAB_WIDTH);
+        print_file(&filename, tab_width, line_numbers.is_some());
+
+        if remote.is_some() {
+            let _ = std::fs::remove_file(&filename);
+        }
+
+        return Ok(());
+    }
 
     // Create and run the editor
     let mut editor = Editor::new(&filename);
+    if let Some(tab_width) = tab_width {
+        editor.set_tab_width(tab_width);
+    }
+    if ruler.is_some() {
+        editor.set_ruler(ruler);
+    }
+    if let Some(scrolloff) = scrolloff {
+        editor.set_scrolloff(scrolloff);
+    }
+    if line_numbers.is_some() {
+        editor.set_line_numbers(line_numbers);
+    }
+    editor.set_screen_reader(screen_reader);
+    editor.set_interrupt_action(interrupt_action);
+    if let Some(preset) = keymap {
+        editor.set_keymap_preset(preset);
+    }
+    if let Some(theme) = theme {
+        editor.set_theme(theme);
+    }
+    if let Some(date_format) = date_format {
+        editor.set_date_format(date_format);
+    }
+    if let Some(zen_width) = zen_width {
+        editor.set_zen_width(zen_width);
+    }
+    editor.set_max_fps(max_fps);
+
+    // The terminal's cursor is left alone unless either part of its style has been chosen
+    if cursor_shape.is_some() || cursor_blink.is_some() {
+        editor.set_cursor_style(Some(CursorStyle {
+            shape: cursor_shape.unwrap_or_default(),
+            blink: cursor_blink.unwrap_or(true),
+        }));
+    }
+
+    for task in tasks {
+        editor.add_task(task);
+    }
+
+    if let Some(status_line) = status_line {
+        editor.set_status_line(status_line);
+    }
+
+    if let Some(word_chars) = word_chars {
+        editor.set_word_chars(word_chars);
+    }
+
+    if let Some(remote) = remote {
+        editor.set_remote(remote);
+    }
+
+    // Share the buffer with other rut instances if requested
+    if host_address.is_some() || serve_address.is_some() {
+        editor.host_session(host_address.as_deref(), serve_address.as_deref());
+    } else if let Some(address) = join_address {
+        editor.join_session(&address, following);
+    }
+
     editor.run()
 }
+
+// Prints the contents of a file to stdout, styled the same way it would be in the editor
+// * Styling is left out when the output is not a terminal, such as when it is piped into a file
+fn print_file(filename: &str, tab_width: usize, line_numbers: bool) {
+    // Reading fails for directories and files which are not text, which are reported the same way
+    let mut buffer = match File::open(filename).and_then(|file| Buffer::read(&file)) {
+        Ok((buffer, _)) => buffer,
+        Err(error) => {
+            eprintln!("rut: {}: {}", filename, error);
+            std::process::exit(1);
+        }
+    };
+
+    buffer.set_tab_width(tab_width);
+
+    let styled = io::stdout().is_terminal();
+    print!("{}", export::to_ansi(&buffer, styled, line_numbers));
+}
+
+// Shows an image file in the terminal if it supports images, and otherwise prints its format and size,
+// returning false without printing anything if the file is not an image
+// * Only the start of the file is read to check whether it is an image, so opening text files stays fast
+fn show_image(filename: &str) -> bool {
+    let mut header = Vec::new();
+    let Ok(file) = File::open(filename) else {
+        return false;
+    };
+    if file.take(64 * 1024).read_to_end(&mut header).is_err() {
+        return false;
+    }
+
+    let Some(info) = images::image_info(&header) else {
+        return false;
+    };
+
+    let size = std::fs::metadata(filename).map_or(0, |metadata| metadata.len() as usize);
+    println!("{}: {}", filename, images::describe(&info, size));
+
+    if info.format == "PNG" && images::graphics_supported() && io::stdout().is_terminal() {
+        let (width, height) = crossterm::terminal::size().unwrap_or((80, 24));
+        let (columns, rows) = images::fit(&info, width, height.saturating_sub(2));
+
+        if let Ok(png) = std::fs::read(filename) {
+            print!("{}", images::kitty_sequence(&png, columns, rows));
+            print!("{}", "\n".repeat(rows as usize));
+        }
+    }
+
+    true
+}
+
+// Parses the address to host a session or its followers at, which is only reachable from this machine if just a port
+// is given, so that the session is only exposed to the network when an address is asked for
+// [EXAMPLE] "7000" -> "127.0.0.1:7000", "0.0.0.0:7000" -> "0.0.0.0:7000"
+fn parse_host_address(arg: &str) -> Option<String> {
+    if let Ok(port) = arg.parse::<u16>() {
+        return Some(format!("127.0.0.1:{}", port));
+    }
+
+    let (_, port) = arg.rsplit_once(':')?;
+    port.parse::<u16>().ok().map(|_| arg.to_string())
+}
+
+// Prints the usage message and exits the program
+fn exit_with_usage() -> ! {
+    println!(
+        "Usage: rut [--tab-width <width>] [--ruler <column>] [--scrolloff <lines>] <filename|directory>"
+    );
+    println!("           [--screen-reader] [--ctrl-c <copy|ignore>] [--keymap <default|emacs>]");
+    println!("           [--theme <name>] [--date-format <format>]");
+    println!("           [--word-chars <characters>] [--zen-width <columns>] [--max-fps <frames>]");
+    println!("           [--cursor-style <block|bar|underline>] [--cursor-blink <on|off>]");
+    println!("           [--line-numbers] [--relative-line-numbers]");
+    println!("           [--host <[address:]port>] [--serve <[address:]port>]");
+    println!("           [--join <address> | --follow <address>]");
+    println!(
+        "           [--status-line <left segments>|<right segments>] [--task <name>=<command>]..."
+    );
+    println!("       rut --cat [--line-numbers] [--tab-width <width>] <filename>");
+    std::process::exit(1);
+}
@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::BufferObserver;
+
+// Represents the set of named marks (bookmarks) in the buffer
+// Marks are stored as buffer indices and are shifted whenever the buffer is edited
+#[derive(Default)]
+pub struct Marks {
+    marks: BTreeMap<char, usize>,
+}
+
+impl Marks {
+    // Checks whether a character can be used as the name of a mark
+    pub fn is_valid_name(name: char) -> bool {
+        name.is_ascii_alphabetic()
+    }
+
+    // Sets a mark at the given buffer index, overwriting any existing mark with the same name
+    pub fn set(&mut self, name: char, index: usize) {
+        self.marks.insert(name, index);
+    }
+
+    // Gets the buffer index of a mark
+    pub fn get(&self, name: char) -> Option<usize> {
+        self.marks.get(&name).copied()
+    }
+
+    // Gets the name and buffer index of the nth mark, in alphabetical order
+    pub fn nth(&self, n: usize) -> Option<(char, usize)> {
+        self.iter().nth(n)
+    }
+
+    // Iterates over all of the marks in alphabetical order
+    pub fn iter(&self) -> impl Iterator<Item = (char, usize)> + '_ {
+        self.marks.iter().map(|(name, index)| (*name, *index))
+    }
+}
+
+impl BufferObserver for Marks {
+    // Shifts the marks after an insertion of `len` characters at the given index
+    fn adjust_for_insert(&mut self, index: usize, len: usize) {
+        for mark in self.marks.values_mut() {
+            *mark = adjust_index_for_insert(*mark, index, len);
+        }
+    }
+
+    // Shifts the marks after the given range of characters has been deleted
+    fn adjust_for_delete(&mut self, range: Range<usize>) {
+        for mark in self.marks.values_mut() {
+            *mark = adjust_index_for_delete(*mark, range.clone());
+        }
+    }
+}
+
+// Gets the new position of a buffer index after an insertion of `len` characters at `index`
+// * Text inserted directly at the position pushes it forward, so it stays attached to the same character
+pub fn adjust_index_for_insert(position: usize, index: usize, len: usize) -> usize {
+    if position >= index {
+        position + len
+    } else {
+        position
+    }
+}
+
+// Gets the new position of a buffer index after the given range of characters has been deleted
+// * Positions inside of the deleted range are moved to the start of the range
+pub fn adjust_index_for_delete(position: usize, range: Range<usize>) -> usize {
+    if position >= range.end {
+        position - range.len()
+    } else if position > range.start {
+        range.start
+    } else {
+        position
+    }
+}
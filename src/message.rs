@@ -0,0 +1,77 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// The amount of time a message is shown for before it disappears
+const MESSAGE_DURATION: Duration = Duration::from_secs(4);
+
+// The shortest amount of time a message is shown for when there are newer messages waiting
+const MINIMUM_MESSAGE_DURATION: Duration = Duration::from_secs(1);
+
+// Represents how important a message is, which determines its color in the status bar
+#[derive(Clone, Copy, PartialEq)]
+pub enum MessageLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+// Represents a message shown in the status bar
+pub struct Message {
+    pub level: MessageLevel,
+    pub text: String,
+}
+
+// Represents the queue of messages waiting to be shown in the status bar
+// Messages are shown one at a time, in the order they were sent, and disappear on their own
+#[derive(Default)]
+pub struct Messages {
+    queue: VecDeque<Message>,
+    // The time at which the first message in the queue started being shown
+    shown_since: Option<Instant>,
+}
+
+impl Messages {
+    // Adds a message to the end of the queue
+    pub fn push(&mut self, level: MessageLevel, text: String) {
+        if self.queue.is_empty() {
+            self.shown_since = Some(Instant::now());
+        }
+
+        self.queue.push_back(Message { level, text });
+    }
+
+    // Replaces every message in the queue with a new one, which is shown immediately
+    // * This is for messages which are out of date as soon as the next one arrives
+    pub fn replace(&mut self, level: MessageLevel, text: String) {
+        self.queue.clear();
+        self.push(level, text);
+    }
+
+    // Gets the message which is currently being shown, if there is one
+    pub fn current(&self) -> Option<&Message> {
+        self.queue.front()
+    }
+
+    // Removes the current message if it has been shown for long enough,
+    // returning whether the message being shown has changed
+    // Messages are cut short if there are newer messages waiting to be shown
+    pub fn expire(&mut self) -> bool {
+        let Some(shown_since) = self.shown_since else {
+            return false;
+        };
+
+        let duration = match self.queue.len() {
+            0 | 1 => MESSAGE_DURATION,
+            _ => MINIMUM_MESSAGE_DURATION,
+        };
+
+        if shown_since.elapsed() < duration {
+            return false;
+        }
+
+        self.queue.pop_front();
+        self.shown_since = (!self.queue.is_empty()).then(Instant::now);
+
+        true
+    }
+}
@@ -0,0 +1,102 @@
+// Represents which feature a panel belongs to, so its selection can be handled by the editor
+#[derive(Clone, Copy, PartialEq)]
+pub enum PanelKind {
+    Marks,
+    UndoTree,
+    Help,
+    Image,
+    Tasks,
+    TaskOutput,
+    Files,
+    Commands,
+    Buffers,
+    Diagnostics,
+    Variables,
+}
+
+// Represents a popup panel containing a selectable list of items
+pub struct Panel {
+    kind: PanelKind,
+    title: String,
+    items: Vec<String>,
+    selected: usize,
+}
+
+impl Panel {
+    // Create a new Panel instance
+    pub fn new(kind: PanelKind, title: &str, items: Vec<String>) -> Self {
+        Self {
+            kind,
+            title: title.to_string(),
+            items,
+            selected: 0,
+        }
+    }
+
+    // Selects the item at the given index
+    pub fn select(&mut self, index: usize) {
+        if index < self.items.len() {
+            self.selected = index;
+        }
+    }
+
+    // Replaces the items in the panel, such as when more output arrives
+    // If the last item was selected, the new last item is selected, so the panel follows the output
+    pub fn set_items(&mut self, items: Vec<String>) {
+        let following = self.selected + 1 >= self.items.len();
+        self.items = items;
+
+        if following || self.selected >= self.items.len() {
+            self.selected = self.items.len().saturating_sub(1);
+        }
+    }
+
+    // Replaces the title of the panel
+    pub fn set_title(&mut self, title: &str) {
+        self.title = title.to_string();
+    }
+
+    // Moves the selection up by one item
+    pub fn select_previous(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        }
+    }
+
+    // Moves the selection down by one item
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.items.len() {
+            self.selected += 1;
+        }
+    }
+
+    // Returns the kind of the panel
+    pub fn kind(&self) -> PanelKind {
+        self.kind
+    }
+
+    // Checks whether the panel takes up the whole text area rather than half of it
+    // * The help screen, command output, and command palette are too wide to fit in half of a
+    // * typical terminal
+    pub fn is_wide(&self) -> bool {
+        matches!(
+            self.kind,
+            PanelKind::Help | PanelKind::TaskOutput | PanelKind::Commands
+        )
+    }
+
+    // Returns the title of the panel
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    // Returns the items in the panel
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    // Returns the index of the selected item
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::fileinfo::format_size;
+use crate::Buffer;
+
+// The number of recent frames which the average and longest frame times are taken from
+const FRAME_WINDOW: usize = 60;
+
+// Keeps track of how long frames take to draw and how many events are handled between them,
+// so that the performance overlay can help to find out why the editor feels sluggish
+#[derive(Default)]
+pub struct Profiler {
+    // How long each of the most recent frames took to draw, newest last
+    frame_times: VecDeque<Duration>,
+    frame_count: u64,
+    // The number of events handled since the last frame was drawn
+    pending_events: usize,
+    // The number of events handled before the last frame was drawn
+    frame_events: usize,
+}
+
+impl Profiler {
+    // Counts an event (such as a keypress) which has been handled
+    pub fn record_event(&mut self) {
+        self.pending_events += 1;
+    }
+
+    // Records how long a frame took to draw
+    pub fn record_frame(&mut self, time: Duration) {
+        if self.frame_times.len() == FRAME_WINDOW {
+            self.frame_times.pop_front();
+        }
+
+        self.frame_times.push_back(time);
+        self.frame_count += 1;
+        self.frame_events = std::mem::take(&mut self.pending_events);
+    }
+
+    // Gets the lines shown in the performance overlay
+    // [EXAMPLE] "Frame time    1.24 ms (average 0.97 ms, longest 3.10 ms)"
+    pub fn overlay(
+        &self,
+        buffer: &Buffer,
+        cached_lines: usize,
+        frame_cap: Option<u32>,
+    ) -> Vec<String> {
+        let last = self.frame_times.back().copied().unwrap_or_default();
+        let longest = self.frame_times.iter().max().copied().unwrap_or_default();
+        let average = match self.frame_times.len() {
+            0 => Duration::ZERO,
+            frames => self.frame_times.iter().sum::<Duration>() / frames as u32,
+        };
+
+        let frame_cap = match frame_cap {
+            Some(fps) => format!("{} fps", fps),
+            None => "off".to_string(),
+        };
+
+        vec![
+            format!(
+                "Frame time    {} (average {}, longest {})",
+                milliseconds(last),
+                milliseconds(average),
+                milliseconds(longest)
+            ),
+            format!("Frames        {}", self.frame_count),
+            format!("Events/frame  {}", self.frame_events),
+            format!("Frame cap     {}", frame_cap),
+            format!("Lines         {}", buffer.line_count()),
+            format!("Characters    {}", buffer.size()),
+            format!("Size          {}", format_size(buffer.size_bytes())),
+            format!("Version       {}", buffer.version()),
+            format!("Cached lines  {}", cached_lines),
+        ]
+    }
+}
+
+// Formats a duration as milliseconds
+// [EXAMPLE] 1.24 ms
+fn milliseconds(duration: Duration) -> String {
+    format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,48 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// Represents how far along a background task is, shared between the task and the editor
+// The task reports its progress and checks whether it has been cancelled,
+// while the editor shows the progress in the status bar and cancels the task on Esc
+#[derive(Clone, Default)]
+pub struct Progress {
+    state: Arc<ProgressState>,
+}
+
+#[derive(Default)]
+struct ProgressState {
+    done: AtomicUsize,
+    // The amount of work the task has to do in total, or zero if it is not known yet
+    total: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl Progress {
+    // Sets the amount of work the task has to do in total, in whatever unit it measures work in
+    pub fn set_total(&self, total: usize) {
+        self.state.total.store(total, Ordering::Relaxed);
+    }
+
+    // Records that some amount of work has been done
+    pub fn advance(&self, amount: usize) {
+        self.state.done.fetch_add(amount, Ordering::Relaxed);
+    }
+
+    // Gets how much of the task is done as a percentage, if the total amount of work is known
+    pub fn percentage(&self) -> Option<usize> {
+        let total = self.state.total.load(Ordering::Relaxed);
+        let done = self.state.done.load(Ordering::Relaxed);
+
+        (total > 0).then(|| (done * 100 / total).min(100))
+    }
+
+    // Asks the task to stop as soon as it can
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    // Checks whether the task has been asked to stop
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,126 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_width::UnicodeWidthStr;
+
+// Represents which feature a prompt belongs to, so its input can be handled by the editor
+#[derive(Clone, Copy, PartialEq)]
+pub enum PromptKind {
+    TimeTravel,
+    ExportHtml,
+    Search,
+    InsertFile,
+    Encoding,
+    ReplacePattern,
+    ReplaceWith,
+    Command,
+    ExCommand,
+    NewFile,
+    RenameFile,
+    DiagnosticsFilter,
+    Sequence,
+}
+
+// Represents what should happen to a prompt after a keypress
+pub enum PromptAction {
+    // The input was accepted with Enter, so the prompt should be closed and its input used
+    Submit,
+    // The prompt was cancelled with Esc, so it should be closed without using its input
+    Cancel,
+    // The prompt should stay open
+    Continue,
+}
+
+// Represents a single line of input typed by the user at the bottom of the screen
+// Every feature which asks the user for input uses this, so they all support the same editing keys
+pub struct Prompt {
+    kind: PromptKind,
+    label: String,
+    input: String,
+    // The position of the cursor in the input, as a character index
+    cursor: usize,
+}
+
+impl Prompt {
+    // Create a new Prompt instance
+    pub fn new(kind: PromptKind, label: &str) -> Self {
+        Self {
+            kind,
+            label: label.to_string(),
+            input: String::new(),
+            cursor: 0,
+        }
+    }
+
+    // Fills in the input ahead of time, such as with a default value the user can edit
+    pub fn with_input(mut self, input: &str) -> Self {
+        self.set_input(input);
+        self
+    }
+
+    // Replaces the input, such as with a completed version of it, and moves the cursor to the end
+    pub fn set_input(&mut self, input: &str) {
+        self.input = input.to_string();
+        self.cursor = self.input.chars().count();
+    }
+
+    // Handles a keypress, editing the input or reporting that the prompt is finished
+    pub fn handle_key(&mut self, event: KeyEvent) -> PromptAction {
+        match (event.code, event.modifiers) {
+            (KeyCode::Enter, _) => return PromptAction::Submit,
+            (KeyCode::Esc, _) => return PromptAction::Cancel,
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => self.insert(c),
+            (KeyCode::Backspace, _) if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.input.remove(self.byte_index(self.cursor));
+            }
+            (KeyCode::Delete, _) if self.cursor < self.input.chars().count() => {
+                self.input.remove(self.byte_index(self.cursor));
+            }
+            (KeyCode::Left, _) => self.cursor = self.cursor.saturating_sub(1),
+            (KeyCode::Right, _) => self.cursor = (self.cursor + 1).min(self.input.chars().count()),
+            (KeyCode::Home, _) => self.cursor = 0,
+            (KeyCode::End, _) => self.cursor = self.input.chars().count(),
+            // Clear the input on Ctrl+U, like in a shell
+            (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
+                self.input.clear();
+                self.cursor = 0;
+            }
+            _ => (),
+        }
+
+        PromptAction::Continue
+    }
+
+    // Inserts a character at the cursor
+    pub fn insert(&mut self, character: char) {
+        self.input.insert(self.byte_index(self.cursor), character);
+        self.cursor += 1;
+    }
+
+    // Returns the kind of the prompt
+    pub fn kind(&self) -> PromptKind {
+        self.kind
+    }
+
+    // Returns the label shown before the input
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    // Returns the text typed by the user
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    // Gets the number of columns between the start of the input and the cursor
+    pub fn cursor_x(&self) -> usize {
+        self.input[..self.byte_index(self.cursor)].width()
+    }
+
+    // Converts a character index in the input to a byte index
+    fn byte_index(&self, index: usize) -> usize {
+        self.input
+            .char_indices()
+            .nth(index)
+            .map_or(self.input.len(), |(byte_index, _)| byte_index)
+    }
+}
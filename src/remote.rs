@@ -0,0 +1,111 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use crate::Progress;
+
+// The number of bytes sent to ssh at a time while uploading, between checks for cancellation
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+// Represents a file on another machine, given as `user@host:/path`, which is accessed over SSH
+// * This runs the system's ssh command, so it uses the user's existing SSH configuration and keys
+#[derive(Clone)]
+pub struct RemoteFile {
+    host: String,
+    path: String,
+}
+
+impl RemoteFile {
+    // Parses a remote file location, returning None if it refers to a local file instead
+    pub fn parse(location: &str) -> Option<Self> {
+        let (host, path) = location.split_once(':')?;
+
+        // A path with a slash before the colon is a local path which happens to contain a colon
+        if !host.contains('@') || host.contains('/') || path.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            host: host.to_string(),
+            path: path.to_string(),
+        })
+    }
+
+    // Copies the file to a temporary local file so that it can be edited, returning the local path
+    // If the file does not exist on the remote machine yet, the local file starts out empty
+    // * This happens before the editor opens, so ssh can still ask for a password if it needs one
+    pub fn download(&self) -> io::Result<String> {
+        let output = Command::new("ssh")
+            .arg(&self.host)
+            .arg(format!(
+                "if [ -e {0} ]; then cat -- {0}; fi",
+                shell_quote(&self.path)
+            ))
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .output()?;
+
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "could not read the file from {}",
+                self.host
+            )));
+        }
+
+        let name = self.path.rsplit('/').next().unwrap_or_default();
+        let local_path = std::env::temp_dir().join(format!("rut-{}-{}", std::process::id(), name));
+        fs::write(&local_path, output.stdout)?;
+
+        Ok(local_path.to_string_lossy().to_string())
+    }
+
+    // Writes the given contents to the file on the remote machine, reporting how many bytes have been sent
+    // If the upload is cancelled, ssh is stopped and the remote file is left as it was
+    // * Batch mode is used because the editor is running, so ssh cannot ask for a password
+    pub fn upload(&self, contents: &[u8], progress: &Progress) -> io::Result<()> {
+        let mut child = Command::new("ssh")
+            .args(["-o", "BatchMode=yes"])
+            .arg(&self.host)
+            .arg(format!("cat > {}", shell_quote(&self.path)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            for chunk in contents.chunks(UPLOAD_CHUNK_SIZE) {
+                if progress.is_cancelled() {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(io::Error::other("the upload was cancelled"));
+                }
+
+                stdin.write_all(chunk)?;
+                progress.advance(chunk.len());
+            }
+        }
+
+        match child.wait()?.success() {
+            true => Ok(()),
+            false => Err(io::Error::other(format!("could not reach {}", self.host))),
+        }
+    }
+
+    // Returns the host the file is on, including the user
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+}
+
+impl fmt::Display for RemoteFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.host, self.path)
+    }
+}
+
+// Quotes a string so that the remote shell treats it as a single word
+// [EXAMPLE] it's -> 'it'\''s'
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
@@ -0,0 +1,150 @@
+use std::io;
+use std::process::{ExitStatus, Stdio};
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::runtime;
+
+// Represents a named shell command which can be run from the editor, such as "build=cargo build"
+#[derive(Clone)]
+pub struct TaskDefinition {
+    pub name: String,
+    pub command: String,
+}
+
+impl TaskDefinition {
+    // Parses a task given on the command line as name=command, returning None if it has no name or command
+    pub fn parse(definition: &str) -> Option<Self> {
+        let (name, command) = definition.split_once('=')?;
+        let (name, command) = (name.trim(), command.trim());
+
+        (!name.is_empty() && !command.is_empty()).then(|| Self {
+            name: name.to_string(),
+            command: command.to_string(),
+        })
+    }
+}
+
+// Represents a task which has been started, whose output is collected as it is printed
+// * The task's standard input is closed, so commands which ask for input fail instead of hanging
+pub struct TaskRun {
+    definition: TaskDefinition,
+    child: Child,
+    output: UnboundedReceiver<String>,
+    lines: Vec<String>,
+    status: Option<ExitStatus>,
+}
+
+impl TaskRun {
+    // Starts running a task with the system shell
+    pub fn start(definition: TaskDefinition) -> io::Result<Self> {
+        // * The process has to be spawned from inside the runtime, which watches it for exiting
+        let _runtime = runtime().enter();
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&definition.command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        // Standard output and error are read by their own async tasks, so that neither can fill up
+        // and block the command while the other is being waited on
+        let (sender, output) = mpsc::unbounded_channel();
+        if let Some(stdout) = child.stdout.take() {
+            forward_lines(stdout, sender.clone());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            forward_lines(stderr, sender);
+        }
+
+        Ok(Self {
+            definition,
+            child,
+            output,
+            lines: Vec::new(),
+            status: None,
+        })
+    }
+
+    // Collects any new output and checks whether the task has finished, without blocking
+    // Returns whether anything changed since the last poll
+    pub fn poll(&mut self) -> bool {
+        let old_line_count = self.lines.len();
+        self.receive_lines();
+
+        let finished = self.status.is_none() && self.update_status();
+
+        finished || self.lines.len() != old_line_count
+    }
+
+    // Stops the task if it is still running
+    pub fn stop(&mut self) {
+        if self.status.is_none() {
+            let _ = self.child.start_kill();
+            let _ = runtime()
+                .block_on(self.child.wait())
+                .map(|status| self.status = Some(status));
+        }
+    }
+
+    // Gets the definition the task was started from, so that it can be restarted
+    pub fn definition(&self) -> &TaskDefinition {
+        &self.definition
+    }
+
+    // Gets the lines the task has printed so far, with standard output and error interleaved
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    // Gets how the task exited, or None if it is still running
+    pub fn status(&self) -> Option<ExitStatus> {
+        self.status
+    }
+
+    // Checks whether the process has exited, returning whether it just did
+    fn update_status(&mut self) -> bool {
+        match self.child.try_wait() {
+            Ok(Some(status)) => {
+                // Any output printed just before exiting may still be on its way
+                self.receive_lines();
+                self.status = Some(status);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Collects the lines which have been read from the task's output since the last call
+    fn receive_lines(&mut self) {
+        while let Ok(line) = self.output.try_recv() {
+            self.lines.push(line);
+        }
+    }
+}
+
+impl Drop for TaskRun {
+    // Stops the task when it is replaced or the editor exits, so it does not keep running unseen
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// Sends each line read from a stream over a channel, from an async task on the runtime
+// * Lines which are not valid UTF-8 are converted lossily rather than ending the output early
+fn forward_lines(stream: impl AsyncRead + Unpin + Send + 'static, sender: UnboundedSender<String>) {
+    runtime().spawn(async move {
+        let mut lines = BufReader::new(stream).split(b'\n');
+        while let Ok(Some(line)) = lines.next_segment().await {
+            let line = String::from_utf8_lossy(&line)
+                .trim_end_matches('\r')
+                .to_string();
+            if sender.send(line).is_err() {
+                return;
+            }
+        }
+    });
+}
@@ -0,0 +1,114 @@
+use std::fs::File;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::Buffer;
+use crate::Encoding;
+use crate::Progress;
+use crate::RemoteFile;
+use crate::{Task, TaskStatus};
+
+// Represents the outcome of a successful save
+pub struct SavedFile {
+    // The version of the buffer that was written
+    pub version: u64,
+    // The modification time of the file after it was written, if the platform supports it
+    pub modified: Option<SystemTime>,
+    // The encoding the buffer was written in
+    pub encoding: Encoding,
+}
+
+// Represents a save which is performed in the background
+// Only one save can be in progress at a time, so that writes to the file never overlap
+#[derive(Default)]
+pub struct SaveTask {
+    // The save in progress, if there is one
+    task: Option<Task<io::Result<SavedFile>>>,
+}
+
+impl SaveTask {
+    // Starts writing a snapshot of the buffer to the file in the given encoding, then uploading it
+    // if it is a remote file
+    // Returns false without doing anything if a save is already in progress
+    pub fn start(
+        &mut self,
+        file: Arc<Mutex<File>>,
+        buffer: Buffer,
+        encoding: Encoding,
+        remote: Option<RemoteFile>,
+    ) -> bool {
+        if self.is_running() {
+            return false;
+        }
+
+        let task = Task::spawn_blocking(move |progress: &Progress| {
+            // The buffer is written once locally, then again to the remote machine if there is one
+            let upload = remote.as_ref().map(|_| {
+                [
+                    encoding.byte_order_mark(),
+                    &encoding.encode(&buffer.to_string()),
+                ]
+                .concat()
+            });
+            progress.set_total(buffer.size_bytes() + upload.as_ref().map_or(0, Vec::len));
+
+            // Acquire a lock on the file so it can be written to
+            // * The local write is never cancelled, since that would leave the file half-written
+            let result = match file.lock() {
+                Ok(mut file) => buffer
+                    .write_to_file(&mut file, encoding, progress)
+                    .map(|_| SavedFile {
+                        version: buffer.version(),
+                        encoding,
+                        modified: file
+                            .metadata()
+                            .and_then(|metadata| metadata.modified())
+                            .ok(),
+                    }),
+                Err(_) => Err(io::Error::other("the file lock was poisoned")),
+            };
+
+            match (result, remote, upload) {
+                (Ok(saved), Some(remote), Some(upload)) => {
+                    remote.upload(&upload, progress).map(|_| saved)
+                }
+                (result, _, _) => result,
+            }
+        });
+
+        self.task = Some(task);
+
+        true
+    }
+
+    // Checks whether a save is currently in progress
+    pub fn is_running(&self) -> bool {
+        self.task.is_some()
+    }
+
+    // Gets the progress of the save in progress, if there is one
+    pub fn progress(&self) -> Option<&Progress> {
+        self.task.as_ref().map(Task::progress)
+    }
+
+    // Asks the save in progress to stop, which only stops it if it is still uploading
+    pub fn cancel(&self) {
+        if let Some(progress) = self.progress() {
+            progress.cancel();
+        }
+    }
+
+    // Gets the result of the save in progress if it has finished, without blocking
+    pub fn poll(&mut self) -> Option<io::Result<SavedFile>> {
+        let result = match self.task.as_mut()?.poll() {
+            TaskStatus::Finished(result) => result,
+            TaskStatus::Running => return None,
+            TaskStatus::Stopped => Err(io::Error::other("the save stopped unexpectedly")),
+        };
+
+        self.task = None;
+
+        Some(result)
+    }
+}
@@ -0,0 +1,205 @@
+use std::ops::Range;
+use std::rc::Rc;
+
+use rayon::prelude::*;
+
+use crate::Buffer;
+
+// The number of characters a buffer needs to have before it is searched on multiple threads
+// * Below this, starting the threads takes longer than searching the whole buffer on one
+const PARALLEL_THRESHOLD: usize = 1 << 20;
+
+// Represents the most recent search, whose matches are found again whenever the buffer changes
+pub struct Search {
+    pattern: String,
+    // * The matches are shared with the terminal to be highlighted, so they are not copied every frame
+    matches: Rc<[Range<usize>]>,
+    // The version of the buffer the matches were found in, or None if they have not been found yet
+    version: Option<u64>,
+}
+
+impl Search {
+    // Create a new Search instance for the given text
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            matches: Rc::default(),
+            version: None,
+        }
+    }
+
+    // Gets the ranges of every match in the buffer, in order
+    pub fn matches(&mut self, buffer: &Buffer) -> Rc<[Range<usize>]> {
+        if self.version != Some(buffer.version()) {
+            self.matches = find_all(buffer, &self.pattern).into();
+            self.version = Some(buffer.version());
+        }
+
+        self.matches.clone()
+    }
+
+    // Returns the text being searched for
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+}
+
+// Finds every occurrence of the pattern in the buffer, returning their buffer index ranges in order
+// Large buffers are split into one chunk per thread, and the chunks are searched in parallel
+pub fn find_all(buffer: &Buffer, pattern: &str) -> Vec<Range<usize>> {
+    let size = buffer.size();
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+
+    let threads = rayon::current_num_threads();
+    if size < PARALLEL_THRESHOLD || threads == 1 {
+        return find_in(&buffer.text_range(0..size), pattern, 0, size);
+    }
+
+    find_in_chunks(buffer, pattern, size.div_ceil(threads))
+}
+
+// Searches the buffer in chunks of the given size in parallel, finding the same matches as a single
+// search over the whole buffer
+// * A single search never finds overlapping matches, so the chunks find every occurrence (including
+// * overlapping ones), and then the ones which overlap the match before them are left out in order
+// [EXAMPLE] "aa" in "aaaaa" occurs at 0, 1, 2 and 3, and the matches are 0..2 and 2..4
+fn find_in_chunks(buffer: &Buffer, pattern: &str, chunk_size: usize) -> Vec<Range<usize>> {
+    let size = buffer.size();
+    let pattern_len = pattern.chars().count();
+
+    // Each chunk overlaps the next by enough to find occurrences which start in it but end in the
+    // next one, and only occurrences which start in the chunk itself are kept
+    let starts: Vec<usize> = (0..size).step_by(chunk_size).collect();
+    let occurrences: Vec<Vec<usize>> = starts
+        .into_par_iter()
+        .map(|start| {
+            let end = (start + chunk_size).min(size);
+            let text = buffer.text_range(start..(end + pattern_len - 1).min(size));
+            find_occurrences(&text, pattern, start, end)
+        })
+        .collect();
+
+    let mut matches = Vec::new();
+    let mut resume = 0;
+    for start in occurrences.into_iter().flatten() {
+        if start >= resume {
+            matches.push(start..start + pattern_len);
+            resume = start + pattern_len;
+        }
+    }
+
+    matches
+}
+
+// Finds the occurrences of the pattern in a piece of the buffer which starts at the given index,
+// keeping only those which start before the given end index
+fn find_in(text: &str, pattern: &str, start: usize, end: usize) -> Vec<Range<usize>> {
+    let pattern_len = pattern.chars().count();
+
+    // Match positions are given in bytes, so they are converted to characters as the text is walked
+    let mut matches = Vec::new();
+    let mut char_index = start;
+    let mut byte_index = 0;
+    for (match_byte, _) in text.match_indices(pattern) {
+        char_index += text[byte_index..match_byte].chars().count();
+        byte_index = match_byte;
+
+        if char_index >= end {
+            break;
+        }
+
+        matches.push(char_index..char_index + pattern_len);
+    }
+
+    matches
+}
+
+// Finds the start index of every occurrence of the pattern in a piece of the buffer, including
+// occurrences which overlap each other, keeping only those which start before the given end index
+fn find_occurrences(text: &str, pattern: &str, start: usize, end: usize) -> Vec<usize> {
+    // Each search resumes one character after the previous occurrence's start, so none are skipped
+    let step = pattern
+        .chars()
+        .next()
+        .expect("[INTERNAL ERROR] Searched for an empty pattern")
+        .len_utf8();
+
+    let mut occurrences = Vec::new();
+    let mut char_index = start;
+    let mut byte_index = 0;
+    while let Some(offset) = text[byte_index..].find(pattern) {
+        char_index += text[byte_index..byte_index + offset].chars().count();
+        byte_index += offset;
+
+        if char_index >= end {
+            break;
+        }
+
+        occurrences.push(char_index);
+        char_index += 1;
+        byte_index += step;
+    }
+
+    occurrences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Creates a buffer containing the given text
+    fn buffer(text: &str) -> Buffer {
+        let mut buffer = Buffer::empty();
+        buffer.insert_str(0, text);
+        buffer
+    }
+
+    // Checks that searching in chunks of every size finds the same matches as a single search
+    fn assert_chunks_match(text: &str, pattern: &str) {
+        let buffer = buffer(text);
+        let expected = find_in(text, pattern, 0, buffer.size());
+
+        for chunk_size in 1..=buffer.size() {
+            assert_eq!(
+                find_in_chunks(&buffer, pattern, chunk_size),
+                expected,
+                "{:?} in {:?} with chunks of {}",
+                pattern,
+                text,
+                chunk_size
+            );
+        }
+    }
+
+    #[test]
+    fn finds_matches_across_chunk_boundaries() {
+        assert_chunks_match("aaaaa", "aa");
+        assert_chunks_match("aaaaaaa", "aaa");
+        assert_chunks_match("abababab", "aba");
+        assert_chunks_match("xabcxabcabcx", "abc");
+        assert_chunks_match("é€é€€é€", "€é");
+        assert_chunks_match("no matches here", "z");
+    }
+
+    #[test]
+    fn finds_the_same_matches_in_parallel_at_the_threshold() {
+        for size in [
+            PARALLEL_THRESHOLD - 1,
+            PARALLEL_THRESHOLD,
+            PARALLEL_THRESHOLD + 1,
+        ] {
+            let text = "a".repeat(size);
+            let buffer = buffer(&text);
+
+            let matches = find_all(&buffer, "aa");
+            assert_eq!(matches.len(), size / 2);
+            assert!(matches
+                .iter()
+                .enumerate()
+                .all(|(i, range)| *range == (2 * i..2 * i + 2)));
+            assert_eq!(find_in_chunks(&buffer, "aa", size / 3 + 1), matches);
+        }
+    }
+}
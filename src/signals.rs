@@ -0,0 +1,47 @@
+use std::io;
+
+use signal_hook::consts::{SIGHUP, SIGSTOP, SIGTERM, SIGTSTP};
+use signal_hook::iterator::Signals;
+use signal_hook::low_level;
+
+// Represents a signal that the editor needs to respond to
+pub enum Signal {
+    // The process has been asked to suspend itself, such as by `kill -TSTP`
+    Suspend,
+    // The process has been asked to exit, or the terminal it was running in has been closed
+    Terminate,
+}
+
+// Listens for signals sent to the process so they can be handled by the event loop
+// * Signals are queued and handled between events, since the terminal cannot safely be restored
+// * from inside of a signal handler
+pub struct SignalListener {
+    signals: Signals,
+}
+
+impl SignalListener {
+    // Create a new SignalListener instance, which starts listening immediately
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            signals: Signals::new([SIGTSTP, SIGTERM, SIGHUP])?,
+        })
+    }
+
+    // Gets the signals which have arrived since the last time this was called
+    pub fn poll(&mut self) -> Vec<Signal> {
+        self.signals
+            .pending()
+            .filter_map(|signal| match signal {
+                SIGTSTP => Some(Signal::Suspend),
+                SIGTERM | SIGHUP => Some(Signal::Terminate),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+// Stops the process until it is continued, such as by the shell's `fg` command
+// * SIGSTOP is used because SIGTSTP is caught by the listener, and SIGSTOP cannot be caught
+pub fn stop_process() -> io::Result<()> {
+    low_level::raise(SIGSTOP)
+}
@@ -0,0 +1,204 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use tui::style::Color;
+
+use crate::theme::parse_color;
+
+// Represents a piece of information which can be shown in the status bar
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    // Whether the buffer has been modified since it was last saved
+    Modified,
+    // The current message, such as "Saved"
+    Message,
+    // The Nerd Font icon for the type of the file being edited, if icons are turned on
+    Icon,
+    // The name of the file being edited
+    File,
+    // The git branch that the file's repository has checked out
+    Branch,
+    // Whether overwrite mode is on
+    Mode,
+    // The background task in progress, such as a save
+    Progress,
+    // The host that a remote file is on
+    Remote,
+    // The cursor's line and column
+    Position,
+    // The number of lines in the buffer
+    Lines,
+    // How far through the buffer the cursor is
+    Percentage,
+    // The current time
+    Clock,
+}
+
+impl FromStr for Segment {
+    type Err = ();
+
+    // Parses the name of a segment, such as "position"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "modified" => Ok(Self::Modified),
+            "message" => Ok(Self::Message),
+            "icon" => Ok(Self::Icon),
+            "file" => Ok(Self::File),
+            "branch" => Ok(Self::Branch),
+            "mode" => Ok(Self::Mode),
+            "progress" => Ok(Self::Progress),
+            "remote" => Ok(Self::Remote),
+            "position" => Ok(Self::Position),
+            "lines" => Ok(Self::Lines),
+            "percentage" => Ok(Self::Percentage),
+            "clock" => Ok(Self::Clock),
+            _ => Err(()),
+        }
+    }
+}
+
+// Represents which segments the status bar shows on each side, and the colors they are drawn in
+// Segments without a color use the status bar's own colors
+#[derive(Clone)]
+pub struct StatusLine {
+    pub left: Vec<(Segment, Option<Color>)>,
+    pub right: Vec<(Segment, Option<Color>)>,
+}
+
+impl Default for StatusLine {
+    fn default() -> Self {
+        use Segment::*;
+
+        let uncolored = |segments: &[Segment]| segments.iter().map(|&s| (s, None)).collect();
+        Self {
+            left: uncolored(&[Modified, Icon, File, Message]),
+            right: uncolored(&[Progress, Mode, Remote, Position, Lines, Percentage]),
+        }
+    }
+}
+
+impl FromStr for StatusLine {
+    type Err = ();
+
+    // Parses a status line layout, which lists the left segments and then the right segments,
+    // separated by a bar, with each segment optionally followed by a color
+    // [EXAMPLE] "modified,file:cyan,message|branch:green,position,clock"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (left, right) = s.split_once('|').unwrap_or((s, ""));
+
+        Ok(Self {
+            left: parse_segments(left)?,
+            right: parse_segments(right)?,
+        })
+    }
+}
+
+impl StatusLine {
+    // Checks whether the status line shows a segment on either side
+    pub fn contains(&self, segment: Segment) -> bool {
+        self.left
+            .iter()
+            .chain(self.right.iter())
+            .any(|&(shown, _)| shown == segment)
+    }
+}
+
+// Represents a piece of information which can be shown in each buffer's entry in the tab line
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TabSegment {
+    // The buffer's number in the list of buffers, which :b switches to
+    Index,
+    // The Nerd Font icon for the type of the file, if icons are turned on
+    Icon,
+    // The name of the file, without the directories it is in
+    Name,
+    // The path of the file, the same as it is shown in the status bar
+    Path,
+    // Whether the buffer has been modified since it was last saved
+    Modified,
+}
+
+impl FromStr for TabSegment {
+    type Err = ();
+
+    // Parses the name of a tab segment, such as "name"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "index" => Ok(Self::Index),
+            "icon" => Ok(Self::Icon),
+            "name" => Ok(Self::Name),
+            "path" => Ok(Self::Path),
+            "modified" => Ok(Self::Modified),
+            _ => Err(()),
+        }
+    }
+}
+
+// Represents which segments each buffer's entry in the tab line shows, and the colors they are
+// drawn in, in the same way as the status line
+// * The tab line is only shown while more than one buffer is open
+#[derive(Clone)]
+pub struct TabLine {
+    pub segments: Vec<(TabSegment, Option<Color>)>,
+}
+
+impl Default for TabLine {
+    fn default() -> Self {
+        use TabSegment::*;
+
+        Self {
+            segments: [Index, Icon, Name, Modified]
+                .iter()
+                .map(|&s| (s, None))
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for TabLine {
+    type Err = ();
+
+    // Parses the layout of a buffer's entry in the tab line
+    // [EXAMPLE] "index,path:cyan,modified:yellow"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            segments: parse_segments(s)?,
+        })
+    }
+}
+
+// Parses a comma-separated list of segments, each of which may be followed by a color
+fn parse_segments<T: FromStr>(s: &str) -> Result<Vec<(T, Option<Color>)>, ()> {
+    let parse = |segment: &str| segment.parse().or(Err(()));
+
+    s.split(',')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| match segment.split_once(':') {
+            Some((segment, color)) => Ok((parse(segment)?, Some(parse_color(color).or(Err(()))?))),
+            None => Ok((parse(segment)?, None)),
+        })
+        .collect()
+}
+
+// Finds the git branch checked out in the repository containing a file, by reading .git/HEAD
+// in the file's directory or the closest directory above it that has one
+// A detached HEAD is shown as the start of the commit hash it points to
+// * This reads the file directly rather than running git, since it is checked on every startup
+pub fn git_branch(filename: &str) -> Option<String> {
+    let path = fs::canonicalize(filename).ok()?;
+    let head = path
+        .ancestors()
+        .skip(1)
+        .map(|directory| directory.join(".git").join("HEAD"))
+        .find(|head| Path::exists(head))?;
+
+    let head = fs::read_to_string(head).ok()?;
+    let head = head.trim();
+
+    match head.strip_prefix("ref: ") {
+        Some(reference) => Some(reference.trim_start_matches("refs/heads/").to_string()),
+        None => Some(head.chars().take(7).collect()),
+    }
+}
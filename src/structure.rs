@@ -0,0 +1,223 @@
+use std::ops::Range;
+
+use tree_sitter::Tree;
+
+use crate::grammar::Grammar;
+use crate::syntax::{Highlight, LineState, Syntax};
+use crate::Buffer;
+
+// Finds the innermost syntax node around a range which is bigger than it, so that selecting the
+// result and finding the node around that again steps outwards
+// [EXAMPLE] In "if x { f(a) }" with the cursor on a, this is the range of "(a)", then of "f(a)",
+// and so on out to the whole if expression
+pub fn enclosing_node(buffer: &Buffer, tree: &Tree, range: Range<usize>) -> Option<Range<usize>> {
+    let (start, end) = (
+        buffer.char_to_byte(range.start),
+        buffer.char_to_byte(range.end),
+    );
+    let mut node = tree
+        .root_node()
+        .named_descendant_for_byte_range(start, end)?;
+
+    // The innermost node can be the range itself, such as when it was just selected this way
+    while node.start_byte() > start
+        || node.end_byte() < end
+        || (node.start_byte() == start && node.end_byte() == end)
+    {
+        node = node.parent()?;
+    }
+
+    Some(buffer.byte_to_char(node.start_byte())..buffer.byte_to_char(node.end_byte()))
+}
+
+// Finds the start of the next function definition after an index, or the previous one before it,
+// from the nodes of the syntax tree which the grammar says are functions
+// Returns None if there is no such function
+pub fn adjacent_function_node(
+    buffer: &Buffer,
+    tree: &Tree,
+    grammar: &Grammar,
+    index: usize,
+    forward: bool,
+) -> Option<usize> {
+    let mut functions = Vec::new();
+
+    // Visit every node in the order they start in, so that the functions are found in order
+    let mut cursor = tree.walk();
+    'nodes: loop {
+        let node = cursor.node();
+        if grammar.is_function(node.kind()) {
+            functions.push(buffer.byte_to_char(node.start_byte()));
+        }
+
+        if cursor.goto_first_child() || cursor.goto_next_sibling() {
+            continue;
+        }
+
+        loop {
+            if !cursor.goto_parent() {
+                break 'nodes;
+            }
+            if cursor.goto_next_sibling() {
+                break;
+            }
+        }
+    }
+
+    match forward {
+        true => functions.into_iter().find(|&start| start > index),
+        false => functions.into_iter().rev().find(|&start| start < index),
+    }
+}
+
+// Gets the first line of the definition of a function with a name, along with the line it is on,
+// from the nodes of the syntax tree which the grammar says are functions
+// The body is left off the line, so that what is left is the function's signature
+// [EXAMPLE] "fn add(a: u32, b: u32) -> u32 {" -> "fn add(a: u32, b: u32) -> u32"
+pub fn function_signature(
+    buffer: &Buffer,
+    tree: &Tree,
+    grammar: &Grammar,
+    name: &str,
+) -> Option<(usize, String)> {
+    let mut nodes = vec![tree.root_node()];
+    while let Some(node) = nodes.pop() {
+        let named = node
+            .child_by_field_name("name")
+            .map(|child| buffer.byte_range(child.start_byte()..child.end_byte()));
+        if grammar.is_function(node.kind()) && named.as_deref() == Some(name) {
+            let line = buffer.line_of(buffer.byte_to_char(node.start_byte()));
+            let contents = buffer.line_contents(line);
+            let signature = contents.trim().trim_end_matches(['{', ':']).trim_end();
+            return Some((line, signature.to_string()));
+        }
+
+        let mut cursor = node.walk();
+        let children: Vec<_> = node.children(&mut cursor).collect();
+        nodes.extend(children.into_iter().rev());
+    }
+
+    None
+}
+
+// Finds the innermost pair of brackets around a range which is bigger than it, including the brackets,
+// so that selecting the result and finding the block around that again steps outwards
+// Brackets inside strings and comments are ignored, as are brackets without a partner
+// * This is only used for files without a grammar, which have no syntax tree to find nodes in
+// [EXAMPLE] In "f(a, [b])" with the cursor on b, this is the range of "[b]", and then of "(a, [b])"
+pub fn enclosing_block(
+    buffer: &Buffer,
+    syntax: Option<&Syntax>,
+    range: Range<usize>,
+) -> Option<Range<usize>> {
+    let mut open_brackets = Vec::new();
+    let mut enclosing: Option<Range<usize>> = None;
+
+    for_each_code_char(buffer, syntax, |index, c| {
+        match c {
+            '(' | '[' | '{' => open_brackets.push((index, c)),
+            ')' | ']' | '}' => {
+                let Some(&(open, open_char)) = open_brackets.last() else {
+                    return;
+                };
+                if closing_bracket(open_char) != c {
+                    return;
+                }
+                open_brackets.pop();
+
+                // The innermost block is the one which opens last, since blocks can only be nested
+                let block = open..index + 1;
+                if block.start <= range.start
+                    && block.end >= range.end
+                    && block != range
+                    && enclosing
+                        .as_ref()
+                        .is_none_or(|inner| block.start > inner.start)
+                {
+                    enclosing = Some(block);
+                }
+            }
+            _ => (),
+        }
+    });
+
+    enclosing
+}
+
+// Finds the start of the next function definition after an index, or the previous one before it,
+// by looking for the keywords which start them in the language
+// Returns None if there is no such function, including when the language has no such keywords
+// * This is only used for files without a grammar, which have no syntax tree to find nodes in
+pub fn adjacent_function(
+    buffer: &Buffer,
+    syntax: &Syntax,
+    index: usize,
+    forward: bool,
+) -> Option<usize> {
+    let mut functions = Vec::new();
+    for_each_line(buffer, syntax, |line, contents, highlights| {
+        let keywords = highlights.iter().filter(|(range, highlight)| {
+            *highlight == Highlight::Keyword && syntax.is_function_keyword(&contents[range.clone()])
+        });
+
+        for (range, _) in keywords {
+            functions.push(buffer.line_start(line) + contents[..range.start].chars().count());
+        }
+    });
+
+    match forward {
+        true => functions.into_iter().find(|&start| start > index),
+        false => functions.into_iter().rev().find(|&start| start < index),
+    }
+}
+
+// Calls a function with the buffer index of every character which is not in a string or comment
+// Without a syntax, every character is treated as code
+fn for_each_code_char(buffer: &Buffer, syntax: Option<&Syntax>, mut f: impl FnMut(usize, char)) {
+    let Some(syntax) = syntax else {
+        for (index, c) in buffer.to_string().chars().enumerate() {
+            f(index, c);
+        }
+        return;
+    };
+
+    for_each_line(buffer, syntax, |line, contents, highlights| {
+        let indices = buffer.line_start(line)..;
+        for (index, (byte_index, c)) in indices.zip(contents.char_indices()) {
+            let ignored = highlights.iter().any(|(range, highlight)| {
+                matches!(highlight, Highlight::String | Highlight::Comment)
+                    && range.contains(&byte_index)
+            });
+
+            if !ignored {
+                f(index, c);
+            }
+        }
+    });
+}
+
+// Calls a function with every line of the buffer, its contents, and its highlights, in order
+// * The whole buffer is lexed each time, which is fine for commands run from a key, but would be
+// * too slow to do on every frame
+fn for_each_line(
+    buffer: &Buffer,
+    syntax: &Syntax,
+    mut f: impl FnMut(usize, &str, &[(Range<usize>, Highlight)]),
+) {
+    let mut state = LineState::default();
+    for line in 0..buffer.line_count() {
+        let contents = buffer.line_contents(line);
+        let (highlights, next_state) = syntax.highlight_line(&contents, state);
+        f(line, &contents, &highlights);
+        state = next_state;
+    }
+}
+
+// Gets the bracket which closes an opening bracket
+fn closing_bracket(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
@@ -0,0 +1,552 @@
+use std::ops::Range;
+
+// Represents what a piece of highlighted text is
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    Comment,
+    String,
+    Number,
+    Keyword,
+    Type,
+    Function,
+    Constant,
+}
+
+// Represents what a line starts inside of, which is carried over from the end of the line before it
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineState {
+    #[default]
+    Normal,
+    BlockComment,
+    // The index of the quote in Syntax::quotes that the string was opened with
+    String(usize),
+}
+
+// Describes how the text of a language is highlighted
+// * This is a simple lexer rather than a full parser, so it only knows about comments, strings,
+// * numbers, and words, which is enough to be accurate for most code
+pub struct Syntax {
+    extensions: &'static [&'static str],
+    keywords: &'static [&'static str],
+    types: &'static [&'static str],
+    constants: &'static [&'static str],
+    line_comment: Option<&'static str>,
+    block_comment: Option<(&'static str, &'static str)>,
+    // The quotes which strings start and end with, and whether the strings can span several lines,
+    // with longer quotes first so that """ is not mistaken for an empty string
+    quotes: &'static [(&'static str, bool)],
+    // Whether ' only starts character literals such as 'a' or '\n', so that Rust's lifetimes
+    // and other uses of it are left alone
+    char_literals: bool,
+    // Whether words starting with a capital letter are types, as they are by convention in some languages
+    capitalized_types: bool,
+    // The keywords which start the definition of a function, which are jumped between
+    function_keywords: &'static [&'static str],
+}
+
+const RUST: Syntax = Syntax {
+    extensions: &["rs"],
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "type",
+        "unsafe", "use", "where", "while",
+    ],
+    types: &[
+        "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32",
+        "i64", "i128", "isize", "f32", "f64",
+    ],
+    constants: &["true", "false", "None", "Some", "Ok", "Err"],
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    quotes: &[("\"", true)],
+    char_literals: true,
+    capitalized_types: true,
+    function_keywords: &["fn"],
+};
+
+const C: Syntax = Syntax {
+    extensions: &["c", "h", "cc", "cpp", "cxx", "hpp", "hh"],
+    keywords: &[
+        "auto",
+        "break",
+        "case",
+        "class",
+        "const",
+        "constexpr",
+        "continue",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "enum",
+        "extern",
+        "for",
+        "goto",
+        "if",
+        "inline",
+        "namespace",
+        "new",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "sizeof",
+        "static",
+        "struct",
+        "switch",
+        "template",
+        "this",
+        "typedef",
+        "typename",
+        "union",
+        "using",
+        "virtual",
+        "volatile",
+        "while",
+        "#include",
+        "#define",
+        "#ifdef",
+        "#ifndef",
+        "#endif",
+        "#if",
+        "#else",
+        "#pragma",
+    ],
+    types: &[
+        "bool", "char", "double", "float", "int", "long", "short", "signed", "unsigned", "void",
+        "size_t", "int8_t", "int16_t", "int32_t", "int64_t", "uint8_t", "uint16_t", "uint32_t",
+        "uint64_t",
+    ],
+    constants: &["true", "false", "NULL", "nullptr"],
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    quotes: &[("\"", false)],
+    char_literals: true,
+    capitalized_types: false,
+    function_keywords: &[],
+};
+
+const GO: Syntax = Syntax {
+    extensions: &["go"],
+    keywords: &[
+        "break",
+        "case",
+        "chan",
+        "const",
+        "continue",
+        "default",
+        "defer",
+        "else",
+        "fallthrough",
+        "for",
+        "func",
+        "go",
+        "goto",
+        "if",
+        "import",
+        "interface",
+        "map",
+        "package",
+        "range",
+        "return",
+        "select",
+        "struct",
+        "switch",
+        "type",
+        "var",
+    ],
+    types: &[
+        "bool", "byte", "error", "float32", "float64", "int", "int8", "int16", "int32", "int64",
+        "rune", "string", "uint", "uint8", "uint16", "uint32", "uint64", "uintptr", "any",
+    ],
+    constants: &["true", "false", "nil", "iota"],
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    quotes: &[("`", true), ("\"", false)],
+    char_literals: true,
+    capitalized_types: false,
+    function_keywords: &["func"],
+};
+
+const JAVA: Syntax = Syntax {
+    extensions: &["java", "kt", "cs"],
+    keywords: &[
+        "abstract",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "continue",
+        "default",
+        "do",
+        "else",
+        "enum",
+        "extends",
+        "final",
+        "finally",
+        "for",
+        "if",
+        "implements",
+        "import",
+        "instanceof",
+        "interface",
+        "new",
+        "package",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "throws",
+        "try",
+        "var",
+        "while",
+    ],
+    types: &[
+        "boolean", "byte", "char", "double", "float", "int", "long", "short", "void",
+    ],
+    constants: &["true", "false", "null"],
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    quotes: &[("\"\"\"", true), ("\"", false)],
+    char_literals: true,
+    capitalized_types: true,
+    function_keywords: &[],
+};
+
+const JAVASCRIPT: Syntax = Syntax {
+    extensions: &["js", "mjs", "cjs", "jsx", "ts", "tsx"],
+    keywords: &[
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "export",
+        "extends",
+        "finally",
+        "for",
+        "from",
+        "function",
+        "if",
+        "import",
+        "in",
+        "instanceof",
+        "interface",
+        "let",
+        "new",
+        "of",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "try",
+        "type",
+        "typeof",
+        "var",
+        "void",
+        "while",
+        "yield",
+    ],
+    types: &[
+        "any", "boolean", "never", "number", "object", "string", "unknown",
+    ],
+    constants: &["true", "false", "null", "undefined", "NaN", "Infinity"],
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    quotes: &[("`", true), ("\"", false), ("'", false)],
+    char_literals: false,
+    capitalized_types: true,
+    function_keywords: &["function"],
+};
+
+const PYTHON: Syntax = Syntax {
+    extensions: &["py", "pyi"],
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is",
+        "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with",
+        "yield", "self",
+    ],
+    types: &[
+        "bool", "bytes", "dict", "float", "int", "list", "object", "set", "str", "tuple",
+    ],
+    constants: &["True", "False", "None"],
+    line_comment: Some("#"),
+    block_comment: None,
+    quotes: &[("\"\"\"", true), ("'''", true), ("\"", false), ("'", false)],
+    char_literals: false,
+    capitalized_types: true,
+    function_keywords: &["def"],
+};
+
+const SHELL: Syntax = Syntax {
+    extensions: &["sh", "bash", "zsh"],
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+        "in", "local", "return", "then", "until", "while",
+    ],
+    types: &[],
+    constants: &["true", "false"],
+    line_comment: Some("#"),
+    block_comment: None,
+    quotes: &[("\"", true), ("'", true)],
+    char_literals: false,
+    capitalized_types: false,
+    function_keywords: &["function"],
+};
+
+const TOML: Syntax = Syntax {
+    extensions: &["toml"],
+    keywords: &[],
+    types: &[],
+    constants: &["true", "false"],
+    line_comment: Some("#"),
+    block_comment: None,
+    quotes: &[("\"\"\"", true), ("'''", true), ("\"", false), ("'", false)],
+    char_literals: false,
+    capitalized_types: false,
+    function_keywords: &[],
+};
+
+const JSON: Syntax = Syntax {
+    extensions: &["json"],
+    keywords: &[],
+    types: &[],
+    constants: &["true", "false", "null"],
+    line_comment: None,
+    block_comment: None,
+    quotes: &[("\"", false)],
+    char_literals: false,
+    capitalized_types: false,
+    function_keywords: &[],
+};
+
+// Every language which can be highlighted
+// * Adding a language only requires adding its syntax here
+const SYNTAXES: &[Syntax] = &[RUST, C, GO, JAVA, JAVASCRIPT, PYTHON, SHELL, TOML, JSON];
+
+impl Syntax {
+    // Gets the syntax for a file based on its extension, if it is a language which can be highlighted
+    // [EXAMPLE] "src/main.rs" -> Rust
+    pub fn for_filename(filename: &str) -> Option<&'static Self> {
+        let (_, extension) = filename.rsplit_once('.')?;
+        let extension = extension.to_lowercase();
+
+        SYNTAXES
+            .iter()
+            .find(|syntax| syntax.extensions.contains(&extension.as_str()))
+    }
+
+    // Highlights a line, given what it starts inside of
+    // Returns the byte ranges of the highlighted parts of the line, in order,
+    // along with what the next line starts inside of
+    pub fn highlight_line(
+        &self,
+        line: &str,
+        state: LineState,
+    ) -> (Vec<(Range<usize>, Highlight)>, LineState) {
+        let mut highlights = Vec::new();
+        let mut index = 0;
+
+        // Finish whatever was left open at the end of the previous line
+        match state {
+            LineState::Normal => (),
+            LineState::BlockComment => match self.block_comment_end(line, 0) {
+                Some(end) => {
+                    highlights.push((0..end, Highlight::Comment));
+                    index = end;
+                }
+                None => return (vec![(0..line.len(), Highlight::Comment)], state),
+            },
+            LineState::String(quote) => match self.string_end(line, 0, quote) {
+                Some(end) => {
+                    highlights.push((0..end, Highlight::String));
+                    index = end;
+                }
+                None => return (vec![(0..line.len(), Highlight::String)], state),
+            },
+        }
+
+        while index < line.len() {
+            let rest = &line[index..];
+            let c = rest
+                .chars()
+                .next()
+                .expect("[INTERNAL ERROR] Line ended in the middle of a character");
+
+            if self
+                .line_comment
+                .is_some_and(|start| rest.starts_with(start))
+            {
+                highlights.push((index..line.len(), Highlight::Comment));
+                break;
+            }
+
+            if let Some((start, _)) = self
+                .block_comment
+                .filter(|(start, _)| rest.starts_with(start))
+            {
+                match self.block_comment_end(line, index + start.len()) {
+                    Some(end) => {
+                        highlights.push((index..end, Highlight::Comment));
+                        index = end;
+                        continue;
+                    }
+                    None => {
+                        highlights.push((index..line.len(), Highlight::Comment));
+                        return (highlights, LineState::BlockComment);
+                    }
+                }
+            }
+
+            if let Some(quote) = self
+                .quotes
+                .iter()
+                .position(|(quote, _)| rest.starts_with(quote))
+            {
+                let (delimiter, multiline) = self.quotes[quote];
+                match self.string_end(line, index + delimiter.len(), quote) {
+                    Some(end) => {
+                        highlights.push((index..end, Highlight::String));
+                        index = end;
+                        continue;
+                    }
+                    None if multiline => {
+                        highlights.push((index..line.len(), Highlight::String));
+                        return (highlights, LineState::String(quote));
+                    }
+                    // An unclosed string which cannot span lines is most likely still being typed,
+                    // so only the quote is skipped instead of highlighting the rest of the line
+                    None => {
+                        index += delimiter.len();
+                        continue;
+                    }
+                }
+            }
+
+            if c == '\'' && self.char_literals {
+                if let Some(length) = char_literal_length(rest) {
+                    highlights.push((index..index + length, Highlight::String));
+                    index += length;
+                    continue;
+                }
+            }
+
+            if c.is_ascii_digit() {
+                let length = word_length(rest, |c| c.is_alphanumeric() || c == '_' || c == '.');
+                highlights.push((index..index + length, Highlight::Number));
+                index += length;
+                continue;
+            }
+
+            if is_word_char(c) || c == '#' {
+                let length = c.len_utf8() + word_length(&rest[c.len_utf8()..], is_word_char);
+                let word = &rest[..length];
+                if let Some(highlight) = self.classify_word(word, &rest[length..]) {
+                    highlights.push((index..index + length, highlight));
+                }
+
+                index += length;
+                continue;
+            }
+
+            index += c.len_utf8();
+        }
+
+        (highlights, LineState::Normal)
+    }
+
+    // Gets what kind of word a word is, given the text after it, if it is one which is highlighted
+    fn classify_word(&self, word: &str, after: &str) -> Option<Highlight> {
+        let first = word.chars().next()?;
+
+        if self.keywords.contains(&word) {
+            Some(Highlight::Keyword)
+        } else if self.constants.contains(&word) {
+            Some(Highlight::Constant)
+        } else if self.types.contains(&word) {
+            Some(Highlight::Type)
+        } else if after.trim_start().starts_with('(') && !first.is_ascii_digit() {
+            Some(Highlight::Function)
+        } else if word.len() > 1 && word.chars().all(|c| c.is_ascii_uppercase() || c == '_') {
+            Some(Highlight::Constant)
+        } else if self.capitalized_types && first.is_uppercase() {
+            Some(Highlight::Type)
+        } else {
+            None
+        }
+    }
+
+    // Checks whether a word starts the definition of a function
+    pub fn is_function_keyword(&self, word: &str) -> bool {
+        self.function_keywords.contains(&word)
+    }
+
+    // Gets the byte index just after the end of the block comment which continues from an index
+    fn block_comment_end(&self, line: &str, from: usize) -> Option<usize> {
+        let (_, end) = self.block_comment?;
+        line[from..]
+            .find(end)
+            .map(|position| from + position + end.len())
+    }
+
+    // Gets the byte index just after the end of the string which continues from an index,
+    // skipping over escaped characters
+    fn string_end(&self, line: &str, from: usize, quote: usize) -> Option<usize> {
+        let (delimiter, _) = self.quotes[quote];
+        let mut escaped = false;
+
+        for (offset, c) in line[from..].char_indices() {
+            let index = from + offset;
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if line[index..].starts_with(delimiter) => return Some(index + delimiter.len()),
+                _ => (),
+            }
+        }
+
+        None
+    }
+}
+
+// Gets the length in bytes of the character literal at the start of some text, such as 'a' or '\n'
+fn char_literal_length(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().skip(1);
+    let (_, c) = chars.next()?;
+    if c == '\\' {
+        // Escapes can be several characters long, such as '\u{1F600}'
+        let (end, _) = chars.take(10).find(|&(_, c)| c == '\'')?;
+        return Some(end + 1);
+    }
+
+    match chars.next()? {
+        (end, '\'') => Some(end + 1),
+        _ => None,
+    }
+}
+
+// Gets the length in bytes of the run of characters at the start of some text which match a predicate
+fn word_length(text: &str, predicate: impl Fn(char) -> bool) -> usize {
+    text.find(|c| !predicate(c)).unwrap_or(text.len())
+}
+
+// Checks whether a character can be part of a word
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
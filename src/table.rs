@@ -0,0 +1,212 @@
+use std::ops::Range;
+
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::Buffer;
+
+// The widest a column is padded to, so that one long cell does not push every other column off screen
+// * Cells wider than this are still drawn in full, they just push the rest of their own row along
+const MAX_COLUMN_WIDTH: usize = 40;
+
+// The delimiters which are recognised when guessing how a file is separated
+const DELIMITERS: [char; 4] = [',', '\t', ';', '|'];
+
+// Represents the table view of a delimiter-separated file, which lines up the columns
+// by drawing extra padding after each cell, without changing the buffer itself
+pub struct Table {
+    delimiter: char,
+    // Whether the first line is drawn as a header
+    header: bool,
+    // The display width of each column, which is the width of its widest cell
+    widths: Vec<usize>,
+    // Whether the buffer has changed since the widths were last measured
+    stale: bool,
+    delimiter_style: Style,
+    header_style: Style,
+}
+
+impl Table {
+    // Create a new Table instance for a file separated by the given delimiter
+    pub fn new(delimiter: char) -> Self {
+        Self {
+            delimiter,
+            header: false,
+            widths: Vec::new(),
+            stale: true,
+            delimiter_style: Style::default().add_modifier(Modifier::DIM),
+            header_style: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+        }
+    }
+
+    // Toggles whether the first line is drawn as a header
+    pub fn toggle_header(&mut self) {
+        self.header = !self.header;
+    }
+
+    // Marks the column widths as needing to be measured again, such as after an edit
+    pub fn mark_stale(&mut self) {
+        self.stale = true;
+    }
+
+    // Measures the column widths again if the buffer has changed, returning whether any of them changed
+    // * Every line is measured, so that the columns do not shift around while scrolling
+    pub fn refresh(&mut self, buffer: &Buffer) -> bool {
+        if !self.stale {
+            return false;
+        }
+        self.stale = false;
+
+        let mut widths: Vec<usize> = Vec::new();
+        for line in 0..buffer.line_count() {
+            let contents = buffer.line_contents(line);
+            let contents = line_text(&contents);
+
+            for (column, cell) in self.cells(contents).into_iter().enumerate() {
+                let width = cell_width(contents, cell).min(MAX_COLUMN_WIDTH);
+
+                match widths.get_mut(column) {
+                    Some(widest) => *widest = (*widest).max(width),
+                    None => widths.push(width),
+                }
+            }
+        }
+
+        let changed = widths != self.widths;
+        self.widths = widths;
+
+        changed
+    }
+
+    // Gets the ranges of character columns that each cell of a line takes up, not including delimiters
+    // * Delimiters inside double quotes are part of the cell, as they are in CSV files
+    pub fn cells(&self, line: &str) -> Vec<Range<usize>> {
+        let mut cells = Vec::new();
+        let mut start = 0;
+        let mut quoted = false;
+
+        for (column, c) in line_text(line).chars().enumerate() {
+            match c {
+                '"' => quoted = !quoted,
+                c if c == self.delimiter && !quoted => {
+                    cells.push(start..column);
+                    start = column + 1;
+                }
+                _ => (),
+            }
+        }
+        cells.push(start..line_text(line).chars().count());
+
+        cells
+    }
+
+    // Builds the text of a line with each cell padded out to the width of its column
+    // [EXAMPLE] "id,name" with columns 4 and 6 wide becomes "id  , name  "
+    pub fn build_line(&self, buffer: &Buffer, line: usize) -> Spans<'static> {
+        let contents = buffer.line_contents(line);
+        let contents = line_text(&contents);
+        let cells = self.cells(contents);
+
+        let cell_style = match self.header && line == 0 {
+            true => self.header_style,
+            false => Style::default(),
+        };
+
+        let mut spans = Vec::with_capacity(cells.len() * 2);
+        for (column, cell) in cells.iter().enumerate() {
+            let text: String = contents.chars().skip(cell.start).take(cell.len()).collect();
+            let padding = self.column_width(column).saturating_sub(text.width());
+            spans.push(Span::styled(text, cell_style));
+
+            // Tabs are drawn as a bar, since a tab character would be expanded by the terminal
+            if column + 1 < cells.len() {
+                let delimiter = match self.delimiter {
+                    '\t' => '│',
+                    delimiter => delimiter,
+                };
+
+                spans.push(Span::raw(" ".repeat(padding)));
+                spans.push(Span::styled(
+                    format!("{} ", delimiter),
+                    self.delimiter_style,
+                ));
+            }
+        }
+
+        Spans::from(spans)
+    }
+
+    // Gets the screen column that a character column of a line is drawn at in the table view
+    pub fn screen_column(&self, line: &str, column: usize) -> usize {
+        let line = line_text(line);
+        let mut x = 0;
+
+        let cells = self.cells(line);
+        for (i, cell) in cells.iter().enumerate() {
+            let padded_width = cell_width(line, cell.clone()).max(self.column_width(i));
+            let last = i + 1 == cells.len();
+
+            // The delimiter after a cell is drawn after the cell's padding
+            if column == cell.end && !last {
+                return x + padded_width;
+            } else if column <= cell.end {
+                return x + cell_width(line, cell.start..column);
+            }
+
+            x += padded_width + 2;
+        }
+
+        x
+    }
+
+    // Gets the character column of the start of the cell after (or before) the one containing a column,
+    // returning None if it is the last (or first) cell of the line
+    pub fn adjacent_cell(&self, line: &str, column: usize, forwards: bool) -> Option<usize> {
+        let cells = self.cells(line);
+        let current = cells
+            .iter()
+            .position(|cell| column <= cell.end)
+            .unwrap_or(cells.len() - 1);
+
+        match forwards {
+            true => cells.get(current + 1).map(|cell| cell.start),
+            false => current
+                .checked_sub(1)
+                .and_then(|previous| cells.get(previous))
+                .map(|cell| cell.start),
+        }
+    }
+
+    // Gets the width that a column is padded to, which is zero for columns which have not been measured
+    fn column_width(&self, column: usize) -> usize {
+        self.widths.get(column).copied().unwrap_or(0)
+    }
+}
+
+// Guesses the delimiter of a file from its extension, or else from whichever delimiter
+// appears most often on its first line
+pub fn detect_delimiter(filename: &str, first_line: &str) -> char {
+    if filename.ends_with(".tsv") || filename.ends_with(".tab") {
+        return '\t';
+    }
+
+    DELIMITERS
+        .into_iter()
+        .map(|delimiter| (first_line.matches(delimiter).count(), delimiter))
+        .filter(|&(count, _)| count > 0)
+        .max_by_key(|&(count, _)| count)
+        .map_or(',', |(_, delimiter)| delimiter)
+}
+
+// Gets the text of a line without its line ending
+fn line_text(line: &str) -> &str {
+    line.trim_end_matches(['\n', '\r'])
+}
+
+// Gets the display width of a cell
+fn cell_width(line: &str, cell: Range<usize>) -> usize {
+    let text: String = line.chars().skip(cell.start).take(cell.len()).collect();
+    text.width()
+}
@@ -0,0 +1,74 @@
+use std::sync::OnceLock;
+
+use tokio::runtime::{Builder, Runtime};
+use tokio::sync::oneshot::{self, error::TryRecvError};
+
+use crate::Progress;
+
+// Represents the state of a background task
+pub enum TaskStatus<T> {
+    Running,
+    Finished(T),
+    // The task stopped without producing a result, such as by panicking
+    Stopped,
+}
+
+// Gets the async runtime which background work runs on, starting it the first time it is needed
+// * Blocking work such as file reads and writes runs on the runtime's pool of blocking threads,
+// * and subprocess IO runs as async tasks on its workers, so neither needs a thread of its own
+pub fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+    RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("rut-worker")
+            .enable_all()
+            .build()
+            .expect("[INTERNAL ERROR] Failed to start the async runtime")
+    })
+}
+
+// Represents work which is done on the async runtime and reports its result back over a channel
+// The editor polls its tasks from the event loop, so slow work never holds up keypresses
+pub struct Task<T> {
+    receiver: oneshot::Receiver<T>,
+    progress: Progress,
+}
+
+impl<T: Send + 'static> Task<T> {
+    // Starts running blocking work, such as reading or writing a file, on the runtime
+    // The work is given a Progress which it can report to and check for cancellation
+    pub fn spawn_blocking(work: impl FnOnce(&Progress) -> T + Send + 'static) -> Self {
+        let (sender, receiver) = oneshot::channel();
+        let progress = Progress::default();
+
+        let task_progress = progress.clone();
+        runtime().spawn_blocking(move || {
+            // * If the editor has stopped listening, there is nobody to report the result to
+            let _ = sender.send(work(&task_progress));
+        });
+
+        Self { receiver, progress }
+    }
+
+    // Checks whether the task has finished, without blocking
+    pub fn poll(&mut self) -> TaskStatus<T> {
+        match self.receiver.try_recv() {
+            Ok(result) => TaskStatus::Finished(result),
+            Err(TryRecvError::Empty) => TaskStatus::Running,
+            Err(TryRecvError::Closed) => TaskStatus::Stopped,
+        }
+    }
+
+    // Blocks until the task has finished, returning None if it stopped without a result
+    // ! This must not be called from inside the runtime, which is never the case on the editor's thread
+    pub fn wait(self) -> Option<T> {
+        self.receiver.blocking_recv().ok()
+    }
+
+    // Gets the progress of the task
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
+}
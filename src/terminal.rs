@@ -1,8 +1,15 @@
-use std::io::{stdout, Stdout};
+use std::io::{stdout, Stdout, Write};
+use std::ops::Range;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crossterm::{
     cursor,
-    event::DisableMouseCapture,
+    event::{
+        DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     Result,
@@ -10,14 +17,216 @@ use crossterm::{
 
 use tui::{
     backend::{Backend, CrosstermBackend},
-    widgets::Paragraph,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
 };
 
+use tree_sitter::{InputEdit, Tree};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::buffer::{char_width, LineChange};
+use crate::clipboard::osc52_sequence;
+use crate::diagnostics::{tree_errors, SyntaxError};
+use crate::grammar::Grammar;
+use crate::highlighter::{Highlighter, LineHighlights};
+use crate::images::{self, image_info, KITTY_DELETE_ALL};
+use crate::statusline::{Segment, StatusLine, TabLine, TabSegment};
+use crate::syntax::{Highlight, Syntax};
+use crate::theme::Theme;
+use crate::wrap;
 use crate::Buffer;
+use crate::DateTime;
+use crate::Dialog;
+use crate::MessageLevel;
+use crate::Prompt;
+use crate::Table;
+use crate::{Panel, PanelKind};
+
+// Represents the information about the editor's state which is shown in the status bar
+pub struct Status<'a> {
+    // The message shown in the status bar, along with its level
+    pub message: Option<(&'a str, MessageLevel)>,
+    pub modified: bool,
+    // The name and percentage done of the background task in progress, if there is one
+    // The percentage is None if the task does not know how much work it has to do
+    pub progress: Option<(&'a str, Option<usize>)>,
+    // Whether typed characters replace the characters under the cursor instead of being inserted
+    pub overwrite: bool,
+    // The host that the file is on if it is a remote file, and whether it could be reached last time
+    pub remote: Option<(&'a str, bool)>,
+    // The name of the file being edited, and its icon if icons are turned on
+    pub file: &'a str,
+    pub icon: Option<char>,
+    // The git branch of the repository the file is in, if it is in one
+    pub branch: Option<&'a str>,
+}
+
+// Represents an open buffer's entry in the tab line
+pub struct Tab {
+    // The path of the file, the same as it is shown in the status bar
+    pub path: String,
+    pub modified: bool,
+    // The icon for the type of the file, if icons are turned on
+    pub icon: Option<char>,
+}
+
+// Represents the parts of the terminal's state which belong to the buffer shown in it, which are
+// kept aside while another buffer is shown in its place
+#[derive(Default)]
+pub struct BufferView {
+    cursor_pos: CursorPosition,
+    scroll_y: usize,
+    table: Option<Table>,
+    syntax: Option<&'static Syntax>,
+    grammar: Option<&'static Grammar>,
+}
+
+// Represents where the cursor's line is placed in the text area when recentering the view
+#[derive(Clone, Copy)]
+pub enum ViewPosition {
+    Center,
+    Top,
+    Bottom,
+}
 
 pub struct Terminal {
     terminal: tui::Terminal<CrosstermBackend<Stdout>>,
     cursor_pos: CursorPosition,
+    show_invisibles: bool,
+    highlight_current_line: bool,
+    // The styles that everything on the screen is drawn in
+    theme: Theme,
+    // The column that the ruler is drawn at, starting from zero
+    ruler: Option<u16>,
+    // How the line numbers to the left of the text are counted, or None if they are not shown
+    line_numbers: Option<LineNumbers>,
+    // The PNG image shown in the image preview panel, if the terminal can show images
+    image: Option<Vec<u8>>,
+    // Whether the image has been drawn, since it only needs to be sent to the terminal once
+    image_drawn: bool,
+    // The lines of the performance overlay, if it is shown
+    overlay: Option<Vec<String>>,
+    // The segments shown on each side of the status bar
+    status_line: StatusLine,
+    // The segments shown in each buffer's entry in the tab line, the open buffers, and which of
+    // them is being edited
+    tab_line: TabLine,
+    tabs: Vec<Tab>,
+    current_tab: usize,
+    // The time shown by the status bar's clock when it was last drawn, if it has a clock
+    drawn_clock: Option<String>,
+    // The width of the centered text column in zen mode, or None if zen mode is off
+    // * Zen mode hides the scrollbar and status bar, except for messages and prompts
+    zen_width: Option<u16>,
+    // Whether purely visual elements such as the scrollbar are drawn
+    // * These are turned off for screen readers, which would otherwise read them out on every redraw
+    decorations: bool,
+    // The minimum number of lines kept visible above and below the cursor
+    scrolloff: usize,
+    // The first line of the buffer which is visible in the text area
+    scroll_y: usize,
+    // The ranges of lines which are hidden by folds, sorted and without any overlaps
+    hidden_lines: Vec<Range<usize>>,
+    // The range of buffer indices which is selected, if there is a selection
+    selection: Option<Range<usize>>,
+    // The ranges of buffer indices matched by the current search, in order, which are highlighted
+    search_matches: Rc<[Range<usize>]>,
+    // The syntax errors in the syntax tree of a version of the buffer, which are underlined
+    syntax_errors: Option<(u64, Vec<SyntaxError>)>,
+    // The message shown in a tooltip where the mouse is resting, along with where it is
+    tooltip: Option<(u16, u16, String)>,
+    // The lines with breakpoints, and the line the program being debugged has stopped on, which are
+    // marked in a column to the left of the line numbers
+    breakpoints: Vec<usize>,
+    debug_line: Option<usize>,
+    // The style of the cursor outside of overwrite mode, or None to leave the terminal's default
+    cursor_style: Option<CursorStyle>,
+    // The buffer indices of the cursors added besides the main one, which are drawn as blocks since
+    // the terminal only has one cursor of its own
+    extra_cursors: Vec<usize>,
+    // The buffer indices of other users' cursors in a collaborative session, along with their site numbers
+    remote_cursors: Vec<(u32, usize)>,
+    // The table view which lines up the columns of a delimiter-separated file, if it is turned on
+    table: Option<Table>,
+    // Whether long lines are wrapped onto several rows, which is ignored while the table view is on
+    soft_wrap: bool,
+    // The width that lines are wrapped to, which is the width of the text area when it was last drawn
+    wrap_width: usize,
+    // The column within its row that moving between rows keeps the cursor in, along with the buffer
+    // index that the last such movement left the cursor at, so that it is only kept while the
+    // cursor has not been moved some other way since
+    row_goal: Option<(usize, usize)>,
+    // The styled text of each line of the buffer, which is only rebuilt when a line changes
+    // Lines which have not been built yet, or have changed since, are None
+    line_cache: Vec<Option<Spans<'static>>>,
+    // The syntax that the text is highlighted with, if the file is in a language which can be highlighted
+    syntax: Option<&'static Syntax>,
+    // The grammar that the text is parsed with, if the file is in a language which has one
+    grammar: Option<&'static Grammar>,
+    // Works out the syntax highlighting of the visible lines in the background
+    highlighter: Highlighter,
+    // The highlighted parts of each line, as they were when the worker last highlighted it, which
+    // is kept alongside the cache of lines
+    // Lines which have not been highlighted yet, or have changed since, are None and drawn as plain text
+    line_highlights: Vec<Option<LineHighlights>>,
+}
+
+// Represents the shape of the terminal's cursor
+#[derive(Clone, Copy, Default)]
+pub enum CursorShape {
+    #[default]
+    Block,
+    Bar,
+    Underline,
+}
+
+impl FromStr for CursorShape {
+    type Err = ();
+
+    // Parses a shape given on the command line, such as "bar"
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "block" => Ok(Self::Block),
+            "bar" => Ok(Self::Bar),
+            "underline" => Ok(Self::Underline),
+            _ => Err(()),
+        }
+    }
+}
+
+// Represents how the line numbers to the left of the text are counted
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LineNumbers {
+    // Every line shows its own number, starting from one
+    Absolute,
+    // Every line shows how many lines away from the cursor it is, except for the cursor's line,
+    // which shows its own number
+    // * This matches the counts given to commands that move the cursor by lines
+    Relative,
+}
+
+// Represents the shape of the cursor and whether it blinks, which replaces the terminal's default
+#[derive(Clone, Copy)]
+pub struct CursorStyle {
+    pub shape: CursorShape,
+    pub blink: bool,
+}
+
+impl CursorStyle {
+    // Gets the number used for this style in the DECSCUSR sequence
+    fn code(self) -> u8 {
+        match (self.shape, self.blink) {
+            (CursorShape::Block, true) => 1,
+            (CursorShape::Block, false) => 2,
+            (CursorShape::Underline, true) => 3,
+            (CursorShape::Underline, false) => 4,
+            (CursorShape::Bar, true) => 5,
+            (CursorShape::Bar, false) => 6,
+        }
+    }
 }
 
 impl Terminal {
@@ -30,52 +239,1485 @@ impl Terminal {
         Self {
             terminal,
             cursor_pos: CursorPosition::default(),
+            show_invisibles: false,
+            highlight_current_line: true,
+            theme: Theme::default(),
+            ruler: None,
+            line_numbers: None,
+            image: None,
+            image_drawn: false,
+            overlay: None,
+            status_line: StatusLine::default(),
+            tab_line: TabLine::default(),
+            tabs: Vec::new(),
+            current_tab: 0,
+            drawn_clock: None,
+            zen_width: None,
+            decorations: true,
+            scrolloff: 0,
+            scroll_y: 0,
+            hidden_lines: Vec::new(),
+            selection: None,
+            search_matches: Rc::default(),
+            syntax_errors: None,
+            breakpoints: Vec::new(),
+            debug_line: None,
+            tooltip: None,
+            cursor_style: None,
+            extra_cursors: Vec::new(),
+            remote_cursors: Vec::new(),
+            table: None,
+            soft_wrap: false,
+            wrap_width: 0,
+            row_goal: None,
+            line_cache: Vec::new(),
+            syntax: None,
+            grammar: None,
+            highlighter: Highlighter::start(),
+            line_highlights: Vec::new(),
         }
     }
 
     // Open the terminal window
+    // * The enhanced keyboard protocol lets terminals which support it report keys like Ctrl+Backspace
+    // * and Ctrl+I distinctly, and terminals which do not support it ignore the request
+    // * The mouse is captured so that clicks can place the cursor and select text, which means the
+    // * terminal's own selection usually needs Shift to be held instead
+    // * Capturing the mouse also reports where it moves, which is used to show tooltips where it rests
     pub fn open(&mut self) -> Result<()> {
         enable_raw_mode()?;
         execute!(
             self.terminal.backend_mut(),
             EnterAlternateScreen,
-            DisableMouseCapture
-        )
+            EnableMouseCapture,
+            EnableBracketedPaste,
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+        )?;
+
+        // Only change the cursor if the user has chosen a style, so the terminal's own is kept otherwise
+        match self.cursor_style {
+            Some(_) => self.set_cursor_shape(false),
+            None => Ok(()),
+        }
     }
 
     // Close the terminal window
     pub fn exit(&mut self) -> Result<()> {
         disable_raw_mode()?;
         self.terminal.backend_mut().show_cursor()?;
-        execute!(self.terminal.backend_mut(), LeaveAlternateScreen)
+        // Restore the terminal's default cursor shape
+        write!(self.terminal.backend_mut(), "\x1b[0 q")?;
+        execute!(
+            self.terminal.backend_mut(),
+            PopKeyboardEnhancementFlags,
+            DisableBracketedPaste,
+            DisableMouseCapture,
+            LeaveAlternateScreen
+        )
+    }
+
+    // Reopens the terminal window after the editor has been suspended
+    // * The screen was cleared while the editor was stopped, so the next frame is drawn in full
+    pub fn resume(&mut self) -> Result<()> {
+        self.open()?;
+        self.terminal.clear()
     }
 
     // Performs a frame update
+    // Only the visible lines of the buffer are drawn, and only lines which have changed are rebuilt
     // ? Is there a way to update the cursor without an entire frame update?
-    pub fn update_frame(&mut self, buffer: &Buffer) -> Result<()> {
-        let block = Paragraph::new(buffer.to_string());
+    pub fn update_frame(
+        &mut self,
+        buffer: &Buffer,
+        status: Status,
+        panel: Option<&Panel>,
+        prompt: Option<&Prompt>,
+        key_hints: Option<(&str, &[(&str, &str)])>,
+        dialog: Option<&Dialog>,
+    ) -> Result<()> {
+        // Split the screen into the tab line, text area, scrollbar, and status bar
+        let size = self.terminal.size()?;
+        let (text_area, scrollbar_area, status_area) = self.layout(size, buffer.line_count());
+        let tab_area = Rect {
+            height: text_area.y - size.y,
+            ..size
+        };
+        let gutter_width = self.gutter_width(buffer.line_count()).min(text_area.x);
+        let gutter_area = Rect {
+            x: text_area.x - gutter_width,
+            width: gutter_width,
+            ..text_area
+        };
+
+        self.wrap_width = text_area.width as usize;
+        self.scroll_to_cursor(buffer, text_area.height as usize);
+        let block = Paragraph::new(self.visible_text(buffer, text_area.height as usize));
+        let gutter = Paragraph::new(self.gutter_text(buffer, text_area.height as usize));
+        let (cursor_x, cursor_y) = self.screen_cursor(buffer);
+        let thumb_area = scrollbar_thumb(scrollbar_area, self.scroll_y, buffer.line_count());
+        let remote_cursors = self.screen_remote_cursors(buffer, text_area);
+        let extra_cursors: Vec<_> = self
+            .extra_cursors
+            .iter()
+            .filter_map(|&index| self.screen_cell(buffer, index, text_area))
+            .collect();
+        let clock = DateTime::now().format(CLOCK_FORMAT);
+        self.drawn_clock = self
+            .status_line
+            .contains(Segment::Clock)
+            .then(|| clock.clone());
+        let current_line_area = self
+            .highlight_current_line
+            .then(|| self.screen_current_line(buffer, text_area))
+            .flatten();
+        let selection_areas = self.screen_selection(buffer, text_area);
+        let search_areas = self.screen_search_matches(buffer, text_area);
+        let error_areas = self.screen_syntax_errors(buffer, text_area);
+        let debug_line_area = self
+            .debug_line
+            .and_then(|line| self.screen_line(buffer, line, text_area));
 
         self.terminal.draw(|f| {
-            // Draw the buffer
-            let size = f.size();
-            f.render_widget(block, size);
+            // Fill the screen with the theme's colors, underneath everything else
+            f.render_widget(Block::default().style(self.theme.text), size);
+
+            // Draw the ruler underneath the buffer's text
+            if let Some(column) = self.ruler.filter(|&column| column < text_area.width) {
+                let ruler_area = Rect {
+                    x: text_area.x + column,
+                    width: 1,
+                    ..text_area
+                };
+
+                f.render_widget(Block::default().style(self.theme.ruler), ruler_area);
+            }
+
+            // Highlight the background of the line the cursor is on, underneath the buffer's text
+            if let Some(area) = current_line_area {
+                f.render_widget(Block::default().style(self.theme.current_line), area);
+            }
+
+            // Highlight the line the program being debugged has stopped on, over the current line
+            if let Some(area) = debug_line_area {
+                f.render_widget(Block::default().style(self.theme.debug_line), area);
+            }
+
+            // Highlight the search matches, underneath the selection and the buffer's text
+            for area in search_areas {
+                f.render_widget(Block::default().style(self.theme.search_match), area);
+            }
+
+            // Highlight the selection, underneath the buffer's text
+            for area in selection_areas {
+                f.render_widget(Block::default().style(self.theme.selection), area);
+            }
+
+            // Mark the syntax errors, which the buffer's text is drawn on top of
+            for area in error_areas {
+                f.render_widget(Block::default().style(self.theme.diagnostic), area);
+            }
+
+            // Draw the buffer, and the line numbers next to it
+            f.render_widget(block, text_area);
+            f.render_widget(gutter, gutter_area);
+
+            // Draw other users' cursors as colored blocks
+            for (area, color) in remote_cursors {
+                f.render_widget(Block::default().style(Style::default().bg(color)), area);
+            }
+
+            // Draw the extra cursors in reverse video
+            for area in extra_cursors {
+                f.render_widget(Block::default().style(self.theme.extra_cursor), area);
+            }
+
+            // Draw the scrollbar, with the thumb showing which part of the buffer is visible
+            if self.decorations && self.zen_width.is_none() {
+                f.render_widget(Block::default().style(self.theme.scrollbar), scrollbar_area);
+                f.render_widget(
+                    Block::default().style(self.theme.scrollbar_thumb),
+                    thumb_area,
+                );
+            }
+
+            // Draw the tab line above the text, if more than one buffer is open
+            if tab_area.height > 0 {
+                let tabs = tab_line(
+                    &self.tab_line,
+                    &self.tabs,
+                    self.current_tab,
+                    tab_area.width,
+                    &self.theme,
+                );
+                f.render_widget(tabs, tab_area);
+            }
+
+            // Draw the status bar, or only the current message in zen mode
+            match self.zen_width {
+                None => {
+                    let (status_left, status_right) = status_bar(
+                        &self.status_line,
+                        &status,
+                        &self.cursor_pos,
+                        buffer,
+                        &clock,
+                        &self.theme,
+                    );
+                    f.render_widget(status_left, status_area);
+                    f.render_widget(status_right, status_area);
+                }
+                Some(_) => {
+                    if let Some((message, level)) = status.message {
+                        let message =
+                            Span::styled(message.to_string(), message_style(level, &self.theme));
+                        f.render_widget(
+                            Paragraph::new(Spans::from(message)).alignment(Alignment::Center),
+                            status_area,
+                        );
+                    }
+                }
+            }
+
+            // Draw the tooltip where the mouse is resting, if one is shown
+            if let Some((x, y, text)) = &self.tooltip {
+                let area = tooltip_area(size, *x, *y, text);
+                f.render_widget(Clear, area);
+                f.render_widget(
+                    Paragraph::new(text.as_str()).block(popup_block(&self.theme)),
+                    area,
+                );
+            }
+
+            // Draw the panel on top of the buffer, if one is open
+            if let Some(panel) = panel {
+                let area = panel_area(text_area, panel.is_wide());
+                let items: Vec<ListItem> = panel
+                    .items()
+                    .iter()
+                    .map(|item| ListItem::new(item.as_str()))
+                    .collect();
+
+                let list = List::new(items)
+                    .block(popup_block(&self.theme).title(panel.title()))
+                    .highlight_style(self.theme.popup_selected);
+
+                let mut state = ListState::default();
+                state.select(Some(panel.selected()));
+
+                f.render_widget(Clear, area);
+                f.render_stateful_widget(list, area, &mut state);
+            }
+
+            // Draw the keys which can complete a key combination, if one is in progress
+            if let Some((title, keys)) = key_hints {
+                let width = keys.iter().map(|(key, _)| key.width()).max().unwrap_or(0);
+                let lines: Vec<String> = keys
+                    .iter()
+                    .map(|(key, action)| format!("{:<width$}  {}", key, action))
+                    .collect();
+
+                let area = key_hints_area(text_area, &lines);
+                let list = List::new(lines.into_iter().map(ListItem::new).collect::<Vec<_>>())
+                    .block(popup_block(&self.theme).title(title));
+
+                f.render_widget(Clear, area);
+                f.render_widget(list, area);
+            }
+
+            // Draw the performance overlay in the top right corner, if it is shown
+            if let Some(lines) = &self.overlay {
+                let area = overlay_area(text_area, lines);
+                let list = List::new(
+                    lines
+                        .iter()
+                        .map(|line| ListItem::new(line.as_str()))
+                        .collect::<Vec<_>>(),
+                )
+                .block(popup_block(&self.theme).title("Performance"));
+
+                f.render_widget(Clear, area);
+                f.render_widget(list, area);
+            }
+
+            // Draw the dialog on top of everything else, if one is open
+            // * The cursor is hidden by not being placed, since the dialog has no text to edit
+            if let Some(dialog) = dialog {
+                let buttons: Vec<Span> = dialog
+                    .labels()
+                    .enumerate()
+                    .flat_map(|(i, label)| {
+                        let style = match i == dialog.selected() {
+                            true => self.theme.popup_selected,
+                            false => Style::default(),
+                        };
+
+                        [Span::raw("  "), Span::styled(format!(" {} ", label), style)]
+                    })
+                    .collect();
+                let buttons = Spans::from(buttons);
+
+                let content_width = dialog.message().width().max(buttons.width());
+                let area = dialog_area(text_area, content_width as u16 + 4, 5);
+                let text = vec![
+                    Spans::from(dialog.message().to_string()),
+                    Spans::default(),
+                    buttons,
+                ];
+
+                f.render_widget(Clear, area);
+                f.render_widget(
+                    Paragraph::new(text)
+                        .alignment(Alignment::Center)
+                        .block(popup_block(&self.theme)),
+                    area,
+                );
+
+                return;
+            }
+
+            // Draw the prompt over the status bar, if one is open, and move the cursor into it
+            if let Some(prompt) = prompt {
+                // If the input is too long to fit, it is scrolled so that the cursor stays visible
+                let area = status_area;
+                let text = format!("{}{}", prompt.label(), prompt.input());
+                let cursor_x = (prompt.label().width() + prompt.cursor_x()) as u16;
+                let offset = (cursor_x + 1).saturating_sub(area.width);
+
+                f.render_widget(Clear, area);
+                f.render_widget(
+                    Paragraph::new(text)
+                        .style(self.theme.text)
+                        .scroll((0, offset)),
+                    area,
+                );
+                f.set_cursor(area.x + cursor_x - offset, area.y);
+
+                return;
+            }
 
             // Update the cursor
-            f.set_cursor(self.cursor_pos.x, self.cursor_pos.y)
+            f.set_cursor(text_area.x + cursor_x, text_area.y + cursor_y)
         })?;
 
+        // Images are drawn by the terminal on top of the text, so they are sent after the frame
+        let image_panel = panel.filter(|panel| panel.kind() == PanelKind::Image);
+        let image_area = image_panel.map(|panel| {
+            let area = panel_area(text_area, panel.is_wide());
+            let text_height = panel.items().len() as u16 + 1;
+
+            Rect {
+                x: area.x + 1,
+                y: area.y + 1 + text_height,
+                width: area.width.saturating_sub(2),
+                height: area.height.saturating_sub(2 + text_height),
+            }
+        });
+        self.draw_image(image_area)?;
+
         Ok(())
     }
 
     // Performs a cursor update
-    pub fn update_cursor(&mut self) {
+    // * The cursor is placed relative to the text area, which does not start at the left edge of the
+    // * screen when line numbers are shown or zen mode is on
+    pub fn update_cursor(&mut self, buffer: &Buffer) {
+        let (x, y) = self.screen_cursor(buffer);
+        let (text_area, _, _) = self.layout(
+            self.terminal.size().unwrap_or_default(),
+            buffer.line_count(),
+        );
+
         execute!(
             self.terminal.backend_mut(),
-            cursor::MoveTo(self.cursor_pos.x, self.cursor_pos.y)
+            cursor::MoveTo(text_area.x + x, text_area.y + y)
         )
         .expect("[INTERNAL ERROR] Failed to move cursor")
     }
 
+    // Updates the cached lines to account for changes made to the buffer
+    pub fn apply_line_changes(&mut self, changes: Vec<LineChange>, line_count: usize) {
+        for change in changes {
+            let start = change.start.min(self.line_cache.len());
+            let end = (change.start + change.removed).min(self.line_cache.len());
+
+            self.line_cache
+                .splice(start..end, std::iter::repeat_n(None, change.inserted));
+
+            // The lines after a change can start inside of something different, such as when a
+            // block comment is opened, so the worker looks at them again before they are drawn
+            let start = change.start.min(self.line_highlights.len());
+            let end = (change.start + change.removed).min(self.line_highlights.len());
+            self.line_highlights
+                .splice(start..end, std::iter::repeat_n(None, change.inserted));
+            self.highlighter.lines_changed(change.start);
+        }
+
+        // If the cache has somehow gotten out of sync with the buffer, rebuild it entirely
+        if self.line_cache.len() != line_count {
+            self.invalidate_lines();
+            self.line_cache.resize(line_count, None);
+        }
+
+        // The last lines are always rebuilt, because the final newline glyph might have moved
+        let last_lines = line_count.saturating_sub(2)..line_count;
+        for line in self.line_cache[last_lines].iter_mut() {
+            *line = None;
+        }
+
+        if let Some(table) = self.table.as_mut() {
+            table.mark_stale();
+        }
+    }
+
+    // Marks every cached line as needing to be rebuilt, such as when a display setting changes
+    // or the whole buffer is replaced
+    pub fn invalidate_lines(&mut self) {
+        self.line_cache.iter_mut().for_each(|line| *line = None);
+        self.line_highlights
+            .iter_mut()
+            .for_each(|line| *line = None);
+        self.highlighter.reset();
+    }
+
+    // Sets the styles that everything on the screen is drawn in
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        self.invalidate_lines();
+    }
+
+    // Gets the syntax that the text is highlighted with, if there is one
+    pub fn syntax(&self) -> Option<&'static Syntax> {
+        self.syntax
+    }
+
+    // Sets the syntax that the text is highlighted with, or turns highlighting off
+    pub fn set_syntax(&mut self, syntax: Option<&'static Syntax>) {
+        self.syntax = syntax;
+        self.invalidate_lines();
+    }
+
+    // Gets the grammar that the text is parsed with, if there is one
+    pub fn grammar(&self) -> Option<&'static Grammar> {
+        self.grammar
+    }
+
+    // Sets the grammar that the text is parsed with, which is highlighted from instead of the syntax
+    pub fn set_grammar(&mut self, grammar: Option<&'static Grammar>) {
+        self.grammar = grammar;
+        self.invalidate_lines();
+    }
+
+    // Passes on the edits made to the buffer to the highlighter, so that it can update the syntax tree
+    pub fn apply_syntax_edits(&mut self, edits: Vec<InputEdit>) {
+        if self.grammar.is_some() {
+            self.highlighter.edit(edits);
+        }
+    }
+
+    // Gets the syntax tree of the buffer as it is now, waiting for the highlighter to parse it
+    // Returns None if the text has no grammar, or if parsing it took too long
+    // ! The buffer's edits have to have been passed on first, or the tree cannot be brought up to date
+    pub fn syntax_tree(&mut self, buffer: &Buffer) -> Option<Tree> {
+        self.grammar?;
+
+        if let Some(tree) = self.highlighter.tree(buffer.version()) {
+            return Some(tree);
+        }
+
+        // The same lines as last time are asked for, since they are most likely still visible
+        let lines = self.highlighter.requested_lines().unwrap_or_default();
+        self.request_highlights(buffer, lines, SYNTAX_TREE_WAIT);
+        self.highlighter.tree(buffer.version())
+    }
+
+    // Asks the highlighter for the lines which are about to be drawn, and takes any that it has
+    // finished, waiting up to the given time for them
+    // * Waiting briefly means that edits are usually drawn highlighted straight away, while large
+    // * files are drawn as plain text instead of holding up the frame
+    fn request_highlights(&mut self, buffer: &Buffer, lines: Range<usize>, timeout: Duration) {
+        if self.syntax.is_none() && self.grammar.is_none() {
+            return;
+        }
+
+        self.line_highlights.resize(buffer.line_count(), None);
+        self.highlighter
+            .request(buffer, self.syntax, self.grammar, lines);
+        self.receive_highlights(timeout);
+    }
+
+    // Takes any lines the highlighter has finished, without blocking
+    // Returns whether any of them look different now, so that the frame can be redrawn
+    pub fn poll_highlights(&mut self) -> bool {
+        self.receive_highlights(Duration::ZERO)
+    }
+
+    // Checks whether the highlighter is still working on the lines that were last drawn
+    pub fn highlights_pending(&self) -> bool {
+        self.highlighter.is_pending()
+    }
+
+    // Stores the lines the highlighter has finished, rebuilding any whose highlights changed
+    fn receive_highlights(&mut self, timeout: Duration) -> bool {
+        let Some((start, lines)) = self.highlighter.receive(timeout) else {
+            return false;
+        };
+
+        let mut changed = false;
+        for (line, highlights) in (start..).zip(lines) {
+            let Some(cached) = self.line_highlights.get_mut(line) else {
+                break;
+            };
+
+            if cached.as_ref() != Some(&highlights) {
+                *cached = Some(highlights);
+                if let Some(built) = self.line_cache.get_mut(line) {
+                    *built = None;
+                }
+                changed = true;
+            }
+        }
+
+        // A new syntax tree can have different syntax errors, even if the highlights are the same
+        changed || self.grammar.is_some()
+    }
+
+    // Gets the range of lines which could be visible in a text area of the given height
+    // * Every line is assumed to take up a single row, so wrapped lines can make this too many
+    fn visible_lines(&self, line_count: usize, height: usize) -> Range<usize> {
+        let mut line = self.scroll_y;
+        let mut rows = 0;
+
+        while line < line_count && rows < height {
+            rows += 1;
+            line += 1;
+            if let Some(fold) = self.hidden_range(line) {
+                line = fold.end;
+            }
+        }
+
+        self.scroll_y..line.min(line_count)
+    }
+
+    // Scrolls the text area so that the cursor is visible, along with the scrolloff lines around it
+    // * Distances are counted in rows rather than lines, because folded lines take up no rows and
+    // * wrapped lines take up several
+    // ! The view always starts at the beginning of a line, so the cursor can end up below it in a
+    // ! wrapped line which is taller than the whole text area
+    fn scroll_to_cursor(&mut self, buffer: &Buffer, height: usize) {
+        if height == 0 {
+            return;
+        }
+
+        let line_count = buffer.line_count();
+        let cursor_y = self.cursor_pos.y as usize;
+        let cursor_row = self.cursor_row(buffer);
+        // The scrolloff is limited so that the cursor can still reach every line of the text area
+        let scrolloff = self.scrolloff.min((height - 1) / 2);
+
+        // The view should never start in the middle of a fold
+        self.scroll_y = self.line_above(buffer, self.scroll_y.min(line_count.saturating_sub(1)), 0);
+
+        // A cursor more lines below the view than it has rows is below it however the lines wrap
+        let above = cursor_y < self.scroll_y;
+        let rows = match !above && self.lines_between(self.scroll_y, cursor_y) < height {
+            true => self.rows_between(buffer, self.scroll_y, cursor_y) + cursor_row,
+            false => height,
+        };
+
+        if above || rows < scrolloff {
+            self.scroll_y = self.line_above(buffer, cursor_y, scrolloff.saturating_sub(cursor_row));
+        } else if rows + scrolloff >= height {
+            // Never scroll further than needed to show the last line of the buffer
+            let last_line = line_count.saturating_sub(1);
+            let last_rows = self.line_rows(buffer, last_line);
+            let last_scroll_y =
+                self.line_above(buffer, last_line, height.saturating_sub(last_rows));
+            let scroll_y = self
+                .line_above(
+                    buffer,
+                    cursor_y,
+                    (height - 1 - scrolloff).saturating_sub(cursor_row),
+                )
+                .min(last_scroll_y);
+            self.scroll_y = self.scroll_y.max(scroll_y);
+        }
+    }
+
+    // Scrolls the text area so that the cursor's line is at the given position, without moving the cursor
+    // * The scrolloff still applies, so the line may not end up exactly at the top or bottom
+    pub fn recenter(&mut self, buffer: &Buffer, position: ViewPosition) -> Result<()> {
+        let (text_area, _, _) = self.layout(self.terminal.size()?, buffer.line_count());
+        let height = text_area.height as usize;
+        let cursor_y = self.cursor_pos.y as usize;
+        let cursor_row = self.cursor_row(buffer);
+
+        self.scroll_y = match position {
+            ViewPosition::Center => {
+                self.line_above(buffer, cursor_y, (height / 2).saturating_sub(cursor_row))
+            }
+            ViewPosition::Top => cursor_y,
+            ViewPosition::Bottom => {
+                self.line_above(buffer, cursor_y, height.saturating_sub(1 + cursor_row))
+            }
+        };
+
+        Ok(())
+    }
+
+    // Gets the styled text of the lines visible in the text area, building any that are not cached
+    // Lines hidden by folds are skipped, and the first line of each fold shows how many lines it hides
+    // Wrapped lines are split into one line of text for each of their rows
+    fn visible_text(&mut self, buffer: &Buffer, height: usize) -> Text<'static> {
+        let line_count = buffer.line_count();
+        self.line_cache.resize(line_count, None);
+
+        // If an edit changed the width of a column, every line of the table has to be padded again
+        if self
+            .table
+            .as_mut()
+            .is_some_and(|table| table.refresh(buffer))
+        {
+            self.invalidate_lines();
+        }
+
+        let visible_lines = self.visible_lines(line_count, height);
+        self.request_highlights(buffer, visible_lines, HIGHLIGHT_WAIT);
+
+        let mut lines = Vec::with_capacity(height);
+        let mut line = self.scroll_y;
+
+        while line < line_count && lines.len() < height {
+            if self.line_cache[line].is_none() {
+                self.line_cache[line] = Some(match &self.table {
+                    Some(table) => table.build_line(buffer, line),
+                    None => build_line(
+                        buffer,
+                        line,
+                        self.show_invisibles,
+                        self.line_highlights
+                            .get(line)
+                            .and_then(Option::as_deref)
+                            .unwrap_or_default(),
+                        &self.theme,
+                    ),
+                });
+            }
+
+            let mut spans = self.line_cache[line]
+                .clone()
+                .expect("[INTERNAL ERROR] Line was not built");
+            let points = self.wrap_points(buffer, line);
+            line += 1;
+
+            if let Some(fold) = self.hidden_range(line) {
+                let marker = format!(" ⋯ {} lines", fold.len());
+                line = fold.end;
+
+                spans.0.push(Span::styled(marker, self.theme.fold));
+            }
+
+            match points.len() {
+                1 => lines.push(spans),
+                _ => lines.extend(wrap::split_rows(spans, &points)),
+            }
+        }
+
+        lines.truncate(height);
+        Text::from(lines)
+    }
+
+    // Gets the line numbers shown next to the visible lines, with the same number of rows as their text
+    // Wrapped lines only have their number on their first row, and the same goes for their breakpoint
+    fn gutter_text(&self, buffer: &Buffer, height: usize) -> Text<'static> {
+        let breakpoint_column = self.shows_breakpoints();
+        if self.line_numbers.is_none() && !breakpoint_column {
+            return Text::default();
+        }
+
+        let line_count = buffer.line_count();
+        let cursor_line = self.cursor_pos.line();
+        let width = line_count.to_string().len();
+
+        let mut rows = Vec::with_capacity(height);
+        let mut line = self.scroll_y;
+
+        while line < line_count && rows.len() < height {
+            let mut spans = Vec::new();
+            if breakpoint_column {
+                spans.push(match line {
+                    _ if self.debug_line == Some(line) => {
+                        Span::styled(DEBUG_LINE_MARKER, self.theme.breakpoint)
+                    }
+                    _ if self.breakpoints.binary_search(&line).is_ok() => {
+                        Span::styled(BREAKPOINT_MARKER, self.theme.breakpoint)
+                    }
+                    _ => Span::raw("  "),
+                });
+            }
+
+            // Lines hidden by folds are not counted, since moving the cursor skips over them
+            let number = self.line_numbers.map(|line_numbers| match line_numbers {
+                LineNumbers::Relative if line < cursor_line => (
+                    self.lines_between(line, cursor_line),
+                    self.theme.line_number,
+                ),
+                LineNumbers::Relative if line > cursor_line => (
+                    self.lines_between(cursor_line, line),
+                    self.theme.line_number,
+                ),
+                _ if line == cursor_line => (line + 1, self.theme.current_line_number),
+                _ => (line + 1, self.theme.line_number),
+            });
+            if let Some((number, style)) = number {
+                spans.push(Span::styled(
+                    format!("{:>width$} ", number, width = width),
+                    style,
+                ));
+            }
+
+            rows.push(Spans::from(spans));
+            rows.extend((1..self.line_rows(buffer, line)).map(|_| Spans::default()));
+
+            line += 1;
+            if let Some(fold) = self.hidden_range(line) {
+                line = fold.end;
+            }
+        }
+
+        rows.truncate(height);
+        Text::from(rows)
+    }
+
+    // Gets the position of the cursor relative to the text area
+    fn screen_cursor(&self, buffer: &Buffer) -> (u16, u16) {
+        let cursor_y = self.cursor_pos.y as usize;
+        let (x, _) = self
+            .screen_coord(buffer, self.cursor_pos.index())
+            .unwrap_or((self.cursor_pos.x, 0));
+
+        let points = self.wrap_points(buffer, cursor_y);
+        let row = wrap::row_at(&points, x as usize);
+        let y = match cursor_y >= self.scroll_y {
+            true => self.rows_between(buffer, self.scroll_y, cursor_y) + row,
+            false => 0,
+        };
+
+        (x - points[row] as u16, y as u16)
+    }
+
+    // Gets the display column and line of a buffer index, accounting for the table view's padding
+    fn screen_coord(&self, buffer: &Buffer, index: usize) -> Option<(u16, u16)> {
+        let (x, line) = buffer.cursor_coord(index)?;
+
+        match &self.table {
+            Some(table) => {
+                let column = index - buffer.line_start(line as usize);
+                let contents = buffer.line_contents(line as usize);
+
+                Some((table.screen_column(&contents, column) as u16, line))
+            }
+            None => Some((x, line)),
+        }
+    }
+
+    // Gets the areas of the text area covered by other users' cursors, along with their colors
+    // Cursors which are scrolled out of view or hidden by folds are left out
+    fn screen_remote_cursors(&self, buffer: &Buffer, text_area: Rect) -> Vec<(Rect, Color)> {
+        self.remote_cursors
+            .iter()
+            .filter_map(|&(site, index)| {
+                let area = self.screen_cell(buffer, index, text_area)?;
+                let colors = &self.theme.remote_cursors;
+                let color = colors[site as usize % colors.len()];
+
+                Some((area, color))
+            })
+            .collect()
+    }
+
+    // Gets the area of the text area covered by a buffer index, if it is in view and not in a fold
+    fn screen_cell(&self, buffer: &Buffer, index: usize, text_area: Rect) -> Option<Rect> {
+        let (x, line) = self.screen_coord(buffer, index)?;
+        let points = self.wrap_points(buffer, line as usize);
+        let row = wrap::row_at(&points, x as usize);
+        let y = self.screen_row(buffer, line as usize, text_area.height)? + row as u16;
+        let x = x - points[row] as u16;
+        if x >= text_area.width || y >= text_area.height {
+            return None;
+        }
+
+        Some(Rect {
+            x: text_area.x + x,
+            y: text_area.y + y,
+            width: 1,
+            height: 1,
+        })
+    }
+
+    // Gets the area of the text area taken up by the line the cursor is on, which is every row of the
+    // line if it is wrapped, or None if the line is not visible
+    fn screen_current_line(&self, buffer: &Buffer, text_area: Rect) -> Option<Rect> {
+        self.screen_line(buffer, self.cursor_pos.line(), text_area)
+    }
+
+    // Gets the area of the text area taken up by every row of a line, or None if it is not visible
+    fn screen_line(&self, buffer: &Buffer, line: usize, text_area: Rect) -> Option<Rect> {
+        let y = self.screen_row(buffer, line, text_area.height)?;
+        let rows = (self.line_rows(buffer, line) as u16).min(text_area.height - y);
+
+        Some(Rect {
+            y: text_area.y + y,
+            height: rows,
+            ..text_area
+        })
+    }
+
+    // Gets the areas of the text area covered by the selection, with one area for each visible line
+    fn screen_selection(&self, buffer: &Buffer, text_area: Rect) -> Vec<Rect> {
+        match self.selection.clone() {
+            Some(selection) => self.screen_range(buffer, selection, text_area),
+            None => Vec::new(),
+        }
+    }
+
+    // Gets the areas of the text area covered by the search matches which are in view
+    // * Only the matches between the first and last visible lines are looked at, since a search can
+    // * match far more text than fits on the screen
+    fn screen_search_matches(&self, buffer: &Buffer, text_area: Rect) -> Vec<Rect> {
+        let line_count = buffer.line_count();
+        if self.search_matches.is_empty() || self.scroll_y >= line_count {
+            return Vec::new();
+        }
+
+        let view_start = buffer.line_start(self.scroll_y);
+        // A line which is only partly in view has its matches looked at too
+        let (line, row) = self.line_at_row(buffer, text_area.height as usize);
+        let view_end = match line + (row > 0) as usize {
+            line if line < line_count => buffer.line_start(line),
+            _ => buffer.size(),
+        };
+
+        let first = self
+            .search_matches
+            .partition_point(|range| range.end <= view_start);
+        self.search_matches[first..]
+            .iter()
+            .take_while(|range| range.start < view_end)
+            .flat_map(|range| self.screen_range(buffer, range.clone(), text_area))
+            .collect()
+    }
+
+    // Gets the areas of the text area covered by the syntax errors in the buffer, once the highlighter
+    // has parsed it
+    // * Errors from an older version of the buffer are not drawn, since their ranges may have moved
+    fn screen_syntax_errors(&mut self, buffer: &Buffer, text_area: Rect) -> Vec<Rect> {
+        let version = buffer.version();
+        if self.grammar.is_none() {
+            self.syntax_errors = None;
+        } else if self.syntax_errors.as_ref().map(|(parsed, _)| *parsed) != Some(version) {
+            self.syntax_errors = self
+                .highlighter
+                .tree(version)
+                .map(|tree| (version, tree_errors(buffer, &tree)));
+        }
+
+        let Some((_, errors)) = &self.syntax_errors else {
+            return Vec::new();
+        };
+        errors
+            .iter()
+            .flat_map(|(range, _)| self.screen_range(buffer, range.clone(), text_area))
+            .collect()
+    }
+
+    // Gets the message of the syntax error at a buffer index, if there is one and it has been drawn
+    pub fn syntax_error_at(&self, buffer: &Buffer, index: usize) -> Option<&str> {
+        let (version, errors) = self.syntax_errors.as_ref()?;
+        if *version != buffer.version() {
+            return None;
+        }
+
+        errors
+            .iter()
+            .find(|(range, _)| range.contains(&index))
+            .map(|(_, message)| message.as_str())
+    }
+
+    // Shows a tooltip next to a position on the screen, replacing any which is already shown
+    pub fn show_tooltip(&mut self, x: u16, y: u16, text: String) {
+        self.tooltip = Some((x, y, text));
+    }
+
+    // Hides the tooltip, returning whether one was shown
+    pub fn hide_tooltip(&mut self) -> bool {
+        self.tooltip.take().is_some()
+    }
+
+    // Gets the areas of the text area covered by a range of the buffer, with one area for each
+    // visible row
+    // The newline at the end of each line is shown as one extra covered column
+    fn screen_range(&self, buffer: &Buffer, range: Range<usize>, text_area: Rect) -> Vec<Rect> {
+        let (Some((start_x, start_line)), Some((end_x, end_line))) = (
+            self.screen_coord(buffer, range.start),
+            self.screen_coord(buffer, range.end),
+        ) else {
+            return Vec::new();
+        };
+
+        // Lines above the view are skipped, because a range can cover most of a large buffer
+        (start_line.max(self.scroll_y as u16)..=end_line)
+            .flat_map(|line| {
+                let Some(first_row) = self.screen_row(buffer, line as usize, text_area.height)
+                else {
+                    return Vec::new();
+                };
+
+                let x = if line == start_line { start_x } else { 0 } as usize;
+                let end = match line == end_line {
+                    true => end_x as usize,
+                    false => {
+                        let line = line as usize;
+                        let line_end =
+                            buffer.line_start(line) + buffer.line_contents(line).chars().count();
+                        self.screen_coord(buffer, line_end).map_or(0, |(x, _)| x) as usize + 1
+                    }
+                };
+
+                // Each row covers the part of the range between its first column and the next row's
+                let points = self.wrap_points(buffer, line as usize);
+                (0..points.len())
+                    .filter_map(|row| {
+                        let row_start = points[row];
+                        let row_end = points.get(row + 1).copied().unwrap_or(usize::MAX);
+                        let y = first_row + row as u16;
+                        if y >= text_area.height || x.max(row_start) >= end.min(row_end) {
+                            return None;
+                        }
+
+                        let width = text_area.width as usize;
+                        let x = (x.max(row_start) - row_start).min(width);
+                        let end = (end.min(row_end) - row_start).min(width);
+                        Some(Rect {
+                            x: text_area.x + x as u16,
+                            y: text_area.y + y,
+                            width: (end - x) as u16,
+                            height: 1,
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Gets the buffer index displayed at a position on the screen, such as where the mouse was clicked
+    // Positions past the end of a line give the end of that line, and positions outside the text
+    // area or below the end of the buffer give None
+    pub fn buffer_index_at(&self, buffer: &Buffer, x: u16, y: u16) -> Option<usize> {
+        let (text_area, _, _) = self.layout(self.terminal.size().ok()?, buffer.line_count());
+        if x < text_area.x
+            || x >= text_area.x + text_area.width
+            || y < text_area.y
+            || y >= text_area.y + text_area.height
+        {
+            return None;
+        }
+
+        let (line, row) = self.line_at_row(buffer, (y - text_area.y) as usize);
+
+        if line >= buffer.line_count() {
+            return None;
+        }
+
+        // Positions past the end of a row which continues onto the next one give its last column
+        let points = self.wrap_points(buffer, line);
+        let x = (x - text_area.x) as usize + points[row];
+        let x = match points.get(row + 1) {
+            Some(&next) => x.min(next - 1),
+            None => x,
+        };
+        let column = match &self.table {
+            Some(table) => {
+                let contents = buffer.line_contents(line);
+                let len = contents.chars().count();
+                (0..len)
+                    .find(|&column| table.screen_column(&contents, column + 1) > x)
+                    .unwrap_or(len)
+            }
+            None => buffer.char_column(line, x),
+        };
+
+        Some(buffer.line_start(line) + column)
+    }
+
+    // Gets the line whose number or breakpoint is displayed at a position on the screen, if the
+    // position is in the gutter to the left of the text area
+    pub fn gutter_line_at(&self, buffer: &Buffer, x: u16, y: u16) -> Option<usize> {
+        let line_count = buffer.line_count();
+        let (text_area, _, _) = self.layout(self.terminal.size().ok()?, line_count);
+        let gutter_width = self.gutter_width(line_count).min(text_area.x);
+        if x < text_area.x - gutter_width
+            || x >= text_area.x
+            || y < text_area.y
+            || y >= text_area.y + text_area.height
+        {
+            return None;
+        }
+
+        let (line, _) = self.line_at_row(buffer, (y - text_area.y) as usize);
+        (line < line_count).then_some(line)
+    }
+
+    // Gets the row of the text area that a line is displayed on, if it is visible
+    // If the line is wrapped, this is the row of its first part
+    fn screen_row(&self, buffer: &Buffer, line: usize, height: u16) -> Option<u16> {
+        if line < self.scroll_y || self.hidden_range(line).is_some() {
+            return None;
+        }
+
+        // Every line takes up at least one row, so lines far below the view are ruled out without
+        // wrapping the lines in between
+        if self.lines_between(self.scroll_y, line) >= height as usize {
+            return None;
+        }
+
+        let row = self.rows_between(buffer, self.scroll_y, line);
+        (row < height as usize).then_some(row as u16)
+    }
+
+    // Sets the ranges of buffer indices matched by the current search, which are highlighted
+    pub fn set_search_matches(&mut self, matches: Rc<[Range<usize>]>) {
+        self.search_matches = matches;
+    }
+
+    // Sets the range of buffer indices which is selected, or clears the selection
+    pub fn set_selection(&mut self, selection: Option<Range<usize>>) {
+        self.selection = selection;
+    }
+
+    // Puts text on the system clipboard by asking the terminal to do it
+    pub fn copy_to_clipboard(&mut self, text: &str) -> Result<()> {
+        let backend = self.terminal.backend_mut();
+        backend.write_all(osc52_sequence(text).as_bytes())?;
+        Write::flush(backend)
+    }
+
+    // Sets the PNG image shown in the image preview panel, removing any image already shown
+    pub fn set_image(&mut self, image: Option<Vec<u8>>) -> Result<()> {
+        if self.image_drawn {
+            self.draw_image(None)?;
+        }
+
+        self.image = image;
+
+        Ok(())
+    }
+
+    // Draws the image in the given area if it has not been drawn yet,
+    // or removes it from the screen if there is no longer an area to draw it in
+    fn draw_image(&mut self, area: Option<Rect>) -> Result<()> {
+        let backend = self.terminal.backend_mut();
+
+        match (area, &self.image) {
+            (Some(area), Some(png)) if !self.image_drawn && area.width > 0 && area.height > 0 => {
+                let Some(info) = image_info(png) else {
+                    return Ok(());
+                };
+
+                let (columns, rows) = images::fit(&info, area.width, area.height);
+                execute!(backend, cursor::MoveTo(area.x, area.y))?;
+                backend.write_all(images::kitty_sequence(png, columns, rows).as_bytes())?;
+                self.image_drawn = true;
+            }
+            (None, _) if self.image_drawn => {
+                backend.write_all(KITTY_DELETE_ALL.as_bytes())?;
+                self.image_drawn = false;
+            }
+            _ => return Ok(()),
+        }
+
+        Write::flush(backend)
+    }
+
+    // Shows the cursor as an underline in overwrite mode, and in the chosen style (or the terminal's
+    // default shape, if there is none) otherwise
+    // * This uses the DECSCUSR sequence directly, which most terminals support
+    pub fn set_cursor_shape(&mut self, overwrite: bool) -> Result<()> {
+        let shape = match (overwrite, self.cursor_style) {
+            (true, style) => CursorStyle {
+                shape: CursorShape::Underline,
+                blink: style.is_some_and(|style| style.blink),
+            }
+            .code(),
+            (false, Some(style)) => style.code(),
+            (false, None) => 0,
+        };
+        let backend = self.terminal.backend_mut();
+        write!(backend, "\x1b[{} q", shape)?;
+        Write::flush(backend)
+    }
+
+    // Sets the buffer indices of the cursors added besides the main one
+    pub fn set_extra_cursors(&mut self, cursors: Vec<usize>) {
+        self.extra_cursors = cursors;
+    }
+
+    // Sets the buffer indices of other users' cursors, along with their site numbers
+    pub fn set_remote_cursors(&mut self, cursors: Vec<(u32, usize)>) {
+        self.remote_cursors = cursors;
+    }
+
+    // Sets the lines with breakpoints, which are marked in the gutter
+    pub fn set_breakpoints(&mut self, mut lines: Vec<usize>) {
+        lines.sort_unstable();
+        lines.dedup();
+        self.breakpoints = lines;
+    }
+
+    // Sets the line the program being debugged has stopped on, which is marked and highlighted
+    pub fn set_debug_line(&mut self, line: Option<usize>) {
+        self.debug_line = line;
+    }
+
+    // Sets the ranges of lines which are hidden by folds
+    pub fn set_hidden_lines(&mut self, hidden_lines: Vec<Range<usize>>) {
+        self.hidden_lines = hidden_lines;
+    }
+
+    // Gets the range of hidden lines that the given line is in, if it is hidden
+    fn hidden_range(&self, line: usize) -> Option<Range<usize>> {
+        self.hidden_lines
+            .iter()
+            .find(|range| range.contains(&line))
+            .cloned()
+    }
+
+    // Gets the number of lines shown from `start` up to (but not including) `end`, which leaves out
+    // the lines hidden by folds
+    fn lines_between(&self, start: usize, end: usize) -> usize {
+        let hidden: usize = self
+            .hidden_lines
+            .iter()
+            .map(|range| range.end.min(end).saturating_sub(range.start.max(start)))
+            .sum();
+
+        end.saturating_sub(start) - hidden
+    }
+
+    // Gets the number of rows taken up by the lines from `start` up to (but not including) `end`
+    // ! With soft wrap on, every line in between is wrapped, so this should only be used near the view
+    fn rows_between(&self, buffer: &Buffer, start: usize, end: usize) -> usize {
+        if !self.wraps_lines() {
+            return self.lines_between(start, end);
+        }
+
+        (start..end)
+            .filter(|&line| self.hidden_range(line).is_none())
+            .map(|line| self.line_rows(buffer, line))
+            .sum()
+    }
+
+    // Gets the line which is displayed the given number of rows below the first line in view, along
+    // with which of its rows that is, and the line may be past the end of the buffer
+    // * This walks down the same way the visible lines are built, skipping over the lines in folds
+    fn line_at_row(&self, buffer: &Buffer, mut rows: usize) -> (usize, usize) {
+        let mut line = self.scroll_y;
+
+        loop {
+            let line_rows = match line < buffer.line_count() {
+                true => self.line_rows(buffer, line),
+                false => 1,
+            };
+            if rows < line_rows {
+                return (line, rows);
+            }
+
+            rows -= line_rows;
+            line += 1;
+            if let Some(fold) = self.hidden_range(line) {
+                line = fold.end;
+            }
+        }
+    }
+
+    // Gets the furthest line above a line which can be displayed with at most the given number of
+    // rows between the two
+    // If the line is hidden, the rows are counted from the first line of its fold
+    fn line_above(&self, buffer: &Buffer, mut line: usize, mut rows: usize) -> usize {
+        if let Some(range) = self.hidden_range(line) {
+            line = range.start - 1;
+        }
+
+        while line > 0 {
+            let mut above = line - 1;
+            if let Some(range) = self.hidden_range(above) {
+                above = range.start - 1;
+            }
+
+            let line_rows = self.line_rows(buffer, above);
+            if line_rows > rows {
+                break;
+            }
+
+            rows -= line_rows;
+            line = above;
+        }
+
+        line
+    }
+
+    // Splits the screen into the text area, scrollbar, and status bar, leaving space for the line
+    // numbers to the left of the text area if they are shown
+    fn layout(&self, size: Rect, line_count: usize) -> (Rect, Rect, Rect) {
+        layout(
+            size,
+            self.decorations,
+            self.zen_width,
+            self.gutter_width(line_count),
+            self.tabs.len() > 1,
+        )
+    }
+
+    // Gets the number of columns taken up by the breakpoints and line numbers, including the space
+    // after them
+    // * Every line number is padded to the width of the largest one, so the text does not shift
+    // * sideways while scrolling
+    fn gutter_width(&self, line_count: usize) -> u16 {
+        let numbers = match self.line_numbers {
+            Some(_) => line_count.to_string().len() as u16 + 1,
+            None => 0,
+        };
+        let breakpoints = match self.shows_breakpoints() {
+            true => BREAKPOINT_MARKER.chars().count() as u16,
+            false => 0,
+        };
+
+        numbers + breakpoints
+    }
+
+    // Checks whether the column of breakpoints is shown, which it only is while there are some or
+    // the program being debugged has stopped, so that it does not take up space the rest of the time
+    fn shows_breakpoints(&self) -> bool {
+        !self.breakpoints.is_empty() || self.debug_line.is_some()
+    }
+
+    // Checks whether lines are currently being wrapped, which they never are in the table view
+    fn wraps_lines(&self) -> bool {
+        self.soft_wrap && self.table.is_none()
+    }
+
+    // Gets the display columns that each row of a line starts at, which is only the first column
+    // unless the line is wrapped
+    fn wrap_points(&self, buffer: &Buffer, line: usize) -> Vec<usize> {
+        match self.wraps_lines() {
+            true => wrap::wrap_points(buffer, line, self.wrap_width),
+            false => vec![0],
+        }
+    }
+
+    // Gets the number of rows that a line takes up
+    fn line_rows(&self, buffer: &Buffer, line: usize) -> usize {
+        self.wrap_points(buffer, line).len()
+    }
+
+    // Gets which row of its line the cursor is on
+    fn cursor_row(&self, buffer: &Buffer) -> usize {
+        let points = self.wrap_points(buffer, self.cursor_pos.line());
+        wrap::row_at(&points, self.cursor_pos.column())
+    }
+
+    // Moves the cursor to the row below or above it, which is in the same line if the line is wrapped
+    // The cursor keeps its column within the row while it is moved repeatedly, even through rows
+    // which are too short for it
+    pub fn move_cursor_row(&mut self, buffer: &Buffer, down: bool) {
+        let line = self.cursor_pos.line();
+        let points = self.wrap_points(buffer, line);
+        let x = self.cursor_pos.column();
+        let row = wrap::row_at(&points, x);
+        let goal = match self.row_goal {
+            Some((goal, index)) if index == self.cursor_pos.index() => goal,
+            _ => x - points[row],
+        };
+
+        let (line, points, row) = match down {
+            true if row + 1 < points.len() => (line, points, row + 1),
+            false if row > 0 => (line, points, row - 1),
+            true => {
+                let mut below = line + 1;
+                if let Some(fold) = self.hidden_range(below) {
+                    below = fold.end;
+                }
+
+                // If the cursor is on the last row of the buffer, do nothing
+                if below >= buffer.line_count() {
+                    return;
+                }
+
+                (below, self.wrap_points(buffer, below), 0)
+            }
+            false => {
+                // If the cursor is on the first row of the buffer, do nothing
+                if line == 0 {
+                    return;
+                }
+
+                let mut above = line - 1;
+                if let Some(range) = self.hidden_range(above) {
+                    above = range.start - 1;
+                }
+
+                let points = self.wrap_points(buffer, above);
+                let row = points.len() - 1;
+                (above, points, row)
+            }
+        };
+
+        // The goal is kept on the row, rather than going past its end onto the next one
+        let row_end = points.get(row + 1).map_or(usize::MAX, |&next| next - 1);
+        let column = buffer.char_column(line, (points[row] + goal).min(row_end));
+        self.cursor_pos
+            .move_to(buffer.line_start(line) + column, buffer);
+        self.row_goal = Some((goal, self.cursor_pos.index()));
+    }
+
+    // Toggles whether whitespace and the final newline are drawn as visible glyphs
+    pub fn toggle_invisibles(&mut self) {
+        self.show_invisibles = !self.show_invisibles;
+        self.invalidate_lines();
+    }
+
+    // Turns the table view on or off
+    pub fn set_table(&mut self, table: Option<Table>) {
+        self.table = table;
+        self.invalidate_lines();
+    }
+
+    // Returns a reference to the table view, if it is turned on
+    pub fn table(&self) -> Option<&Table> {
+        self.table.as_ref()
+    }
+
+    // Toggles whether the first line of the table view is drawn as a header
+    pub fn toggle_table_header(&mut self) {
+        if let Some(table) = self.table.as_mut() {
+            table.toggle_header();
+            self.invalidate_lines();
+        }
+    }
+
+    // Sets whether long lines are wrapped onto several rows
+    pub fn set_soft_wrap(&mut self, soft_wrap: bool) {
+        self.soft_wrap = soft_wrap;
+    }
+
+    // Toggles whether long lines are wrapped onto several rows
+    pub fn toggle_soft_wrap(&mut self) {
+        self.soft_wrap = !self.soft_wrap;
+    }
+
+    // Checks whether soft wrap is turned on, even if the table view is stopping lines from wrapping
+    pub fn soft_wrap(&self) -> bool {
+        self.soft_wrap
+    }
+
+    // Sets how the line numbers to the left of the text are counted, or hides them
+    pub fn set_line_numbers(&mut self, line_numbers: Option<LineNumbers>) {
+        self.line_numbers = line_numbers;
+    }
+
+    // Sets whether the line the cursor is on is highlighted
+    pub fn set_current_line_highlight(&mut self, enabled: bool) {
+        self.highlight_current_line = enabled;
+    }
+
+    // Toggles whether the line the cursor is on is highlighted
+    pub fn toggle_current_line_highlight(&mut self) {
+        self.highlight_current_line = !self.highlight_current_line;
+    }
+
+    // Sets the column that the ruler is drawn at, or disables it
+    // The column is given starting from one, so a ruler at column 80 marks the 80th character
+    pub fn set_ruler(&mut self, column: Option<u16>) {
+        self.ruler = column.filter(|&column| column > 0).map(|column| column - 1);
+    }
+
+    // Sets which segments the status bar shows
+    pub fn set_status_line(&mut self, status_line: StatusLine) {
+        self.status_line = status_line;
+    }
+
+    // Sets the segments shown in each buffer's entry in the tab line
+    pub fn set_tab_line(&mut self, tab_line: TabLine) {
+        self.tab_line = tab_line;
+    }
+
+    // Sets the open buffers shown in the tab line, and which of them is being edited
+    pub fn set_tabs(&mut self, tabs: Vec<Tab>, current: usize) {
+        self.tabs = tabs;
+        self.current_tab = current;
+    }
+
+    // Checks whether the status bar shows a clock which has changed since it was last drawn
+    pub fn clock_outdated(&self) -> bool {
+        self.drawn_clock
+            .as_ref()
+            .is_some_and(|drawn| *drawn != DateTime::now().format(CLOCK_FORMAT))
+    }
+
+    // Turns zen mode on with the given text column width, or turns it off
+    pub fn set_zen_width(&mut self, width: Option<u16>) {
+        self.zen_width = width;
+    }
+
+    // Returns the width of the text column in zen mode, if zen mode is on
+    pub fn zen_width(&self) -> Option<u16> {
+        self.zen_width
+    }
+
+    // Sets the lines shown in the performance overlay, or hides it
+    pub fn set_overlay(&mut self, overlay: Option<Vec<String>>) {
+        self.overlay = overlay;
+    }
+
+    // Gets the number of lines whose styled text is currently cached, for the performance overlay
+    pub fn cached_lines(&self) -> usize {
+        self.line_cache.iter().filter(|line| line.is_some()).count()
+    }
+
+    // Turns the screen reader mode on or off, which hides the ruler, scrollbar,
+    // and current line highlight so that only the text and status bar are drawn
+    pub fn set_screen_reader(&mut self, enabled: bool) {
+        self.decorations = !enabled;
+        if enabled {
+            self.highlight_current_line = false;
+            self.ruler = None;
+        }
+    }
+
+    // Sets the minimum number of lines kept visible above and below the cursor
+    pub fn set_scrolloff(&mut self, lines: usize) {
+        self.scrolloff = lines;
+    }
+
+    // Sets the style of the cursor, which is applied when the terminal is opened
+    pub fn set_cursor_style(&mut self, style: Option<CursorStyle>) {
+        self.cursor_style = style;
+    }
+
+    // Shows another buffer's view in place of the current one, which is kept in its place
+    // * The buffer is about to be replaced, so every line is rebuilt and the text is parsed again
+    pub fn swap_view(&mut self, view: &mut BufferView) {
+        std::mem::swap(&mut self.cursor_pos, &mut view.cursor_pos);
+        std::mem::swap(&mut self.scroll_y, &mut view.scroll_y);
+        std::mem::swap(&mut self.table, &mut view.table);
+        std::mem::swap(&mut self.syntax, &mut view.syntax);
+        std::mem::swap(&mut self.grammar, &mut view.grammar);
+        self.syntax_errors = None;
+
+        self.row_goal = None;
+        self.hidden_lines.clear();
+        self.invalidate_lines();
+    }
+
     // Returns a reference to the terminal's cursor
     pub fn cursor(&self) -> &CursorPosition {
         &self.cursor_pos
@@ -87,98 +1729,512 @@ impl Terminal {
     }
 }
 
+// Builds the text of a line of the buffer, styled for display
+// Tabs are expanded to the next tab stop, and if show_invisibles is enabled,
+// whitespace is replaced with faint glyphs
+// The highlights are the byte ranges of the line to style for syntax highlighting, in order
+// [EXAMPLE] "\tlet x = 1;  \n" becomes "→   let·x·=·1;··¬", with the trailing spaces highlighted
+pub fn build_line(
+    buffer: &Buffer,
+    line: usize,
+    show_invisibles: bool,
+    highlights: &[(Range<usize>, Highlight)],
+    theme: &Theme,
+) -> Spans<'static> {
+    let invisible_style = theme.invisible;
+    let trailing_style = theme.trailing_whitespace;
+
+    let contents = buffer.line_contents(line);
+    let contents = contents.trim_end_matches('\r');
+    let trailing_start = contents.trim_end().len();
+
+    // The line is built one grapheme at a time so that combining characters always stay in
+    // the same span as the character they modify, which lets them be drawn on top of it
+    let mut spans = Vec::new();
+    let mut display_column = 0;
+    let mut highlights = highlights.iter().peekable();
+    for (byte_index, grapheme) in contents.grapheme_indices(true) {
+        let width = grapheme.chars().fold(0, |width, c| {
+            width + char_width(c, display_column + width, buffer.tab_width())
+        });
+        display_column += width;
+
+        let style = match byte_index >= trailing_start {
+            true => trailing_style,
+            false => invisible_style,
+        };
+
+        while highlights
+            .next_if(|(range, _)| range.end <= byte_index)
+            .is_some()
+        {}
+        let highlight_style = match highlights.peek() {
+            Some((range, highlight)) if range.contains(&byte_index) => theme.highlight(*highlight),
+            _ => Style::default(),
+        };
+
+        match (grapheme, show_invisibles) {
+            (" ", true) => spans.push(Span::styled("·", style)),
+            ("\t", true) => {
+                spans.push(Span::styled("→", style));
+                spans.push(Span::styled(" ".repeat(width - 1), highlight_style));
+            }
+            ("\t", false) => spans.push(Span::styled(" ".repeat(width), highlight_style)),
+            _ => spans.push(Span::styled(grapheme.to_string(), highlight_style)),
+        }
+    }
+
+    // The final newline is the one ending the second-to-last line,
+    // because the buffer's last line comes after it
+    if show_invisibles && buffer.ends_with_newline() && line + 2 == buffer.line_count() {
+        spans.push(Span::styled("¬", invisible_style));
+    }
+
+    Spans::from(merge_spans(spans))
+}
+
+// Combines adjacent spans that have the same style, to reduce the work done while rendering
+fn merge_spans(spans: Vec<Span<'static>>) -> Vec<Span<'static>> {
+    let mut merged: Vec<Span<'static>> = Vec::with_capacity(spans.len());
+
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if last.style == span.style => {
+                last.content.to_mut().push_str(&span.content);
+            }
+            _ => merged.push(span),
+        }
+    }
+
+    merged
+}
+
+// Splits the screen into the text area, the scrollbar to its right, and the status bar below them
+// The scrollbar is only given space if decorations are drawn and zen mode is off,
+// and in zen mode the text area is a column of the given width in the middle of the screen
+// The given number of columns on the left of the text area are left empty for the line numbers
+fn layout(
+    size: Rect,
+    decorations: bool,
+    zen_width: Option<u16>,
+    gutter_width: u16,
+    tab_line: bool,
+) -> (Rect, Rect, Rect) {
+    let status_height = size.height.min(1);
+    // The tab line is a decoration too, and zen mode hides it along with the status bar
+    let tab_height =
+        (size.height - status_height).min((tab_line && decorations && zen_width.is_none()) as u16);
+    let scrollbar_width = size.width.min((decorations && zen_width.is_none()) as u16);
+    let text_width = match zen_width {
+        Some(width) => width.min(size.width),
+        None => size.width - scrollbar_width,
+    };
+    let gutter_width = gutter_width.min(text_width);
+    let text_area = Rect {
+        x: size.x + (size.width - scrollbar_width - text_width) / 2 + gutter_width,
+        y: size.y + tab_height,
+        width: text_width - gutter_width,
+        height: size.height - status_height - tab_height,
+    };
+    let scrollbar_area = Rect {
+        x: text_area.x + text_area.width,
+        width: scrollbar_width,
+        ..text_area
+    };
+    let status_area = Rect {
+        y: text_area.y + text_area.height,
+        height: status_height,
+        ..size
+    };
+
+    (text_area, scrollbar_area, status_area)
+}
+
+// Gets the area of the scrollbar's thumb, whose position and size match the visible part of the buffer
+// * The view can be scrolled past the end of the buffer, so that space is counted as part of its length
+fn scrollbar_thumb(track: Rect, scroll_y: usize, line_count: usize) -> Rect {
+    let height = track.height as usize;
+    let length = line_count.max(scroll_y + height).max(1);
+
+    let thumb_height = (height * height / length).clamp(1, height.max(1));
+    let thumb_y = (scroll_y * height / length).min(height.saturating_sub(thumb_height));
+
+    Rect {
+        y: track.y + thumb_y as u16,
+        height: thumb_height.min(height) as u16,
+        ..track
+    }
+}
+
+// Builds the status bar, which is made up of a left-aligned and a right-aligned section
+// Each section shows the segments the status line gives it, in order, leaving out empty ones
+// By default, the left section shows whether the buffer has been modified and the current message,
+// and the right section shows the task in progress, overwrite mode, the remote host (if any),
+// the cursor's line and column, and how far through the buffer it is
+fn status_bar<'a>(
+    status_line: &StatusLine,
+    status: &Status,
+    cursor: &CursorPosition,
+    buffer: &Buffer,
+    clock: &str,
+    theme: &Theme,
+) -> (Paragraph<'a>, Paragraph<'a>) {
+    let style = theme.status_bar;
+
+    let segments = |segments: &[(Segment, Option<Color>)], separator: &str| {
+        segments
+            .iter()
+            .filter_map(|&(segment, color)| {
+                let (text, segment_style) =
+                    segment_text(segment, status, cursor, buffer, clock, theme)?;
+                let segment_style = match color {
+                    Some(color) => segment_style.fg(color),
+                    None => segment_style,
+                };
+
+                Some([
+                    Span::styled(text, segment_style),
+                    Span::raw(separator.to_string()),
+                ])
+            })
+            .flatten()
+            .collect::<Vec<Span>>()
+    };
+
+    let mut left = vec![Span::raw(" ")];
+    left.extend(segments(&status_line.left, " "));
+
+    // The right section ends one column from the edge, rather than with a full separator
+    let mut right = segments(&status_line.right, "  ");
+    if let Some(last) = right.last_mut() {
+        *last = Span::raw(" ");
+    }
+
+    (
+        Paragraph::new(Spans::from(left)).style(style),
+        Paragraph::new(Spans::from(right))
+            .alignment(Alignment::Right)
+            .style(style),
+    )
+}
+
+// Builds the tab line, which has an entry for each open buffer made up of the segments the tab line
+// gives it, with the buffer being edited drawn in the text's colors
+// If the entries do not all fit, the ones at the start are left out until the current one fits
+fn tab_line<'a>(
+    template: &TabLine,
+    tabs: &[Tab],
+    current: usize,
+    width: u16,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let entries: Vec<Vec<Span>> = tabs
+        .iter()
+        .enumerate()
+        .map(|(index, tab)| {
+            let style = match index == current {
+                true => theme.text.remove_modifier(Modifier::REVERSED),
+                false => Style::default(),
+            };
+
+            let mut spans = vec![Span::styled(" ", style)];
+            for &(segment, color) in &template.segments {
+                let text = match segment {
+                    TabSegment::Index => (index + 1).to_string(),
+                    TabSegment::Icon => match tab.icon {
+                        Some(icon) => icon.to_string(),
+                        None => continue,
+                    },
+                    TabSegment::Name => match tab.path.rsplit_once('/') {
+                        Some((_, name)) if !name.is_empty() => name.to_string(),
+                        _ => tab.path.clone(),
+                    },
+                    TabSegment::Path => tab.path.clone(),
+                    TabSegment::Modified if tab.modified => "[+]".to_string(),
+                    TabSegment::Modified => continue,
+                };
+                let segment_style = match color {
+                    Some(color) => style.fg(color),
+                    None => style,
+                };
+
+                spans.push(Span::styled(text, segment_style));
+                spans.push(Span::styled(" ", style));
+            }
+            spans.push(Span::raw(" "));
+
+            spans
+        })
+        .collect();
+
+    let entry_width = |spans: &Vec<Span>| spans.iter().map(Span::width).sum::<usize>();
+    let mut start = 0;
+    while start < current
+        && entries[start..=current]
+            .iter()
+            .map(entry_width)
+            .sum::<usize>()
+            > width as usize
+    {
+        start += 1;
+    }
+
+    let spans: Vec<Span> = entries.into_iter().skip(start).flatten().collect();
+    Paragraph::new(Spans::from(spans)).style(theme.status_bar)
+}
+
+// Gets the text of a status bar segment and the style it is drawn in, or None if it has nothing to show
+fn segment_text(
+    segment: Segment,
+    status: &Status,
+    cursor: &CursorPosition,
+    buffer: &Buffer,
+    clock: &str,
+    theme: &Theme,
+) -> Option<(String, Style)> {
+    let text = match segment {
+        Segment::Modified => status.modified.then(|| "[+]".to_string())?,
+        Segment::Message => {
+            let (message, level) = status.message.filter(|(message, _)| !message.is_empty())?;
+            return Some((message.to_string(), message_style(level, theme)));
+        }
+        Segment::Icon => status.icon?.to_string(),
+        Segment::File => status.file.to_string(),
+        Segment::Branch => status.branch?.to_string(),
+        Segment::Mode => status.overwrite.then(|| "OVR".to_string())?,
+        Segment::Progress => match status.progress? {
+            (task, Some(percentage)) => format!("{} {} {}%", spinner_frame(), task, percentage),
+            (task, None) => format!("{} {}", spinner_frame(), task),
+        },
+        Segment::Remote => match status.remote? {
+            (host, true) => format!("ssh:{}", host),
+            (host, false) => format!("ssh:{} (disconnected)", host),
+        },
+        Segment::Position => format!("{}:{}", cursor.line() + 1, cursor.column() + 1),
+        // The buffer's last line comes after its final newline, so it is not counted if it is empty
+        Segment::Lines => {
+            let lines = buffer.line_count() - buffer.ends_with_newline() as usize;
+            match lines {
+                1 => "1 line".to_string(),
+                lines => format!("{} lines", lines),
+            }
+        }
+        Segment::Percentage => {
+            let line = cursor.line() + 1;
+            format!("{}%", line * 100 / buffer.line_count().max(1))
+        }
+        Segment::Clock => clock.to_string(),
+    };
+
+    Some((text, Style::default()))
+}
+
+// The format of the time shown by the status bar's clock
+const CLOCK_FORMAT: &str = "%H:%M";
+
+// The markers shown in the gutter next to a line with a breakpoint, and the line the program being
+// debugged has stopped on, which are both two columns wide
+const BREAKPOINT_MARKER: &str = "● ";
+const DEBUG_LINE_MARKER: &str = "▶ ";
+
+// The longest a frame waits for the highlighter to finish the lines it is about to draw
+const HIGHLIGHT_WAIT: Duration = Duration::from_millis(5);
+
+// The longest a structural motion waits for the highlighter to parse the buffer
+const SYNTAX_TREE_WAIT: Duration = Duration::from_secs(1);
+
+// Gets the current frame of the spinner shown next to background tasks in the status bar
+// * The frame is based on the clock, so the spinner turns at the same speed however often it is drawn
+fn spinner_frame() -> char {
+    const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+
+    FRAMES[(millis / 100 % FRAMES.len() as u128) as usize]
+}
+
+// Gets the style of a message in the status bar, which is colored to match its level
+// * Info messages are left in the status bar's own style, and the others are not reversed along
+// * with it, so that their colors are the ones the theme gives them
+fn message_style(level: MessageLevel, theme: &Theme) -> Style {
+    match level {
+        MessageLevel::Info => Style::default(),
+        MessageLevel::Warning => theme.warning.remove_modifier(Modifier::REVERSED),
+        MessageLevel::Error => theme.error.remove_modifier(Modifier::REVERSED),
+    }
+}
+
+// Gets the bordered block that panels, dialogs, and other popups are drawn in
+fn popup_block(theme: &Theme) -> Block<'static> {
+    Block::default().borders(Borders::ALL).style(theme.popup)
+}
+
+// Gets the area of the screen that a panel should be drawn in
+// Panels take up the right half of the screen, unless they are wide
+fn panel_area(size: Rect, wide: bool) -> Rect {
+    let width = if wide { size.width } else { size.width / 2 };
+
+    Rect {
+        x: size.x + size.width - width,
+        y: size.y,
+        width,
+        height: size.height,
+    }
+}
+
+// Gets the area of the screen that a dialog should be drawn in, centered in the text area
+fn dialog_area(text_area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(text_area.width);
+    let height = height.min(text_area.height);
+
+    Rect {
+        x: text_area.x + (text_area.width - width) / 2,
+        y: text_area.y + (text_area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+// Gets the area of the screen that the key hints popup should be drawn in
+// The popup sits in the bottom right corner of the text area, just large enough to fit its lines
+fn key_hints_area(text_area: Rect, lines: &[String]) -> Rect {
+    let content_width = lines.iter().map(|line| line.width()).max().unwrap_or(0);
+    let width = (content_width as u16 + 2).min(text_area.width);
+    let height = (lines.len() as u16 + 2).min(text_area.height);
+
+    Rect {
+        x: text_area.x + text_area.width - width,
+        y: text_area.y + text_area.height - height,
+        width,
+        height,
+    }
+}
+
+// Gets the area of a tooltip, on the row below a position on the screen (or above it at the bottom
+// of the screen), as wide as its text but kept inside the screen
+fn tooltip_area(size: Rect, x: u16, y: u16, text: &str) -> Rect {
+    let width = (text.width() as u16 + 2).min(size.width);
+    let height = 3.min(size.height);
+    let y = match y + 1 + height <= size.y + size.height {
+        true => y + 1,
+        false => y.saturating_sub(height).max(size.y),
+    };
+
+    Rect {
+        x: x.min(size.x + size.width - width),
+        y,
+        width,
+        height,
+    }
+}
+
+// Gets the area of the performance overlay, in the top right corner of the text area
+fn overlay_area(text_area: Rect, lines: &[String]) -> Rect {
+    let content_width = lines.iter().map(|line| line.width()).max().unwrap_or(0);
+    let width = (content_width as u16 + 2).min(text_area.width);
+    let height = (lines.len() as u16 + 2).min(text_area.height);
+
+    Rect {
+        x: text_area.x + text_area.width - width,
+        y: text_area.y,
+        width,
+        height,
+    }
+}
+
 // Represents the position of the cursor in the buffer and in the terminal
+#[derive(Default)]
 pub struct CursorPosition {
     buffer_index: usize,
     x: u16,
     y: u16,
-}
-
-impl Default for CursorPosition {
-    fn default() -> Self {
-        Self {
-            buffer_index: 0,
-            x: 0,
-            y: 0,
-        }
-    }
+    // The display column that vertical movement tries to return to
+    // * This is only changed by horizontal movement and edits, so moving through a short line
+    // * and onwards to a longer one puts the cursor back in its original column
+    desired_x: u16,
 }
 
 impl CursorPosition {
     // Moves the cursor up
     pub fn move_up(&mut self, buffer: &Buffer) {
         let y = self.y as usize;
-        let x = self.x as usize;
 
         // If the cursor is at the first line of the buffer, do nothing
         if y == 0 {
             return;
         }
 
-        let prev_line_len = buffer.line_len(y - 1);
-
-        // If the previous line is longer than the current X position,
-        // move to the same X position on the previous line
-        // Otherwise, move to the end of the previous line
-        if prev_line_len > x {
-            let extra_chars_on_previous_line = prev_line_len - x;
-            self.buffer_index -= x + extra_chars_on_previous_line;
-        } else {
-            self.buffer_index -= x + 1;
-        }
-
-        self.update_coords(buffer);
+        self.move_to_line(y - 1, buffer);
     }
 
     // Moves the cursor down
     pub fn move_down(&mut self, buffer: &Buffer) {
         let y = self.y as usize;
-        let x = self.x as usize;
 
         // If the cursor is at the last line of the buffer, do nothing
         if y == buffer.line_count() - 1 {
             return;
         }
 
-        let next_line_len = buffer.line_len(y + 1);
-        let remaining_chars_on_current_line = buffer.line_len(y) - x;
-
-        // If the next line is longer than the current X position,
-        // move to the same X position on the next line
-        // Otherwise, move to the end of the next line
-        if next_line_len > x {
-            self.buffer_index += remaining_chars_on_current_line + x;
-        } else {
-            // If the next line's length is 0 but the program has not tripped a guard clause,
-            // it means that the last line of the buffer is empty, which requires a special case
-            self.buffer_index += remaining_chars_on_current_line + next_line_len - match next_line_len {
-                0 => 0,
-                _ => 1,
-            };
-        }
+        self.move_to_line(y + 1, buffer);
+    }
+
+    // Moves the cursor to another line, keeping the desired display column if the line is long enough
+    // Otherwise, the cursor is moved to the end of the line
+    // * Display columns are used rather than character columns so that the cursor
+    // * does not appear to jump sideways when moving between lines with tabs
+    pub fn move_to_line(&mut self, line: usize, buffer: &Buffer) {
+        let desired_x = self.desired_x;
+        let column = buffer.char_column(line, desired_x as usize);
+        self.buffer_index = buffer.line_start(line) + column;
 
         self.update_coords(buffer);
+        self.desired_x = desired_x;
     }
 
     // Moves the cursor left
+    // * The cursor moves by whole graphemes, so that it never ends up inside of one
     pub fn move_left(&mut self, buffer: &Buffer) {
         if self.buffer_index > 0 {
-            self.buffer_index -= 1;
+            self.buffer_index = buffer.prev_grapheme_boundary(self.buffer_index);
         }
 
         self.update_coords(buffer);
     }
 
     // Moves the cursor right
+    // * The cursor moves by whole graphemes, so that it never ends up inside of one
     pub fn move_right(&mut self, buffer: &Buffer) {
         if self.buffer_index < buffer.size() {
-            self.buffer_index += 1;
+            self.buffer_index = buffer.next_grapheme_boundary(self.buffer_index);
         }
 
         self.update_coords(buffer);
     }
 
-    // Moves the cursor to teh start of the word
+    // Moves the cursor to the given buffer index
+    pub fn move_to(&mut self, index: usize, buffer: &Buffer) {
+        self.buffer_index = index.min(buffer.size());
+
+        self.update_coords(buffer);
+    }
+
+    // Moves the cursor to the start of the line
+    pub fn move_line_start(&mut self, buffer: &Buffer) {
+        self.buffer_index = buffer.line_start(self.y as usize);
+        self.update_coords(buffer);
+    }
+
+    // Moves the cursor to the end of the line
+    pub fn move_line_end(&mut self, buffer: &Buffer) {
+        self.buffer_index = buffer.line_end(self.y as usize);
+        self.update_coords(buffer);
+    }
+
+    // Moves the cursor to the start of the word
     pub fn move_word_left(&mut self, buffer: &Buffer) {
         if self.buffer_index > 0 {
             self.buffer_index = buffer.start_of_word(self.buffer_index);
@@ -196,11 +2252,71 @@ impl CursorPosition {
         self.update_coords(buffer);
     }
 
+    // Moves the cursor to the start of the subword
+    pub fn move_subword_left(&mut self, buffer: &Buffer) {
+        self.buffer_index = buffer.start_of_subword(self.buffer_index);
+        self.update_coords(buffer);
+    }
+
+    // Moves the cursor to the end of the subword
+    pub fn move_subword_right(&mut self, buffer: &Buffer) {
+        self.buffer_index = buffer.end_of_subword(self.buffer_index);
+        self.update_coords(buffer);
+    }
+
+    // Moves the cursor to the start of the sentence
+    pub fn move_sentence_left(&mut self, buffer: &Buffer) {
+        self.buffer_index = buffer.start_of_sentence(self.buffer_index);
+        self.update_coords(buffer);
+    }
+
+    // Moves the cursor to the end of the sentence
+    pub fn move_sentence_right(&mut self, buffer: &Buffer) {
+        self.buffer_index = buffer.end_of_sentence(self.buffer_index);
+        self.update_coords(buffer);
+    }
+
+    // Moves the cursor to the first line of the indentation block it is in
+    pub fn move_block_start(&mut self, buffer: &Buffer) {
+        let line = buffer.block_start(self.y as usize);
+        self.move_to(buffer.first_non_whitespace(line), buffer);
+    }
+
+    // Moves the cursor to the last line of the indentation block it is in
+    pub fn move_block_end(&mut self, buffer: &Buffer) {
+        let line = buffer.block_end(self.y as usize);
+        self.move_to(buffer.first_non_whitespace(line), buffer);
+    }
+
+    // Moves the cursor to the blank line after the current paragraph
+    pub fn move_paragraph_down(&mut self, buffer: &Buffer) {
+        let line = buffer.next_paragraph(self.y as usize);
+        self.move_to(buffer.line_start(line), buffer);
+    }
+
+    // Moves the cursor to the blank line before the current paragraph
+    pub fn move_paragraph_up(&mut self, buffer: &Buffer) {
+        let line = buffer.previous_paragraph(self.y as usize);
+        self.move_to(buffer.line_start(line), buffer);
+    }
+
     // Gets the cursor coordinate from its current buffer index
+    // This also resets the desired column, which vertical movement restores afterwards
     fn update_coords(&mut self, buffer: &Buffer) {
         (self.x, self.y) = buffer
             .cursor_coord(self.buffer_index)
             .expect("[INTERNAL ERROR] Cursor position was out of bounds");
+        self.desired_x = self.x;
+    }
+
+    // Returns the line that the cursor is on, starting from zero
+    pub fn line(&self) -> usize {
+        self.y as usize
+    }
+
+    // Returns the column that the cursor is on, starting from zero
+    pub fn column(&self) -> usize {
+        self.x as usize
     }
 
     // Returns the cursor's buffer index
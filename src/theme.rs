@@ -0,0 +1,429 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use tui::style::{Color, Modifier, Style};
+
+use crate::config::config_path;
+use crate::syntax::Highlight;
+use crate::toml::{self, Entry, Value};
+
+// Represents the styles that everything on the screen is drawn in, for both the editor's own
+// elements and each kind of syntax highlighted text
+// Custom themes are loaded from themes/<name>.toml next to the config file, and either start from
+// one of the built-in themes or from the default theme, replacing any of its styles
+// Each style is written as a list of words, where a color sets the text color, a color after "on"
+// sets the background color, and the others are modifiers such as "bold" or "italic"
+// [EXAMPLE]
+// inherits = "light"
+//
+// [ui]
+// text = "black on #fdf6e3"
+// selection = "on 254"
+// remote-cursors = "magenta, cyan, yellow"
+//
+// [syntax]
+// comment = "gray italic"
+#[derive(Clone)]
+pub struct Theme {
+    // The buffer's text and the background of the whole screen
+    pub text: Style,
+    // The background of the line the cursor is on
+    pub current_line: Style,
+    pub ruler: Style,
+    pub line_number: Style,
+    pub current_line_number: Style,
+    pub scrollbar: Style,
+    pub scrollbar_thumb: Style,
+    // The marker shown after a folded line
+    pub fold: Style,
+    pub selection: Style,
+    // The cursors added besides the main one
+    pub extra_cursor: Style,
+    pub search_match: Style,
+    // The text which has a syntax error
+    pub diagnostic: Style,
+    // The markers of breakpoints in the gutter, and the line the program being debugged stopped on
+    pub breakpoint: Style,
+    pub debug_line: Style,
+    // The glyphs that whitespace is replaced with when invisibles are shown
+    pub invisible: Style,
+    pub trailing_whitespace: Style,
+    pub status_bar: Style,
+    pub warning: Style,
+    pub error: Style,
+    // Panels, dialogs, and other popups drawn on top of the buffer
+    pub popup: Style,
+    // The selected item in a panel or the selected button in a dialog
+    pub popup_selected: Style,
+    // The colors given to other users' cursors in a collaborative session, in turn
+    pub remote_cursors: Vec<Color>,
+    pub comment: Style,
+    pub string: Style,
+    pub number: Style,
+    pub keyword: Style,
+    pub type_name: Style,
+    pub function: Style,
+    pub constant: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::terminal()
+    }
+}
+
+impl Theme {
+    // Gets the theme which uses the terminal's own colors for text, and its basic colors for everything else
+    // * This follows the terminal's color scheme, so it looks reasonable on both dark and light terminals
+    fn terminal() -> Self {
+        let plain = Style::default();
+
+        Self {
+            text: plain,
+            current_line: plain.bg(Color::Indexed(236)),
+            ruler: plain.bg(Color::Indexed(235)),
+            line_number: plain.fg(Color::DarkGray),
+            current_line_number: plain,
+            scrollbar: plain.bg(Color::Indexed(235)),
+            scrollbar_thumb: plain.bg(Color::Indexed(244)),
+            fold: plain.fg(Color::DarkGray),
+            selection: plain.bg(Color::Indexed(239)),
+            extra_cursor: plain.add_modifier(Modifier::REVERSED),
+            search_match: plain.bg(Color::Indexed(58)),
+            diagnostic: plain.add_modifier(Modifier::UNDERLINED),
+            breakpoint: plain.fg(Color::Red),
+            debug_line: plain.bg(Color::Indexed(22)),
+            invisible: plain.add_modifier(Modifier::DIM),
+            trailing_whitespace: plain.fg(Color::Red).add_modifier(Modifier::DIM),
+            status_bar: plain.add_modifier(Modifier::REVERSED),
+            warning: plain.fg(Color::Black).bg(Color::Yellow),
+            error: plain.fg(Color::White).bg(Color::Red),
+            popup: plain,
+            popup_selected: plain.add_modifier(Modifier::REVERSED),
+            remote_cursors: vec![
+                Color::Magenta,
+                Color::Cyan,
+                Color::Yellow,
+                Color::Green,
+                Color::Blue,
+                Color::Red,
+            ],
+            comment: plain.fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+            string: plain.fg(Color::Green),
+            number: plain.fg(Color::Cyan),
+            keyword: plain.fg(Color::Magenta),
+            type_name: plain.fg(Color::Yellow),
+            function: plain.fg(Color::Blue),
+            constant: plain.fg(Color::Cyan),
+        }
+    }
+
+    // Gets a dark theme with its own background, which looks the same whatever the terminal's colors are
+    fn dark() -> Self {
+        let plain = Style::default();
+        let background = Color::Rgb(0x1e, 0x1e, 0x2e);
+
+        Self {
+            text: plain.fg(Color::Rgb(0xcd, 0xd6, 0xf4)).bg(background),
+            current_line: plain.bg(Color::Rgb(0x2a, 0x2b, 0x3c)),
+            ruler: plain.bg(Color::Rgb(0x24, 0x25, 0x36)),
+            line_number: plain.fg(Color::Rgb(0x58, 0x5b, 0x70)),
+            current_line_number: plain.fg(Color::Rgb(0xb4, 0xbe, 0xfe)),
+            scrollbar: plain.bg(Color::Rgb(0x24, 0x25, 0x36)),
+            scrollbar_thumb: plain.bg(Color::Rgb(0x58, 0x5b, 0x70)),
+            fold: plain.fg(Color::Rgb(0x7f, 0x84, 0x9c)),
+            selection: plain.bg(Color::Rgb(0x45, 0x47, 0x5a)),
+            extra_cursor: plain.add_modifier(Modifier::REVERSED),
+            search_match: plain.bg(Color::Rgb(0x5c, 0x4f, 0x2a)),
+            diagnostic: plain.add_modifier(Modifier::UNDERLINED),
+            breakpoint: plain.fg(Color::Rgb(0xf3, 0x8b, 0xa8)),
+            debug_line: plain.bg(Color::Rgb(0x2b, 0x3d, 0x32)),
+            invisible: plain.fg(Color::Rgb(0x45, 0x47, 0x5a)),
+            trailing_whitespace: plain.fg(Color::Rgb(0xf3, 0x8b, 0xa8)),
+            status_bar: plain
+                .fg(Color::Rgb(0xcd, 0xd6, 0xf4))
+                .bg(Color::Rgb(0x31, 0x32, 0x44)),
+            warning: plain.fg(background).bg(Color::Rgb(0xf9, 0xe2, 0xaf)),
+            error: plain.fg(background).bg(Color::Rgb(0xf3, 0x8b, 0xa8)),
+            popup: plain
+                .fg(Color::Rgb(0xcd, 0xd6, 0xf4))
+                .bg(Color::Rgb(0x18, 0x18, 0x25)),
+            popup_selected: plain.fg(background).bg(Color::Rgb(0x89, 0xb4, 0xfa)),
+            remote_cursors: vec![
+                Color::Rgb(0xcb, 0xa6, 0xf7),
+                Color::Rgb(0x94, 0xe2, 0xd5),
+                Color::Rgb(0xf9, 0xe2, 0xaf),
+                Color::Rgb(0xa6, 0xe3, 0xa1),
+                Color::Rgb(0x89, 0xb4, 0xfa),
+                Color::Rgb(0xf3, 0x8b, 0xa8),
+            ],
+            comment: plain
+                .fg(Color::Rgb(0x7f, 0x84, 0x9c))
+                .add_modifier(Modifier::ITALIC),
+            string: plain.fg(Color::Rgb(0xa6, 0xe3, 0xa1)),
+            number: plain.fg(Color::Rgb(0xfa, 0xb3, 0x87)),
+            keyword: plain.fg(Color::Rgb(0xcb, 0xa6, 0xf7)),
+            type_name: plain.fg(Color::Rgb(0xf9, 0xe2, 0xaf)),
+            function: plain.fg(Color::Rgb(0x89, 0xb4, 0xfa)),
+            constant: plain.fg(Color::Rgb(0xfa, 0xb3, 0x87)),
+        }
+    }
+
+    // Gets a light theme with its own background, for use in bright surroundings
+    fn light() -> Self {
+        let plain = Style::default();
+        let background = Color::Rgb(0xfa, 0xfa, 0xfa);
+
+        Self {
+            text: plain.fg(Color::Rgb(0x38, 0x3a, 0x42)).bg(background),
+            current_line: plain.bg(Color::Rgb(0xef, 0xef, 0xf1)),
+            ruler: plain.bg(Color::Rgb(0xf0, 0xf0, 0xf0)),
+            line_number: plain.fg(Color::Rgb(0x9d, 0x9d, 0x9f)),
+            current_line_number: plain.fg(Color::Rgb(0x38, 0x3a, 0x42)),
+            scrollbar: plain.bg(Color::Rgb(0xe5, 0xe5, 0xe6)),
+            scrollbar_thumb: plain.bg(Color::Rgb(0xa0, 0xa1, 0xa7)),
+            fold: plain.fg(Color::Rgb(0xa0, 0xa1, 0xa7)),
+            selection: plain.bg(Color::Rgb(0xd0, 0xd8, 0xf0)),
+            extra_cursor: plain.add_modifier(Modifier::REVERSED),
+            search_match: plain.bg(Color::Rgb(0xf5, 0xe3, 0xa3)),
+            diagnostic: plain.add_modifier(Modifier::UNDERLINED),
+            breakpoint: plain.fg(Color::Rgb(0xd2, 0x0f, 0x39)),
+            debug_line: plain.bg(Color::Rgb(0xdc, 0xf2, 0xd8)),
+            invisible: plain.fg(Color::Rgb(0xc8, 0xc8, 0xca)),
+            trailing_whitespace: plain.fg(Color::Rgb(0xe4, 0x56, 0x49)),
+            status_bar: plain
+                .fg(Color::Rgb(0x38, 0x3a, 0x42))
+                .bg(Color::Rgb(0xe5, 0xe5, 0xe6)),
+            warning: plain
+                .fg(Color::Rgb(0x38, 0x3a, 0x42))
+                .bg(Color::Rgb(0xf0, 0xc6, 0x74)),
+            error: plain.fg(background).bg(Color::Rgb(0xe4, 0x56, 0x49)),
+            popup: plain
+                .fg(Color::Rgb(0x38, 0x3a, 0x42))
+                .bg(Color::Rgb(0xf0, 0xf0, 0xf1)),
+            popup_selected: plain.fg(background).bg(Color::Rgb(0x40, 0x78, 0xf2)),
+            remote_cursors: vec![
+                Color::Rgb(0xa6, 0x26, 0xa4),
+                Color::Rgb(0x01, 0x84, 0xbc),
+                Color::Rgb(0xc1, 0x84, 0x01),
+                Color::Rgb(0x50, 0xa1, 0x4f),
+                Color::Rgb(0x40, 0x78, 0xf2),
+                Color::Rgb(0xe4, 0x56, 0x49),
+            ],
+            comment: plain
+                .fg(Color::Rgb(0xa0, 0xa1, 0xa7))
+                .add_modifier(Modifier::ITALIC),
+            string: plain.fg(Color::Rgb(0x50, 0xa1, 0x4f)),
+            number: plain.fg(Color::Rgb(0x98, 0x68, 0x01)),
+            keyword: plain.fg(Color::Rgb(0xa6, 0x26, 0xa4)),
+            type_name: plain.fg(Color::Rgb(0xc1, 0x84, 0x01)),
+            function: plain.fg(Color::Rgb(0x40, 0x78, 0xf2)),
+            constant: plain.fg(Color::Rgb(0x98, 0x68, 0x01)),
+        }
+    }
+
+    // Gets one of the themes which are built into the editor, which custom themes can also start from
+    fn built_in(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::terminal()),
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            _ => None,
+        }
+    }
+
+    // Loads a theme by name, which is either one of the built-in themes or a custom theme's file
+    // ! Any mistake in a custom theme is returned as an error, so that it is not silently ignored
+    pub fn load(name: &str) -> Result<Self, String> {
+        if let Some(theme) = Self::built_in(name) {
+            return Ok(theme);
+        }
+
+        let Some(path) = theme_path(name) else {
+            return Err(format!("Unknown theme: {}", name));
+        };
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Err(format!("Unknown theme: {}", name));
+            }
+            Err(error) => return Err(format!("{}: {}", path.display(), error)),
+        };
+
+        Self::parse(&text).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    // Parses the contents of a custom theme's file, describing the first thing wrong with it
+    fn parse(text: &str) -> Result<Self, String> {
+        let document = toml::parse(text)?;
+        if let Some(table) = document
+            .table_names()
+            .find(|&name| name != "ui" && name != "syntax")
+        {
+            return Err(format!("Unknown table: [{}]", table));
+        }
+
+        // Custom themes can only start from a built-in theme, so that they cannot include each other
+        let mut theme = Self::default();
+        for entry in document.table("") {
+            let base = match (entry.key.as_str(), &entry.value) {
+                ("inherits", Value::String(base)) => {
+                    Self::built_in(base).ok_or_else(|| format!("Unknown built-in theme: {}", base))
+                }
+                ("inherits", other) => Err(format!("inherits cannot be {}", other)),
+                (key, _) => Err(format!("Unknown option: {}", key)),
+            };
+
+            theme = base.map_err(|error| format!("line {}: {}", entry.line, error))?;
+        }
+
+        for entry in document.table("ui").iter().chain(document.table("syntax")) {
+            theme
+                .set_style(entry)
+                .map_err(|error| format!("line {}: {}", entry.line, error))?;
+        }
+
+        Ok(theme)
+    }
+
+    // Sets one of the styles in a custom theme's [ui] or [syntax] table
+    fn set_style(&mut self, entry: &Entry) -> Result<(), String> {
+        let Value::String(value) = &entry.value else {
+            return Err(format!("{} cannot be {}", entry.key, entry.value));
+        };
+
+        if entry.key == "remote-cursors" {
+            self.remote_cursors = value
+                .split(',')
+                .map(|color| parse_color(color.trim()))
+                .collect::<Result<_, _>>()?;
+            return Ok(());
+        }
+
+        let style = match entry.key.as_str() {
+            "text" => &mut self.text,
+            "current-line" => &mut self.current_line,
+            "ruler" => &mut self.ruler,
+            "line-number" => &mut self.line_number,
+            "current-line-number" => &mut self.current_line_number,
+            "scrollbar" => &mut self.scrollbar,
+            "scrollbar-thumb" => &mut self.scrollbar_thumb,
+            "fold" => &mut self.fold,
+            "selection" => &mut self.selection,
+            "extra-cursor" => &mut self.extra_cursor,
+            "search-match" => &mut self.search_match,
+            "diagnostic" => &mut self.diagnostic,
+            "breakpoint" => &mut self.breakpoint,
+            "debug-line" => &mut self.debug_line,
+            "invisible" => &mut self.invisible,
+            "trailing-whitespace" => &mut self.trailing_whitespace,
+            "status-bar" => &mut self.status_bar,
+            "warning" => &mut self.warning,
+            "error" => &mut self.error,
+            "popup" => &mut self.popup,
+            "popup-selected" => &mut self.popup_selected,
+            "comment" => &mut self.comment,
+            "string" => &mut self.string,
+            "number" => &mut self.number,
+            "keyword" => &mut self.keyword,
+            "type" => &mut self.type_name,
+            "function" => &mut self.function,
+            "constant" => &mut self.constant,
+            key => return Err(format!("Unknown style: {}", key)),
+        };
+
+        *style = parse_style(value)?;
+        Ok(())
+    }
+
+    // Gets the style that syntax highlighted text of a kind is drawn in
+    pub fn highlight(&self, highlight: Highlight) -> Style {
+        match highlight {
+            Highlight::Comment => self.comment,
+            Highlight::String => self.string,
+            Highlight::Number => self.number,
+            Highlight::Keyword => self.keyword,
+            Highlight::Type => self.type_name,
+            Highlight::Function => self.function,
+            Highlight::Constant => self.constant,
+        }
+    }
+}
+
+// Gets the path of a custom theme's file, which is in the themes directory next to the config file
+// [EXAMPLE] "/home/user/.config/rut/themes/solarized.toml"
+fn theme_path(name: &str) -> Option<PathBuf> {
+    // Names are not allowed to leave the themes directory
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return None;
+    }
+
+    let directory = config_path()?.parent()?.join("themes");
+    Some(directory.join(format!("{}.toml", name)))
+}
+
+// Parses a style, which is a list of colors and modifiers separated by spaces
+// A color on its own sets the text color, and a color after "on" sets the background color
+// [EXAMPLE] "yellow on #3a3a3a bold"
+fn parse_style(s: &str) -> Result<Style, String> {
+    let mut style = Style::default();
+    let mut words = s.split_whitespace();
+
+    while let Some(word) = words.next() {
+        style = match word {
+            "on" => match words.next() {
+                Some(color) => style.bg(parse_color(color)?),
+                None => return Err("Expected a color after on".to_string()),
+            },
+            "bold" => style.add_modifier(Modifier::BOLD),
+            "dim" => style.add_modifier(Modifier::DIM),
+            "italic" => style.add_modifier(Modifier::ITALIC),
+            "underlined" => style.add_modifier(Modifier::UNDERLINED),
+            "reversed" => style.add_modifier(Modifier::REVERSED),
+            "crossed-out" => style.add_modifier(Modifier::CROSSED_OUT),
+            color => style.fg(parse_color(color)?),
+        };
+    }
+
+    Ok(style)
+}
+
+// Parses a color, which is the name of one of the terminal's basic colors, the number of one of
+// its 256 colors, or a hex code
+// "default" is the terminal's own color for text or the background
+// [EXAMPLE] "light-blue", "236", or "#1e1e2e"
+pub fn parse_color(s: &str) -> Result<Color, String> {
+    let unknown = || format!("Unknown color: {}", s);
+
+    let color = match s {
+        "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "dark-gray" | "dark-grey" => Color::DarkGray,
+        "light-red" => Color::LightRed,
+        "light-green" => Color::LightGreen,
+        "light-yellow" => Color::LightYellow,
+        "light-blue" => Color::LightBlue,
+        "light-magenta" => Color::LightMagenta,
+        "light-cyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => match s.strip_prefix('#') {
+            Some(hex) if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+                let rgb = u32::from_str_radix(hex, 16).map_err(|_| unknown())?;
+                Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+            }
+            Some(_) => return Err(unknown()),
+            None => Color::Indexed(s.parse().map_err(|_| unknown())?),
+        },
+    };
+
+    Ok(color)
+}
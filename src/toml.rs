@@ -0,0 +1,291 @@
+use std::fmt;
+
+// Represents a value in a TOML document
+// * Only the types which configuration needs are supported, so arrays, floats, dates,
+// * and inline tables are reported as errors
+// TODO: Support arrays once an option needs a list of values
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+}
+
+impl fmt::Display for Value {
+    // Formats the type of the value, for error messages
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::String(_) => "a string",
+            Self::Integer(_) => "an integer",
+            Self::Boolean(_) => "a boolean",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+// Represents a key and its value, along with the line it was on so that errors can point to it
+pub struct Entry {
+    pub key: String,
+    pub value: Value,
+    pub line: usize,
+}
+
+// Represents a parsed TOML document, as the entries of each of its tables in the order they were written
+// The entries before the first table header belong to the table with an empty name
+pub struct Document {
+    tables: Vec<(String, Vec<Entry>)>,
+}
+
+impl Document {
+    // Gets the entries of a table, which are empty if the document does not have it
+    pub fn table(&self, name: &str) -> &[Entry] {
+        self.tables
+            .iter()
+            .find(|(table, _)| table == name)
+            .map(|(_, entries)| entries.as_slice())
+            .unwrap_or_default()
+    }
+
+    // Gets the names of the tables in the document, other than the one with an empty name
+    pub fn table_names(&self) -> impl Iterator<Item = &str> {
+        self.tables
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| !name.is_empty())
+    }
+}
+
+// Parses a TOML document, or describes the first thing wrong with it along with its line number
+// [EXAMPLE] "tab-width = 4\n[keys]\n\"ctrl+a\" = \"line-start\""
+pub fn parse(text: &str) -> Result<Document, String> {
+    let mut tables = vec![(String::new(), Vec::new())];
+
+    for (index, line) in text.lines().enumerate() {
+        let number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[') {
+            let name =
+                parse_header(header).map_err(|error| format!("line {}: {}", number, error))?;
+            if tables.iter().any(|(table, _)| *table == name) {
+                return Err(format!(
+                    "line {}: Table [{}] is defined twice",
+                    number, name
+                ));
+            }
+
+            tables.push((name, Vec::new()));
+            continue;
+        }
+
+        let (key, value) =
+            parse_entry(line).map_err(|error| format!("line {}: {}", number, error))?;
+        let (_, entries) = tables
+            .last_mut()
+            .expect("[INTERNAL ERROR] Document has no tables");
+        if entries.iter().any(|entry: &Entry| entry.key == key) {
+            return Err(format!("line {}: {} is set twice", number, key));
+        }
+
+        entries.push(Entry {
+            key,
+            value,
+            line: number,
+        });
+    }
+
+    Ok(Document { tables })
+}
+
+// Parses the name of a table from its header, after the opening bracket
+fn parse_header(header: &str) -> Result<String, String> {
+    let (name, rest) = header
+        .split_once(']')
+        .ok_or_else(|| "Expected ] at the end of the table header".to_string())?;
+    if !is_comment(rest) {
+        return Err(format!(
+            "Unexpected text after the table header: {}",
+            rest.trim()
+        ));
+    }
+
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(is_bare_key_char) {
+        return Err(format!("Invalid table name: {}", name));
+    }
+
+    Ok(name.to_string())
+}
+
+// Parses a line of the form key = value
+fn parse_entry(line: &str) -> Result<(String, Value), String> {
+    let (key, rest) = parse_key(line)?;
+    let rest = rest
+        .trim_start()
+        .strip_prefix('=')
+        .ok_or_else(|| format!("Expected = after {}", key))?;
+
+    let (value, rest) = parse_value(rest.trim_start())?;
+    if !is_comment(rest) {
+        return Err(format!("Unexpected text after the value: {}", rest.trim()));
+    }
+
+    Ok((key, value))
+}
+
+// Parses a key, which is either bare (letters, digits, - and _) or quoted, returning it and what comes after it
+fn parse_key(line: &str) -> Result<(String, &str), String> {
+    if line.starts_with(['"', '\'']) {
+        return parse_string(line);
+    }
+
+    let end = line
+        .find(|c: char| !is_bare_key_char(c))
+        .unwrap_or(line.len());
+    if end == 0 {
+        return Err(format!("Expected a key, found {}", line));
+    }
+
+    Ok((line[..end].to_string(), &line[end..]))
+}
+
+// Parses a value, returning it and what comes after it
+fn parse_value(text: &str) -> Result<(Value, &str), String> {
+    if text.starts_with(['"', '\'']) {
+        let (string, rest) = parse_string(text)?;
+        return Ok((Value::String(string), rest));
+    }
+
+    let end = text
+        .find(|c: char| c.is_whitespace() || c == '#')
+        .unwrap_or(text.len());
+    let (word, rest) = text.split_at(end);
+
+    let value = match word {
+        "" => return Err("Expected a value".to_string()),
+        "true" => Value::Boolean(true),
+        "false" => Value::Boolean(false),
+        // Underscores can be used to separate digits, such as 1_000
+        _ => match word.replace('_', "").parse() {
+            Ok(integer) => Value::Integer(integer),
+            Err(_) => return Err(format!("Unsupported value: {}", word)),
+        },
+    };
+
+    Ok((value, rest))
+}
+
+// Parses a basic string ("...", which can contain escapes) or a literal string ('...', which cannot),
+// returning it and what comes after it
+fn parse_string(text: &str) -> Result<(String, &str), String> {
+    let mut chars = text.char_indices();
+    let (_, quote) = chars
+        .next()
+        .expect("[INTERNAL ERROR] String does not start with a quote");
+
+    let mut string = String::new();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            c if c == quote => return Ok((string, &text[index + 1..])),
+            '\\' if quote == '"' => {
+                let escaped = match chars.next() {
+                    Some((_, 'n')) => '\n',
+                    Some((_, 't')) => '\t',
+                    Some((_, 'r')) => '\r',
+                    Some((_, '"')) => '"',
+                    Some((_, '\\')) => '\\',
+                    Some((_, other)) => return Err(format!("Unknown escape: \\{}", other)),
+                    None => break,
+                };
+                string.push(escaped);
+            }
+            c => string.push(c),
+        }
+    }
+
+    Err(format!("Expected {} at the end of the string", quote))
+}
+
+// Checks whether the rest of a line is empty or a comment
+fn is_comment(rest: &str) -> bool {
+    let rest = rest.trim_start();
+    rest.is_empty() || rest.starts_with('#')
+}
+
+// Checks whether a character can be used in a key without quoting it
+fn is_bare_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Gets the string value of a key in a table, failing the test if it is missing or not a string
+    fn string(document: &Document, table: &str, key: &str) -> String {
+        match document.table(table).iter().find(|entry| entry.key == key) {
+            Some(Entry {
+                value: Value::String(string),
+                ..
+            }) => string.clone(),
+            _ => panic!("{} is not a string in [{}]", key, table),
+        }
+    }
+
+    #[test]
+    fn parses_values_and_tables() {
+        let document = parse(
+            "# Settings\ntab-width = 1_000\nicons = false # inline comment\n\n[keys]\n\"ctrl+a\" = 'line-start'\n",
+        )
+        .unwrap();
+
+        let root = document.table("");
+        assert!(matches!(root[0].value, Value::Integer(1000)));
+        assert!(matches!(root[1].value, Value::Boolean(false)));
+        assert_eq!(root[1].line, 3);
+        assert_eq!(string(&document, "keys", "ctrl+a"), "line-start");
+        assert_eq!(document.table_names().collect::<Vec<_>>(), ["keys"]);
+        assert!(document.table("missing").is_empty());
+    }
+
+    #[test]
+    fn handles_escapes_in_basic_strings_only() {
+        let document = parse(
+            r#"basic = "a\tb\n\"c\"\\"
+literal = 'a\tb'"#,
+        )
+        .unwrap();
+
+        assert_eq!(string(&document, "", "basic"), "a\tb\n\"c\"\\");
+        assert_eq!(string(&document, "", "literal"), "a\\tb");
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        let error = |text: &str| parse(text).err().unwrap();
+
+        assert_eq!(error("a = 1\nb = \"\\q\""), "line 2: Unknown escape: \\q");
+        assert_eq!(
+            error("a = 1\n\nb = \"open"),
+            "line 3: Expected \" at the end of the string"
+        );
+        assert_eq!(error("a = 1\na = 2"), "line 2: a is set twice");
+        assert_eq!(
+            error("[keys]\n[keys]"),
+            "line 2: Table [keys] is defined twice"
+        );
+        assert_eq!(
+            error("[keys"),
+            "line 1: Expected ] at the end of the table header"
+        );
+        assert_eq!(error("a 1"), "line 1: Expected = after a");
+        assert_eq!(error("a = 1.5"), "line 1: Unsupported value: 1.5");
+        assert_eq!(
+            error("a = 1 2"),
+            "line 1: Unexpected text after the value: 2"
+        );
+    }
+}
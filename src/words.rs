@@ -0,0 +1,55 @@
+// Represents which characters make up a word for word motions and deletion
+// Letters and digits are always word characters, and some punctuation can be added to them
+// Other punctuation forms words of its own, so foo.bar(baz) is made up of five words
+#[derive(Clone)]
+pub struct WordChars {
+    extra: Vec<char>,
+}
+
+// Represents the kinds of characters which word motions stop between
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum WordClass {
+    Whitespace,
+    Word,
+    Punctuation,
+}
+
+impl Default for WordChars {
+    fn default() -> Self {
+        Self::new("_")
+    }
+}
+
+impl WordChars {
+    // Create a new WordChars instance which treats the given punctuation as part of words
+    pub fn new(extra: &str) -> Self {
+        Self {
+            extra: extra.chars().collect(),
+        }
+    }
+
+    // Chooses the word characters for a file based on its extension
+    // * Languages which allow hyphens in names (such as CSS classes and Lisp symbols)
+    // * treat them as part of words, and everything else only treats underscores as part of words
+    pub fn for_filename(filename: &str) -> Self {
+        let extension = filename
+            .rsplit_once('.')
+            .map_or("", |(_, extension)| extension);
+
+        match extension {
+            "css" | "scss" | "sass" | "less" | "html" | "htm" | "xml" | "svg" => Self::new("_-"),
+            "lisp" | "el" | "clj" | "cljs" | "scm" | "rkt" => Self::new("_-!?*<>=/+"),
+            "sh" | "bash" | "zsh" | "fish" | "mk" => Self::new("_$"),
+            _ => Self::default(),
+        }
+    }
+
+    // Gets the word class of a character
+    pub fn class(&self, c: char) -> WordClass {
+        match c {
+            c if c.is_whitespace() => WordClass::Whitespace,
+            c if c.is_alphanumeric() || self.extra.contains(&c) => WordClass::Word,
+            _ => WordClass::Punctuation,
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use tui::text::{Span, Spans};
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::buffer::char_width;
+use crate::Buffer;
+
+// Gets the display columns that each row of a soft wrapped line starts at, the first always being zero
+// Rows are broken after whitespace where possible, and words which are too long for a whole row
+// are broken wherever they reach the edge
+// * The cursor can sit one column past the end of a line, so a line which exactly fills its last row
+// * gets an extra empty row for it
+// [EXAMPLE] "the quick brown fox" with a width of 12 starts its rows at [0, 10]
+pub fn wrap_points(buffer: &Buffer, line: usize, width: usize) -> Vec<usize> {
+    let mut points = vec![0];
+    if width == 0 {
+        return points;
+    }
+
+    let contents = buffer.line_contents(line);
+    let contents = contents.trim_end_matches('\r');
+
+    let mut display_column = 0;
+    let mut row_start = 0;
+    // The display column just after the last whitespace in the current row, where it can be broken
+    let mut break_point = None;
+    for grapheme in contents.graphemes(true) {
+        let grapheme_width = grapheme.chars().fold(0, |grapheme_width, c| {
+            grapheme_width + char_width(c, display_column + grapheme_width, buffer.tab_width())
+        });
+        let whitespace = grapheme.chars().all(char::is_whitespace);
+
+        if display_column + grapheme_width > row_start + width && display_column > row_start {
+            row_start = match (whitespace, break_point) {
+                (false, Some(point)) if point > row_start => point,
+                _ => display_column,
+            };
+            break_point = None;
+            points.push(row_start);
+        }
+
+        display_column += grapheme_width;
+        if whitespace {
+            break_point = Some(display_column);
+        }
+    }
+
+    if display_column >= row_start + width {
+        points.push(display_column);
+    }
+
+    points
+}
+
+// Gets which row of a wrapped line a display column is on, given the columns that the rows start at
+pub fn row_at(points: &[usize], display_column: usize) -> usize {
+    points.partition_point(|&point| point <= display_column) - 1
+}
+
+// Splits the styled text of a line into one line of text for each row it is wrapped onto
+pub fn split_rows(spans: Spans<'static>, points: &[usize]) -> Vec<Spans<'static>> {
+    let mut rows = vec![Vec::new()];
+    let mut display_column = 0;
+
+    for span in spans.0 {
+        let mut content = String::new();
+        for grapheme in span.content.graphemes(true) {
+            if rows.len() < points.len() && display_column >= points[rows.len()] {
+                if !content.is_empty() {
+                    let row = rows.last_mut().expect("[INTERNAL ERROR] No rows to add to");
+                    row.push(Span::styled(std::mem::take(&mut content), span.style));
+                }
+
+                rows.push(Vec::new());
+            }
+
+            display_column += grapheme.width();
+            content.push_str(grapheme);
+        }
+
+        if !content.is_empty() {
+            let row = rows.last_mut().expect("[INTERNAL ERROR] No rows to add to");
+            row.push(Span::styled(content, span.style));
+        }
+    }
+
+    // The empty row after a line which exactly fills its last row has no text to put in it
+    rows.resize_with(points.len(), Vec::new);
+
+    rows.into_iter().map(Spans::from).collect()
+}